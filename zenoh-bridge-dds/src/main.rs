@@ -11,10 +11,13 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
-use async_liveliness_monitor::LivelinessMonitor;
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
+use std::net::IpAddr;
 use std::str::FromStr;
-use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing_subscriber::EnvFilter;
 use zenoh::config::{Config, ModeDependentValue};
 use zenoh::plugins::PluginsManager;
 use zenoh::prelude::r#async::*;
@@ -22,6 +25,21 @@ use zenoh::runtime::RuntimeBuilder;
 use zenoh_plugin_dds::DDSPlugin;
 use zenoh_plugin_trait::Plugin;
 
+mod watchdog;
+use watchdog::{run_watchdog, WatchdogConfig};
+
+// the environment variable setting the format of the logs, if --log-format is not set
+const LOG_FORMAT_ENV_VAR: &str = "Z_LOG_FORMAT";
+
+// max duration of the graceful shutdown on SIGINT/SIGTERM, after which the process exits anyway
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+// period of the check of the reception of a termination signal
+const SIGNAL_CHECK_PERIOD: Duration = Duration::from_millis(100);
+// max duration of the reload of the DDS plugin configuration on SIGHUP
+const RELOAD_TIMEOUT: Duration = Duration::from_secs(10);
+// exit code of the process when the self-test fails (see --self-test)
+const SELF_TEST_FAILURE_EXIT_CODE: i32 = 1;
+
 lazy_static::lazy_static!(
     pub static ref DEFAULT_DOMAIN_STR: String = zenoh_plugin_dds::config::DEFAULT_DOMAIN.to_string();
 );
@@ -29,22 +47,176 @@ lazy_static::lazy_static!(
 macro_rules! insert_json5 {
     ($config: expr, $args: expr, $key: expr, if $name: expr) => {
         if $args.occurrences_of($name) > 0 {
-            $config.insert_json5($key, "true").unwrap();
+            insert_arg(&mut $config, $key, $name, serde_json::Value::Bool(true))?;
+        }
+    };
+    ($config: expr, $args: expr, $key: expr, if $name: expr, as $t: ty, $expected: expr) => {
+        if $args.occurrences_of($name) > 0 {
+            let value: $t = parse_arg($name, $args.value_of($name).unwrap(), $expected)?;
+            insert_arg(&mut $config, $key, $name, serde_json::json!(value))?;
         }
     };
-    ($config: expr, $args: expr, $key: expr, if $name: expr, $($t: tt)*) => {
+    ($config: expr, $args: expr, $key: expr, if $name: expr,) => {
         if $args.occurrences_of($name) > 0 {
-            $config.insert_json5($key, &serde_json::to_string(&$args.value_of($name).unwrap()$($t)*).unwrap()).unwrap();
+            let value = $args.value_of($name).unwrap();
+            insert_arg(&mut $config, $key, $name, serde_json::json!(value))?;
         }
     };
-    ($config: expr, $args: expr, $key: expr, for $name: expr, $($t: tt)*) => {
-        if let Some(value) = $args.values_of($name) {
-            $config.insert_json5($key, &serde_json::to_string(&value$($t)*).unwrap()).unwrap();
+    ($config: expr, $args: expr, $key: expr, for $name: expr) => {
+        if let Some(values) = $args.values_of($name) {
+            let values: Vec<&str> = values.collect();
+            insert_arg(&mut $config, $key, $name, serde_json::json!(values))?;
         }
     };
 }
 
-fn parse_args() -> (Config, Option<f32>) {
+// Parse the value of an argument, or return an error message such as:
+// "invalid value for --domain: 'abc' (expected integer)"
+fn parse_arg<T: FromStr>(name: &str, value: &str, expected: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| invalid_arg(name, value, expected))
+}
+
+// Parse an argument as a duration in seconds
+fn parse_seconds_arg(name: &str, value: &str) -> Result<Duration, String> {
+    let secs: f32 = parse_arg(name, value, "expected float")?;
+    Duration::try_from_secs_f32(secs)
+        .map_err(|_| invalid_arg(name, value, "expected a positive float"))
+}
+
+fn invalid_arg(name: &str, value: &str, expected: &str) -> String {
+    format!("invalid value for --{name}: '{value}' ({expected})")
+}
+
+// Insert the (JSON) value of an argument in the config
+fn insert_arg(
+    config: &mut Config,
+    key: &str,
+    name: &str,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    config
+        .insert_json5(key, &value.to_string())
+        .map_err(|e| format!("invalid value for --{name}: {value} ({e})"))
+}
+
+// Check the "<regex>=<float>[:<latest|first>]" format of a --max-frequency argument
+fn check_max_frequency(value: &str) -> Result<(), String> {
+    let expected = "expected <regex>=<float>[:<latest|first>]";
+    let (_, frequency) = value
+        .split_once('=')
+        .ok_or_else(|| invalid_arg("max-frequency", value, expected))?;
+    let frequency = match frequency.split_once(':') {
+        Some((frequency, "latest" | "first")) => frequency,
+        Some(_) => return Err(invalid_arg("max-frequency", value, expected)),
+        None => frequency,
+    };
+    match frequency.parse::<f32>() {
+        Ok(f) if f > 0.0 => Ok(()),
+        _ => Err(invalid_arg("max-frequency", value, expected)),
+    }
+}
+
+// Check the "<regex>=<DROP|BLOCK>" format of a --congestion-control argument
+fn check_congestion_control(value: &str) -> Result<(), String> {
+    match value.rsplit_once('=') {
+        Some((_, cc)) if cc.eq_ignore_ascii_case("drop") || cc.eq_ignore_ascii_case("block") => {
+            Ok(())
+        }
+        _ => Err(invalid_arg(
+            "congestion-control",
+            value,
+            "expected <regex>=<DROP|BLOCK>",
+        )),
+    }
+}
+
+// The format of the logs
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LogFormat {
+    // the human readable format of zenoh
+    Text,
+    // 1 JSON object per line, with the fields of the log events at top level (for logs ingestion systems)
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format '{s}' (expected text or json)")),
+        }
+    }
+}
+
+// The format of the logs: from --log-format, or from the Z_LOG_FORMAT environment variable (text by default)
+fn log_format_from_args(args: &ArgMatches, env_value: Option<&str>) -> Result<LogFormat, String> {
+    match (args.value_of("log-format"), env_value) {
+        (Some(format), _) => parse_arg("log-format", format, "expected text or json"),
+        (None, Some(format)) => format
+            .parse()
+            .map_err(|e| format!("invalid value for ${LOG_FORMAT_ENV_VAR}: {e}")),
+        (None, None) => Ok(LogFormat::Text),
+    }
+}
+
+// The timeout of the self-test run at startup, if --self-test is set
+fn self_test_from_args(args: &ArgMatches) -> Result<Option<Duration>, String> {
+    match args.value_of("self-test") {
+        Some(timeout) if args.is_present("self-test") => {
+            parse_seconds_arg("self-test", timeout).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+// Initialize the logs, with the RUST_LOG environment variable filtering the events ("z=info" if not set)
+fn init_logs(format: LogFormat) {
+    match format {
+        LogFormat::Text => zenoh_util::init_log_from_env_or("z=info"),
+        LogFormat::Json => {
+            let filter =
+                EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("z=info"));
+            tracing_subscriber::fmt()
+                .json()
+                .flatten_event(true)
+                .with_env_filter(filter)
+                .init();
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn parse_args() -> (
+    ArgMatches,
+    Config,
+    Option<WatchdogConfig>,
+    LogFormat,
+    Option<Duration>,
+) {
+    let args = build_app().get_matches();
+    let env_log_format = std::env::var(LOG_FORMAT_ENV_VAR).ok();
+    match config_from_args(&args).and_then(|(config, watchdog)| {
+        let log_format = log_format_from_args(&args, env_log_format.as_deref())?;
+        let self_test = self_test_from_args(&args)?;
+        Ok((config, watchdog, log_format, self_test))
+    }) {
+        Ok((config, watchdog, log_format, self_test)) => {
+            (args, config, watchdog, log_format, self_test)
+        }
+        Err(e) => {
+            // report the error as clap does for the arguments it validates itself
+            eprintln!("error: {e}");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn build_app() -> App<'static> {
     let mut app = App::new("zenoh bridge for DDS")
         .version(DDSPlugin::PLUGIN_VERSION)
         .long_version(DDSPlugin::PLUGIN_LONG_VERSION)
@@ -102,6 +274,18 @@ r#"-d, --domain=[ID]   'The DDS Domain ID. The default value is "$ROS_DOMAIN_ID"
 r#"--dds-localhost-only \
 'Configure CycloneDDS to use only the localhost interface. If not set, CycloneDDS will pick the interface defined in "$CYCLONEDDS_URI" configuration, or automatically choose one.
 This option is not active by default, unless the "ROS_LOCALHOST_ONLY" environment variable is set to "1".'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--dds-interface=[String]   'Configure CycloneDDS to use only this network interface, by name (e.g. "eth0") or by address (e.g. "192.168.1.10"). The interface must exist on the host. Ignored if --dds-localhost-only is set. If not set, CycloneDDS will pick the interface defined in "$CYCLONEDDS_URI" configuration, or automatically choose one.'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--dds-ipv6 'Configure CycloneDDS to use IPv6 (UDP/IPv6 transport) rather than IPv4.'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--dds-domain-tag=[String]   'Configure CycloneDDS with this domain tag: only the DDS participants with the same domain tag are discovered, isolating the bridges sharing the same network and domain ID. If not set, the domain tag defined in "$CYCLONEDDS_URI" configuration applies (if any).'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--cyclonedds-config=[XML]   'A raw CycloneDDS XML configuration fragment (e.g. "<CycloneDDS><Domain><General><MaxMessageSize>65500B</MaxMessageSize></General></Domain></CycloneDDS>"), applied when creating the DDS participant. It takes precedence over the "$CYCLONEDDS_URI" configuration, which takes precedence over the --dds-localhost-only, --dds-interface, --dds-ipv6, --dds-domain-tag and --dds-enable-shm settings.'"#
         ));
 
     // Add option to enable DDS SHM if feature is enabled
@@ -122,22 +306,34 @@ r#"--group-member-id=[ID]   'A custom identifier for the bridge, that will be us
 r#"-a, --allow=[String]...   'A regular expression matching the set of 'partition/topic-name' that must be routed via zenoh. By default, all partitions and topics are allowed.
 If both '--allow' and '--deny' are set a partition and/or topic will be allowed if it matches only the 'allow' expression.
 Repeat this option to configure several topic expressions. These expressions are concatenated with '|'.
-Examples of expressions: '.*/TopicA', 'Partition-?/.*', 'cmd_vel|rosout'...'"#
+An expression prefixed with 'type:' matches the DDS type name instead.
+Examples of expressions: '.*/TopicA', 'Partition-?/.*', 'cmd_vel|rosout', 'type:sensor_msgs::msg::dds_::.*'...'"#
         ))
         .arg(Arg::from_usage(
 r#"--deny=[String]...   'A regular expression matching the set of 'partition/topic-name' that must not be routed via zenoh. By default, no partitions and no topics are denied.
 If both '--allow' and '--deny' are set a partition and/or topic will be allowed if it matches only the 'allow' expression.
 Repeat this option to configure several topic expressions. These expressions are concatenated with '|'.
-Examples of expressions: '.*/TopicA', 'Partition-?/.*', 'cmd_vel|rosout'...'"#
+An expression prefixed with 'type:' matches the DDS type name instead. An entity matched by a 'deny' expression is never routed.
+Examples of expressions: '.*/TopicA', 'Partition-?/.*', 'cmd_vel|rosout', 'type:rcl_interfaces::msg::dds_::ParameterEvent_'...'"#
         ))
         .arg(Arg::from_usage(
-r#"--max-frequency=[String]...   'Specifies a maximum frequency of data routing over zenoh for a set of topics. The string must have the format "<regex>=<float>":
+r#"--max-frequency=[String]...   'Specifies a maximum frequency of data routing over zenoh for a set of topics. The string must have the format "<regex>=<float>[:<strategy>]":
   - "regex" is a regular expression matching the set of 'partition/topic-name' (same syntax than --allow option)
     for which the data (per DDS instance) must be routed at no higher rate than the specified max frequency.
   - "float" is the maximum frequency in Hertz; if publication rate is higher, downsampling will occur when routing.
+  - "strategy" is either "latest" (default: the latest sample per period is routed at the end of the period)
+    or "first" (the first sample per period is routed as soon as received, the others being dropped).
 Repeat this option to configure several topics expressions with a max frequency.'"#
         ))
         .arg(Arg::from_usage(
+r#"--congestion-control=[String]...   'Specifies the zenoh congestion control of the data routed from DDS for a set of topics. The string must have the format "<regex>=<DROP|BLOCK>":
+  - "regex" is a regular expression matching the set of 'partition/topic-name' (same syntax than --allow option)
+    for which the specified congestion control applies.
+  - "DROP" drops the publications if the network is congested, while "BLOCK" blocks until they can be sent.
+The topics not matching any expression are routed with BLOCK if their DDS Writer is RELIABLE (and 'reliable_routes_blocking' is not disabled), with DROP otherwise.
+Repeat this option to configure several topics expressions with a congestion control.'"#
+        ))
+        .arg(Arg::from_usage(
 r#"-r, --generalise-sub=[String]...   'A list of key expression to use for generalising subscriptions (usable multiple times).'"#
         ))
         .arg(Arg::from_usage(
@@ -153,13 +349,40 @@ queries any other remote bridge for discovery information and for historical dat
 (i.e. if the query to the remote bridge exceed the timeout, some historical samples might be not routed to the Readers, but the route will not be blocked forever)."#
         ))
         .arg(Arg::from_usage(
-r#"--watchdog=[PERIOD]   'Experimental!! Run a watchdog thread that monitors the bridge's async executor and reports as error log any stalled status during the specified period (default: 1.0 second)'"#
-        ).default_missing_value("1.0"));
-    let args = app.get_matches();
+r#"--dry-run   'Run the DDS discovery, but only log the routes that would be created (with their zenoh key expression), without routing any data. Useful to validate the --allow, --deny and --max-frequency expressions against a live system.'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--metrics-port=[PORT]   'Configures an HTTP endpoint exposing the bridge metrics in the Prometheus text format on "http://<host>:<PORT>/metrics" (disabled by default, setting this option enables it).'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--metrics-address=[IP]   'The IP address the metrics endpoint (see --metrics-port) listens on (default: 127.0.0.1). Use "0.0.0.0" to listen on all network interfaces.'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--log-format=[FORMAT]   'The format of the logs: "text" (default) or "json" (1 JSON object per line, for logs ingestion systems). If not set, the Z_LOG_FORMAT environment variable is used.'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--watchdog=[PERIOD]   'Experimental!! Run a watchdog thread that monitors the bridge's async executor and reports as error log any stalled status during the specified period (default: 1.0 second). The stalls are also counted in the DDS plugin heartbeat.'"#
+        ).default_missing_value("1.0"))
+        .arg(Arg::from_usage(
+r#"--watchdog-info-threshold=[SECONDS]   'The duration without task scheduling from which the watchdog reports a stall as info log (default: 0.01 second)'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--watchdog-warn-threshold=[SECONDS]   'The duration without task scheduling from which the watchdog reports a stall as warning log (default: 0.1 second). It must be lower than the watchdog period.'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--watchdog-max-sleep-delta=[SECONDS]   'The max delay of the watchdog thread's wake up, beyond which a warning is logged (default: 0.05 second)'"#
+        ))
+        .arg(Arg::from_usage(
+r#"--self-test=[TIMEOUT]   'Run a self-test at startup and exit: a temporary DDS Writer and DDS Reader are created on the "zenoh_bridge_dds_self_test_<zid>" topic, and the bridge must route them and receive the written sample via zenoh within the timeout (default: 10.0 seconds). The result is logged as PASS or FAIL, the exit code being 1 on failure. Useful for health checks and CI gating of a deployed bridge.'"#
+        ).default_missing_value("10.0"));
+    app
+}
 
+fn config_from_args(args: &ArgMatches) -> Result<(Config, Option<WatchdogConfig>), String> {
     // load config file at first
     let mut config = match args.value_of("config") {
-        Some(conf_file) => Config::from_file(conf_file).unwrap(),
+        Some(conf_file) => Config::from_file(conf_file)
+            .map_err(|e| format!("invalid value for --config: '{conf_file}' ({e})"))?,
         None => Config::default(),
     };
     // if "dds" plugin conf is not present, add it (empty to use default config)
@@ -170,34 +393,47 @@ r#"--watchdog=[PERIOD]   'Experimental!! Run a watchdog thread that monitors the
     // apply zenoh related arguments over config
     // NOTE: only if args.occurrences_of()>0 to avoid overriding config with the default arg value
     if args.occurrences_of("id") > 0 {
+        let id = args.value_of("id").unwrap();
+        let expected = "expected an hexadecimal string of 32 chars maximum";
+        let zid: ZenohId = parse_arg("id", id, expected)?;
         config
-            .set_id(ZenohId::from_str(args.value_of("id").unwrap()).unwrap())
-            .unwrap();
+            .set_id(zid)
+            .map_err(|_| invalid_arg("id", id, expected))?;
     }
     if args.occurrences_of("mode") > 0 {
+        let mode = args.value_of("mode").unwrap();
         config
-            .set_mode(Some(args.value_of("mode").unwrap().parse().unwrap()))
-            .unwrap();
+            .set_mode(Some(parse_arg("mode", mode, "expected peer or client")?))
+            .map_err(|_| invalid_arg("mode", mode, "expected peer or client"))?;
     }
     if let Some(endpoints) = args.values_of("connect") {
-        config
-            .connect
-            .endpoints
-            .extend(endpoints.map(|p| p.parse().unwrap()))
+        for endpoint in endpoints {
+            config.connect.endpoints.push(parse_arg(
+                "connect",
+                endpoint,
+                "expected an endpoint such as tcp/192.168.1.1:7447",
+            )?);
+        }
     }
     if let Some(endpoints) = args.values_of("listen") {
-        config
-            .listen
-            .endpoints
-            .extend(endpoints.map(|p| p.parse().unwrap()))
+        for endpoint in endpoints {
+            config.listen.endpoints.push(parse_arg(
+                "listen",
+                endpoint,
+                "expected an endpoint such as tcp/0.0.0.0:7447",
+            )?);
+        }
     }
     if args.is_present("no-multicast-scouting") {
         config.scouting.multicast.set_enabled(Some(false)).unwrap();
     }
     if let Some(port) = args.value_of("rest-http-port") {
-        config
-            .insert_json5("plugins/rest/http_port", &format!(r#""{port}""#))
-            .unwrap();
+        insert_arg(
+            &mut config,
+            "plugins/rest/http_port",
+            "rest-http-port",
+            serde_json::json!(port),
+        )?;
     }
     // Always add timestamps to publications (required for PublicationCache used in case of TRANSIENT_LOCAL topics)
     config
@@ -211,44 +447,72 @@ r#"--watchdog=[PERIOD]   'Experimental!! Run a watchdog thread that monitors the
 
     // apply DDS related arguments over config
     insert_json5!(config, args, "plugins/dds/scope", if "scope",);
-    insert_json5!(config, args, "plugins/dds/domain", if "domain", .parse::<u64>().unwrap());
+    insert_json5!(config, args, "plugins/dds/domain", if "domain", as u32, "expected integer");
     insert_json5!(config, args, "plugins/dds/localhost_only", if "dds-localhost-only");
+    insert_json5!(config, args, "plugins/dds/interface", if "dds-interface",);
+    insert_json5!(config, args, "plugins/dds/ipv6", if "dds-ipv6");
+    insert_json5!(config, args, "plugins/dds/domain_tag", if "dds-domain-tag",);
+    insert_json5!(config, args, "plugins/dds/cyclonedds_xml", if "cyclonedds-config",);
     #[cfg(feature = "dds_shm")]
     {
         insert_json5!(config, args, "plugins/dds/shm_enabled", if "dds-enable-shm");
     }
     insert_json5!(config, args, "plugins/dds/group_member_id", if "group-member-id", );
-    insert_json5!(config, args, "plugins/dds/allow", for "allow", .collect::<Vec<_>>());
-    insert_json5!(config, args, "plugins/dds/deny", for "deny", .collect::<Vec::<_>>());
-    insert_json5!(config, args, "plugins/dds/max_frequencies", for "max-frequency", .collect::<Vec<_>>());
-    insert_json5!(config, args, "plugins/dds/generalise_pubs", for "generalise-pub", .collect::<Vec<_>>());
-    insert_json5!(config, args, "plugins/dds/generalise_subs", for "generalise-sub", .collect::<Vec<_>>());
-    insert_json5!(config, args, "plugins/dds/queries_timeout", if "queries-timeout", .parse::<f64>().unwrap());
+    insert_json5!(config, args, "plugins/dds/allow", for "allow");
+    insert_json5!(config, args, "plugins/dds/deny", for "deny");
+    for value in args.values_of("max-frequency").into_iter().flatten() {
+        check_max_frequency(value)?;
+    }
+    insert_json5!(config, args, "plugins/dds/max_frequencies", for "max-frequency");
+    for value in args.values_of("congestion-control").into_iter().flatten() {
+        check_congestion_control(value)?;
+    }
+    insert_json5!(config, args, "plugins/dds/congestion_control", for "congestion-control");
+    insert_json5!(config, args, "plugins/dds/generalise_pubs", for "generalise-pub");
+    insert_json5!(config, args, "plugins/dds/generalise_subs", for "generalise-sub");
+    insert_json5!(config, args, "plugins/dds/queries_timeout", if "queries-timeout", as f64, "expected float");
+    insert_json5!(config, args, "plugins/dds/metrics_port", if "metrics-port", as u16, "expected port number");
+    insert_json5!(config, args, "plugins/dds/metrics_address", if "metrics-address", as IpAddr, "expected IP address");
+    insert_json5!(config, args, "plugins/dds/dry_run", if "dry-run");
     if args.is_present("fwd-discovery") {
         config
             .insert_json5("plugins/dds/forward_discovery", "true")
             .unwrap();
     }
 
-    let watchdog_period = if args.is_present("watchdog") {
-        args.value_of("watchdog").map(|s| s.parse::<f32>().unwrap())
-    } else {
-        None
+    let watchdog = match args.value_of("watchdog") {
+        Some(period) if args.is_present("watchdog") => {
+            let mut watchdog = WatchdogConfig::new(parse_seconds_arg("watchdog", period)?);
+            if let Some(value) = args.value_of("watchdog-info-threshold") {
+                watchdog.report_threshold_1 = parse_seconds_arg("watchdog-info-threshold", value)?;
+            }
+            if let Some(value) = args.value_of("watchdog-warn-threshold") {
+                watchdog.report_threshold_2 = parse_seconds_arg("watchdog-warn-threshold", value)?;
+            }
+            if let Some(value) = args.value_of("watchdog-max-sleep-delta") {
+                watchdog.max_sleep_delta = parse_seconds_arg("watchdog-max-sleep-delta", value)?;
+            }
+            watchdog
+                .validate()
+                .map_err(|e| format!("invalid watchdog configuration: {e}"))?;
+            Some(watchdog)
+        }
+        _ => None,
     };
 
-    (config, watchdog_period)
+    Ok((config, watchdog))
 }
 
 #[async_std::main]
 async fn main() {
-    zenoh_util::init_log_from_env_or("z=info");
+    let (args, config, watchdog, log_format, self_test) = parse_args();
+    init_logs(log_format);
     tracing::info!("zenoh-bridge-dds {}", DDSPlugin::PLUGIN_LONG_VERSION);
-
-    let (config, watchdog_period) = parse_args();
     tracing::info!("Zenoh {config:?}");
 
-    if let Some(period) = watchdog_period {
-        run_watchdog(period);
+    if let Some(watchdog) = watchdog {
+        // the stalls are counted in the DDS plugin heartbeat, for a remote supervisor to react
+        run_watchdog(watchdog, zenoh_plugin_dds::report_watchdog_stall);
     }
 
     let mut plugins_mgr = PluginsManager::static_plugins_only();
@@ -280,54 +544,245 @@ async fn main() {
         std::process::exit(-1);
     }
 
-    async_std::future::pending::<()>().await;
+    if let Some(timeout) = self_test {
+        let exit_code = match zenoh_plugin_dds::self_test(timeout).await {
+            Ok(()) => {
+                tracing::info!("Self-test PASS");
+                0
+            }
+            Err(e) => {
+                tracing::error!("Self-test FAIL: {e}");
+                SELF_TEST_FAILURE_EXIT_CODE
+            }
+        };
+        shutdown_dds_plugin().await;
+        std::process::exit(exit_code);
+    }
+
+    // wait for a termination signal, then shutdown the DDS plugin gracefully (deleting its DDS entities)
+    let terminate = Arc::new(AtomicBool::new(false));
+    for signal in signal_hook::consts::TERM_SIGNALS {
+        if let Err(e) = signal_hook::flag::register(*signal, terminate.clone()) {
+            tracing::warn!("Failed to register handler for signal {signal}: {e}");
+        }
+    }
+    // on SIGHUP, reload the DDS plugin configuration from the configuration file and the arguments
+    let reload = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    if let Err(e) = signal_hook::flag::register(signal_hook::consts::SIGHUP, reload.clone()) {
+        tracing::warn!("Failed to register handler for signal SIGHUP: {e}");
+    }
+    while !terminate.load(Ordering::Relaxed) {
+        async_std::task::sleep(SIGNAL_CHECK_PERIOD).await;
+        if reload.swap(false, Ordering::Relaxed) {
+            reload_dds_config(&args).await;
+        }
+    }
+    tracing::info!("Termination signal received: shutting down...");
+    shutdown_dds_plugin().await;
 }
 
-fn run_watchdog(period: f32) {
-    let sleep_time = Duration::from_secs_f32(period);
-    // max delta accepted for watchdog thread sleep period
-    let max_sleep_delta = Duration::from_millis(50);
-    // 1st threshold of duration since last report => debug info if exceeded
-    let report_threshold_1 = Duration::from_millis(10);
-    // 2nd threshold of duration since last report => debug warn if exceeded
-    let report_threshold_2 = Duration::from_millis(100);
-
-    assert!(
-        sleep_time > report_threshold_2,
-        "Watchdog period must be greater than {} seconds",
-        report_threshold_2.as_secs_f32()
-    );
-
-    // Start a Liveliness Monitor thread for async_std Runtime
-    let (_task, monitor) = LivelinessMonitor::start(async_std::task::spawn);
-    std::thread::spawn(move || {
-        tracing::debug!(
-            "Watchdog started with period {} sec",
-            sleep_time.as_secs_f32()
+async fn shutdown_dds_plugin() {
+    if !zenoh_plugin_dds::shutdown(SHUTDOWN_TIMEOUT).await {
+        tracing::warn!(
+            "DDS plugin shutdown not completed within {SHUTDOWN_TIMEOUT:?} - exiting anyway"
         );
-        loop {
-            let before = SystemTime::now();
-            std::thread::sleep(sleep_time);
-            let elapsed = SystemTime::now().duration_since(before).unwrap();
-
-            // Monitor watchdog thread itself
-            if elapsed > sleep_time + max_sleep_delta {
-                tracing::warn!(
-                    "Watchdog thread slept more than configured: {} seconds",
-                    elapsed.as_secs_f32()
-                );
-            }
-            // check last LivelinessMonitor's report
-            let report = monitor.latest_report();
-            if report.elapsed() > report_threshold_1 {
-                if report.elapsed() > sleep_time {
-                    tracing::error!("Watchdog detecting async_std is stalled! No task scheduling since {} seconds", report.elapsed().as_secs_f32());
-                } else if report.elapsed() > report_threshold_2 {
-                    tracing::warn!("Watchdog detecting async_std was not scheduling tasks during the last {} ms", report.elapsed().as_micros());
-                } else {
-                    tracing::info!("Watchdog detecting async_std was not scheduling tasks during the last {} ms", report.elapsed().as_micros());
-                }
+    }
+}
+
+async fn reload_dds_config(args: &ArgMatches) {
+    tracing::info!("SIGHUP received: reloading the DDS plugin configuration...");
+    let config = match config_from_args(args) {
+        Ok((config, _)) => config,
+        Err(e) => {
+            tracing::error!("Failed to reload the configuration: {e}");
+            return;
+        }
+    };
+    match config.plugin("dds") {
+        Some(plugin_conf) => {
+            if let Err(e) = zenoh_plugin_dds::reload(plugin_conf, RELOAD_TIMEOUT).await {
+                tracing::error!("Failed to reload the DDS plugin configuration: {e}");
             }
         }
-    });
+        None => tracing::error!("Failed to reload the configuration: no 'plugins/dds' section"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_app, config_from_args, log_format_from_args, self_test_from_args, LogFormat,
+        WatchdogConfig,
+    };
+    use std::time::Duration;
+
+    // Return the error message of the arguments parsing
+    fn parse_error(args: &[&str]) -> String {
+        let matches = build_app()
+            .try_get_matches_from(std::iter::once("zenoh-bridge-dds").chain(args.iter().copied()))
+            .unwrap();
+        match config_from_args(&matches) {
+            Ok(_) => panic!("{args:?} should be invalid"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn test_valid_args() {
+        let matches = build_app()
+            .try_get_matches_from([
+                "zenoh-bridge-dds",
+                "-i",
+                "0a0b23",
+                "-d",
+                "42",
+                "-e",
+                "tcp/192.168.1.1:7447",
+                "--max-frequency",
+                "rt/.*=10.5",
+                "--queries-timeout",
+                "2.5",
+                "--watchdog",
+            ])
+            .unwrap();
+        let (_, watchdog) = config_from_args(&matches).unwrap();
+        assert_eq!(watchdog, Some(WatchdogConfig::new(Duration::from_secs(1))));
+
+        let matches = build_app()
+            .try_get_matches_from([
+                "zenoh-bridge-dds",
+                "--watchdog",
+                "2",
+                "--watchdog-info-threshold",
+                "0.05",
+                "--watchdog-warn-threshold",
+                "0.5",
+                "--watchdog-max-sleep-delta",
+                "0.2",
+            ])
+            .unwrap();
+        let (_, watchdog) = config_from_args(&matches).unwrap();
+        assert_eq!(
+            watchdog,
+            Some(WatchdogConfig {
+                period: Duration::from_secs(2),
+                max_sleep_delta: Duration::from_millis(200),
+                report_threshold_1: Duration::from_millis(50),
+                report_threshold_2: Duration::from_millis(500),
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_args() {
+        assert_eq!(
+            parse_error(&["--domain", "abc"]),
+            "invalid value for --domain: 'abc' (expected integer)"
+        );
+        assert_eq!(
+            parse_error(&["-i", "xyz"]),
+            "invalid value for --id: 'xyz' (expected an hexadecimal string of 32 chars maximum)"
+        );
+        assert_eq!(
+            parse_error(&["-e", "192.168.1.1"]),
+            "invalid value for --connect: '192.168.1.1' (expected an endpoint such as tcp/192.168.1.1:7447)"
+        );
+        assert_eq!(
+            parse_error(&["--max-frequency", "rt/.*"]),
+            "invalid value for --max-frequency: 'rt/.*' (expected <regex>=<float>[:<latest|first>])"
+        );
+        assert_eq!(
+            parse_error(&["--max-frequency", "rt/a=1", "--max-frequency", "rt/b=fast"]),
+            "invalid value for --max-frequency: 'rt/b=fast' (expected <regex>=<float>[:<latest|first>])"
+        );
+        assert_eq!(
+            parse_error(&["--max-frequency", "rt/a=1:first", "--max-frequency", "rt/b=1:last"]),
+            "invalid value for --max-frequency: 'rt/b=1:last' (expected <regex>=<float>[:<latest|first>])"
+        );
+        assert_eq!(
+            parse_error(&["--congestion-control", "rt/scan=fast"]),
+            "invalid value for --congestion-control: 'rt/scan=fast' (expected <regex>=<DROP|BLOCK>)"
+        );
+        assert_eq!(
+            parse_error(&["--queries-timeout", "soon"]),
+            "invalid value for --queries-timeout: 'soon' (expected float)"
+        );
+        assert_eq!(
+            parse_error(&["--metrics-port", "99999"]),
+            "invalid value for --metrics-port: '99999' (expected port number)"
+        );
+        assert_eq!(
+            parse_error(&["--metrics-address", "localhost"]),
+            "invalid value for --metrics-address: 'localhost' (expected IP address)"
+        );
+        assert_eq!(
+            parse_error(&["--watchdog", "1s"]),
+            "invalid value for --watchdog: '1s' (expected float)"
+        );
+        assert_eq!(
+            parse_error(&["--watchdog", "1", "--watchdog-warn-threshold", "-1"]),
+            "invalid value for --watchdog-warn-threshold: '-1' (expected a positive float)"
+        );
+        assert_eq!(
+            parse_error(&["--watchdog", "0.1"]),
+            "invalid watchdog configuration: the watchdog period must be greater than the warn threshold (0.1 seconds)"
+        );
+    }
+
+    #[test]
+    fn test_log_format() {
+        let matches = |args: &[&str]| {
+            build_app()
+                .try_get_matches_from(
+                    std::iter::once("zenoh-bridge-dds").chain(args.iter().copied()),
+                )
+                .unwrap()
+        };
+        assert_eq!(
+            log_format_from_args(&matches(&[]), None),
+            Ok(LogFormat::Text)
+        );
+        assert_eq!(
+            log_format_from_args(&matches(&[]), Some("json")),
+            Ok(LogFormat::Json)
+        );
+        // the argument takes precedence over the environment variable
+        assert_eq!(
+            log_format_from_args(&matches(&["--log-format", "text"]), Some("json")),
+            Ok(LogFormat::Text)
+        );
+        assert_eq!(
+            log_format_from_args(&matches(&["--log-format", "xml"]), None),
+            Err("invalid value for --log-format: 'xml' (expected text or json)".to_string())
+        );
+        assert_eq!(
+            log_format_from_args(&matches(&[]), Some("yaml")),
+            Err(
+                "invalid value for $Z_LOG_FORMAT: unknown log format 'yaml' (expected text or json)"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_self_test() {
+        let matches = |args: &[&str]| {
+            build_app()
+                .try_get_matches_from(
+                    std::iter::once("zenoh-bridge-dds").chain(args.iter().copied()),
+                )
+                .unwrap()
+        };
+        assert_eq!(self_test_from_args(&matches(&[])), Ok(None));
+        assert_eq!(
+            self_test_from_args(&matches(&["--self-test"])),
+            Ok(Some(Duration::from_secs(10)))
+        );
+        assert_eq!(
+            self_test_from_args(&matches(&["--self-test=2.5"])),
+            Ok(Some(Duration::from_millis(2500)))
+        );
+        assert!(self_test_from_args(&matches(&["--self-test=abc"])).is_err());
+    }
 }
@@ -0,0 +1,160 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The experimental watchdog thread monitoring the async executor of the bridge (see --watchdog argument): each
+// stall detected is logged and reported to a callback, for a supervisor to react (e.g. alert or restart).
+use async_liveliness_monitor::LivelinessMonitor;
+use std::time::{Duration, SystemTime};
+use zenoh_plugin_dds::{StallReport, StallSeverity};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct WatchdogConfig {
+    // the period of the checks: no task scheduling during a period is reported as an error
+    pub(crate) period: Duration,
+    // max delta accepted for watchdog thread sleep period
+    pub(crate) max_sleep_delta: Duration,
+    // 1st threshold of duration since last report => reported as info if exceeded
+    pub(crate) report_threshold_1: Duration,
+    // 2nd threshold of duration since last report => reported as warning if exceeded
+    pub(crate) report_threshold_2: Duration,
+}
+
+impl WatchdogConfig {
+    pub(crate) const DEFAULT_MAX_SLEEP_DELTA: Duration = Duration::from_millis(50);
+    pub(crate) const DEFAULT_REPORT_THRESHOLD_1: Duration = Duration::from_millis(10);
+    pub(crate) const DEFAULT_REPORT_THRESHOLD_2: Duration = Duration::from_millis(100);
+
+    pub(crate) fn new(period: Duration) -> Self {
+        WatchdogConfig {
+            period,
+            max_sleep_delta: Self::DEFAULT_MAX_SLEEP_DELTA,
+            report_threshold_1: Self::DEFAULT_REPORT_THRESHOLD_1,
+            report_threshold_2: Self::DEFAULT_REPORT_THRESHOLD_2,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.report_threshold_1 > self.report_threshold_2 {
+            return Err(format!(
+                "the info threshold ({} seconds) must not be greater than the warn threshold ({} seconds)",
+                self.report_threshold_1.as_secs_f32(),
+                self.report_threshold_2.as_secs_f32()
+            ));
+        }
+        if self.period <= self.report_threshold_2 {
+            return Err(format!(
+                "the watchdog period must be greater than the warn threshold ({} seconds)",
+                self.report_threshold_2.as_secs_f32()
+            ));
+        }
+        Ok(())
+    }
+
+    // Return the severity of a duration without task scheduling, or None if it's not a stall
+    fn stall_severity(&self, elapsed: Duration) -> Option<StallSeverity> {
+        if elapsed > self.period {
+            Some(StallSeverity::Error)
+        } else if elapsed > self.report_threshold_2 {
+            Some(StallSeverity::Warn)
+        } else if elapsed > self.report_threshold_1 {
+            Some(StallSeverity::Info)
+        } else {
+            None
+        }
+    }
+}
+
+// Start the watchdog thread, calling on_stall for each stall detected (after its log)
+pub(crate) fn run_watchdog<F>(config: WatchdogConfig, on_stall: F)
+where
+    F: Fn(StallReport) + Send + 'static,
+{
+    let sleep_time = config.period;
+
+    // Start a Liveliness Monitor thread for async_std Runtime
+    let (_task, monitor) = LivelinessMonitor::start(async_std::task::spawn);
+    std::thread::spawn(move || {
+        tracing::debug!(
+            "Watchdog started with period {} sec",
+            sleep_time.as_secs_f32()
+        );
+        loop {
+            let before = SystemTime::now();
+            std::thread::sleep(sleep_time);
+            let elapsed = SystemTime::now().duration_since(before).unwrap();
+
+            // Monitor watchdog thread itself
+            if elapsed > sleep_time + config.max_sleep_delta {
+                tracing::warn!(
+                    "Watchdog thread slept more than configured: {} seconds",
+                    elapsed.as_secs_f32()
+                );
+            }
+            // check last LivelinessMonitor's report
+            let report = monitor.latest_report();
+            // make it observable remotely in the DDS plugin heartbeat
+            zenoh_plugin_dds::report_watchdog(report.elapsed());
+            let elapsed = report.elapsed();
+            if let Some(severity) = config.stall_severity(elapsed) {
+                match severity {
+                    StallSeverity::Error => tracing::error!("Watchdog detecting async_std is stalled! No task scheduling since {} seconds", elapsed.as_secs_f32()),
+                    StallSeverity::Warn => tracing::warn!("Watchdog detecting async_std was not scheduling tasks during the last {} ms", elapsed.as_micros()),
+                    StallSeverity::Info => tracing::info!("Watchdog detecting async_std was not scheduling tasks during the last {} ms", elapsed.as_micros()),
+                }
+                on_stall(StallReport { elapsed, severity });
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WatchdogConfig;
+    use std::time::Duration;
+    use zenoh_plugin_dds::StallSeverity;
+
+    #[test]
+    fn test_stall_severity() {
+        let config = WatchdogConfig::new(Duration::from_secs(1));
+        assert!(config.validate().is_ok());
+        let severity = |millis| config.stall_severity(Duration::from_millis(millis));
+        assert_eq!(severity(5), None);
+        assert_eq!(severity(50), Some(StallSeverity::Info));
+        assert_eq!(severity(500), Some(StallSeverity::Warn));
+        assert_eq!(severity(1500), Some(StallSeverity::Error));
+
+        let config = WatchdogConfig {
+            report_threshold_1: Duration::from_millis(100),
+            report_threshold_2: Duration::from_millis(400),
+            ..WatchdogConfig::new(Duration::from_secs(2))
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.stall_severity(Duration::from_millis(50)), None);
+        assert_eq!(
+            config.stall_severity(Duration::from_millis(300)),
+            Some(StallSeverity::Info)
+        );
+
+        // the period must be greater than the warn threshold, itself not lower than the info threshold
+        assert!(WatchdogConfig::new(Duration::from_millis(100))
+            .validate()
+            .is_err());
+        assert!(WatchdogConfig {
+            report_threshold_1: Duration::from_millis(500),
+            ..WatchdogConfig::new(Duration::from_secs(1))
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The samples routed from DDS to zenoh accumulated in a batch (see 'batch' configuration), until the batch
+// is full (max_count samples) or its oldest sample waited for max_latency: the batch is then published as
+// a single zenoh payload.
+use std::time::{Duration, Instant};
+
+use crate::config::BatchConfig;
+
+#[derive(Debug)]
+pub(crate) struct SampleBatch {
+    max_count: usize,
+    max_latency: Duration,
+    // the serialized samples in their order of reception, with their source timestamp
+    samples: Vec<(i64, Vec<u8>)>,
+    // the time the oldest sample of the batch was added
+    oldest: Option<Instant>,
+}
+
+impl SampleBatch {
+    pub(crate) fn new(config: BatchConfig) -> SampleBatch {
+        SampleBatch {
+            max_count: config.max_count,
+            max_latency: config.max_latency,
+            samples: Vec::with_capacity(config.max_count),
+            oldest: None,
+        }
+    }
+
+    // Add a sample at time "now", returning true if the batch is full and must be published
+    pub(crate) fn push(&mut self, source_timestamp: i64, data: Vec<u8>, now: Instant) -> bool {
+        self.oldest.get_or_insert(now);
+        self.samples.push((source_timestamp, data));
+        self.samples.len() >= self.max_count
+    }
+
+    // Return the time the batch must be published at the latest, or None if it's empty
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.oldest.map(|oldest| oldest + self.max_latency)
+    }
+
+    // Return true if the oldest sample of the batch waited for max_latency at time "now"
+    pub(crate) fn is_due(&self, now: Instant) -> bool {
+        self.deadline().map_or(false, |deadline| deadline <= now)
+    }
+
+    // Take the samples of the batch to be published, leaving it empty
+    pub(crate) fn take(&mut self) -> Vec<(i64, Vec<u8>)> {
+        self.oldest = None;
+        std::mem::replace(&mut self.samples, Vec::with_capacity(self.max_count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SampleBatch;
+    use crate::config::BatchConfig;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_sample_batch() {
+        let mut batch = SampleBatch::new(BatchConfig {
+            max_count: 3,
+            max_latency: Duration::from_millis(10),
+        });
+        let start = Instant::now();
+        assert_eq!(batch.deadline(), None);
+        assert!(!batch.is_due(start + Duration::from_secs(1)));
+        assert!(batch.take().is_empty());
+
+        // the deadline is set by the oldest sample
+        assert!(!batch.push(1, vec![1], start));
+        assert!(!batch.push(2, vec![2], start + Duration::from_millis(5)));
+        assert_eq!(batch.deadline(), Some(start + Duration::from_millis(10)));
+        assert!(!batch.is_due(start + Duration::from_millis(9)));
+        assert!(batch.is_due(start + Duration::from_millis(10)));
+
+        // the batch is full at max_count samples, which are taken in their order of reception
+        assert!(batch.push(3, vec![3], start + Duration::from_millis(6)));
+        assert_eq!(batch.take(), vec![(1, vec![1]), (2, vec![2]), (3, vec![3])]);
+        assert_eq!(batch.deadline(), None);
+
+        // a new batch starts with the next sample
+        assert!(!batch.push(4, vec![4], start + Duration::from_millis(20)));
+        assert_eq!(batch.deadline(), Some(start + Duration::from_millis(30)));
+        assert_eq!(batch.take(), vec![(4, vec![4])]);
+    }
+}
@@ -0,0 +1,440 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The decoding of a serialized sample to JSON, for debugging. The sample is decoded walking the serialization
+// instructions ("ops") of its type, as found in the topic descriptor created by Cyclone DDS from the type
+// information (see dds_opcodes.h in Cyclone DDS).
+//
+// As the names of the members are not part of the ops, a struct is represented as the array of its members
+// values, in their declaration order. An enum is represented by its integer value.
+// The unions, the optional members, the bitmasks and the mutable types are not supported.
+use crate::sample_hook::{CdrReader, FieldType, FieldValue};
+use serde_json::Value;
+
+// The opcodes (bits 24-31 of an op)
+const DDS_OP_MASK: u32 = 0xff00_0000;
+const DDS_OP_RTS: u32 = 0x0000_0000;
+const DDS_OP_ADR: u32 = 0x0100_0000;
+const DDS_OP_JSR: u32 = 0x0200_0000;
+const DDS_OP_DLC: u32 = 0x0400_0000;
+
+// The type codes of a DDS_OP_ADR op (bits 16-23 for the type, bits 8-15 for the elements type of a collection)
+const DDS_OP_VAL_1BY: u32 = 0x01;
+const DDS_OP_VAL_2BY: u32 = 0x02;
+const DDS_OP_VAL_4BY: u32 = 0x03;
+const DDS_OP_VAL_8BY: u32 = 0x04;
+const DDS_OP_VAL_STR: u32 = 0x05;
+const DDS_OP_VAL_BST: u32 = 0x06;
+const DDS_OP_VAL_SEQ: u32 = 0x07;
+const DDS_OP_VAL_ARR: u32 = 0x08;
+const DDS_OP_VAL_STU: u32 = 0x0a;
+const DDS_OP_VAL_BSQ: u32 = 0x0b;
+const DDS_OP_VAL_ENU: u32 = 0x0c;
+const DDS_OP_VAL_EXT: u32 = 0x0d;
+const DDS_OP_VAL_BLN: u32 = 0x0e;
+
+// The flags of a DDS_OP_ADR op (bits 0-7), describing the elements for a collection
+const DDS_OP_FLAG_FP: u32 = 1 << 1;
+const DDS_OP_FLAG_SGN: u32 = 1 << 2;
+const DDS_OP_FLAG_OPT: u32 = 1 << 4;
+
+// The max nesting of the types (protecting from recursive types)
+const MAX_DEPTH: usize = 32;
+
+// Decode a serialized sample (including its CDR header) to JSON, given the ops of its type
+pub(crate) fn cdr_to_json(ops: &[u32], data: &[u8]) -> Result<Value, String> {
+    if data.len() < 4 {
+        return Err(format!("truncated sample ({} bytes)", data.len()));
+    }
+    let reader = CdrReader::from_encapsulation(&data[0..2], &data[4..]).ok_or_else(|| {
+        format!(
+            "unsupported encapsulation {:02x?} (only the plain and delimited CDR are supported)",
+            &data[0..2]
+        )
+    })?;
+    let mut decoder = Decoder { ops, reader };
+    let mut members = Vec::new();
+    decoder.decode_struct(0, 0, &mut members)?;
+    Ok(Value::Array(members))
+}
+
+// Return the leading members of the type described by ops that are of a primitive type (including enums) or strings,
+// up to the first member of another type: the fields decoded for the sample hook (see FieldsLayout::from_type_ops()).
+// As the names of the members are not part of the ops, the fields are named after their index in declaration order.
+pub(crate) fn leading_fields(ops: &[u32]) -> Vec<(String, FieldType)> {
+    let mut fields = Vec::new();
+    let mut i = 0;
+    while let Some(&op) = ops.get(i) {
+        match op & DDS_OP_MASK {
+            // an appendable type (its DHEADER is skipped by FieldsLayout::decode())
+            DDS_OP_DLC if i == 0 => i += 1,
+            DDS_OP_ADR if op & DDS_OP_FLAG_OPT == 0 => {
+                let type_code = (op >> 16) & 0xff;
+                let ty = match primitive_type(type_code, op & 0xff) {
+                    Some(ty) => ty,
+                    None => break,
+                };
+                fields.push((fields.len().to_string(), ty));
+                // the string bound, or the enum max value, follows the member offset
+                i += match type_code {
+                    DDS_OP_VAL_BST | DDS_OP_VAL_ENU => 3,
+                    _ => 2,
+                };
+            }
+            _ => break,
+        }
+    }
+    fields
+}
+
+struct Decoder<'a, 'b> {
+    ops: &'a [u32],
+    reader: CdrReader<'b>,
+}
+
+// Return the index of the op at a relative jump from the op at index i
+fn jump(i: usize, offset: i16) -> Result<usize, String> {
+    i.checked_add_signed(offset as isize)
+        .ok_or_else(|| format!("invalid jump {offset} from op {i}"))
+}
+
+// Return the field type of a primitive type code, given the flags of its op
+fn primitive_type(type_code: u32, flags: u32) -> Option<FieldType> {
+    let signed = flags & DDS_OP_FLAG_SGN != 0;
+    let float = flags & DDS_OP_FLAG_FP != 0;
+    Some(match type_code {
+        DDS_OP_VAL_1BY if signed => FieldType::Int8,
+        DDS_OP_VAL_1BY => FieldType::UInt8,
+        DDS_OP_VAL_2BY if signed => FieldType::Int16,
+        DDS_OP_VAL_2BY => FieldType::UInt16,
+        DDS_OP_VAL_4BY if float => FieldType::Float32,
+        DDS_OP_VAL_4BY if signed => FieldType::Int32,
+        DDS_OP_VAL_4BY => FieldType::UInt32,
+        DDS_OP_VAL_8BY if float => FieldType::Float64,
+        DDS_OP_VAL_8BY if signed => FieldType::Int64,
+        DDS_OP_VAL_8BY => FieldType::UInt64,
+        DDS_OP_VAL_BLN => FieldType::Bool,
+        DDS_OP_VAL_ENU => FieldType::UInt32,
+        DDS_OP_VAL_STR | DDS_OP_VAL_BST => FieldType::String,
+        _ => return None,
+    })
+}
+
+// The strings and the constructed types are not primitive (their collections have a DHEADER with XCDR2)
+fn is_primitive(type_code: u32) -> bool {
+    !matches!(primitive_type(type_code, 0), None | Some(FieldType::String))
+}
+
+fn to_json(value: FieldValue) -> Value {
+    match value {
+        FieldValue::Bool(b) => Value::Bool(b),
+        FieldValue::Int(i) => i.into(),
+        FieldValue::UInt(u) => u.into(),
+        FieldValue::Float(f) => f.into(),
+        FieldValue::String(s) => Value::String(s),
+    }
+}
+
+impl Decoder<'_, '_> {
+    fn op(&self, i: usize) -> Result<u32, String> {
+        self.ops
+            .get(i)
+            .copied()
+            .ok_or_else(|| format!("invalid type ops: no op at index {i}"))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        self.reader
+            .read_u32()
+            .ok_or_else(|| "truncated sample".into())
+    }
+
+    fn read_primitive(&mut self, ty: FieldType) -> Result<Value, String> {
+        self.reader
+            .read_value(ty)
+            .map(to_json)
+            .ok_or_else(|| "truncated sample".into())
+    }
+
+    // Decode the members of the struct whose ops start at index i (up to DDS_OP_RTS)
+    fn decode_struct(
+        &mut self,
+        mut i: usize,
+        depth: usize,
+        members: &mut Vec<Value>,
+    ) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err(format!("types nested deeper than {MAX_DEPTH} levels"));
+        }
+        loop {
+            let op = self.op(i)?;
+            match op & DDS_OP_MASK {
+                DDS_OP_RTS => return Ok(()),
+                DDS_OP_ADR => {
+                    let (value, next) = self.decode_member(i, depth)?;
+                    members.push(value);
+                    i = next;
+                }
+                DDS_OP_JSR => {
+                    // the members of a base type
+                    self.decode_struct(jump(i, op as u16 as i16)?, depth + 1, members)?;
+                    i += 1;
+                }
+                DDS_OP_DLC => {
+                    // an appendable type, with a DHEADER in XCDR2
+                    if self.reader.is_xcdr2() {
+                        self.read_u32()?;
+                    }
+                    i += 1;
+                }
+                _ => {
+                    return Err(format!(
+                        "unsupported op {op:#010x} (mutable types are not supported)"
+                    ))
+                }
+            }
+        }
+    }
+
+    // Decode the member described by the DDS_OP_ADR op at index i, returning its value and the index of the next op
+    fn decode_member(&mut self, i: usize, depth: usize) -> Result<(Value, usize), String> {
+        let op = self.op(i)?;
+        if op & DDS_OP_FLAG_OPT != 0 {
+            return Err(format!("unsupported optional member (op {op:#010x})"));
+        }
+        let type_code = (op >> 16) & 0xff;
+        let subtype_code = (op >> 8) & 0xff;
+        let flags = op & 0xff;
+        match type_code {
+            // the string bound follows the member offset
+            DDS_OP_VAL_BST => Ok((self.read_primitive(FieldType::String)?, i + 3)),
+            // the max value follows the member offset
+            DDS_OP_VAL_ENU => Ok((self.read_primitive(FieldType::UInt32)?, i + 3)),
+            DDS_OP_VAL_SEQ | DDS_OP_VAL_BSQ => {
+                // the sequence bound follows the member offset
+                let j = if type_code == DDS_OP_VAL_BSQ {
+                    i + 3
+                } else {
+                    i + 2
+                };
+                if self.reader.is_xcdr2() && !is_primitive(subtype_code) {
+                    self.read_u32()?;
+                }
+                let len = self.read_u32()? as usize;
+                self.decode_elements(i, j, subtype_code, flags, len, depth)
+            }
+            DDS_OP_VAL_ARR => {
+                let len = self.op(i + 2)? as usize;
+                if self.reader.is_xcdr2() && !is_primitive(subtype_code) {
+                    self.read_u32()?;
+                }
+                self.decode_elements(i, i + 3, subtype_code, flags, len, depth)
+            }
+            DDS_OP_VAL_EXT => {
+                // a nested struct: its ops are at a relative jump, and the next op at the jump in the upper bits
+                let jumps = self.op(i + 2)?;
+                let mut members = Vec::new();
+                self.decode_struct(jump(i, jumps as u16 as i16)?, depth + 1, &mut members)?;
+                Ok((Value::Array(members), i + (jumps >> 16) as usize))
+            }
+            _ => match primitive_type(type_code, flags) {
+                Some(ty) => Ok((self.read_primitive(ty)?, i + 2)),
+                None => Err(format!("unsupported member type (op {op:#010x})")),
+            },
+        }
+    }
+
+    // Decode the len elements of a collection described by the DDS_OP_ADR op at index i,
+    // the ops describing the elements type starting at index j
+    fn decode_elements(
+        &mut self,
+        i: usize,
+        j: usize,
+        subtype_code: u32,
+        flags: u32,
+        len: usize,
+        depth: usize,
+    ) -> Result<(Value, usize), String> {
+        // each element takes at least 1 byte: a greater length is a corrupted sample (not to be iterated over)
+        if len > self.reader.remaining() {
+            return Err(format!(
+                "invalid collection length {len} ({} bytes remaining)",
+                self.reader.remaining()
+            ));
+        }
+        let mut elements = Vec::with_capacity(len);
+        match subtype_code {
+            DDS_OP_VAL_SEQ | DDS_OP_VAL_BSQ | DDS_OP_VAL_ARR | DDS_OP_VAL_STU | DDS_OP_VAL_EXT => {
+                // the elements ops are at a relative jump, and the next op at the jump in the upper bits
+                // (after the elements size for a sequence, before it for an array)
+                let jumps = if (self.op(i)? >> 16) & 0xff == DDS_OP_VAL_ARR {
+                    self.op(j)?
+                } else {
+                    self.op(j + 1)?
+                };
+                let elem_ops = jump(i, jumps as u16 as i16)?;
+                for _ in 0..len {
+                    if subtype_code == DDS_OP_VAL_STU || subtype_code == DDS_OP_VAL_EXT {
+                        let mut members = Vec::new();
+                        self.decode_struct(elem_ops, depth + 1, &mut members)?;
+                        elements.push(Value::Array(members));
+                    } else {
+                        elements.push(self.decode_member(elem_ops, depth + 1)?.0);
+                    }
+                }
+                Ok((Value::Array(elements), i + (jumps >> 16) as usize))
+            }
+            _ => {
+                let ty = primitive_type(subtype_code, flags).ok_or_else(|| {
+                    format!(
+                        "unsupported elements type (op {:#010x})",
+                        self.op(i).unwrap_or(0)
+                    )
+                })?;
+                for _ in 0..len {
+                    elements.push(self.read_primitive(ty)?);
+                }
+                // the strings bound, or the enum max value, follows
+                let next = match subtype_code {
+                    DDS_OP_VAL_BST if (self.op(i)? >> 16) & 0xff == DDS_OP_VAL_ARR => j + 2,
+                    DDS_OP_VAL_BST | DDS_OP_VAL_ENU => j + 1,
+                    _ => j,
+                };
+                Ok((Value::Array(elements), next))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cdr_to_json, leading_fields, DDS_OP_ADR, DDS_OP_DLC, DDS_OP_FLAG_FP, DDS_OP_FLAG_SGN,
+        DDS_OP_RTS, DDS_OP_VAL_1BY, DDS_OP_VAL_2BY, DDS_OP_VAL_4BY, DDS_OP_VAL_8BY, DDS_OP_VAL_ARR,
+        DDS_OP_VAL_BLN, DDS_OP_VAL_SEQ, DDS_OP_VAL_STR, DDS_OP_VAL_STU,
+    };
+    use crate::sample_hook::FieldType;
+
+    const ADR: u32 = DDS_OP_ADR;
+    const fn ty(type_code: u32) -> u32 {
+        type_code << 16
+    }
+    const fn subty(type_code: u32) -> u32 {
+        type_code << 8
+    }
+
+    #[test]
+    fn test_cdr_to_json() {
+        // struct Point { double x; double y; };
+        // struct Shape { int32 id; string name; sequence<Point> points; sequence<uint8> data; boolean ok; int16 level[2]; };
+        let ops = [
+            // Shape
+            ADR | ty(DDS_OP_VAL_4BY) | DDS_OP_FLAG_SGN,
+            0,
+            ADR | ty(DDS_OP_VAL_STR),
+            8,
+            ADR | ty(DDS_OP_VAL_SEQ) | subty(DDS_OP_VAL_STU),
+            16,
+            16,
+            (4 << 16) + 12,
+            ADR | ty(DDS_OP_VAL_SEQ) | subty(DDS_OP_VAL_1BY),
+            32,
+            ADR | ty(DDS_OP_VAL_BLN),
+            48,
+            ADR | ty(DDS_OP_VAL_ARR) | subty(DDS_OP_VAL_2BY) | DDS_OP_FLAG_SGN,
+            50,
+            2,
+            DDS_OP_RTS,
+            // Point
+            ADR | ty(DDS_OP_VAL_8BY) | DDS_OP_FLAG_FP,
+            0,
+            ADR | ty(DDS_OP_VAL_8BY) | DDS_OP_FLAG_FP,
+            8,
+            DDS_OP_RTS,
+        ];
+
+        // XCDR1 little endian: the doubles are aligned on 8 bytes
+        let mut data = vec![0x00, 0x01, 0x00, 0x00];
+        data.extend_from_slice(&(-7i32).to_le_bytes());
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(b"ab\0");
+        data.extend_from_slice(&[0]);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&1.5f64.to_le_bytes());
+        data.extend_from_slice(&(-2.0f64).to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&[0xca, 0xfe]);
+        data.extend_from_slice(&[1]);
+        data.extend_from_slice(&[0]);
+        data.extend_from_slice(&(-1i16).to_le_bytes());
+        data.extend_from_slice(&2i16.to_le_bytes());
+
+        assert_eq!(
+            cdr_to_json(&ops, &data).unwrap(),
+            serde_json::json!([-7, "ab", [[1.5, -2.0]], [202, 254], true, [-1, 2]])
+        );
+
+        // truncated sample, unsupported encapsulation (XCDR2 parameter list) and invalid ops
+        assert!(cdr_to_json(&ops, &data[..data.len() - 1]).is_err());
+        assert!(cdr_to_json(&ops, &[0x00, 0x0a, 0x00, 0x00]).is_err());
+        assert!(cdr_to_json(&ops[..4], &data).is_err());
+
+        // a sequence length exceeding the sample size
+        let mut corrupted = data[..16].to_vec();
+        corrupted.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(cdr_to_json(&ops, &corrupted)
+            .unwrap_err()
+            .contains("invalid collection length"));
+
+        // the fields decoded for the sample hook stop at the 1st non-primitive member
+        assert_eq!(
+            leading_fields(&ops),
+            vec![
+                ("0".to_string(), FieldType::Int32),
+                ("1".to_string(), FieldType::String)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cdr_to_json_xcdr2() {
+        // @appendable struct Status { uint32 code; sequence<string> messages; };
+        let ops = [
+            DDS_OP_DLC,
+            ADR | ty(DDS_OP_VAL_4BY),
+            0,
+            ADR | ty(DDS_OP_VAL_SEQ) | subty(DDS_OP_VAL_STR),
+            8,
+            DDS_OP_RTS,
+        ];
+
+        // delimited XCDR2 big endian: DHEADER for the struct, and for the sequence of strings
+        let mut data = vec![0x00, 0x08, 0x00, 0x00];
+        data.extend_from_slice(&18u32.to_be_bytes());
+        data.extend_from_slice(&42u32.to_be_bytes());
+        data.extend_from_slice(&10u32.to_be_bytes());
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(b"x\0");
+
+        assert_eq!(
+            cdr_to_json(&ops, &data).unwrap(),
+            serde_json::json!([42, ["x"]])
+        );
+        assert_eq!(
+            leading_fields(&ops),
+            vec![("0".to_string(), FieldType::UInt32)]
+        );
+    }
+}
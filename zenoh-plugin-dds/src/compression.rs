@@ -0,0 +1,147 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The compression of the payloads routed from DDS to zenoh (see 'compress' configuration). A compressed payload
+// is tagged with a "dds_compression" attachment naming its algorithm ("lz4" or "zstd"): the receiving bridge
+// decompresses it before writing it to DDS, whatever its own configuration. The payload's encoding is unchanged.
+use zenoh::sample::Attachment;
+
+use crate::config::Compression;
+
+// The key of the zenoh attachment naming the algorithm that compressed the payload of a sample routed from DDS
+pub(crate) const COMPRESSION_ATTACHMENT_KEY: &str = "dds_compression";
+
+// The zstd compression level (0 meaning zstd's default level, i.e. 3)
+const ZSTD_LEVEL: i32 = 0;
+
+pub(crate) fn compress(compression: Compression, data: &[u8]) -> Result<Vec<u8>, String> {
+    match compression {
+        // the uncompressed size is prepended (or written in the zstd frame header),
+        // to allocate the decompression buffer at once
+        Compression::Lz4 => Ok(lz4_flex::compress_prepend_size(data)),
+        Compression::Zstd => zstd::bulk::compress(data, ZSTD_LEVEL).map_err(|e| e.to_string()),
+    }
+}
+
+// Decompress a payload received from zenoh. As it's untrusted, the decompression fails if the payload's size once
+// decompressed is above max_size (see max_decompressed_size configuration), without allocating more than max_size.
+pub(crate) fn decompress(
+    compression: Compression,
+    data: &[u8],
+    max_size: usize,
+) -> Result<Vec<u8>, String> {
+    let too_large =
+        |size: u64| format!("decompressed size of {size} bytes above the max of {max_size} bytes");
+    match compression {
+        Compression::Lz4 => {
+            let (size, data) = match data.get(..4) {
+                Some(prefix) => (
+                    u32::from_le_bytes(prefix.try_into().unwrap()) as usize,
+                    &data[4..],
+                ),
+                None => return Err("missing uncompressed size prefix".into()),
+            };
+            if size > max_size {
+                return Err(too_large(size as u64));
+            }
+            lz4_flex::decompress(data, size).map_err(|e| e.to_string())
+        }
+        Compression::Zstd => {
+            // the buffer is allocated with the size written in the frame header (if any)
+            let capacity = match zstd::zstd_safe::get_frame_content_size(data) {
+                Ok(Some(size)) if size > max_size as u64 => return Err(too_large(size)),
+                Ok(Some(size)) => size as usize,
+                _ => max_size,
+            };
+            zstd::bulk::decompress(data, capacity).map_err(|e| e.to_string())
+        }
+    }
+}
+
+// Return the compression of a sample's payload named by its attachment, if any
+// (or an error if the algorithm is unknown, e.g. if supported only by a more recent bridge)
+pub(crate) fn get_compression(attachment: &Attachment) -> Option<Result<Compression, String>> {
+    let bytes = attachment.get(&COMPRESSION_ATTACHMENT_KEY)?;
+    Some(
+        std::str::from_utf8(&bytes[..])
+            .map_err(|e| e.to_string())
+            .and_then(str::parse),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compress, decompress};
+    use crate::config::Compression;
+
+    const MAX_SIZE: usize = 1024 * 1024;
+
+    #[test]
+    fn test_compression() {
+        // a CDR-like payload, with a lot of padding
+        let data: Vec<u8> = [0x00, 0x01, 0x00, 0x00]
+            .into_iter()
+            .chain((0..1000u32).flat_map(|i| (i % 10).to_le_bytes()))
+            .collect();
+        for compression in [Compression::Lz4, Compression::Zstd] {
+            let compressed = compress(compression, &data).unwrap();
+            assert!(compressed.len() < data.len() / 4);
+            assert_eq!(
+                decompress(compression, &compressed, MAX_SIZE).unwrap(),
+                data
+            );
+            // a truncated payload can't be decompressed
+            assert!(
+                decompress(compression, &compressed[..compressed.len() / 2], MAX_SIZE).is_err()
+            );
+            // nor a payload larger than the max once decompressed
+            assert!(decompress(compression, &compressed, data.len() - 1).is_err());
+            assert_eq!(
+                decompress(compression, &compressed, data.len()).unwrap(),
+                data
+            );
+        }
+        assert_eq!(
+            decompress(
+                Compression::Lz4,
+                &compress(Compression::Lz4, &[]).unwrap(),
+                MAX_SIZE
+            )
+            .unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn test_decompression_bounds() {
+        // an lz4 payload whose size prefix claims ~4 GiB is rejected without allocating it
+        let mut oversized = u32::MAX.to_le_bytes().to_vec();
+        oversized.extend_from_slice(&lz4_flex::compress(b"tiny"));
+        assert!(decompress(Compression::Lz4, &oversized, MAX_SIZE).is_err());
+        // as well as an lz4 payload not matching its size prefix
+        let mut lying = 4u32.to_le_bytes().to_vec();
+        lying.extend_from_slice(&lz4_flex::compress(&[0u8; 1000]));
+        assert!(decompress(Compression::Lz4, &lying, MAX_SIZE).is_err());
+        assert!(decompress(Compression::Lz4, &[0, 0], MAX_SIZE).is_err());
+
+        // a zstd bomb (a few KiB decompressing to 64 MiB), with or without its size in the frame header
+        let bomb = vec![0u8; 64 * 1024 * 1024];
+        let with_size = zstd::bulk::compress(&bomb, 0).unwrap();
+        let without_size = zstd::encode_all(&bomb[..], 0).unwrap();
+        for compressed in [with_size, without_size] {
+            assert!(compressed.len() < 64 * 1024);
+            assert!(decompress(Compression::Zstd, &compressed, MAX_SIZE).is_err());
+        }
+    }
+}
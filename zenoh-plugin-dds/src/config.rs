@@ -11,292 +11,3986 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
-use regex::Regex;
+use cyclors::qos::Qos;
+use regex::{Regex, RegexBuilder};
 use serde::de::Visitor;
-use serde::{de, Deserialize, Deserializer};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::env;
 use std::fmt;
+use std::net::IpAddr;
 use std::time::Duration;
 use zenoh::prelude::*;
+use zenoh::publication::{CongestionControl, Priority};
+
+use crate::qos_helpers::{validate_qos, OVERRIDABLE_QOS_POLICIES};
+use crate::sample_hook::{FieldType, FieldValue, FieldsLayout};
 
 pub const DEFAULT_DOMAIN: u32 = 0;
 pub const DEFAULT_FORWARD_DISCOVERY: bool = false;
 pub const DEFAULT_RELIABLE_ROUTES_BLOCKING: bool = true;
+pub const DEFAULT_VALIDATE_FORWARDED_QOS: bool = true;
 pub const DEFAULT_QUERIES_TIMEOUT: f32 = 5.0;
+#[cfg(feature = "zenoh_shm")]
+pub const DEFAULT_ZENOH_SHM_SIZE: usize = 64 * 1024 * 1024;
+pub const DEFAULT_QUERIES_MAX_RETRIES: u32 = 0;
+pub const DEFAULT_QUERIES_RETRY_BACKOFF: f32 = 0.5;
+pub const DEFAULT_HISTORICAL_DATA_TIMEOUT: f32 = 0.1;
 pub const DEFAULT_DDS_LOCALHOST_ONLY: bool = false;
+pub const DEFAULT_SLA_CHECK_PERIOD: f32 = 1.0;
+pub const DEFAULT_STATS_STATE_SAVE_PERIOD: f32 = 10.0;
+pub const DEFAULT_METRICS_ADDRESS: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+pub const DEFAULT_ROUTE_CREATION_PERIOD: f32 = 0.1;
+pub const DEFAULT_LAST_SAMPLE_PREVIEW_MAX_BYTES: usize = 64;
+pub const DEFAULT_DISCOVERY_MAX_SAMPLES: usize = 32;
+pub const DEFAULT_HEARTBEAT_PERIOD: f32 = 5.0;
+pub const DEFAULT_PAYLOAD_LOG_MAX_SIZE: u64 = 100 * 1024 * 1024;
+pub const DEFAULT_PARTICIPANT_CHECK_PERIOD: f32 = 5.0;
+pub const DEFAULT_PARTICIPANT_REDISCOVERY_TIMEOUT: f32 = 30.0;
+pub const DEFAULT_ROUTE_CREATION_MAX_ATTEMPTS: u32 = 5;
+pub const DEFAULT_ENTITY_NAME_PREFIX: &str = "zenoh-bridge-dds";
+pub const DEFAULT_ASYNC_FORWARD_QUEUE_SIZE: usize = 1024;
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
 
 #[derive(Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_scope")]
     pub scope: Option<OwnedKeyExpr>,
     #[serde(default = "default_domain")]
     pub domain: u32,
     #[serde(default)]
     pub group_member_id: Option<OwnedKeyExpr>,
+    #[serde(default, deserialize_with = "deserialize_entity_matcher")]
+    pub allow: Option<EntityMatcher>,
+    #[serde(default, deserialize_with = "deserialize_entity_matcher")]
+    pub deny: Option<EntityMatcher>,
+    #[serde(default)]
+    pub filter_precedence: FilterPrecedence,
+    #[serde(default)]
+    pub partition_filter: PartitionFilter,
+    #[serde(default, deserialize_with = "deserialize_partition_rewrite")]
+    pub partition_rewrite: Option<(Regex, String)>,
+    #[serde(default)]
+    pub topic_normalization: TopicNormalization,
+    #[serde(default, deserialize_with = "deserialize_keyexpr_template")]
+    pub partition_keyexpr_template: Option<KeyExprTemplate>,
+    #[serde(default, deserialize_with = "deserialize_topic_keyexpr_map")]
+    pub topic_keyexpr_map: Vec<(Regex, String)>,
+    #[serde(default, deserialize_with = "deserialize_compatible_types")]
+    pub compatible_types: Vec<(String, String)>,
+    #[serde(default)]
+    pub route_setup: RouteSetup,
+    #[serde(default)]
+    pub route_creation_limit: Option<RouteCreationLimit>,
+    #[serde(
+        default = "default_route_creation_max_attempts",
+        deserialize_with = "deserialize_route_creation_max_attempts"
+    )]
+    pub route_creation_max_attempts: u32,
+    #[serde(default, deserialize_with = "deserialize_max_routes")]
+    pub max_routes: Option<usize>,
+    #[serde(default)]
+    pub max_routes_eviction: RouteEviction,
+    #[serde(default = "default_keyless_conflict")]
+    pub keyless_conflict: KeylessConflict,
+    #[serde(default = "default_directions")]
+    pub directions: Vec<RouteDirection>,
+    #[serde(
+        default = "default_discovery_max_samples",
+        deserialize_with = "deserialize_discovery_max_samples"
+    )]
+    pub discovery_max_samples: usize,
+    #[serde(default, deserialize_with = "deserialize_discovery_queue_capacity")]
+    pub discovery_queue_capacity: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_opt_duration")]
+    pub put_timeout: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_put_timeouts")]
+    pub put_timeouts: Vec<(Regex, f32)>,
+    #[serde(default, deserialize_with = "deserialize_max_frequencies")]
+    pub max_frequencies: Vec<(Regex, f32, DownsamplingStrategy)>,
+    #[serde(default, deserialize_with = "deserialize_downsampling_depths")]
+    pub downsampling_depths: Vec<(Regex, i32)>,
+    #[serde(default, deserialize_with = "deserialize_batch")]
+    pub batch: Vec<(Regex, BatchConfig)>,
     #[serde(default, deserialize_with = "deserialize_regex")]
-    pub allow: Option<Regex>,
+    pub express: Option<Regex>,
     #[serde(default, deserialize_with = "deserialize_regex")]
-    pub deny: Option<Regex>,
-    #[serde(default, deserialize_with = "deserialize_max_frequencies")]
-    pub max_frequencies: Vec<(Regex, f32)>,
+    pub keyed_instance_routing: Option<Regex>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub async_forward: Option<Regex>,
+    #[serde(
+        default = "default_async_forward_queue_size",
+        deserialize_with = "deserialize_async_forward_queue_size"
+    )]
+    pub async_forward_queue_size: usize,
+    #[serde(default)]
+    pub async_forward_overflow: QueueOverflow,
+    #[serde(default, deserialize_with = "deserialize_compress")]
+    pub compress: Vec<(Regex, Compression)>,
+    #[serde(default = "default_max_decompressed_size")]
+    pub max_decompressed_size: usize,
+    #[serde(default, deserialize_with = "deserialize_delivery_modes")]
+    pub delivery_modes: Vec<(Regex, DeliveryMode)>,
+    #[serde(default, deserialize_with = "deserialize_transient_local_max_samples")]
+    pub transient_local_max_samples: Vec<(Regex, usize)>,
+    #[serde(default, deserialize_with = "deserialize_priorities")]
+    pub priorities: Vec<(Regex, Priority)>,
+    #[serde(default, deserialize_with = "deserialize_congestion_controls")]
+    pub congestion_control: Vec<(Regex, CongestionControl)>,
+    #[serde(
+        default = "default_transport_priority_mapping",
+        deserialize_with = "deserialize_transport_priority_mapping"
+    )]
+    pub transport_priority_mapping: Vec<(i32, Priority)>,
+    #[serde(default, deserialize_with = "deserialize_payload_filters")]
+    pub payload_filters: Vec<(Regex, PayloadMatcher)>,
+    #[serde(default, deserialize_with = "deserialize_content_filter")]
+    pub content_filter: Vec<(Regex, ContentFilter)>,
+    #[serde(default, deserialize_with = "deserialize_qos_overrides")]
+    pub qos_overrides: Vec<(Regex, Qos)>,
+    #[serde(default)]
+    pub forwarding_profiles: HashMap<String, ForwardingProfile>,
+    #[serde(default, deserialize_with = "deserialize_topic_forwarding_profiles")]
+    pub topic_forwarding_profiles: Vec<(Regex, String)>,
+    #[serde(default)]
+    pub sla_thresholds: Vec<SlaThresholds>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub last_sample_preview: Option<Regex>,
+    #[serde(default = "default_last_sample_preview_max_bytes")]
+    pub last_sample_preview_max_bytes: usize,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub coalesce_writers: Option<Regex>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub force_keyless: Option<Regex>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub force_keyed: Option<Regex>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub allow_best_effort: Option<Regex>,
+    #[serde(
+        default = "default_sla_check_period",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub sla_check_period: Duration,
     #[serde(default)]
     pub generalise_subs: Vec<OwnedKeyExpr>,
     #[serde(default)]
     pub generalise_pubs: Vec<OwnedKeyExpr>,
+    #[serde(default)]
+    pub generalise_overlap_error: bool,
     #[serde(default = "default_forward_discovery")]
     pub forward_discovery: bool,
+    #[serde(default)]
+    pub allowed_peers: HashSet<String>,
+    #[serde(default)]
+    pub dead_letters_key: Option<OwnedKeyExpr>,
     #[serde(default = "default_reliable_routes_blocking")]
     pub reliable_routes_blocking: bool,
+    #[serde(default)]
+    pub preserve_source_timestamp: bool,
+    #[serde(default)]
+    pub admin_deny_updates: bool,
+    #[serde(default)]
+    pub dry_run: bool,
     #[serde(default = "default_localhost_only")]
     pub localhost_only: bool,
+    #[serde(default, deserialize_with = "deserialize_interface")]
+    pub interface: Option<DdsInterface>,
+    #[serde(default)]
+    pub ipv6: bool,
+    #[serde(default)]
+    pub domain_tag: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_cyclonedds_xml")]
+    pub cyclonedds_xml: Option<String>,
     #[serde(default)]
     #[cfg(feature = "dds_shm")]
     pub shm_enabled: bool,
+    #[serde(default = "default_zenoh_shm_size")]
+    #[cfg(feature = "zenoh_shm")]
+    pub zenoh_shm_size: usize,
     #[serde(
         default = "default_queries_timeout",
         deserialize_with = "deserialize_duration"
     )]
     pub queries_timeout: Duration,
+    #[serde(default = "default_queries_max_retries")]
+    pub queries_max_retries: u32,
+    #[serde(
+        default = "default_queries_retry_backoff",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub queries_retry_backoff: Duration,
+    #[serde(
+        default = "default_historical_data_timeout",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub historical_data_timeout: Duration,
+    #[serde(default)]
+    pub admin_route_qos: bool,
+    #[serde(default)]
+    pub admin_query_default_limit: Option<usize>,
+    #[serde(default)]
+    pub matched_liveliness: bool,
+    #[serde(default)]
+    pub route_liveliness: bool,
+    #[serde(default)]
+    pub deadline_missed_events: bool,
+    #[serde(default)]
+    pub check_qos_compatibility: bool,
+    #[serde(default = "default_validate_forwarded_qos")]
+    pub validate_forwarded_qos: bool,
+    #[serde(default, deserialize_with = "deserialize_opt_duration")]
+    pub route_ack_timeout: Option<Duration>,
+    #[serde(
+        default,
+        rename = "topic_aliases_file",
+        deserialize_with = "deserialize_topic_aliases"
+    )]
+    pub topic_aliases: HashMap<String, String>,
+    #[serde(
+        default,
+        rename = "manifest_file",
+        deserialize_with = "deserialize_manifest"
+    )]
+    pub manifest: Vec<ManifestEntry>,
+    #[serde(default, deserialize_with = "deserialize_regex")]
+    pub prime_writers: Option<Regex>,
+    #[serde(default)]
+    pub topic_types: HashMap<String, String>,
+    #[serde(default)]
+    pub stats_state_file: Option<String>,
+    #[serde(
+        default = "default_stats_state_save_period",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub stats_state_save_period: Duration,
+    #[serde(default)]
+    pub metrics_port: Option<u16>,
+    #[serde(default = "default_metrics_address")]
+    pub metrics_address: IpAddr,
+    #[serde(default)]
+    pub heartbeat_key: Option<OwnedKeyExpr>,
+    #[serde(
+        default = "default_heartbeat_period",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub heartbeat_period: Duration,
+    #[serde(default)]
+    pub payload_log_file: Option<String>,
+    #[serde(
+        default = "default_payload_log_max_size",
+        deserialize_with = "deserialize_payload_log_max_size"
+    )]
+    pub payload_log_max_size: u64,
+    #[serde(
+        default = "default_participant_check_period",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub participant_check_period: Duration,
+    #[serde(
+        default = "default_participant_rediscovery_timeout",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub participant_rediscovery_timeout: Duration,
+    #[serde(default = "default_entity_name_prefix")]
+    pub entity_name_prefix: String,
+    #[serde(default)]
+    pub case_insensitive_matching: bool,
     __required__: Option<bool>,
     #[serde(default, deserialize_with = "deserialize_path")]
     __path__: Option<Vec<String>>,
 }
 
-fn default_domain() -> u32 {
-    if let Ok(s) = env::var("ROS_DOMAIN_ID") {
-        s.parse::<u32>().unwrap_or(DEFAULT_DOMAIN)
-    } else {
-        DEFAULT_DOMAIN
+// A filter on the set of partitions of a DDS entity.
+// An entity without partition is considered as being in the default partition: "".
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PartitionFilter {
+    // if not empty, the entity must be in at least one of those partitions
+    #[serde(default)]
+    pub any_of: HashSet<String>,
+    // the entity must be in all of those partitions
+    #[serde(default)]
+    pub all_of: HashSet<String>,
+    // the entity must be in none of those partitions
+    #[serde(default)]
+    pub none_of: HashSet<String>,
+}
+
+impl PartitionFilter {
+    pub fn is_allowed(&self, partitions: &Option<Vec<String>>) -> bool {
+        let default_partition = [String::new()];
+        let partitions = match partitions.as_deref() {
+            None | Some([]) => &default_partition[..],
+            Some(p) => p,
+        };
+        (self.any_of.is_empty() || partitions.iter().any(|p| self.any_of.contains(p)))
+            && self.all_of.iter().all(|p| partitions.contains(p))
+            && !partitions.iter().any(|p| self.none_of.contains(p))
     }
 }
 
-fn deserialize_path<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    deserializer.deserialize_option(OptPathVisitor)
+// The 'allow' or 'deny' expressions matching the DDS entities: the ones prefixed with "type:" match the
+// type name of the entities, the others match their 'partition/topic-name'.
+#[derive(Debug, Clone)]
+pub struct EntityMatcher {
+    pub topic: Option<Regex>,
+    pub type_name: Option<Regex>,
 }
 
-struct OptPathVisitor;
+const TYPE_MATCHER_PREFIX: &str = "type:";
 
-impl<'de> serde::de::Visitor<'de> for OptPathVisitor {
-    type Value = Option<Vec<String>>;
+impl EntityMatcher {
+    pub fn new<S: AsRef<str>>(exprs: &[S]) -> Result<Self, String> {
+        let mut topic_exprs = Vec::new();
+        let mut type_exprs = Vec::new();
+        for expr in exprs.iter().map(AsRef::as_ref) {
+            match expr.strip_prefix(TYPE_MATCHER_PREFIX) {
+                Some(type_expr) => type_exprs.push(type_expr),
+                None => topic_exprs.push(expr),
+            }
+        }
+        let to_regex = |exprs: Vec<&str>| -> Result<Option<Regex>, String> {
+            if exprs.is_empty() {
+                return Ok(None);
+            }
+            let s = concat_regex_exprs(&exprs);
+            topic_regex(&s)
+                .map(Some)
+                .map_err(|e| format!("Invalid regex '{s}': {e}"))
+        };
+        Ok(EntityMatcher {
+            topic: to_regex(topic_exprs)?,
+            type_name: to_regex(type_exprs)?,
+        })
+    }
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(formatter, "none or a string or an array of strings")
+    // Return true if either the 'partition/topic-name' or the type name of an entity is matched
+    pub fn is_match(&self, ke: &str, type_name: &str) -> bool {
+        self.topic.as_ref().map_or(false, |re| re.is_match(ke))
+            || self
+                .type_name
+                .as_ref()
+                .map_or(false, |re| re.is_match(type_name))
     }
 
-    fn visit_none<E>(self) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(None)
+    // Re-compile the topic and type name regular expressions as case-insensitive
+    pub fn make_case_insensitive(&mut self) {
+        self.topic.iter_mut().for_each(make_case_insensitive);
+        self.type_name.iter_mut().for_each(make_case_insensitive);
     }
+}
 
-    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+// Serialized as a string if there are only topic expressions (as before the "type:" prefix was supported),
+// or as the list of the topic expression and of the prefixed type expression otherwise.
+impl Serialize for EntityMatcher {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
-        D: Deserializer<'de>,
+        S: serde::Serializer,
     {
-        deserializer.deserialize_any(PathVisitor).map(Some)
+        match (&self.topic, &self.type_name) {
+            (Some(topic), None) => serializer.serialize_str(topic.as_str()),
+            _ => {
+                let exprs: Vec<String> = self
+                    .topic
+                    .iter()
+                    .map(|re| re.to_string())
+                    .chain(
+                        self.type_name
+                            .iter()
+                            .map(|re| format!("{TYPE_MATCHER_PREFIX}{re}")),
+                    )
+                    .collect();
+                exprs.serialize(serializer)
+            }
+        }
     }
 }
 
-struct PathVisitor;
+// The normalization of the discovered topic names, applied before their mapping to key expressions
+// (and thus before the allow/deny decisions). All the steps are disabled by default.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TopicNormalization {
+    // remove the leading and trailing whitespaces
+    #[serde(default)]
+    pub trim: bool,
+    // replace the repeated '/' with a single one
+    #[serde(default)]
+    pub collapse_slashes: bool,
+    // convert to lowercase
+    #[serde(default)]
+    pub lowercase: bool,
+}
 
-impl<'de> serde::de::Visitor<'de> for PathVisitor {
-    type Value = Vec<String>;
+impl TopicNormalization {
+    pub fn normalize<'a>(&self, topic_name: &'a str) -> Cow<'a, str> {
+        let mut name = Cow::Borrowed(topic_name);
+        if self.trim {
+            let trimmed = name.trim();
+            if trimmed.len() != name.len() {
+                name = Cow::Owned(trimmed.to_string());
+            }
+        }
+        if self.collapse_slashes && name.contains("//") {
+            let mut collapsed = String::with_capacity(name.len());
+            for c in name.chars() {
+                if c != '/' || !collapsed.ends_with('/') {
+                    collapsed.push(c);
+                }
+            }
+            name = Cow::Owned(collapsed);
+        }
+        if self.lowercase && name.chars().any(char::is_uppercase) {
+            name = Cow::Owned(name.to_lowercase());
+        }
+        name
+    }
+}
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(formatter, "a string or an array of strings")
+// A template of the zenoh key expression a DDS topic is mapped to, with "{scope}", "{partition}" and "{topic}"
+// placeholders. A segment consisting only of "{scope}" or "{partition}" is omitted when there is no scope
+// or no partition (or the default partition "").
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyExprTemplate {
+    template: String,
+}
+
+impl KeyExprTemplate {
+    pub fn new(template: &str) -> Result<Self, String> {
+        if !template.contains("{topic}") {
+            return Err(format!(
+                "invalid template '{template}': the '{{topic}}' placeholder is missing"
+            ));
+        }
+        let remainder = template
+            .replace("{scope}", "")
+            .replace("{partition}", "")
+            .replace("{topic}", "");
+        if remainder.contains('{') || remainder.contains('}') {
+            return Err(format!(
+                "invalid template '{template}': only the '{{scope}}', '{{partition}}' and '{{topic}}' placeholders are supported"
+            ));
+        }
+        if template.split('/').any(str::is_empty) {
+            return Err(format!("invalid template '{template}': empty segment"));
+        }
+        // the literal parts of the template must give a valid key expression once the placeholders are
+        // substituted (e.g. no '*', '$', '?' or '#' in them)
+        let substituted = template
+            .replace("{scope}", "x")
+            .replace("{partition}", "x")
+            .replace("{topic}", "x");
+        if let Err(e) = OwnedKeyExpr::try_from(substituted) {
+            return Err(format!(
+                "invalid template '{template}': it doesn't give a valid key expression: {e}"
+            ));
+        }
+        Ok(KeyExprTemplate {
+            template: template.to_string(),
+        })
     }
 
-    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(vec![v.into()])
+    pub fn apply(&self, scope: Option<&str>, partition: Option<&str>, topic_name: &str) -> String {
+        let scope = scope.filter(|s| !s.is_empty());
+        let partition = partition.filter(|p| !p.is_empty());
+        self.template
+            .split('/')
+            .filter(|segment| match *segment {
+                "{scope}" => scope.is_some(),
+                "{partition}" => partition.is_some(),
+                _ => true,
+            })
+            .map(|segment| {
+                segment
+                    .replace("{scope}", scope.unwrap_or_default())
+                    .replace("{partition}", partition.unwrap_or_default())
+                    .replace("{topic}", topic_name)
+            })
+            .collect::<Vec<String>>()
+            .join("/")
     }
+}
 
-    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-    where
-        A: de::SeqAccess<'de>,
-    {
-        let mut v = if let Some(l) = seq.size_hint() {
-            Vec::with_capacity(l)
-        } else {
-            Vec::new()
-        };
-        while let Some(s) = seq.next_element()? {
-            v.push(s);
-        }
-        Ok(v)
+impl fmt::Display for KeyExprTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.template)
     }
 }
 
-fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+fn deserialize_keyexpr_template<'de, D>(
+    deserializer: D,
+) -> Result<Option<KeyExprTemplate>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    deserializer.deserialize_any(RegexVisitor)
+    let template: Option<String> = Deserialize::deserialize(deserializer)?;
+    template
+        .map(|t| {
+            KeyExprTemplate::new(&t).map_err(|e| {
+                de::Error::custom(format!("Invalid 'partition_keyexpr_template': {e}"))
+            })
+        })
+        .transpose()
 }
 
-fn deserialize_max_frequencies<'de, D>(deserializer: D) -> Result<Vec<(Regex, f32)>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
-    let mut result: Vec<(Regex, f32)> = Vec::with_capacity(strs.len());
-    for s in strs {
-        let i = s
-            .find('=')
-            .ok_or_else(|| de::Error::custom(format!("Invalid 'max_frequency': {s}")))?;
-        let regex = Regex::new(&s[0..i]).map_err(|e| {
-            de::Error::custom(format!("Invalid regex for 'max_frequency': '{s}': {e}"))
-        })?;
-        let frequency: f32 = s[i + 1..].parse().map_err(|e| {
-            de::Error::custom(format!(
-                "Invalid float value for 'max_frequency': '{s}': {e}"
-            ))
-        })?;
-        result.push((regex, frequency));
+// The order of creation of the zenoh-side and DDS-side entities of a route
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SetupOrder {
+    ZenohFirst,
+    DdsFirst,
+}
+
+// How the routes are set up: for each direction, the entity on the receiving side of the data is created first
+// by default, so the end-to-end path is established before any data can flow.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct RouteSetup {
+    // for routes from DDS: the zenoh Publisher (or PublicationCache) and the DDS Reader
+    #[serde(default = "default_from_dds_setup_order")]
+    pub from_dds: SetupOrder,
+    // for routes to DDS: the DDS Writer and the zenoh Subscriber
+    #[serde(default = "default_to_dds_setup_order")]
+    pub to_dds: SetupOrder,
+    // delay between the creation of the 1st and of the 2nd entity
+    #[serde(
+        default,
+        deserialize_with = "deserialize_duration",
+        serialize_with = "serialize_duration"
+    )]
+    pub settle_delay: Duration,
+}
+
+impl Default for RouteSetup {
+    fn default() -> Self {
+        RouteSetup {
+            from_dds: default_from_dds_setup_order(),
+            to_dds: default_to_dds_setup_order(),
+            settle_delay: Duration::ZERO,
+        }
     }
-    Ok(result)
 }
 
-fn default_queries_timeout() -> Duration {
-    Duration::from_secs_f32(DEFAULT_QUERIES_TIMEOUT)
+// The strategy of the downsampling of the topics routed with a maximum frequency
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DownsamplingStrategy {
+    // route the latest sample(s) of each instance received within a period, at the end of the period
+    #[default]
+    Latest,
+    // route the first sample of each instance received within a period as soon as received, dropping the others
+    First,
 }
 
-fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+impl fmt::Display for DownsamplingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownsamplingStrategy::Latest => write!(f, "latest"),
+            DownsamplingStrategy::First => write!(f, "first"),
+        }
+    }
+}
+
+// The batching of the samples routed from DDS to zenoh: the samples are accumulated and published as a single
+// zenoh payload once max_count samples are accumulated, or once the oldest one waited for max_latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    pub max_count: usize,
+    pub max_latency: Duration,
+}
+
+impl fmt::Display for BatchConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.max_count, self.max_latency.as_millis())
+    }
+}
+
+// How to route a topic for which the DDS Writers disagree on keyless-ness
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeylessConflict {
+    // the route is (re-)created as keyed
+    PreferKeyed,
+    // the route is (re-)created as keyless
+    PreferKeyless,
+    // the Writers disagreeing with the existing route are routed by a separate route,
+    // with "keyed" or "keyless" appended to the key expression
+    SeparateRoutes,
+}
+
+// How an entity matched by both the 'allow' and the 'deny' expressions is filtered
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterPrecedence {
+    // the entity is allowed
+    AllowWins,
+    // the entity is not allowed
+    #[default]
+    DenyWins,
+}
+
+// What to do when a new route is required while the max_routes cap is reached
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteEviction {
+    // the new route is throttled, until some routes are deleted
+    #[default]
+    None,
+    // the least recently active route is evicted (its DDS entities being throttled), to create the new route
+    LeastRecentlyActive,
+}
+
+// What to do when the queue of a route from DDS configured with async_forward is full
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueOverflow {
+    // the oldest queued sample is dropped, to queue the new one
+    #[default]
+    DropOldest,
+    // the new sample is dropped
+    DropNewest,
+}
+
+fn default_async_forward_queue_size() -> usize {
+    DEFAULT_ASYNC_FORWARD_QUEUE_SIZE
+}
+
+fn deserialize_async_forward_queue_size<'de, D>(deserializer: D) -> Result<usize, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let seconds: f32 = Deserialize::deserialize(deserializer)?;
-    Ok(Duration::from_secs_f32(seconds))
+    let n: usize = Deserialize::deserialize(deserializer)?;
+    if n == 0 {
+        return Err(de::Error::custom(
+            "Invalid value for 'async_forward_queue_size': must be at least 1",
+        ));
+    }
+    Ok(n)
 }
 
-fn default_forward_discovery() -> bool {
-    DEFAULT_FORWARD_DISCOVERY
+fn default_keyless_conflict() -> KeylessConflict {
+    KeylessConflict::PreferKeyed
 }
 
-fn default_reliable_routes_blocking() -> bool {
-    DEFAULT_RELIABLE_ROUTES_BLOCKING
+fn default_directions() -> Vec<RouteDirection> {
+    vec![RouteDirection::FromDds, RouteDirection::ToDds]
 }
 
-fn default_localhost_only() -> bool {
-    env::var("ROS_LOCALHOST_ONLY").as_deref() == Ok("1")
+// The limit of the rate of creation of new routes: at most max_routes per period.
+// The route creations in excess are not done at the discovery of their DDS entities (that get the "RateLimited" route
+// status): they're retried on a timer at the next refill of the limit, without holding up the discovery events.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct RouteCreationLimit {
+    pub max_routes: u32,
+    #[serde(
+        default = "default_route_creation_period",
+        deserialize_with = "deserialize_duration",
+        serialize_with = "serialize_duration"
+    )]
+    pub period: Duration,
 }
 
-// Serde Visitor for Regex deserialization.
-// It accepts either a String, either a list of Strings (that are concatenated with `|`)
-struct RegexVisitor;
+fn default_discovery_max_samples() -> usize {
+    DEFAULT_DISCOVERY_MAX_SAMPLES
+}
 
-impl<'de> Visitor<'de> for RegexVisitor {
-    type Value = Option<Regex>;
+fn default_route_creation_max_attempts() -> u32 {
+    DEFAULT_ROUTE_CREATION_MAX_ATTEMPTS
+}
 
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str(r#"either a string or a list of strings"#)
+fn deserialize_route_creation_max_attempts<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let n: u32 = Deserialize::deserialize(deserializer)?;
+    if n == 0 {
+        return Err(de::Error::custom(
+            "Invalid value for 'route_creation_max_attempts': must be at least 1",
+        ));
     }
+    Ok(n)
+}
 
-    // for `null` value
-    fn visit_unit<E>(self) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Ok(None)
+// The scope is normalized removing its leading and trailing '/' (an empty scope meaning no scope), and must be a
+// key expression without wildcards, not in the zenoh admin space (i.e. its 1st chunk must not start with '@')
+fn deserialize_scope<'de, D>(deserializer: D) -> Result<Option<OwnedKeyExpr>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let scope: Option<String> = Deserialize::deserialize(deserializer)?;
+    match scope {
+        Some(scope) => parse_scope(&scope).map_err(de::Error::custom),
+        None => Ok(None),
     }
+}
 
-    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
-    where
-        E: de::Error,
-    {
-        Regex::new(value)
-            .map(Some)
-            .map_err(|e| de::Error::custom(format!("Invalid regex '{value}': {e}")))
+fn parse_scope(scope: &str) -> Result<Option<OwnedKeyExpr>, String> {
+    let normalized = scope.trim_matches('/');
+    if normalized.is_empty() {
+        return Ok(None);
     }
+    if normalized.starts_with('@') {
+        return Err(format!(
+            "Invalid 'scope' {scope}: it must not start with '@', reserved for the zenoh admin space"
+        ));
+    }
+    let ke = OwnedKeyExpr::try_from(normalized.to_string())
+        .map_err(|e| format!("Invalid 'scope' {scope}: not a valid key expression: {e}"))?;
+    if ke.is_wild() {
+        return Err(format!(
+            "Invalid 'scope' {scope}: it must not contain wildcards ('*', '**' or '$*')"
+        ));
+    }
+    Ok(Some(ke))
+}
 
-    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-    where
-        A: de::SeqAccess<'de>,
-    {
-        let mut vec: Vec<String> = Vec::new();
-        while let Some(s) = seq.next_element()? {
-            vec.push(s);
-        }
-        let s: String = vec.join("|");
-        Regex::new(&s)
-            .map(Some)
-            .map_err(|e| de::Error::custom(format!("Invalid regex '{s}': {e}")))
+fn deserialize_max_routes<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let n: Option<usize> = Deserialize::deserialize(deserializer)?;
+    if n == Some(0) {
+        return Err(de::Error::custom(
+            "Invalid value for 'max_routes': must be at least 1",
+        ));
+    }
+    Ok(n)
+}
+
+fn deserialize_discovery_max_samples<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let n: usize = Deserialize::deserialize(deserializer)?;
+    if n == 0 {
+        return Err(de::Error::custom(
+            "Invalid value for 'discovery_max_samples': must be at least 1",
+        ));
+    }
+    Ok(n)
+}
+
+fn deserialize_discovery_queue_capacity<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let n: usize = Deserialize::deserialize(deserializer)?;
+    if n == 0 {
+        return Err(de::Error::custom(
+            "Invalid value for 'discovery_queue_capacity': must be at least 1",
+        ));
+    }
+    Ok(Some(n))
+}
+
+// The network interface the DDS traffic is bound to, by name (e.g. "eth0") or by address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DdsInterface {
+    Name(String),
+    Address(IpAddr),
+}
+
+impl fmt::Display for DdsInterface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DdsInterface::Name(name) => write!(f, "{name}"),
+            DdsInterface::Address(addr) => write!(f, "{addr}"),
+        }
+    }
+}
+
+fn deserialize_interface<'de, D>(deserializer: D) -> Result<Option<DdsInterface>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = Deserialize::deserialize(deserializer)?;
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(de::Error::custom(
+            "Invalid value for 'interface': expected an interface name or address",
+        ));
+    }
+    Ok(Some(match s.parse::<IpAddr>() {
+        Ok(addr) => DdsInterface::Address(addr),
+        Err(_) => DdsInterface::Name(s.to_string()),
+    }))
+}
+
+fn deserialize_cyclonedds_xml<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let xml: String = Deserialize::deserialize(deserializer)?;
+    let xml = xml.trim();
+    // CycloneDDS interprets a "$CYCLONEDDS_URI" element not starting with '<' as a file URI
+    if !xml.starts_with('<') {
+        return Err(de::Error::custom(format!(
+            "Invalid value for 'cyclonedds_xml': '{xml}' is not an XML fragment (expected e.g. '<CycloneDDS>...</CycloneDDS>')"
+        )));
+    }
+    Ok(Some(xml.to_string()))
+}
+
+fn default_last_sample_preview_max_bytes() -> usize {
+    DEFAULT_LAST_SAMPLE_PREVIEW_MAX_BYTES
+}
+
+fn default_max_decompressed_size() -> usize {
+    DEFAULT_MAX_DECOMPRESSED_SIZE
+}
+
+fn default_route_creation_period() -> Duration {
+    Duration::from_secs_f32(DEFAULT_ROUTE_CREATION_PERIOD)
+}
+
+fn default_from_dds_setup_order() -> SetupOrder {
+    SetupOrder::ZenohFirst
+}
+
+fn default_to_dds_setup_order() -> SetupOrder {
+    SetupOrder::DdsFirst
+}
+
+// How the data routed from DDS are delivered to zenoh subscribers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryMode {
+    // publish only (no cache for late joiners)
+    Push,
+    // publish and cache in a PublicationCache, that can be queried by late joiners
+    Pull,
+}
+
+impl std::str::FromStr for DeliveryMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "push" => Ok(DeliveryMode::Push),
+            "pull" => Ok(DeliveryMode::Pull),
+            _ => Err(format!(r#"expected "push" or "pull", got "{s}""#)),
+        }
+    }
+}
+
+impl fmt::Display for DeliveryMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeliveryMode::Push => write!(f, "push"),
+            DeliveryMode::Pull => write!(f, "pull"),
+        }
+    }
+}
+
+// The algorithm compressing the payloads routed from DDS to zenoh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    // fast, with a moderate ratio
+    Lz4,
+    // slower, with a better ratio
+    Zstd,
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lz4" => Ok(Compression::Lz4),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(format!(r#"expected "lz4" or "zstd", got "{s}""#)),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Lz4 => write!(f, "lz4"),
+            Compression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+// The names of the zenoh priorities in configuration
+pub(crate) fn parse_priority(s: &str) -> Result<Priority, String> {
+    match s {
+        "real_time" => Ok(Priority::RealTime),
+        "interactive_high" => Ok(Priority::InteractiveHigh),
+        "interactive_low" => Ok(Priority::InteractiveLow),
+        "data_high" => Ok(Priority::DataHigh),
+        "data" => Ok(Priority::Data),
+        "data_low" => Ok(Priority::DataLow),
+        "background" => Ok(Priority::Background),
+        _ => Err(format!(
+            r#"expected "real_time", "interactive_high", "interactive_low", "data_high", "data", "data_low" or "background", got "{s}""#
+        )),
+    }
+}
+
+pub(crate) fn priority_to_str(priority: Priority) -> &'static str {
+    match priority {
+        Priority::RealTime => "real_time",
+        Priority::InteractiveHigh => "interactive_high",
+        Priority::InteractiveLow => "interactive_low",
+        Priority::DataHigh => "data_high",
+        Priority::Data => "data",
+        Priority::DataLow => "data_low",
+        Priority::Background => "background",
+    }
+}
+
+// The names of the zenoh congestion controls in configuration (case insensitive)
+pub(crate) fn parse_congestion_control(s: &str) -> Result<CongestionControl, String> {
+    if s.eq_ignore_ascii_case("drop") {
+        Ok(CongestionControl::Drop)
+    } else if s.eq_ignore_ascii_case("block") {
+        Ok(CongestionControl::Block)
+    } else {
+        Err(format!(r#"expected "DROP" or "BLOCK", got "{s}""#))
+    }
+}
+
+pub(crate) fn congestion_control_to_str(congestion_ctrl: CongestionControl) -> &'static str {
+    match congestion_ctrl {
+        CongestionControl::Drop => "drop",
+        CongestionControl::Block => "block",
+    }
+}
+
+// A matcher on the raw payload of the samples (i.e. after the 4 bytes CDR header),
+// the matching samples being dropped by the route from DDS to zenoh
+#[derive(Debug, Clone)]
+pub enum PayloadMatcher {
+    // match if the payload contains those bytes at this offset
+    Bytes { offset: usize, bytes: Vec<u8> },
+    // match if the regex matches the payload bytes
+    Regex(regex::bytes::Regex),
+}
+
+impl PayloadMatcher {
+    pub fn is_match(&self, payload: &[u8]) -> bool {
+        match self {
+            PayloadMatcher::Bytes { offset, bytes } => payload
+                .get(*offset..offset + bytes.len())
+                .map_or(false, |b| b == bytes.as_slice()),
+            PayloadMatcher::Regex(re) => re.is_match(payload),
+        }
+    }
+}
+
+impl fmt::Display for PayloadMatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadMatcher::Bytes { offset, bytes } => {
+                write!(f, "bytes {} at offset {offset}", hex::encode(bytes))
+            }
+            PayloadMatcher::Regex(re) => write!(f, "regex {re}"),
+        }
+    }
+}
+
+// A filter on the content of the samples, comparing a primitive field to a value,
+// the non-matching samples (or the ones that can't be decoded) being dropped by the route from DDS to zenoh
+#[derive(Debug, Clone)]
+pub struct ContentFilter {
+    // the expression, as specified by the user
+    expr: String,
+    // the leading fields of the type, up to the compared one (the last)
+    layout: FieldsLayout,
+    op: CompareOp,
+    value: FieldValue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+// the operators, the 2 chars ones first so they're found before their 1st char
+const COMPARE_OPS: [(&str, CompareOp); 6] = [
+    ("==", CompareOp::Eq),
+    ("!=", CompareOp::Ne),
+    ("<=", CompareOp::Le),
+    (">=", CompareOp::Ge),
+    ("<", CompareOp::Lt),
+    (">", CompareOp::Gt),
+];
+
+impl ContentFilter {
+    // Create a filter from an expression "<fields><op><value>" where <fields> are the leading fields
+    // of the type up to the compared one as comma-separated "<name>:<type>" (see FieldType),
+    // the last one being compared with <value> using <op> (one of ==, !=, <, <=, > or >=).
+    // E.g.: "level:u8>=2" or "sec:i32,nanosec:u32,level:u8>=2"
+    pub fn new(expr: &str) -> Result<Self, String> {
+        let (i, op_str, op) = COMPARE_OPS
+            .iter()
+            .find_map(|(op_str, op)| expr.find(op_str).map(|i| (i, *op_str, *op)))
+            .ok_or_else(|| {
+                format!("no comparison operator in '{expr}' (expected ==, !=, <, <=, > or >=)")
+            })?;
+        let specs: Vec<String> = expr[..i].split(',').map(|s| s.trim().to_string()).collect();
+        let layout = FieldsLayout::new(&specs)?;
+        // the layout validated the specs: the type of the last field is known to be valid
+        let (_, ty) = specs.last().unwrap().split_once(':').unwrap();
+        let value = parse_field_value(ty.parse()?, expr[i + op_str.len()..].trim())?;
+        Ok(ContentFilter {
+            expr: expr.to_string(),
+            layout,
+            op,
+            value,
+        })
+    }
+
+    // Return true if the compared field of the sample (given the encapsulation identifier of its CDR header) matches
+    pub fn is_match(&self, encapsulation: &[u8], payload: &[u8]) -> bool {
+        let fields = match self.layout.decode(encapsulation, payload) {
+            Some(fields) => fields,
+            None => return false,
+        };
+        let ordering = match (&fields.last().unwrap().1, &self.value) {
+            (FieldValue::Bool(a), FieldValue::Bool(b)) => a.partial_cmp(b),
+            (FieldValue::Int(a), FieldValue::Int(b)) => a.partial_cmp(b),
+            (FieldValue::UInt(a), FieldValue::UInt(b)) => a.partial_cmp(b),
+            (FieldValue::Float(a), FieldValue::Float(b)) => a.partial_cmp(b),
+            _ => None,
+        };
+        match ordering {
+            Some(ordering) => match self.op {
+                CompareOp::Eq => ordering.is_eq(),
+                CompareOp::Ne => ordering.is_ne(),
+                CompareOp::Lt => ordering.is_lt(),
+                CompareOp::Le => ordering.is_le(),
+                CompareOp::Gt => ordering.is_gt(),
+                CompareOp::Ge => ordering.is_ge(),
+            },
+            // NaN or mismatching types: only != matches
+            None => self.op == CompareOp::Ne,
+        }
+    }
+}
+
+impl fmt::Display for ContentFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+// Parse the value a field of this type is compared with (only the primitive types are supported)
+fn parse_field_value(ty: FieldType, s: &str) -> Result<FieldValue, String> {
+    let invalid = |e: &dyn fmt::Display| format!("invalid {ty} value '{s}': {e}");
+    match ty {
+        FieldType::Bool => s.parse().map(FieldValue::Bool).map_err(|e| invalid(&e)),
+        FieldType::Int8 | FieldType::Int16 | FieldType::Int32 | FieldType::Int64 => {
+            s.parse().map(FieldValue::Int).map_err(|e| invalid(&e))
+        }
+        FieldType::UInt8 | FieldType::UInt16 | FieldType::UInt32 | FieldType::UInt64 => {
+            s.parse().map(FieldValue::UInt).map_err(|e| invalid(&e))
+        }
+        FieldType::Float32 | FieldType::Float64 => {
+            s.parse().map(FieldValue::Float).map_err(|e| invalid(&e))
+        }
+        FieldType::String => Err(format!(
+            "can't compare a string field with '{s}' (only the primitive types are supported)"
+        )),
+    }
+}
+
+// The configuration of a payload filter, as specified by the user
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PayloadFilterConf {
+    topic: String,
+    #[serde(default)]
+    offset: usize,
+    bytes: Option<String>,
+    regex: Option<String>,
+}
+
+// A named set of payload-handling choices for the data routed from DDS to zenoh.
+// The default profile routes the payloads as received from DDS (passthrough).
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ForwardingProfile {
+    // remove the trailing padding bytes of the serialized payloads (as indicated in their CDR header)
+    #[serde(default)]
+    pub trim_padding: bool,
+    // route only the fact that a sample arrived, as an empty payload (e.g. for heartbeat topics)
+    #[serde(default)]
+    pub event_only: bool,
+}
+
+// The fields of the configuration used only at startup (e.g. to create the zenoh Session or the DDS Participant),
+// that are not changed by a reload of the configuration
+pub const STARTUP_FIELDS: &[&str] = &[
+    "domain",
+    "group_member_id",
+    "directions",
+    "generalise_overlap_error",
+    "forward_discovery",
+    "partition_rewrite",
+    "localhost_only",
+    "interface",
+    "ipv6",
+    "domain_tag",
+    "cyclonedds_xml",
+    "shm_enabled",
+    "zenoh_shm_size",
+    "discovery_max_samples",
+    "discovery_queue_capacity",
+    "force_keyless",
+    "force_keyed",
+    "dry_run",
+    "manifest",
+    "prime_writers",
+    "topic_types",
+    "stats_state_file",
+    "stats_state_save_period",
+    "metrics_port",
+    "metrics_address",
+    "heartbeat_key",
+    "heartbeat_period",
+    "payload_log_file",
+    "payload_log_max_size",
+    "participant_check_period",
+    "route_ack_timeout",
+];
+
+// The fields of the configuration determining the DDS entities that are routed and the key expressions of their
+// routes: if changed by a reload of the configuration, all the routes are re-created
+pub const ROUTING_FIELDS: &[&str] = &[
+    "scope",
+    "partition_filter",
+    "topic_normalization",
+    "partition_keyexpr_template",
+    "topic_keyexpr_map",
+    "keyless_conflict",
+    "keyed_instance_routing",
+];
+
+// The fields of the configuration setting the parameters of the routes at their creation: if changed by a reload of
+// the configuration, the routes whose parameters changed are re-created
+pub const ROUTE_SETTINGS_FIELDS: &[&str] = &[
+    "route_setup",
+    "put_timeout",
+    "put_timeouts",
+    "max_frequencies",
+    "downsampling_depths",
+    "batch",
+    "express",
+    "async_forward",
+    "async_forward_queue_size",
+    "async_forward_overflow",
+    "compress",
+    "max_decompressed_size",
+    "delivery_modes",
+    "transient_local_max_samples",
+    "priorities",
+    "congestion_control",
+    "transport_priority_mapping",
+    "payload_filters",
+    "content_filter",
+    "qos_overrides",
+    "forwarding_profiles",
+    "topic_forwarding_profiles",
+    "sla_thresholds",
+    "sla_check_period",
+    "last_sample_preview",
+    "last_sample_preview_max_bytes",
+    "coalesce_writers",
+    "reliable_routes_blocking",
+    "preserve_source_timestamp",
+    "historical_data_timeout",
+    "matched_liveliness",
+    "route_liveliness",
+    "deadline_missed_events",
+    "entity_name_prefix",
+    "dead_letters_key",
+];
+
+// The fields of the configuration used to create the zenoh Session: if changed by a reload of the configuration,
+// the plugin is restarted with a new zenoh Session (re-creating its DDS Participant and all its routes)
+pub const SESSION_FIELDS: &[&str] = &["generalise_subs", "generalise_pubs"];
+
+// The fields of the configuration read by the running plugin when it needs them (e.g. at the discovery of a DDS
+// entity, at a route creation or at an admin query): if changed by a reload of the configuration, they apply
+// without re-creating the existing routes
+pub const RUNTIME_FIELDS: &[&str] = &[
+    "allow",
+    "deny",
+    "filter_precedence",
+    "compatible_types",
+    "route_creation_limit",
+    "route_creation_max_attempts",
+    "max_routes",
+    "max_routes_eviction",
+    "allow_best_effort",
+    "allowed_peers",
+    "admin_deny_updates",
+    "queries_timeout",
+    "queries_max_retries",
+    "queries_retry_backoff",
+    "admin_route_qos",
+    "admin_query_default_limit",
+    "check_qos_compatibility",
+    "validate_forwarded_qos",
+    "topic_aliases",
+    "participant_rediscovery_timeout",
+    "case_insensitive_matching",
+];
+
+impl Config {
+    // Return the names of the fields having a different value in another configuration
+    // (compared via their Debug representation, as the regular expressions don't implement PartialEq)
+    pub fn changed_fields(&self, other: &Config) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+        macro_rules! compare {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if format!("{:?}", self.$field) != format!("{:?}", other.$field) {
+                        changed.push(stringify!($field));
+                    }
+                )*
+            };
+        }
+        compare!(
+            scope,
+            domain,
+            group_member_id,
+            allow,
+            deny,
+            filter_precedence,
+            partition_filter,
+            partition_rewrite,
+            topic_normalization,
+            partition_keyexpr_template,
+            topic_keyexpr_map,
+            compatible_types,
+            route_setup,
+            route_creation_limit,
+            route_creation_max_attempts,
+            max_routes,
+            max_routes_eviction,
+            keyless_conflict,
+            directions,
+            discovery_max_samples,
+            discovery_queue_capacity,
+            put_timeout,
+            put_timeouts,
+            max_frequencies,
+            downsampling_depths,
+            batch,
+            express,
+            keyed_instance_routing,
+            async_forward,
+            async_forward_queue_size,
+            async_forward_overflow,
+            compress,
+            max_decompressed_size,
+            delivery_modes,
+            transient_local_max_samples,
+            priorities,
+            congestion_control,
+            transport_priority_mapping,
+            payload_filters,
+            content_filter,
+            qos_overrides,
+            topic_forwarding_profiles,
+            sla_thresholds,
+            last_sample_preview,
+            last_sample_preview_max_bytes,
+            coalesce_writers,
+            force_keyless,
+            force_keyed,
+            allow_best_effort,
+            sla_check_period,
+            generalise_subs,
+            generalise_pubs,
+            generalise_overlap_error,
+            forward_discovery,
+            dead_letters_key,
+            reliable_routes_blocking,
+            preserve_source_timestamp,
+            admin_deny_updates,
+            dry_run,
+            localhost_only,
+            interface,
+            ipv6,
+            domain_tag,
+            cyclonedds_xml,
+            queries_timeout,
+            queries_max_retries,
+            queries_retry_backoff,
+            historical_data_timeout,
+            admin_route_qos,
+            admin_query_default_limit,
+            matched_liveliness,
+            route_liveliness,
+            deadline_missed_events,
+            check_qos_compatibility,
+            validate_forwarded_qos,
+            route_ack_timeout,
+            manifest,
+            prime_writers,
+            stats_state_file,
+            stats_state_save_period,
+            metrics_port,
+            metrics_address,
+            heartbeat_key,
+            heartbeat_period,
+            payload_log_file,
+            payload_log_max_size,
+            participant_check_period,
+            participant_rediscovery_timeout,
+            entity_name_prefix,
+            case_insensitive_matching,
+        );
+        #[cfg(feature = "dds_shm")]
+        compare!(shm_enabled);
+        #[cfg(feature = "zenoh_shm")]
+        compare!(zenoh_shm_size);
+        // the hash maps and sets are compared sorted
+        if self.forwarding_profiles.iter().collect::<BTreeMap<_, _>>()
+            != other.forwarding_profiles.iter().collect::<BTreeMap<_, _>>()
+        {
+            changed.push("forwarding_profiles");
+        }
+        if self.allowed_peers.iter().collect::<BTreeSet<_>>()
+            != other.allowed_peers.iter().collect::<BTreeSet<_>>()
+        {
+            changed.push("allowed_peers");
+        }
+        if self.topic_aliases.iter().collect::<BTreeMap<_, _>>()
+            != other.topic_aliases.iter().collect::<BTreeMap<_, _>>()
+        {
+            changed.push("topic_aliases");
+        }
+        if self.topic_types.iter().collect::<BTreeMap<_, _>>()
+            != other.topic_types.iter().collect::<BTreeMap<_, _>>()
+        {
+            changed.push("topic_types");
+        }
+        changed
+    }
+
+    // Keep the values of the fields used only at startup (see STARTUP_FIELDS) from the current configuration
+    pub fn keep_startup_fields(&mut self, current: &Config) {
+        self.domain = current.domain;
+        self.group_member_id = current.group_member_id.clone();
+        self.directions = current.directions.clone();
+        self.generalise_overlap_error = current.generalise_overlap_error;
+        self.forward_discovery = current.forward_discovery;
+        self.partition_rewrite = current.partition_rewrite.clone();
+        self.localhost_only = current.localhost_only;
+        self.interface = current.interface.clone();
+        self.ipv6 = current.ipv6;
+        self.domain_tag = current.domain_tag.clone();
+        self.cyclonedds_xml = current.cyclonedds_xml.clone();
+        #[cfg(feature = "dds_shm")]
+        {
+            self.shm_enabled = current.shm_enabled;
+        }
+        #[cfg(feature = "zenoh_shm")]
+        {
+            self.zenoh_shm_size = current.zenoh_shm_size;
+        }
+        self.discovery_max_samples = current.discovery_max_samples;
+        self.discovery_queue_capacity = current.discovery_queue_capacity;
+        self.force_keyless = current.force_keyless.clone();
+        self.force_keyed = current.force_keyed.clone();
+        self.dry_run = current.dry_run;
+        self.manifest = current.manifest.clone();
+        self.prime_writers = current.prime_writers.clone();
+        self.topic_types = current.topic_types.clone();
+        self.stats_state_file = current.stats_state_file.clone();
+        self.stats_state_save_period = current.stats_state_save_period;
+        self.metrics_port = current.metrics_port;
+        self.metrics_address = current.metrics_address;
+        self.heartbeat_key = current.heartbeat_key.clone();
+        self.heartbeat_period = current.heartbeat_period;
+        self.payload_log_file = current.payload_log_file.clone();
+        self.payload_log_max_size = current.payload_log_max_size;
+        self.participant_check_period = current.participant_check_period;
+        self.route_ack_timeout = current.route_ack_timeout;
+    }
+
+    // Return the pairs of overlapping (i.e. intersecting) key expressions of generalise_subs and of generalise_pubs,
+    // with the name of their field: the topics whose key expression matches both are served by 2 generalised
+    // declarations, and might be routed twice
+    pub fn generalise_overlaps(&self) -> Vec<(&'static str, &OwnedKeyExpr, &OwnedKeyExpr)> {
+        let mut overlaps = Vec::new();
+        for (field, kes) in [
+            ("generalise_subs", &self.generalise_subs),
+            ("generalise_pubs", &self.generalise_pubs),
+        ] {
+            for (i, ke1) in kes.iter().enumerate() {
+                for ke2 in &kes[i + 1..] {
+                    if ke1.intersects(ke2) {
+                        overlaps.push((field, ke1, ke2));
+                    }
+                }
+            }
+        }
+        overlaps
+    }
+
+    // Return a partition rewritten by partition_rewrite: the 1st match of its regex is replaced, with the capture groups
+    // substituted in the replacement. The partition is unchanged if not matching (or if partition_rewrite is not set).
+    pub fn rewrite_partition<'a>(&self, partition: &'a str) -> Cow<'a, str> {
+        match &self.partition_rewrite {
+            Some((re, replacement)) => re.replace(partition, replacement.as_str()),
+            None => Cow::Borrowed(partition),
+        }
+    }
+
+    // Rewrite the partitions of a QoS (see rewrite_partition()), a QoS without partition being left unchanged
+    pub fn rewrite_partitions(&self, qos: &mut Qos) {
+        if let Some(partitions) = &mut qos.partition {
+            for p in partitions.iter_mut() {
+                if let Cow::Owned(rewritten) = self.rewrite_partition(p) {
+                    *p = rewritten;
+                }
+            }
+        }
+    }
+
+    // Return the key expression a (normalized) topic name is explicitly mapped to by topic_keyexpr_map, with the
+    // capture groups of the 1st matching regex substituted in its template, or None if no regex matches.
+    // The substitution might not give a valid key expression (e.g. with an empty capture group).
+    pub fn mapped_topic_keyexpr(&self, topic_name: &str) -> Option<Result<OwnedKeyExpr, String>> {
+        self.topic_keyexpr_map.iter().find_map(|(re, template)| {
+            let captures = re.captures(topic_name)?;
+            let mut ke = String::new();
+            captures.expand(template, &mut ke);
+            Some(
+                OwnedKeyExpr::try_from(ke)
+                    .map_err(|e| format!("'{re}={template}' gives an invalid key expression: {e}")),
+            )
+        })
+    }
+
+    // The routes to DDS to pre-create for the topics of topic_types matching prime_writers (sorted by topic), as
+    // manifest entries expecting DDS Readers with the default QoS. The topics are keyed unless matching force_keyless.
+    pub fn primed_writers(&self) -> Vec<ManifestEntry> {
+        let re = match &self.prime_writers {
+            Some(re) => re,
+            None => return Vec::new(),
+        };
+        let mut entries: Vec<ManifestEntry> = self
+            .topic_types
+            .iter()
+            .filter(|(topic, _)| re.is_match(topic))
+            .map(|(topic, type_name)| ManifestEntry {
+                topic: topic.clone(),
+                type_name: type_name.clone(),
+                direction: RouteDirection::ToDds,
+                keyless: self
+                    .force_keyless
+                    .as_ref()
+                    .map_or(false, |re| re.is_match(topic)),
+                qos: Qos::default(),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.topic.cmp(&b.topic));
+        entries
+    }
+
+    // Return true if DDS entities with those type names can be served by the same route: if the type names are
+    // the same, or declared as compatible (see compatible_types configuration)
+    pub fn are_compatible_types(&self, type_a: &str, type_b: &str) -> bool {
+        type_a == type_b
+            || self
+                .compatible_types
+                .iter()
+                .any(|(a, b)| (a == type_a && b == type_b) || (a == type_b && b == type_a))
+    }
+
+    // Keep the values of the fields determining the routes (see ROUTING_FIELDS) from the current configuration
+    pub fn keep_routing_fields(&mut self, current: &Config) {
+        self.scope = current.scope.clone();
+        self.partition_filter = current.partition_filter.clone();
+        self.topic_normalization = current.topic_normalization.clone();
+        self.partition_keyexpr_template = current.partition_keyexpr_template.clone();
+        self.topic_keyexpr_map = current.topic_keyexpr_map.clone();
+        self.keyless_conflict = current.keyless_conflict;
+        self.keyed_instance_routing = current.keyed_instance_routing.clone();
+    }
+
+    // If case_insensitive_matching is set, re-compile all the regular expressions matching the topics
+    // (and the type names) as case-insensitive. To be called once the configuration is deserialized.
+    pub fn apply_case_insensitive_matching(&mut self) {
+        if !self.case_insensitive_matching {
+            return;
+        }
+        for matcher in [&mut self.allow, &mut self.deny].into_iter().flatten() {
+            matcher.make_case_insensitive();
+        }
+        self.topic_keyexpr_map
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.put_timeouts
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.max_frequencies
+            .iter_mut()
+            .for_each(|(re, _, _)| make_case_insensitive(re));
+        self.downsampling_depths
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.batch
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.express.iter_mut().for_each(make_case_insensitive);
+        self.keyed_instance_routing
+            .iter_mut()
+            .for_each(make_case_insensitive);
+        self.async_forward
+            .iter_mut()
+            .for_each(make_case_insensitive);
+        self.compress
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.partition_rewrite
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.transient_local_max_samples
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.delivery_modes
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.priorities
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.congestion_control
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.payload_filters
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.content_filter
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.qos_overrides
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.topic_forwarding_profiles
+            .iter_mut()
+            .for_each(|(re, _)| make_case_insensitive(re));
+        self.sla_thresholds
+            .iter_mut()
+            .for_each(|t| make_case_insensitive(&mut t.topic));
+        self.last_sample_preview
+            .iter_mut()
+            .for_each(make_case_insensitive);
+        self.coalesce_writers
+            .iter_mut()
+            .for_each(make_case_insensitive);
+        self.force_keyless
+            .iter_mut()
+            .for_each(make_case_insensitive);
+        self.force_keyed.iter_mut().for_each(make_case_insensitive);
+        self.prime_writers
+            .iter_mut()
+            .for_each(make_case_insensitive);
+        self.allow_best_effort
+            .iter_mut()
+            .for_each(make_case_insensitive);
+    }
+}
+
+// Compile a regular expression matching topic (or type) names. All of those are compiled with the same builder,
+// allowing inline flags such as "(?i)" (case-insensitive), and to be re-compiled with case_insensitive_matching.
+fn topic_regex(expr: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(expr).build()
+}
+
+fn make_case_insensitive(re: &mut Regex) {
+    *re = RegexBuilder::new(re.as_str())
+        .case_insensitive(true)
+        .build()
+        .expect("regex already compiled once");
+}
+
+// Concatenate several regular expressions with '|', each in its own group so that its inline flags
+// (e.g. "(?i)") apply to it only, and not to the following expressions
+fn concat_regex_exprs<S: AsRef<str>>(exprs: &[S]) -> String {
+    match exprs {
+        [expr] => expr.as_ref().to_string(),
+        _ => exprs
+            .iter()
+            .map(|e| format!("(?:{})", e.as_ref()))
+            .collect::<Vec<_>>()
+            .join("|"),
+    }
+}
+
+// The SLA thresholds for the routes from DDS to zenoh on a set of topics
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SlaThresholds {
+    // regex matching the set of "partition/topic-name"
+    #[serde(deserialize_with = "deserialize_topic_regex")]
+    pub topic: Regex,
+    // maximum latency between the source timestamp and the reception of a sample
+    #[serde(default, deserialize_with = "deserialize_opt_duration")]
+    pub max_latency: Option<Duration>,
+    // minimum rate of samples, in Hertz
+    #[serde(default)]
+    pub min_rate: Option<f64>,
+    // maximum standard deviation of the samples inter-arrival intervals
+    #[serde(default, deserialize_with = "deserialize_opt_duration")]
+    pub max_jitter: Option<Duration>,
+}
+
+// A route to be pre-created at startup, independently of the DDS discovery
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestEntry {
+    pub topic: String,
+    #[serde(rename = "type")]
+    pub type_name: String,
+    pub direction: RouteDirection,
+    #[serde(default)]
+    pub keyless: bool,
+    // the QoS of the DDS Writers (for "from_dds") or Readers (for "to_dds") expected on this topic
+    #[serde(default)]
+    pub qos: Qos,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteDirection {
+    FromDds,
+    ToDds,
+}
+
+fn default_domain() -> u32 {
+    if let Ok(s) = env::var("ROS_DOMAIN_ID") {
+        s.parse::<u32>().unwrap_or(DEFAULT_DOMAIN)
+    } else {
+        DEFAULT_DOMAIN
+    }
+}
+
+fn deserialize_path<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(OptPathVisitor)
+}
+
+struct OptPathVisitor;
+
+impl<'de> serde::de::Visitor<'de> for OptPathVisitor {
+    type Value = Option<Vec<String>>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "none or a string or an array of strings")
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(PathVisitor).map(Some)
+    }
+}
+
+struct PathVisitor;
+
+impl<'de> serde::de::Visitor<'de> for PathVisitor {
+    type Value = Vec<String>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(formatter, "a string or an array of strings")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(vec![v.into()])
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut v = if let Some(l) = seq.size_hint() {
+            Vec::with_capacity(l)
+        } else {
+            Vec::new()
+        };
+        while let Some(s) = seq.next_element()? {
+            v.push(s);
+        }
+        Ok(v)
+    }
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(RegexVisitor)
+}
+
+fn deserialize_entity_matcher<'de, D>(deserializer: D) -> Result<Option<EntityMatcher>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(EntityMatcherVisitor)
+}
+
+fn deserialize_put_timeouts<'de, D>(deserializer: D) -> Result<Vec<(Regex, f32)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, f32)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .find('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'put_timeouts': {s}")))?;
+        let regex = topic_regex(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!("Invalid regex for 'put_timeouts': '{s}': {e}"))
+        })?;
+        let timeout: f32 = s[i + 1..].parse().map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid float value for 'put_timeouts': '{s}': {e}"
+            ))
+        })?;
+        if !timeout.is_finite() || timeout < 0.0 {
+            return Err(de::Error::custom(format!(
+                "Invalid timeout for 'put_timeouts': '{s}' (expected a positive number of seconds, or 0 for no timeout)"
+            )));
+        }
+        result.push((regex, timeout));
+    }
+    Ok(result)
+}
+
+fn deserialize_topic_keyexpr_map<'de, D>(deserializer: D) -> Result<Vec<(Regex, String)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, String)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .find('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'topic_keyexpr_map': {s}")))?;
+        let regex = topic_regex(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!("Invalid regex for 'topic_keyexpr_map': '{s}': {e}"))
+        })?;
+        let template = &s[i + 1..];
+        check_keyexpr_template(&regex, template).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid key expression template for 'topic_keyexpr_map': '{s}': {e}"
+            ))
+        })?;
+        result.push((regex, template.to_string()));
+    }
+    Ok(result)
+}
+
+fn deserialize_partition_rewrite<'de, D>(
+    deserializer: D,
+) -> Result<Option<(Regex, String)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = Deserialize::deserialize(deserializer)?;
+    let i = s
+        .find('=')
+        .ok_or_else(|| de::Error::custom(format!("Invalid 'partition_rewrite': {s}")))?;
+    let regex = topic_regex(&s[0..i]).map_err(|e| {
+        de::Error::custom(format!("Invalid regex for 'partition_rewrite': '{s}': {e}"))
+    })?;
+    let replacement = &s[i + 1..];
+    check_capture_groups(&regex, replacement).map_err(|e| {
+        de::Error::custom(format!(
+            "Invalid replacement for 'partition_rewrite': '{s}': {e}"
+        ))
+    })?;
+    Ok(Some((regex, replacement.to_string())))
+}
+
+fn deserialize_compatible_types<'de, D>(deserializer: D) -> Result<Vec<(String, String)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(String, String)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        match s.split_once('=') {
+            Some((a, b)) if !a.is_empty() && !b.is_empty() => {
+                result.push((a.to_string(), b.to_string()))
+            }
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "Invalid 'compatible_types': '{s}' (expected '<type-name>=<type-name>')"
+                )))
+            }
+        }
+    }
+    Ok(result)
+}
+
+// Check that a template referencing the capture groups of a regex ("$1", "${1}", "$name" or "${name}")
+// gives a valid key expression, substituting each referenced group with a placeholder chunk
+fn check_keyexpr_template(regex: &Regex, template: &str) -> Result<(), String> {
+    let ke = check_capture_groups(regex, template)?;
+    OwnedKeyExpr::try_from(ke)
+        .map(|_| ())
+        .map_err(|e| format!("'{template}' doesn't give a valid key expression: {e}"))
+}
+
+// Check that the capture groups referenced by a replacement template exist in the regex,
+// returning the template with each reference replaced by "x"
+fn check_capture_groups(regex: &Regex, template: &str) -> Result<String, String> {
+    let names: Vec<&str> = regex.capture_names().flatten().collect();
+    let mut ke = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find('$') {
+        ke.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let group = match rest.strip_prefix('{') {
+            Some(braced) => {
+                let end = braced
+                    .find('}')
+                    .ok_or_else(|| format!("unclosed '${{' in '{template}'"))?;
+                rest = &braced[end + 1..];
+                &braced[..end]
+            }
+            None => {
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                let group = &rest[..end];
+                rest = &rest[end..];
+                group
+            }
+        };
+        let exists = match group.parse::<usize>() {
+            Ok(index) => index < regex.captures_len(),
+            Err(_) => names.contains(&group),
+        };
+        if !exists {
+            return Err(format!(
+                "'{template}' references the capture group '{group}' that doesn't exist in '{regex}'"
+            ));
+        }
+        ke.push('x');
+    }
+    ke.push_str(rest);
+    Ok(ke)
+}
+
+fn deserialize_content_filter<'de, D>(
+    deserializer: D,
+) -> Result<Vec<(Regex, ContentFilter)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, ContentFilter)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let (topic, expr) = s
+            .split_once('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'content_filter': {s}")))?;
+        let regex = topic_regex(topic).map_err(|e| {
+            de::Error::custom(format!("Invalid regex for 'content_filter': '{s}': {e}"))
+        })?;
+        let filter = ContentFilter::new(expr).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid expression for 'content_filter': '{s}': {e}"
+            ))
+        })?;
+        result.push((regex, filter));
+    }
+    Ok(result)
+}
+
+fn deserialize_qos_overrides<'de, D>(deserializer: D) -> Result<Vec<(Regex, Qos)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, Qos)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let (topic, fragment) = s
+            .split_once('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'qos_overrides': {s}")))?;
+        let regex = topic_regex(topic).map_err(|e| {
+            de::Error::custom(format!("Invalid regex for 'qos_overrides': '{s}': {e}"))
+        })?;
+        let value: serde_json::Value = serde_json::from_str(fragment).map_err(|e| {
+            de::Error::custom(format!("Invalid JSON for 'qos_overrides': '{s}': {e}"))
+        })?;
+        let policies = value.as_object().ok_or_else(|| {
+            de::Error::custom(format!(
+                "Invalid 'qos_overrides': '{s}' (expected a JSON object)"
+            ))
+        })?;
+        if let Some(policy) = policies
+            .keys()
+            .find(|p| !OVERRIDABLE_QOS_POLICIES.contains(&p.as_str()))
+        {
+            return Err(de::Error::custom(format!(
+                "Invalid 'qos_overrides': '{s}': the '{policy}' policy can't be overridden (expected one of: {})",
+                OVERRIDABLE_QOS_POLICIES.join(", ")
+            )));
+        }
+        let qos: Qos = serde_json::from_value(value).map_err(|e| {
+            de::Error::custom(format!("Invalid QoS for 'qos_overrides': '{s}': {e}"))
+        })?;
+        validate_qos(&qos).map_err(|e| {
+            de::Error::custom(format!("Invalid QoS for 'qos_overrides': '{s}': {e}"))
+        })?;
+        result.push((regex, qos));
+    }
+    Ok(result)
+}
+
+fn deserialize_max_frequencies<'de, D>(
+    deserializer: D,
+) -> Result<Vec<(Regex, f32, DownsamplingStrategy)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, f32, DownsamplingStrategy)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .find('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'max_frequency': {s}")))?;
+        let regex = topic_regex(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!("Invalid regex for 'max_frequency': '{s}': {e}"))
+        })?;
+        // the frequency can be followed by the downsampling strategy (":latest" by default)
+        let (frequency, strategy) = match s[i + 1..].split_once(':') {
+            Some((frequency, "latest")) => (frequency, DownsamplingStrategy::Latest),
+            Some((frequency, "first")) => (frequency, DownsamplingStrategy::First),
+            Some(_) => {
+                return Err(de::Error::custom(format!(
+                    "Invalid downsampling strategy for 'max_frequency': '{s}' (expected 'latest' or 'first')"
+                )))
+            }
+            None => (&s[i + 1..], DownsamplingStrategy::Latest),
+        };
+        let frequency: f32 = frequency.parse().map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid float value for 'max_frequency': '{s}': {e}"
+            ))
+        })?;
+        result.push((regex, frequency, strategy));
+    }
+    Ok(result)
+}
+
+// Read the JSON file at the configured path, which must contain an object
+// mapping DDS topic names or entity GUIDs to a human-readable label.
+fn deserialize_topic_aliases<'de, D>(deserializer: D) -> Result<HashMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let path: Option<String> = Deserialize::deserialize(deserializer)?;
+    match path {
+        Some(path) => {
+            let content = std::fs::read_to_string(&path).map_err(|e| {
+                de::Error::custom(format!("Failed to read 'topic_aliases_file' {path}: {e}"))
+            })?;
+            serde_json::from_str(&content)
+                .map_err(|e| de::Error::custom(format!("Invalid 'topic_aliases_file' {path}: {e}")))
+        }
+        None => Ok(HashMap::new()),
+    }
+}
+
+// Read the JSON file at the configured path, which must contain a list of routes to pre-create.
+fn deserialize_manifest<'de, D>(deserializer: D) -> Result<Vec<ManifestEntry>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let path: Option<String> = Deserialize::deserialize(deserializer)?;
+    match path {
+        Some(path) => {
+            let content = std::fs::read_to_string(&path).map_err(|e| {
+                de::Error::custom(format!("Failed to read 'manifest_file' {path}: {e}"))
+            })?;
+            serde_json::from_str(&content)
+                .map_err(|e| de::Error::custom(format!("Invalid 'manifest_file' {path}: {e}")))
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+fn deserialize_downsampling_depths<'de, D>(deserializer: D) -> Result<Vec<(Regex, i32)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, i32)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .rfind('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'downsampling_depth': {s}")))?;
+        let regex = topic_regex(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid regex for 'downsampling_depth': '{s}': {e}"
+            ))
+        })?;
+        let depth: i32 = s[i + 1..].parse().map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid integer value for 'downsampling_depth': '{s}': {e}"
+            ))
+        })?;
+        if depth < 1 {
+            return Err(de::Error::custom(format!(
+                "Invalid value for 'downsampling_depth': '{s}': must be at least 1"
+            )));
+        }
+        result.push((regex, depth));
+    }
+    Ok(result)
+}
+
+fn deserialize_transient_local_max_samples<'de, D>(
+    deserializer: D,
+) -> Result<Vec<(Regex, usize)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, usize)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s.rfind('=').ok_or_else(|| {
+            de::Error::custom(format!("Invalid 'transient_local_max_samples': {s}"))
+        })?;
+        let regex = topic_regex(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid regex for 'transient_local_max_samples': '{s}': {e}"
+            ))
+        })?;
+        let max_samples: usize = s[i + 1..].parse().map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid integer value for 'transient_local_max_samples': '{s}': {e}"
+            ))
+        })?;
+        if max_samples < 1 {
+            return Err(de::Error::custom(format!(
+                "Invalid value for 'transient_local_max_samples': '{s}': must be at least 1"
+            )));
+        }
+        result.push((regex, max_samples));
+    }
+    Ok(result)
+}
+
+fn deserialize_batch<'de, D>(deserializer: D) -> Result<Vec<(Regex, BatchConfig)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, BatchConfig)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .rfind('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'batch': {s}")))?;
+        let regex = topic_regex(&s[0..i])
+            .map_err(|e| de::Error::custom(format!("Invalid regex for 'batch': '{s}': {e}")))?;
+        let (max_count, max_latency) = s[i + 1..].split_once(':').ok_or_else(|| {
+            de::Error::custom(format!(
+                "Invalid 'batch': '{s}' (expected <regex>=<max_count>:<max_latency_ms>)"
+            ))
+        })?;
+        let max_count: usize = max_count
+            .parse()
+            .map_err(|e| de::Error::custom(format!("Invalid max_count for 'batch': '{s}': {e}")))?;
+        let max_latency: u64 = max_latency.parse().map_err(|e| {
+            de::Error::custom(format!("Invalid max_latency_ms for 'batch': '{s}': {e}"))
+        })?;
+        if max_count < 1 || max_latency < 1 {
+            return Err(de::Error::custom(format!(
+                "Invalid value for 'batch': '{s}': max_count and max_latency_ms must be at least 1"
+            )));
+        }
+        result.push((
+            regex,
+            BatchConfig {
+                max_count,
+                max_latency: Duration::from_millis(max_latency),
+            },
+        ));
+    }
+    Ok(result)
+}
+
+fn deserialize_payload_filters<'de, D>(
+    deserializer: D,
+) -> Result<Vec<(Regex, PayloadMatcher)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let confs: Vec<PayloadFilterConf> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, PayloadMatcher)> = Vec::with_capacity(confs.len());
+    for conf in confs {
+        let topic = topic_regex(&conf.topic).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid regex for 'payload_filters' topic: '{}': {e}",
+                conf.topic
+            ))
+        })?;
+        let matcher = match (conf.bytes, conf.regex) {
+            (Some(bytes), None) => PayloadMatcher::Bytes {
+                offset: conf.offset,
+                bytes: hex::decode(&bytes).map_err(|e| {
+                    de::Error::custom(format!(
+                        "Invalid hex string for 'payload_filters' bytes: '{bytes}': {e}"
+                    ))
+                })?,
+            },
+            (None, Some(re)) => {
+                PayloadMatcher::Regex(regex::bytes::Regex::new(&re).map_err(|e| {
+                    de::Error::custom(format!("Invalid regex for 'payload_filters': '{re}': {e}"))
+                })?)
+            }
+            _ => {
+                return Err(de::Error::custom(format!(
+                    "Invalid 'payload_filters' for topic '{}': exactly one of 'bytes' or 'regex' must be set",
+                    conf.topic
+                )))
+            }
+        };
+        result.push((topic, matcher));
+    }
+    Ok(result)
+}
+
+fn deserialize_compress<'de, D>(deserializer: D) -> Result<Vec<(Regex, Compression)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, Compression)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .rfind('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'compress': {s}")))?;
+        let regex = topic_regex(&s[0..i])
+            .map_err(|e| de::Error::custom(format!("Invalid regex for 'compress': '{s}': {e}")))?;
+        let compression: Compression = s[i + 1..]
+            .parse()
+            .map_err(|e| de::Error::custom(format!("Invalid value for 'compress': '{s}': {e}")))?;
+        result.push((regex, compression));
+    }
+    Ok(result)
+}
+
+fn deserialize_delivery_modes<'de, D>(
+    deserializer: D,
+) -> Result<Vec<(Regex, DeliveryMode)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, DeliveryMode)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .rfind('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'delivery_mode': {s}")))?;
+        let regex = topic_regex(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!("Invalid regex for 'delivery_mode': '{s}': {e}"))
+        })?;
+        let mode: DeliveryMode = s[i + 1..].parse().map_err(|e| {
+            de::Error::custom(format!("Invalid value for 'delivery_mode': '{s}': {e}"))
+        })?;
+        result.push((regex, mode));
+    }
+    Ok(result)
+}
+
+fn deserialize_topic_forwarding_profiles<'de, D>(
+    deserializer: D,
+) -> Result<Vec<(Regex, String)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, String)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s.rfind('=').ok_or_else(|| {
+            de::Error::custom(format!("Invalid 'topic_forwarding_profiles': {s}"))
+        })?;
+        let regex = topic_regex(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid regex for 'topic_forwarding_profiles': '{s}': {e}"
+            ))
+        })?;
+        result.push((regex, s[i + 1..].to_string()));
+    }
+    Ok(result)
+}
+
+fn deserialize_priorities<'de, D>(deserializer: D) -> Result<Vec<(Regex, Priority)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, Priority)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .rfind('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'priorities': {s}")))?;
+        let regex = topic_regex(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!("Invalid regex for 'priorities': '{s}': {e}"))
+        })?;
+        let priority = parse_priority(&s[i + 1..]).map_err(|e| {
+            de::Error::custom(format!("Invalid value for 'priorities': '{s}': {e}"))
+        })?;
+        result.push((regex, priority));
+    }
+    Ok(result)
+}
+
+fn deserialize_congestion_controls<'de, D>(
+    deserializer: D,
+) -> Result<Vec<(Regex, CongestionControl)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(Regex, CongestionControl)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s
+            .rfind('=')
+            .ok_or_else(|| de::Error::custom(format!("Invalid 'congestion_control': {s}")))?;
+        let regex = Regex::new(&s[0..i]).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid regex for 'congestion_control': '{s}': {e}"
+            ))
+        })?;
+        let congestion_ctrl = parse_congestion_control(&s[i + 1..]).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid value for 'congestion_control': '{s}': {e}"
+            ))
+        })?;
+        result.push((regex, congestion_ctrl));
+    }
+    Ok(result)
+}
+
+// The default mapping of the DDS TRANSPORT_PRIORITY values to zenoh priorities
+// (a value lower than all the thresholds is mapped to the default "data" priority)
+fn default_transport_priority_mapping() -> Vec<(i32, Priority)> {
+    vec![
+        (100, Priority::InteractiveHigh),
+        (10, Priority::InteractiveLow),
+        (1, Priority::DataHigh),
+    ]
+}
+
+fn deserialize_transport_priority_mapping<'de, D>(
+    deserializer: D,
+) -> Result<Vec<(i32, Priority)>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let strs: Vec<String> = Deserialize::deserialize(deserializer)?;
+    let mut result: Vec<(i32, Priority)> = Vec::with_capacity(strs.len());
+    for s in strs {
+        let i = s.rfind('=').ok_or_else(|| {
+            de::Error::custom(format!("Invalid 'transport_priority_mapping': {s}"))
+        })?;
+        let threshold: i32 = s[0..i].parse().map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid threshold for 'transport_priority_mapping': '{s}': {e}"
+            ))
+        })?;
+        let priority = parse_priority(&s[i + 1..]).map_err(|e| {
+            de::Error::custom(format!(
+                "Invalid value for 'transport_priority_mapping': '{s}': {e}"
+            ))
+        })?;
+        result.push((threshold, priority));
+    }
+    // sort by decreasing threshold, so the 1st threshold lower or equal to a value gives its priority
+    result.sort_by(|(t1, _), (t2, _)| t2.cmp(t1));
+    Ok(result)
+}
+
+fn deserialize_topic_regex<'de, D>(deserializer: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s: String = Deserialize::deserialize(deserializer)?;
+    topic_regex(&s).map_err(|e| de::Error::custom(format!("Invalid regex '{s}': {e}")))
+}
+
+fn default_stats_state_save_period() -> Duration {
+    Duration::from_secs_f32(DEFAULT_STATS_STATE_SAVE_PERIOD)
+}
+
+fn default_metrics_address() -> IpAddr {
+    DEFAULT_METRICS_ADDRESS
+}
+
+fn default_heartbeat_period() -> Duration {
+    Duration::from_secs_f32(DEFAULT_HEARTBEAT_PERIOD)
+}
+
+fn default_participant_check_period() -> Duration {
+    Duration::from_secs_f32(DEFAULT_PARTICIPANT_CHECK_PERIOD)
+}
+
+fn default_participant_rediscovery_timeout() -> Duration {
+    Duration::from_secs_f32(DEFAULT_PARTICIPANT_REDISCOVERY_TIMEOUT)
+}
+
+fn default_entity_name_prefix() -> String {
+    DEFAULT_ENTITY_NAME_PREFIX.to_string()
+}
+
+fn default_payload_log_max_size() -> u64 {
+    DEFAULT_PAYLOAD_LOG_MAX_SIZE
+}
+
+fn deserialize_payload_log_max_size<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let n: u64 = Deserialize::deserialize(deserializer)?;
+    if n == 0 {
+        return Err(de::Error::custom(
+            "Invalid value for 'payload_log_max_size': must be at least 1",
+        ));
+    }
+    Ok(n)
+}
+
+fn default_sla_check_period() -> Duration {
+    Duration::from_secs_f32(DEFAULT_SLA_CHECK_PERIOD)
+}
+
+fn deserialize_opt_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds: Option<f32> = Deserialize::deserialize(deserializer)?;
+    Ok(seconds.map(Duration::from_secs_f32))
+}
+
+#[cfg(feature = "zenoh_shm")]
+fn default_zenoh_shm_size() -> usize {
+    DEFAULT_ZENOH_SHM_SIZE
+}
+
+fn default_queries_timeout() -> Duration {
+    Duration::from_secs_f32(DEFAULT_QUERIES_TIMEOUT)
+}
+
+fn default_historical_data_timeout() -> Duration {
+    Duration::from_secs_f32(DEFAULT_HISTORICAL_DATA_TIMEOUT)
+}
+
+fn default_queries_max_retries() -> u32 {
+    DEFAULT_QUERIES_MAX_RETRIES
+}
+
+fn default_queries_retry_backoff() -> Duration {
+    Duration::from_secs_f32(DEFAULT_QUERIES_RETRY_BACKOFF)
+}
+
+fn deserialize_duration<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let seconds: f32 = Deserialize::deserialize(deserializer)?;
+    Ok(Duration::from_secs_f32(seconds))
+}
+
+fn serialize_duration<S>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_f32(duration.as_secs_f32())
+}
+
+fn default_forward_discovery() -> bool {
+    DEFAULT_FORWARD_DISCOVERY
+}
+
+fn default_reliable_routes_blocking() -> bool {
+    DEFAULT_RELIABLE_ROUTES_BLOCKING
+}
+
+fn default_validate_forwarded_qos() -> bool {
+    DEFAULT_VALIDATE_FORWARDED_QOS
+}
+
+fn default_localhost_only() -> bool {
+    env::var("ROS_LOCALHOST_ONLY").as_deref() == Ok("1")
+}
+
+// Serde Visitor for Regex deserialization.
+// It accepts either a String, either a list of Strings (that are concatenated with `|`)
+struct RegexVisitor;
+
+impl<'de> Visitor<'de> for RegexVisitor {
+    type Value = Option<Regex>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(r#"either a string or a list of strings"#)
+    }
+
+    // for `null` value
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        topic_regex(value)
+            .map(Some)
+            .map_err(|e| de::Error::custom(format!("Invalid regex '{value}': {e}")))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut vec: Vec<String> = Vec::new();
+        while let Some(s) = seq.next_element()? {
+            vec.push(s);
+        }
+        let s: String = concat_regex_exprs(&vec);
+        topic_regex(&s)
+            .map(Some)
+            .map_err(|e| de::Error::custom(format!("Invalid regex '{s}': {e}")))
+    }
+}
+
+struct EntityMatcherVisitor;
+
+impl<'de> Visitor<'de> for EntityMatcherVisitor {
+    type Value = Option<EntityMatcher>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(r#"either a string or a list of strings"#)
+    }
+
+    // for `null` value
+    fn visit_unit<E>(self) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(None)
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        EntityMatcher::new(&[value])
+            .map(Some)
+            .map_err(de::Error::custom)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: de::SeqAccess<'de>,
+    {
+        let mut vec: Vec<String> = Vec::new();
+        while let Some(s) = seq.next_element()? {
+            vec.push(s);
+        }
+        EntityMatcher::new(&vec)
+            .map(Some)
+            .map_err(de::Error::custom)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Config;
+    use super::{
+        BatchConfig, Compression, Config, DdsInterface, DeliveryMode, DownsamplingStrategy,
+        FilterPrecedence, ForwardingProfile, KeyExprTemplate, KeylessConflict, PartitionFilter,
+        QueueOverflow, RouteDirection, RouteEviction, SetupOrder, DEFAULT_ASYNC_FORWARD_QUEUE_SIZE,
+        DEFAULT_ENTITY_NAME_PREFIX, DEFAULT_HISTORICAL_DATA_TIMEOUT,
+        DEFAULT_LAST_SAMPLE_PREVIEW_MAX_BYTES, DEFAULT_MAX_DECOMPRESSED_SIZE,
+        DEFAULT_METRICS_ADDRESS, DEFAULT_PAYLOAD_LOG_MAX_SIZE, DEFAULT_ROUTE_CREATION_MAX_ATTEMPTS,
+        ROUTE_SETTINGS_FIELDS, ROUTING_FIELDS, RUNTIME_FIELDS, SESSION_FIELDS, STARTUP_FIELDS,
+    };
+    use cyclors::qos::Qos;
+    use std::time::Duration;
+    use zenoh::publication::{CongestionControl, Priority};
+
+    #[test]
+    fn test_path_field() {
+        // See: https://github.com/eclipse-zenoh/zenoh-plugin-webserver/issues/19
+        let config = serde_json::from_str::<Config>(r#"{"__path__": "/example/path"}"#);
+
+        assert!(config.is_ok());
+        let Config {
+            __required__,
+            __path__,
+            ..
+        } = config.unwrap();
+
+        assert_eq!(__path__, Some(vec![String::from("/example/path")]));
+        assert_eq!(__required__, None);
+    }
+
+    #[test]
+    fn test_required_field() {
+        // See: https://github.com/eclipse-zenoh/zenoh-plugin-webserver/issues/19
+        let config = serde_json::from_str::<Config>(r#"{"__required__": true}"#);
+        assert!(config.is_ok());
+        let Config {
+            __required__,
+            __path__,
+            ..
+        } = config.unwrap();
+
+        assert_eq!(__path__, None);
+        assert_eq!(__required__, Some(true));
+    }
+
+    #[test]
+    fn test_path_field_and_required_field() {
+        // See: https://github.com/eclipse-zenoh/zenoh-plugin-webserver/issues/19
+        let config = serde_json::from_str::<Config>(
+            r#"{"__path__": "/example/path", "__required__": true}"#,
+        );
+
+        assert!(config.is_ok());
+        let Config {
+            __required__,
+            __path__,
+            ..
+        } = config.unwrap();
+
+        assert_eq!(__path__, Some(vec![String::from("/example/path")]));
+        assert_eq!(__required__, Some(true));
+    }
+
+    #[test]
+    fn test_no_path_field_and_no_required_field() {
+        // See: https://github.com/eclipse-zenoh/zenoh-plugin-webserver/issues/19
+        let config = serde_json::from_str::<Config>("{}");
+
+        assert!(config.is_ok());
+        let Config {
+            __required__,
+            __path__,
+            ..
+        } = config.unwrap();
+
+        assert_eq!(__path__, None);
+        assert_eq!(__required__, None);
+    }
+
+    #[test]
+    fn test_delivery_modes() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"delivery_modes": ["rt/map.*=pull", ".*/status=push"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.delivery_modes.len(), 2);
+        assert!(config.delivery_modes[0].0.is_match("rt/map_updates"));
+        assert_eq!(config.delivery_modes[0].1, DeliveryMode::Pull);
+        assert_eq!(config.delivery_modes[1].1, DeliveryMode::Push);
+
+        let config = serde_json::from_str::<Config>(r#"{"delivery_modes": ["rt/map=poll"]}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(r#"{"delivery_modes": ["rt/map"]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_priorities() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"priorities": ["rt/cmd_vel=interactive_high"],
+                "transport_priority_mapping": ["1=data_high", "50=real_time"]}"#,
+        )
+        .unwrap();
+        assert!(config.priorities[0].0.is_match("rt/cmd_vel"));
+        assert_eq!(config.priorities[0].1, Priority::InteractiveHigh);
+        // sorted by decreasing threshold
+        assert_eq!(
+            config.transport_priority_mapping,
+            vec![(50, Priority::RealTime), (1, Priority::DataHigh)]
+        );
+
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.transport_priority_mapping.len(), 3);
+
+        let config = serde_json::from_str::<Config>(r#"{"priorities": ["rt/cmd_vel=urgent"]}"#);
+        assert!(config.is_err());
+        let config =
+            serde_json::from_str::<Config>(r#"{"transport_priority_mapping": ["high=data"]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_congestion_control() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"congestion_control": ["rt/scan.*=DROP", "rt/cmd_vel=block"]}"#,
+        )
+        .unwrap();
+        assert!(config.congestion_control[0].0.is_match("rt/scan_front"));
+        assert_eq!(config.congestion_control[0].1, CongestionControl::Drop);
+        assert!(config.congestion_control[1].0.is_match("rt/cmd_vel"));
+        assert_eq!(config.congestion_control[1].1, CongestionControl::Block);
+
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.congestion_control.is_empty());
+
+        let config = serde_json::from_str::<Config>(r#"{"congestion_control": ["rt/scan=FAST"]}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(r#"{"congestion_control": ["rt/scan"]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_route_setup() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.route_setup.from_dds, SetupOrder::ZenohFirst);
+        assert_eq!(config.route_setup.to_dds, SetupOrder::DdsFirst);
+        assert_eq!(config.route_setup.settle_delay, Duration::ZERO);
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"route_setup": {"to_dds": "zenoh_first", "settle_delay": 0.05}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.route_setup.from_dds, SetupOrder::ZenohFirst);
+        assert_eq!(config.route_setup.to_dds, SetupOrder::ZenohFirst);
+        assert_eq!(
+            config.route_setup.settle_delay,
+            Duration::from_secs_f32(0.05)
+        );
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"route_setup": {"to_dds": "reader_first"}}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_last_sample_preview() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.last_sample_preview.is_none());
+        assert_eq!(
+            config.last_sample_preview_max_bytes,
+            DEFAULT_LAST_SAMPLE_PREVIEW_MAX_BYTES
+        );
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"last_sample_preview": ["rt/chatter", "rt/status"], "last_sample_preview_max_bytes": 16}"#,
+        )
+        .unwrap();
+        let topics = config.last_sample_preview.unwrap();
+        assert!(topics.is_match("rt/status"));
+        assert!(!topics.is_match("rt/scan"));
+        assert_eq!(config.last_sample_preview_max_bytes, 16);
+    }
+
+    #[test]
+    fn test_force_keyless_keyed() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.force_keyless.is_none());
+        assert!(config.force_keyed.is_none());
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"force_keyless": ["rt/chatter", "rt/status"], "force_keyed": "rt/tf.*"}"#,
+        )
+        .unwrap();
+        let keyless = config.force_keyless.unwrap();
+        assert!(keyless.is_match("rt/status"));
+        assert!(!keyless.is_match("rt/tf"));
+        assert!(config.force_keyed.unwrap().is_match("rt/tf_static"));
+    }
+
+    #[test]
+    fn test_allow_best_effort() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.allow_best_effort.is_none());
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"allow_best_effort": ["rt/camera/.*", "rt/lidar"]}"#,
+        )
+        .unwrap();
+        let allow_best_effort = config.allow_best_effort.unwrap();
+        assert!(allow_best_effort.is_match("rt/camera/image_raw"));
+        assert!(allow_best_effort.is_match("rt/lidar"));
+        assert!(!allow_best_effort.is_match("rt/cmd_vel"));
+    }
+
+    #[test]
+    fn test_topic_keyexpr_map() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.topic_keyexpr_map.is_empty());
+        assert_eq!(config.mapped_topic_keyexpr("rt/cmd_vel"), None);
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"topic_keyexpr_map": [
+                "^rt/(robot\\d+)/cmd_vel$=robots/fleet/$1/cmd_vel",
+                "^rt/(?P<ns>\\w+)/(?P<name>\\w+)$=fleet/${name}/${ns}",
+                "^rt/tf$=robots/tf"
+            ]}"#,
+        )
+        .unwrap();
+        let mapped = |topic_name: &str| {
+            config
+                .mapped_topic_keyexpr(topic_name)
+                .map(|ke| ke.unwrap().to_string())
+        };
+        // the capture groups are substituted, by index or by name
+        assert_eq!(
+            mapped("rt/robot42/cmd_vel").as_deref(),
+            Some("robots/fleet/robot42/cmd_vel")
+        );
+        assert_eq!(mapped("rt/arm/joints").as_deref(), Some("fleet/joints/arm"));
+        assert_eq!(mapped("rt/tf").as_deref(), Some("robots/tf"));
+        // no matching regex: the default mapping applies
+        assert_eq!(mapped("rt/chatter/extra/segment"), None);
+        assert_eq!(mapped("rq/cmd_vel"), None);
+
+        // a substitution giving an invalid key expression is an error
+        let config = serde_json::from_str::<Config>(
+            r#"{"topic_keyexpr_map": ["^rt/(\\w*)/cmd_vel$=robots/$1/cmd_vel"]}"#,
+        )
+        .unwrap();
+        assert!(config.mapped_topic_keyexpr("rt//cmd_vel").unwrap().is_err());
+
+        // invalid templates are rejected
+        for invalid in [
+            r#"{"topic_keyexpr_map": ["rt/cmd_vel"]}"#,
+            r#"{"topic_keyexpr_map": ["rt/cmd_vel=robots//cmd_vel"]}"#,
+            r#"{"topic_keyexpr_map": ["rt/cmd_vel=robots/#/cmd_vel"]}"#,
+            r#"{"topic_keyexpr_map": ["rt/(\\w+)=robots/$2"]}"#,
+            r#"{"topic_keyexpr_map": ["rt/(\\w+)=robots/${name}"]}"#,
+            r#"{"topic_keyexpr_map": ["rt/(\\w+)=robots/${1"]}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<Config>(invalid).is_err(),
+                "{invalid} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_compatible_types() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.compatible_types.is_empty());
+        assert!(config.are_compatible_types(
+            "std_msgs::msg::dds_::String_",
+            "std_msgs::msg::dds_::String_"
+        ));
+        assert!(!config.are_compatible_types("pkg::msg::dds_::Pose_", "pkg::msg::dds_::PoseV2_"));
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"compatible_types": ["pkg::msg::dds_::Pose_=pkg::msg::dds_::PoseV2_"]}"#,
+        )
+        .unwrap();
+        // the compatibility is symmetric
+        assert!(config.are_compatible_types("pkg::msg::dds_::Pose_", "pkg::msg::dds_::PoseV2_"));
+        assert!(config.are_compatible_types("pkg::msg::dds_::PoseV2_", "pkg::msg::dds_::Pose_"));
+        assert!(!config.are_compatible_types("pkg::msg::dds_::Pose_", "pkg::msg::dds_::PoseV3_"));
+
+        let config = serde_json::from_str::<Config>(r#"{"compatible_types": ["pkg::Pose"]}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(r#"{"compatible_types": ["pkg::Pose="]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_put_timeouts() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.put_timeout.is_none());
+        assert!(config.put_timeouts.is_empty());
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"put_timeout": 0.5, "put_timeouts": ["rt/cmd_vel=0.05", "rt/map=0"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.put_timeout, Some(Duration::from_millis(500)));
+        assert!(config.put_timeouts[0].0.is_match("rt/cmd_vel"));
+        assert_eq!(config.put_timeouts[0].1, 0.05);
+        assert_eq!(config.put_timeouts[1].1, 0.0);
+
+        let config = serde_json::from_str::<Config>(r#"{"put_timeouts": ["rt/cmd_vel=-1"]}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(r#"{"put_timeouts": ["rt/cmd_vel"]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_directions() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(
+            config.directions,
+            vec![RouteDirection::FromDds, RouteDirection::ToDds]
+        );
+        let config = serde_json::from_str::<Config>(r#"{"directions": ["from_dds"]}"#).unwrap();
+        assert_eq!(config.directions, vec![RouteDirection::FromDds]);
+        let config = serde_json::from_str::<Config>(r#"{"directions": ["both"]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_partition_keyexpr_template() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.partition_keyexpr_template.is_none());
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"partition_keyexpr_template": "{scope}/robots/{partition}/{topic}"}"#,
+        )
+        .unwrap();
+        let template = config.partition_keyexpr_template.unwrap();
+        // no partition, with and without scope
+        assert_eq!(
+            template.apply(Some("S"), None, "cmd_vel"),
+            "S/robots/cmd_vel"
+        );
+        assert_eq!(template.apply(None, None, "cmd_vel"), "robots/cmd_vel");
+        assert_eq!(template.apply(None, Some(""), "cmd_vel"), "robots/cmd_vel");
+        // a topic in multiple partitions is mapped to distinct key expressions
+        assert_eq!(
+            template.apply(Some("S"), Some("RobotA"), "cmd_vel"),
+            "S/robots/RobotA/cmd_vel"
+        );
+        assert_eq!(
+            template.apply(Some("S"), Some("RobotB"), "cmd_vel"),
+            "S/robots/RobotB/cmd_vel"
+        );
+        // placeholders within a segment
+        let template = KeyExprTemplate::new("{topic}/p_{partition}").unwrap();
+        assert_eq!(
+            template.apply(None, Some("A"), "rt/chatter"),
+            "rt/chatter/p_A"
+        );
+
+        for invalid in [
+            "{scope}/{partition}",
+            "{scope}/{name}/{topic}",
+            "{scope}//{topic}",
+            "{scope}/*/{topic}",
+            "{topic}/**",
+            "$x/{topic}",
+            "{topic}/p?{partition}",
+        ] {
+            assert!(KeyExprTemplate::new(invalid).is_err(), "{invalid}");
+        }
+        assert!(
+            serde_json::from_str::<Config>(r#"{"partition_keyexpr_template": "{partition}"}"#)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_admin_deny_updates() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(!config.admin_deny_updates);
+        let config = serde_json::from_str::<Config>(r#"{"admin_deny_updates": true}"#).unwrap();
+        assert!(config.admin_deny_updates);
+    }
+
+    #[test]
+    fn test_preserve_source_timestamp() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(!config.preserve_source_timestamp);
+        let config =
+            serde_json::from_str::<Config>(r#"{"preserve_source_timestamp": true}"#).unwrap();
+        assert!(config.preserve_source_timestamp);
+    }
+
+    #[test]
+    fn test_metrics_port() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.metrics_port, None);
+        let config = serde_json::from_str::<Config>(r#"{"metrics_port": 9464}"#).unwrap();
+        assert_eq!(config.metrics_port, Some(9464));
+        let config = serde_json::from_str::<Config>(r#"{"metrics_port": 70000}"#);
+        assert!(config.is_err());
+
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.metrics_address, DEFAULT_METRICS_ADDRESS);
+        assert!(config.metrics_address.is_loopback());
+        let config = serde_json::from_str::<Config>(r#"{"metrics_address": "0.0.0.0"}"#).unwrap();
+        assert!(config.metrics_address.is_unspecified());
+        let config = serde_json::from_str::<Config>(r#"{"metrics_address": "::1"}"#).unwrap();
+        assert!(config.metrics_address.is_ipv6());
+        let config = serde_json::from_str::<Config>(r#"{"metrics_address": "localhost"}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.heartbeat_key, None);
+        assert_eq!(config.heartbeat_period, Duration::from_secs(5));
+        let config = serde_json::from_str::<Config>(
+            r#"{"heartbeat_key": "fleet/robot-1/heartbeat", "heartbeat_period": 0.5}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.heartbeat_key.unwrap().as_str(),
+            "fleet/robot-1/heartbeat"
+        );
+        assert_eq!(config.heartbeat_period, Duration::from_millis(500));
+        let config = serde_json::from_str::<Config>(r#"{"heartbeat_key": "fleet//heartbeat"}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_participant_check_period() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.participant_check_period, Duration::from_secs(5));
+        let config =
+            serde_json::from_str::<Config>(r#"{"participant_check_period": 0.5}"#).unwrap();
+        assert_eq!(config.participant_check_period, Duration::from_millis(500));
+        let config = serde_json::from_str::<Config>(r#"{"participant_check_period": 0}"#).unwrap();
+        assert!(config.participant_check_period.is_zero());
+    }
+
+    #[test]
+    fn test_participant_rediscovery_timeout() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(
+            config.participant_rediscovery_timeout,
+            Duration::from_secs(30)
+        );
+        let config =
+            serde_json::from_str::<Config>(r#"{"participant_rediscovery_timeout": 2.5}"#).unwrap();
+        assert_eq!(
+            config.participant_rediscovery_timeout,
+            Duration::from_millis(2500)
+        );
+    }
+
+    #[test]
+    fn test_entity_name_prefix() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.entity_name_prefix, DEFAULT_ENTITY_NAME_PREFIX);
+        let config =
+            serde_json::from_str::<Config>(r#"{"entity_name_prefix": "bridge-robot-1"}"#).unwrap();
+        assert_eq!(config.entity_name_prefix, "bridge-robot-1");
+        let config = serde_json::from_str::<Config>(r#"{"entity_name_prefix": ""}"#).unwrap();
+        assert!(config.entity_name_prefix.is_empty());
+    }
+
+    #[test]
+    fn test_payload_log_file() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.payload_log_file, None);
+        assert_eq!(config.payload_log_max_size, DEFAULT_PAYLOAD_LOG_MAX_SIZE);
+        let config = serde_json::from_str::<Config>(
+            r#"{"payload_log_file": "/tmp/payloads.log", "payload_log_max_size": 1048576}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.payload_log_file.as_deref(),
+            Some("/tmp/payloads.log")
+        );
+        assert_eq!(config.payload_log_max_size, 1048576);
+        let config = serde_json::from_str::<Config>(r#"{"payload_log_max_size": 0}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_interface() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.interface, None);
+        assert!(!config.ipv6);
+
+        let config = serde_json::from_str::<Config>(r#"{"interface": " eth0 "}"#).unwrap();
+        assert_eq!(config.interface, Some(DdsInterface::Name("eth0".into())));
+        let config =
+            serde_json::from_str::<Config>(r#"{"interface": "192.168.1.10", "ipv6": false}"#)
+                .unwrap();
+        assert_eq!(
+            config.interface,
+            Some(DdsInterface::Address([192, 168, 1, 10].into()))
+        );
+        let config =
+            serde_json::from_str::<Config>(r#"{"interface": "fe80::1", "ipv6": true}"#).unwrap();
+        assert_eq!(
+            config.interface,
+            Some(DdsInterface::Address("fe80::1".parse().unwrap()))
+        );
+        assert!(config.ipv6);
+
+        assert!(serde_json::from_str::<Config>(r#"{"interface": ""}"#).is_err());
+    }
 
     #[test]
-    fn test_path_field() {
-        // See: https://github.com/eclipse-zenoh/zenoh-plugin-webserver/issues/19
-        let config = serde_json::from_str::<Config>(r#"{"__path__": "/example/path"}"#);
+    fn test_generalise_overlaps() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.generalise_overlaps().is_empty());
+        assert!(!config.generalise_overlap_error);
 
-        assert!(config.is_ok());
-        let Config {
-            __required__,
-            __path__,
-            ..
-        } = config.unwrap();
+        // a subscription and a publication generalised by intersecting key expressions don't overlap
+        let config = serde_json::from_str::<Config>(
+            r#"{"generalise_subs": ["rt/**", "cmd/*"], "generalise_pubs": ["rt/chatter", "cmd/**"]}"#,
+        )
+        .unwrap();
+        assert!(config.generalise_overlaps().is_empty());
 
-        assert_eq!(__path__, Some(vec![String::from("/example/path")]));
-        assert_eq!(__required__, None);
+        let config = serde_json::from_str::<Config>(
+            r#"{"generalise_subs": ["rt/**", "cmd/*", "rt/*/status", "cmd/reset"],
+                "generalise_pubs": ["a/*/c", "a/b/*", "x/y", "x/y"],
+                "generalise_overlap_error": true}"#,
+        )
+        .unwrap();
+        assert!(config.generalise_overlap_error);
+        let overlaps: Vec<(&str, &str, &str)> = config
+            .generalise_overlaps()
+            .into_iter()
+            .map(|(field, ke1, ke2)| (field, ke1.as_str(), ke2.as_str()))
+            .collect();
+        assert_eq!(
+            overlaps,
+            vec![
+                ("generalise_subs", "rt/**", "rt/*/status"),
+                ("generalise_subs", "cmd/*", "cmd/reset"),
+                ("generalise_pubs", "a/*/c", "a/b/*"),
+                ("generalise_pubs", "x/y", "x/y"),
+            ]
+        );
     }
 
     #[test]
-    fn test_required_field() {
-        // See: https://github.com/eclipse-zenoh/zenoh-plugin-webserver/issues/19
-        let config = serde_json::from_str::<Config>(r#"{"__required__": true}"#);
-        assert!(config.is_ok());
-        let Config {
-            __required__,
-            __path__,
-            ..
-        } = config.unwrap();
+    fn test_cyclonedds_xml() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.cyclonedds_xml, None);
+        let config = serde_json::from_str::<Config>(
+            r#"{"cyclonedds_xml": " <CycloneDDS><Domain><General><MaxMessageSize>65500B</MaxMessageSize></General></Domain></CycloneDDS> "}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.cyclonedds_xml.as_deref(),
+            Some("<CycloneDDS><Domain><General><MaxMessageSize>65500B</MaxMessageSize></General></Domain></CycloneDDS>")
+        );
+        let config =
+            serde_json::from_str::<Config>(r#"{"cyclonedds_xml": "file:///etc/cyclonedds.xml"}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(r#"{"cyclonedds_xml": ""}"#);
+        assert!(config.is_err());
+    }
 
-        assert_eq!(__path__, None);
-        assert_eq!(__required__, Some(true));
+    #[test]
+    fn test_route_liveliness() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(!config.route_liveliness);
+        let config = serde_json::from_str::<Config>(r#"{"route_liveliness": true}"#).unwrap();
+        assert!(config.route_liveliness);
     }
 
     #[test]
-    fn test_path_field_and_required_field() {
-        // See: https://github.com/eclipse-zenoh/zenoh-plugin-webserver/issues/19
+    fn test_deadline_missed_events() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(!config.deadline_missed_events);
+        let config = serde_json::from_str::<Config>(r#"{"deadline_missed_events": true}"#).unwrap();
+        assert!(config.deadline_missed_events);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(!config.dry_run);
+        let config = serde_json::from_str::<Config>(r#"{"dry_run": true}"#).unwrap();
+        assert!(config.dry_run);
+    }
+
+    #[test]
+    fn test_discovery_max_samples() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.discovery_max_samples, 32);
+        let config = serde_json::from_str::<Config>(r#"{"discovery_max_samples": 256}"#).unwrap();
+        assert_eq!(config.discovery_max_samples, 256);
+        let config = serde_json::from_str::<Config>(r#"{"discovery_max_samples": 0}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(r#"{"discovery_max_samples": -1}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_discovery_queue_capacity() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.discovery_queue_capacity, None);
+        let config =
+            serde_json::from_str::<Config>(r#"{"discovery_queue_capacity": 1024}"#).unwrap();
+        assert_eq!(config.discovery_queue_capacity, Some(1024));
+        let config = serde_json::from_str::<Config>(r#"{"discovery_queue_capacity": 0}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_filter_precedence() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.filter_precedence, FilterPrecedence::DenyWins);
+        let config =
+            serde_json::from_str::<Config>(r#"{"filter_precedence": "allow_wins"}"#).unwrap();
+        assert_eq!(config.filter_precedence, FilterPrecedence::AllowWins);
+        let config = serde_json::from_str::<Config>(r#"{"filter_precedence": "allow"}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_keyless_conflict() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.keyless_conflict, KeylessConflict::PreferKeyed);
+        let config =
+            serde_json::from_str::<Config>(r#"{"keyless_conflict": "separate_routes"}"#).unwrap();
+        assert_eq!(config.keyless_conflict, KeylessConflict::SeparateRoutes);
+        let config = serde_json::from_str::<Config>(r#"{"keyless_conflict": "first"}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_route_creation_max_attempts() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(
+            config.route_creation_max_attempts,
+            DEFAULT_ROUTE_CREATION_MAX_ATTEMPTS
+        );
+        let config =
+            serde_json::from_str::<Config>(r#"{"route_creation_max_attempts": 1}"#).unwrap();
+        assert_eq!(config.route_creation_max_attempts, 1);
+        let config = serde_json::from_str::<Config>(r#"{"route_creation_max_attempts": 0}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_max_routes() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.max_routes, None);
+        assert_eq!(config.max_routes_eviction, RouteEviction::None);
+
         let config = serde_json::from_str::<Config>(
-            r#"{"__path__": "/example/path", "__required__": true}"#,
+            r#"{"max_routes": 100, "max_routes_eviction": "least_recently_active"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.max_routes, Some(100));
+        assert_eq!(
+            config.max_routes_eviction,
+            RouteEviction::LeastRecentlyActive
         );
 
-        assert!(config.is_ok());
-        let Config {
-            __required__,
-            __path__,
-            ..
-        } = config.unwrap();
+        assert!(serde_json::from_str::<Config>(r#"{"max_routes": 0}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"max_routes_eviction": "oldest"}"#).is_err());
+    }
 
-        assert_eq!(__path__, Some(vec![String::from("/example/path")]));
-        assert_eq!(__required__, Some(true));
+    #[test]
+    fn test_scope() {
+        let scope = |s: &str| {
+            serde_json::from_value::<Config>(serde_json::json!({ "scope": s }))
+                .map(|config| config.scope.map(|ke| ke.to_string()))
+        };
+        assert_eq!(serde_json::from_str::<Config>(r#"{}"#).unwrap().scope, None);
+        assert_eq!(scope("robot1").unwrap().as_deref(), Some("robot1"));
+        assert_eq!(
+            scope("fleet/robot1").unwrap().as_deref(),
+            Some("fleet/robot1")
+        );
+
+        // leading and trailing '/' are removed, an empty scope meaning no scope
+        assert_eq!(
+            scope("/fleet/robot1/").unwrap().as_deref(),
+            Some("fleet/robot1")
+        );
+        assert_eq!(scope("").unwrap(), None);
+        assert_eq!(scope("/").unwrap(), None);
+
+        // wildcards are rejected
+        assert!(scope("fleet/**").is_err());
+        assert!(scope("*/robot1").is_err());
+        assert!(scope("robot$*").is_err());
+
+        // the zenoh admin space is rejected
+        assert!(scope("@").is_err());
+        assert!(scope("@/robot1").is_err());
+        assert!(scope("/@dds").is_err());
+
+        // invalid key expressions are rejected
+        assert!(scope("fleet//robot1").is_err());
+        assert!(scope("robot#1").is_err());
+        assert!(scope("robot?1").is_err());
     }
 
     #[test]
-    fn test_no_path_field_and_no_required_field() {
-        // See: https://github.com/eclipse-zenoh/zenoh-plugin-webserver/issues/19
-        let config = serde_json::from_str::<Config>("{}");
+    fn test_async_forward() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.async_forward.is_none());
+        assert_eq!(
+            config.async_forward_queue_size,
+            DEFAULT_ASYNC_FORWARD_QUEUE_SIZE
+        );
+        assert_eq!(config.async_forward_overflow, QueueOverflow::DropOldest);
 
-        assert!(config.is_ok());
-        let Config {
-            __required__,
-            __path__,
-            ..
-        } = config.unwrap();
+        let config = serde_json::from_str::<Config>(
+            r#"{"async_forward": "rt/scan", "async_forward_queue_size": 16, "async_forward_overflow": "drop_newest"}"#,
+        )
+        .unwrap();
+        assert!(config.async_forward.unwrap().is_match("rt/scan"));
+        assert_eq!(config.async_forward_queue_size, 16);
+        assert_eq!(config.async_forward_overflow, QueueOverflow::DropNewest);
 
-        assert_eq!(__path__, None);
-        assert_eq!(__required__, None);
+        assert!(serde_json::from_str::<Config>(r#"{"async_forward_queue_size": 0}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"async_forward_overflow": "block"}"#).is_err());
+    }
+
+    #[test]
+    fn test_compress() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.compress.is_empty());
+        assert_eq!(config.max_decompressed_size, DEFAULT_MAX_DECOMPRESSED_SIZE);
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"compress": ["rt/camera/.*=zstd", "rt/scan=lz4"]}"#)
+                .unwrap();
+        assert_eq!(config.compress.len(), 2);
+        assert!(config.compress[0].0.is_match("rt/camera/image_raw"));
+        assert_eq!(config.compress[0].1, Compression::Zstd);
+        assert!(config.compress[1].0.is_match("rt/scan"));
+        assert_eq!(config.compress[1].1, Compression::Lz4);
+
+        assert!(serde_json::from_str::<Config>(r#"{"compress": ["rt/scan=gzip"]}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"compress": ["rt/scan"]}"#).is_err());
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"max_decompressed_size": 1048576}"#).unwrap();
+        assert_eq!(config.max_decompressed_size, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_route_creation_limit() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.route_creation_limit.is_none());
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"route_creation_limit": {"max_routes": 20}}"#)
+                .unwrap();
+        let limit = config.route_creation_limit.unwrap();
+        assert_eq!(limit.max_routes, 20);
+        assert_eq!(limit.period, Duration::from_millis(100));
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"route_creation_limit": {"max_routes": 5, "period": 1.0}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.route_creation_limit.unwrap().period,
+            Duration::from_secs(1)
+        );
+
+        let config = serde_json::from_str::<Config>(r#"{"route_creation_limit": {"period": 1.0}}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_forwarding_profiles() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"forwarding_profiles": {"compact": {"trim_padding": true}, "raw": {}},
+                "topic_forwarding_profiles": ["rt/scan=compact", ".*=raw"]}"#,
+        )
+        .unwrap();
+        assert!(config.forwarding_profiles["compact"].trim_padding);
+        assert!(!config.forwarding_profiles["compact"].event_only);
+        assert_eq!(
+            config.forwarding_profiles["raw"],
+            ForwardingProfile::default()
+        );
+        assert!(config.topic_forwarding_profiles[0].0.is_match("rt/scan"));
+        assert_eq!(config.topic_forwarding_profiles[0].1, "compact");
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"forwarding_profiles": {"compact": {"compress": true}}}"#,
+        );
+        assert!(config.is_err());
+        let config =
+            serde_json::from_str::<Config>(r#"{"topic_forwarding_profiles": ["rt/scan"]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_topic_normalization() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(
+            config.topic_normalization.normalize(" rt//Chatter "),
+            " rt//Chatter "
+        );
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"topic_normalization": {"trim": true, "collapse_slashes": true}}"#,
+        )
+        .unwrap();
+        let normalization = &config.topic_normalization;
+        assert_eq!(normalization.normalize(" rt//Chatter\t"), "rt/Chatter");
+        assert_eq!(
+            normalization.normalize("/rt///ns/chatter/"),
+            "/rt/ns/chatter/"
+        );
+        assert_eq!(normalization.normalize("//"), "/");
+        assert_eq!(normalization.normalize(" \t"), "");
+        assert!(matches!(
+            normalization.normalize("rt/chatter"),
+            std::borrow::Cow::Borrowed(_)
+        ));
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"topic_normalization": {"lowercase": true}}"#)
+                .unwrap();
+        assert_eq!(
+            config.topic_normalization.normalize(" rt//Chatter"),
+            " rt//chatter"
+        );
+
+        let config = serde_json::from_str::<Config>(r#"{"topic_normalization": {"strip": true}}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_allow_deny_type() {
+        const PARAM_EVENT: &str = "rcl_interfaces::msg::dds_::ParameterEvent_";
+        const STRING: &str = "std_msgs::msg::dds_::String_";
+
+        // expressions without prefix only match the 'partition/topic-name'
+        let config = serde_json::from_str::<Config>(r#"{"deny": "rt/parameter_events"}"#).unwrap();
+        let deny = config.deny.unwrap();
+        assert!(deny.type_name.is_none());
+        assert!(deny.is_match("rt/parameter_events", PARAM_EVENT));
+        assert!(!deny.is_match("rt/chatter", PARAM_EVENT));
+        assert_eq!(
+            serde_json::to_value(&deny).unwrap(),
+            serde_json::json!("rt/parameter_events")
+        );
+
+        // mixed rule set: an entity is matched by either its topic or its type
+        let config = serde_json::from_str::<Config>(
+            r#"{"deny": ["rt/rosout", "type:.*ParameterEvent_", "type:.*::Log_"]}"#,
+        )
+        .unwrap();
+        let deny = config.deny.unwrap();
+        assert_eq!(deny.topic.as_ref().unwrap().as_str(), "rt/rosout");
+        assert_eq!(
+            deny.type_name.as_ref().unwrap().as_str(),
+            "(?:.*ParameterEvent_)|(?:.*::Log_)"
+        );
+        assert!(deny.is_match("rt/rosout", STRING));
+        assert!(deny.is_match("rt/events", PARAM_EVENT));
+        assert!(!deny.is_match("rt/chatter", STRING));
+        assert_eq!(
+            serde_json::to_value(&deny).unwrap(),
+            serde_json::json!(["rt/rosout", "type:(?:.*ParameterEvent_)|(?:.*::Log_)"])
+        );
+
+        // type expressions only
+        let config = serde_json::from_str::<Config>(r#"{"allow": "type:std_msgs::.*"}"#).unwrap();
+        let allow = config.allow.unwrap();
+        assert!(allow.topic.is_none());
+        assert!(allow.is_match("rt/chatter", STRING));
+        assert!(!allow.is_match("rt/std_msgs", PARAM_EVENT));
+
+        let config = serde_json::from_str::<Config>(r#"{"deny": ["rt/chatter", "type:("]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_historical_data_timeout() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(
+            config.historical_data_timeout,
+            Duration::from_secs_f32(DEFAULT_HISTORICAL_DATA_TIMEOUT)
+        );
+
+        let config = serde_json::from_str::<Config>(r#"{"historical_data_timeout": 2.5}"#).unwrap();
+        assert_eq!(config.historical_data_timeout, Duration::from_millis(2500));
+
+        let config = serde_json::from_str::<Config>(r#"{"historical_data_timeout": "1s"}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_route_ack_timeout() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert_eq!(config.route_ack_timeout, None);
+
+        let config = serde_json::from_str::<Config>(r#"{"route_ack_timeout": 2.5}"#).unwrap();
+        assert_eq!(config.route_ack_timeout, Some(Duration::from_millis(2500)));
+    }
+
+    #[test]
+    fn test_max_frequencies() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"max_frequencies": ["rt/scan=10", "rt/image=2.5:first", "rt/map=1:latest"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.max_frequencies.len(), 3);
+        assert_eq!(config.max_frequencies[0].0.as_str(), "rt/scan");
+        assert_eq!(config.max_frequencies[0].1, 10.0);
+        assert_eq!(config.max_frequencies[0].2, DownsamplingStrategy::Latest);
+        assert_eq!(config.max_frequencies[1].0.as_str(), "rt/image");
+        assert_eq!(config.max_frequencies[1].1, 2.5);
+        assert_eq!(config.max_frequencies[1].2, DownsamplingStrategy::First);
+        assert_eq!(config.max_frequencies[2].2, DownsamplingStrategy::Latest);
+
+        let config = serde_json::from_str::<Config>(r#"{"max_frequencies": ["rt/scan=10:last"]}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(r#"{"max_frequencies": ["rt/scan=:first"]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_case_insensitive_matching() {
+        // the inline flags apply only to the expression they are part of
+        let config = serde_json::from_str::<Config>(
+            r#"{"deny": ["(?i)rosout", "Chatter", "type:(?i)log_"]}"#,
+        )
+        .unwrap();
+        let deny = config.deny.as_ref().unwrap();
+        assert!(deny.is_match("rt/RosOut", ""));
+        assert!(deny.is_match("rt/Chatter", ""));
+        assert!(!deny.is_match("rt/chatter", ""));
+        assert!(deny.is_match("rt/a", "rcl_interfaces::msg::dds_::Log_"));
+
+        // without case_insensitive_matching, the configuration is left unchanged
+        let mut config = serde_json::from_str::<Config>(
+            r#"{"allow": "rt/RosOut", "max_frequencies": ["Scan=10"]}"#,
+        )
+        .unwrap();
+        config.apply_case_insensitive_matching();
+        assert!(!config.allow.as_ref().unwrap().is_match("rt/rosout", ""));
+        assert!(!config.max_frequencies[0].0.is_match("rt/scan"));
+
+        let mut config = serde_json::from_str::<Config>(
+            r#"{"case_insensitive_matching": true, "allow": ["rt/RosOut", "Chatter"],
+                "max_frequencies": ["Scan=10"], "sla_thresholds": [{"topic": "Map"}]}"#,
+        )
+        .unwrap();
+        config.apply_case_insensitive_matching();
+        let allow = config.allow.as_ref().unwrap();
+        assert!(allow.is_match("rt/rosout", ""));
+        assert!(allow.is_match("rt/chatter", ""));
+        assert!(!allow.is_match("rt/scan", ""));
+        assert!(config.max_frequencies[0].0.is_match("rt/scan"));
+        assert!(config.sla_thresholds[0].topic.is_match("rt/map"));
+    }
+
+    #[test]
+    fn test_partition_rewrite() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.partition_rewrite.is_none());
+        assert_eq!(config.rewrite_partition("A"), "A");
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"partition_rewrite": "^(?P<name>\w+)$=site1_${name}"}"#,
+        )
+        .unwrap();
+        assert_eq!(config.rewrite_partition("A"), "site1_A");
+        assert_eq!(config.rewrite_partition("a/b"), "a/b");
+
+        // a single-partition entity is rewritten
+        let mut qos = Qos::default();
+        qos.partition = Some(vec!["robot".into()]);
+        config.rewrite_partitions(&mut qos);
+        assert_eq!(qos.partition, Some(vec!["site1_robot".to_string()]));
+        // a partition-less entity remains partition-less
+        let mut qos = Qos::default();
+        config.rewrite_partitions(&mut qos);
+        assert_eq!(qos.partition, None);
+        qos.partition = Some(vec![]);
+        config.rewrite_partitions(&mut qos);
+        assert_eq!(qos.partition, Some(vec![]));
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"partition_rewrite": "^fleet/(\d+)=robot_$1"}"#)
+                .unwrap();
+        assert_eq!(config.rewrite_partition("fleet/12/arm"), "robot_12/arm");
+
+        for invalid in [
+            r#"{"partition_rewrite": "^fleet"}"#,
+            r#"{"partition_rewrite": "^fleet(=x"}"#,
+            r#"{"partition_rewrite": "^(?P<name>\w+)$=site1_${other}"}"#,
+            r#"{"partition_rewrite": "^(\w+)$=site1_$2"}"#,
+            r#"{"partition_rewrite": ["^(\w+)$=site1_$1"]}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<Config>(invalid).is_err(),
+                "{invalid} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_partition_filter() {
+        let partitions = |p: &[&str]| Some(p.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+
+        // allow entities in partitions A or B, but not if also in C
+        let config = serde_json::from_str::<Config>(
+            r#"{"partition_filter": {"any_of": ["A", "B"], "none_of": ["C"]}}"#,
+        )
+        .unwrap();
+        let filter = &config.partition_filter;
+        assert!(filter.is_allowed(&partitions(&["A"])));
+        assert!(filter.is_allowed(&partitions(&["B", "D"])));
+        assert!(!filter.is_allowed(&partitions(&["A", "C"])));
+        assert!(!filter.is_allowed(&partitions(&["D"])));
+        assert!(!filter.is_allowed(&None));
+
+        // allow entities in both partitions A and B
+        let config =
+            serde_json::from_str::<Config>(r#"{"partition_filter": {"all_of": ["A", "B"]}}"#)
+                .unwrap();
+        let filter = &config.partition_filter;
+        assert!(filter.is_allowed(&partitions(&["A", "B", "C"])));
+        assert!(!filter.is_allowed(&partitions(&["A", "C"])));
+
+        // deny entities in the default partition only
+        let config =
+            serde_json::from_str::<Config>(r#"{"partition_filter": {"none_of": [""]}}"#).unwrap();
+        let filter = &config.partition_filter;
+        assert!(!filter.is_allowed(&None));
+        assert!(!filter.is_allowed(&partitions(&[])));
+        assert!(filter.is_allowed(&partitions(&["A"])));
+
+        // no filter
+        assert!(PartitionFilter::default().is_allowed(&partitions(&["A", "C"])));
+        assert!(PartitionFilter::default().is_allowed(&None));
+
+        let config = serde_json::from_str::<Config>(r#"{"partition_filter": {"one_of": ["A"]}}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_topic_aliases_file() {
+        let path = std::env::temp_dir().join("zenoh-plugin-dds-test-topic-aliases.json");
+        std::fs::write(
+            &path,
+            r#"{"rt/cmd_vel": "Robot velocity command", "0110a3b5c1d2e3f40000000000000103": "Lidar"}"#,
+        )
+        .unwrap();
+        let config = serde_json::from_value::<Config>(
+            serde_json::json!({ "topic_aliases_file": path.to_str().unwrap() }),
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.topic_aliases.len(), 2);
+        assert_eq!(
+            config.topic_aliases.get("rt/cmd_vel").map(String::as_str),
+            Some("Robot velocity command")
+        );
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"topic_aliases_file": "/non/existing/aliases.json"}"#,
+        );
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let current = serde_json::from_str::<Config>(
+            r#"{"scope": "a", "allow": "rt/.*", "max_frequencies": ["rt/scan=10"],
+                "forwarding_profiles": {"p1": {}, "p2": {"trim_padding": true}}}"#,
+        )
+        .unwrap();
+        let same = serde_json::from_str::<Config>(
+            r#"{"scope": "a", "allow": "rt/.*", "max_frequencies": ["rt/scan=10"],
+                "forwarding_profiles": {"p2": {"trim_padding": true}, "p1": {}}}"#,
+        )
+        .unwrap();
+        assert!(current.changed_fields(&same).is_empty());
+
+        let mut new = serde_json::from_str::<Config>(
+            r#"{"scope": "b", "domain": 2, "allow": "rt/.*", "deny": "rosout", "max_frequencies": ["rt/scan=5"],
+                "generalise_pubs": ["rt/**"], "forwarding_profiles": {"p1": {}, "p2": {"trim_padding": true}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            current.changed_fields(&new),
+            vec![
+                "scope",
+                "domain",
+                "deny",
+                "max_frequencies",
+                "generalise_pubs"
+            ]
+        );
+        // the startup fields are kept from the current configuration, but not the fields of the zenoh Session
+        new.keep_startup_fields(&current);
+        assert_eq!(
+            current.changed_fields(&new),
+            vec!["scope", "deny", "max_frequencies", "generalise_pubs"]
+        );
+        assert!(!current
+            .changed_fields(&new)
+            .iter()
+            .any(|f| STARTUP_FIELDS.contains(f)));
+        // a reloaded field is either ignored, or applied to the routes or to the zenoh Session
+        assert!(!ROUTING_FIELDS
+            .iter()
+            .chain(ROUTE_SETTINGS_FIELDS)
+            .chain(SESSION_FIELDS)
+            .chain(RUNTIME_FIELDS)
+            .any(|f| STARTUP_FIELDS.contains(f)));
+    }
+
+    #[test]
+    fn test_reload_fields_categories() {
+        // all the fields of the configuration, as listed by the error on an unknown field
+        let err = serde_json::from_str::<Config>(r#"{"__unknown__": 0}"#)
+            .unwrap_err()
+            .to_string();
+        let expected = &err[err.find("expected one of").unwrap()..];
+        let fields: Vec<&str> = expected
+            .split('`')
+            .skip(1)
+            .step_by(2)
+            .filter(|f| !f.starts_with("__"))
+            .collect();
+        assert!(fields.contains(&"scope") && fields.contains(&"dead_letters_key"));
+        // each field has a single reload category: a new field missing from all of them must be added to one
+        // (and, if a route setting, to the settings of the routes compared at reload)
+        for field in fields {
+            let categories = [
+                STARTUP_FIELDS,
+                ROUTING_FIELDS,
+                ROUTE_SETTINGS_FIELDS,
+                SESSION_FIELDS,
+                RUNTIME_FIELDS,
+            ]
+            .iter()
+            .filter(|category| category.contains(&field))
+            .count();
+            assert_eq!(
+                categories, 1,
+                "field '{field}' in {categories} reload categories"
+            );
+        }
+    }
+
+    #[test]
+    fn test_express() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.express.is_none());
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"express": ["rt/cmd_vel", "rt/estop/.*"]}"#)
+                .unwrap();
+        let express = config.express.unwrap();
+        assert!(express.is_match("rt/cmd_vel"));
+        assert!(express.is_match("rt/estop/trigger"));
+        assert!(!express.is_match("rt/imu"));
+
+        assert!(serde_json::from_str::<Config>(r#"{"express": "rt/("}"#).is_err());
+    }
+
+    #[test]
+    fn test_keyed_instance_routing() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.keyed_instance_routing.is_none());
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"keyed_instance_routing": "rt/robots/.*"}"#)
+                .unwrap();
+        let re = config.keyed_instance_routing.as_ref().unwrap();
+        assert!(re.is_match("rt/robots/pose"));
+        assert!(!re.is_match("rt/imu"));
+
+        // it changes the key expressions of the routes: it's a routing field
+        let default = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        let changed = config.changed_fields(&default);
+        assert_eq!(changed, vec!["keyed_instance_routing"]);
+        assert!(ROUTING_FIELDS.contains(&"keyed_instance_routing"));
+
+        assert!(serde_json::from_str::<Config>(r#"{"keyed_instance_routing": "rt/("}"#).is_err());
+    }
+
+    #[test]
+    fn test_batch() {
+        let config =
+            serde_json::from_str::<Config>(r#"{"batch": ["rt/imu=100:5", "rt/a=b=10:20"]}"#)
+                .unwrap();
+        assert_eq!(config.batch.len(), 2);
+        assert_eq!(config.batch[0].0.as_str(), "rt/imu");
+        assert_eq!(
+            config.batch[0].1,
+            BatchConfig {
+                max_count: 100,
+                max_latency: Duration::from_millis(5)
+            }
+        );
+        assert_eq!(config.batch[1].0.as_str(), "rt/a=b");
+        assert_eq!(config.batch[1].1.to_string(), "10:20");
+
+        assert!(serde_json::from_str::<Config>(r#"{"batch": ["rt/imu=100"]}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"batch": ["rt/imu=0:5"]}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"batch": ["rt/imu=10:0"]}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"batch": ["rt/imu=10:1.5"]}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"batch": ["rt/(=10:5"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_transient_local_max_samples() {
+        let config = serde_json::from_str::<Config>(r#"{}"#).unwrap();
+        assert!(config.transient_local_max_samples.is_empty());
+        let config = serde_json::from_str::<Config>(
+            r#"{"transient_local_max_samples": ["rt/map=10", "rt/tf.*=100"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.transient_local_max_samples.len(), 2);
+        assert_eq!(config.transient_local_max_samples[0].0.as_str(), "rt/map");
+        assert_eq!(config.transient_local_max_samples[0].1, 10);
+        assert_eq!(config.transient_local_max_samples[1].0.as_str(), "rt/tf.*");
+        assert_eq!(config.transient_local_max_samples[1].1, 100);
+
+        for invalid in [
+            "rt/map",
+            "rt/map=0",
+            "rt/map=-1",
+            "rt/map=ten",
+            "rt/(map=10",
+        ] {
+            let config = serde_json::from_str::<Config>(&format!(
+                r#"{{"transient_local_max_samples": ["{invalid}"]}}"#
+            ));
+            assert!(config.is_err(), "{invalid} should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_downsampling_depths() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"max_frequencies": ["rt/.*=10"], "downsampling_depths": ["rt/scan=5", "rt/a=b=2"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.downsampling_depths.len(), 2);
+        assert_eq!(config.downsampling_depths[0].0.as_str(), "rt/scan");
+        assert_eq!(config.downsampling_depths[0].1, 5);
+        assert_eq!(config.downsampling_depths[1].0.as_str(), "rt/a=b");
+        assert_eq!(config.downsampling_depths[1].1, 2);
+
+        let config = serde_json::from_str::<Config>(r#"{"downsampling_depths": ["rt/scan=0"]}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(r#"{"downsampling_depths": ["rt/scan=1.5"]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_qos_overrides() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"qos_overrides": [
+                "rt/map={\"durability\": {\"kind\": \"TRANSIENT_LOCAL\"}}",
+                "rt/scan={\"reliability\": {\"kind\": \"BEST_EFFORT\", \"max_blocking_time\": 0}, \"history\": {\"kind\": \"KEEP_LAST\", \"depth\": 10}}"
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.qos_overrides.len(), 2);
+        let (re, qos) = &config.qos_overrides[0];
+        assert_eq!(re.as_str(), "rt/map");
+        assert!(qos.durability.is_some());
+        assert!(qos.reliability.is_none());
+        let (re, qos) = &config.qos_overrides[1];
+        assert_eq!(re.as_str(), "rt/scan");
+        assert!(qos.reliability.is_some());
+        assert_eq!(qos.history.as_ref().map(|h| h.depth), Some(10));
+
+        for invalid in [
+            r#"["rt/foo"]"#,
+            r#"["rt/foo={durability}"]"#,
+            r#"["rt/foo=[]"]"#,
+            r#"["rt/foo={\"partition\": [\"P1\"]}"]"#,
+            r#"["rt/foo={\"history\": {\"kind\": \"KEEP_LAST\", \"depth\": 0}}"]"#,
+            r#"["rt/(foo={}"]"#,
+        ] {
+            let config =
+                serde_json::from_str::<Config>(&format!(r#"{{"qos_overrides": {invalid}}}"#));
+            assert!(config.is_err(), "{invalid} should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_content_filter() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"content_filter": ["rt/diagnostics=level:u8>=2", "rt/pose=sec:i32,nanosec:u32,x:f64<0.5"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.content_filter.len(), 2);
+        let (re, filter) = &config.content_filter[0];
+        assert_eq!(re.as_str(), "rt/diagnostics");
+        assert_eq!(filter.to_string(), "level:u8>=2");
+        // XCDR1 little endian encapsulation
+        let le = [0x00, 0x01];
+        assert!(filter.is_match(&le, &[2]));
+        assert!(filter.is_match(&le, &[3, 0, 0, 0]));
+        assert!(!filter.is_match(&le, &[1]));
+        // too short payload or unsupported encapsulation
+        assert!(!filter.is_match(&le, &[]));
+        assert!(!filter.is_match(&[0x00, 0x0a], &[2]));
+
+        // the f64 is aligned on 8 bytes with XCDR1
+        let (_, filter) = &config.content_filter[1];
+        let mut payload = vec![0u8; 8];
+        payload.extend_from_slice(&0.25f64.to_le_bytes());
+        assert!(filter.is_match(&le, &payload));
+        payload.truncate(8);
+        payload.extend_from_slice(&0.75f64.to_le_bytes());
+        assert!(!filter.is_match(&le, &payload));
+
+        for invalid in [
+            r#"["rt/foo"]"#,
+            r#"["rt/foo=level:u8"]"#,
+            r#"["rt/foo=level:u8>=x"]"#,
+            r#"["rt/foo=level:u128>=2"]"#,
+            r#"["rt/foo=name:string==abc"]"#,
+            r#"["rt/foo=level>=2"]"#,
+        ] {
+            let config =
+                serde_json::from_str::<Config>(&format!(r#"{{"content_filter": {invalid}}}"#));
+            assert!(config.is_err(), "{invalid} should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_payload_filters() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"payload_filters": [
+                {"topic": "rt/foo", "offset": 2, "bytes": "dead"},
+                {"topic": "rt/bar", "regex": "(?-u)^\\xff+$"}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.payload_filters.len(), 2);
+        let (re, matcher) = &config.payload_filters[0];
+        assert_eq!(re.as_str(), "rt/foo");
+        assert!(matcher.is_match(&[0x00, 0x01, 0xde, 0xad, 0x02]));
+        assert!(!matcher.is_match(&[0xde, 0xad, 0x00, 0x00]));
+        assert!(!matcher.is_match(&[0x00, 0x01, 0xde]));
+        let (re, matcher) = &config.payload_filters[1];
+        assert_eq!(re.as_str(), "rt/bar");
+        assert!(matcher.is_match(&[0xff, 0xff]));
+        assert!(!matcher.is_match(&[0xff, 0x00]));
+
+        let config = serde_json::from_str::<Config>(
+            r#"{"payload_filters": [{"topic": "rt/foo", "bytes": "00", "regex": "x"}]}"#,
+        );
+        assert!(config.is_err());
+        let config =
+            serde_json::from_str::<Config>(r#"{"payload_filters": [{"topic": "rt/foo"}]}"#);
+        assert!(config.is_err());
+        let config = serde_json::from_str::<Config>(
+            r#"{"payload_filters": [{"topic": "rt/foo", "bytes": "xyz"}]}"#,
+        );
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_sla_thresholds() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"sla_thresholds": [{"topic": "rt/cmd_vel", "max_latency": 0.05, "min_rate": 10}], "sla_check_period": 2.0}"#,
+        )
+        .unwrap();
+        assert_eq!(config.sla_thresholds.len(), 1);
+        let t = &config.sla_thresholds[0];
+        assert!(t.topic.is_match("rt/cmd_vel"));
+        assert_eq!(t.max_latency, Some(Duration::from_secs_f32(0.05)));
+        assert_eq!(t.min_rate, Some(10.0));
+        assert_eq!(t.max_jitter, None);
+        assert_eq!(config.sla_check_period, Duration::from_secs(2));
+
+        let config = serde_json::from_str::<Config>(r#"{"sla_thresholds": [{"topic": "("}]}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_manifest_file() {
+        let path = std::env::temp_dir().join("zenoh-plugin-dds-test-manifest.json");
+        std::fs::write(
+            &path,
+            r#"[
+                {"topic": "rt/cmd_vel", "type": "geometry_msgs::msg::dds_::Twist_", "direction": "to_dds"},
+                {"topic": "rt/map", "type": "nav_msgs::msg::dds_::OccupancyGrid_", "direction": "from_dds",
+                 "keyless": true, "qos": {"partition": ["P1", "P2"]}}
+            ]"#,
+        )
+        .unwrap();
+        let config = serde_json::from_value::<Config>(
+            serde_json::json!({ "manifest_file": path.to_str().unwrap() }),
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.manifest.len(), 2);
+        assert_eq!(config.manifest[0].topic, "rt/cmd_vel");
+        assert_eq!(config.manifest[0].direction, RouteDirection::ToDds);
+        assert!(!config.manifest[0].keyless);
+        assert_eq!(config.manifest[0].qos.partition, None);
+        assert_eq!(config.manifest[1].direction, RouteDirection::FromDds);
+        assert!(config.manifest[1].keyless);
+        assert_eq!(
+            config.manifest[1].qos.partition,
+            Some(vec!["P1".to_string(), "P2".to_string()])
+        );
+
+        let config =
+            serde_json::from_str::<Config>(r#"{"manifest_file": "/non/existing/manifest.json"}"#);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn test_primed_writers() {
+        let config = serde_json::from_str::<Config>(
+            r#"{"topic_types": {"rt/cmd_vel": "geometry_msgs::msg::dds_::Twist_"}}"#,
+        )
+        .unwrap();
+        assert!(config.primed_writers().is_empty());
+
+        let config = serde_json::from_str::<Config>(
+            r#"{
+                "prime_writers": "rt/cmd_.*|rt/status",
+                "force_keyless": "rt/status",
+                "topic_types": {
+                    "rt/status": "std_msgs::msg::dds_::String_",
+                    "rt/cmd_vel": "geometry_msgs::msg::dds_::Twist_",
+                    "rt/map": "nav_msgs::msg::dds_::OccupancyGrid_"
+                }
+            }"#,
+        )
+        .unwrap();
+        let primed = config.primed_writers();
+        assert_eq!(primed.len(), 2);
+        assert_eq!(primed[0].topic, "rt/cmd_vel");
+        assert_eq!(primed[0].type_name, "geometry_msgs::msg::dds_::Twist_");
+        assert_eq!(primed[0].direction, RouteDirection::ToDds);
+        assert!(!primed[0].keyless);
+        assert_eq!(primed[1].topic, "rt/status");
+        assert!(primed[1].keyless);
+
+        let reloaded = serde_json::from_str::<Config>(r#"{"prime_writers": "rt/cmd_.*"}"#).unwrap();
+        let changed = config.changed_fields(&reloaded);
+        assert!(changed.contains(&"prime_writers"));
+        assert!(changed.contains(&"topic_types"));
     }
 }
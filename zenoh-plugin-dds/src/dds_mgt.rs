@@ -12,33 +12,273 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 use async_std::task;
-use cyclors::qos::{History, HistoryKind, Qos};
+use cyclors::qos::{EntityName, History, HistoryKind, Qos};
 use cyclors::*;
-use flume::Sender;
+use flume::{Receiver, Sender, TrySendError};
+use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize, Serializer};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ffi::{CStr, CString};
 use std::fmt;
 use std::mem::MaybeUninit;
 use std::slice;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tracing::{debug, error, warn};
 #[cfg(feature = "dds_shm")]
 use zenoh::buffers::{ZBuf, ZSlice};
+use zenoh::liveliness::LivelinessToken;
 use zenoh::prelude::*;
-use zenoh::publication::CongestionControl;
+use zenoh::publication::{CongestionControl, Priority};
+use zenoh::sample::{Attachment, AttachmentBuilder};
 use zenoh::Session;
 use zenoh_core::SyncResolve;
 
+use crate::batch::SampleBatch;
+use crate::compression::{compress, COMPRESSION_ATTACHMENT_KEY};
+use crate::config::{
+    BatchConfig, Compression, ContentFilter, DownsamplingStrategy, ForwardingProfile,
+    PayloadMatcher, RouteDirection,
+};
+use crate::forward_queue::{AsyncForward, ForwardQueue};
+use crate::metrics::BridgeMetrics;
+use crate::payload_log::{self, PayloadDirection};
+use crate::qos_helpers::{apply_qos_override, get_deadline, is_coherent_access};
+use crate::rate_limit::IntervalGate;
+use crate::routing::KeyFilter;
+use crate::sample_hook::{get_sample_hook, FieldsLayout, HookDecision, HookSample};
+use crate::sla::SlaMonitor;
+use crate::stats_state::RouteStats;
+
 const MAX_SAMPLES: usize = 32;
 
+// The key of the zenoh attachment carrying the source timestamp of a sample routed from DDS
+// (in nanoseconds since UNIX epoch, as i64 LE), if preserve_source_timestamp is configured
+pub(crate) const SOURCE_TIMESTAMP_ATTACHMENT_KEY: &str = "dds_source_timestamp";
+
+// Return the attachment of a sample routed from DDS, carrying its DDS source timestamp (unless invalid, i.e. unknown)
+// and the compression of its payload, or None if there is none of those
+fn sample_attachment(
+    source_timestamp: Option<i64>,
+    compression: Option<Compression>,
+) -> Option<Attachment> {
+    let source_timestamp = source_timestamp.filter(|t| *t >= 0);
+    if source_timestamp.is_none() && compression.is_none() {
+        return None;
+    }
+    let mut builder = AttachmentBuilder::new();
+    if let Some(source_timestamp) = source_timestamp {
+        builder.insert(
+            SOURCE_TIMESTAMP_ATTACHMENT_KEY,
+            &source_timestamp.to_le_bytes(),
+        );
+    }
+    if let Some(compression) = compression {
+        builder.insert(
+            COMPRESSION_ATTACHMENT_KEY,
+            compression.to_string().as_bytes(),
+        );
+    }
+    Some(builder.build())
+}
+
+// Return the DDS source timestamp carried by an attachment, if any
+pub(crate) fn get_source_timestamp(attachment: &Attachment) -> Option<i64> {
+    let bytes = attachment.get(&SOURCE_TIMESTAMP_ATTACHMENT_KEY)?;
+    let bytes: [u8; 8] = bytes[..].try_into().ok()?;
+    Some(i64::from_le_bytes(bytes)).filter(|t| *t >= 0)
+}
+
+// An error returned by a CycloneDDS function, with the failed operation and its (negative) DDS retcode.
+// The callers can match on the retcode to distinguish e.g. a transient failure from a permanent one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DdsError {
+    CreateParticipant(dds_return_t),
+    CreateTopic(dds_return_t),
+    CreateTopicDescriptor(dds_return_t),
+    CreateReader(dds_return_t),
+    CreateWriter(dds_return_t),
+    Delete(dds_return_t),
+    GetGuid(dds_return_t),
+    GetInstanceHandle(dds_return_t),
+}
+
+impl DdsError {
+    pub fn retcode(&self) -> dds_return_t {
+        match self {
+            DdsError::CreateParticipant(r)
+            | DdsError::CreateTopic(r)
+            | DdsError::CreateTopicDescriptor(r)
+            | DdsError::CreateReader(r)
+            | DdsError::CreateWriter(r)
+            | DdsError::Delete(r)
+            | DdsError::GetGuid(r)
+            | DdsError::GetInstanceHandle(r) => *r,
+        }
+    }
+
+    // Return true if the failed operation can't succeed if retried (e.g. invalid parameters or QoS),
+    // false if the failure might be transient (e.g. out of resources)
+    pub fn is_permanent(&self) -> bool {
+        matches!(
+            self.retcode(),
+            DDS_RETCODE_BAD_PARAMETER
+                | DDS_RETCODE_UNSUPPORTED
+                | DDS_RETCODE_PRECONDITION_NOT_MET
+                | DDS_RETCODE_ALREADY_DELETED
+                | DDS_RETCODE_ILLEGAL_OPERATION
+                | DDS_RETCODE_INCONSISTENT_POLICY
+                | DDS_RETCODE_IMMUTABLE_POLICY
+        )
+    }
+
+    // The human-readable description of the retcode, as provided by CycloneDDS
+    pub fn message(&self) -> String {
+        unsafe {
+            CStr::from_ptr(dds_strretcode(-self.retcode()))
+                .to_str()
+                .unwrap_or("unrecoverable DDS retcode")
+                .to_string()
+        }
+    }
+}
+
+impl fmt::Display for DdsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DdsError::CreateParticipant(_) => {
+                write!(f, "Error creating DDS Participant: {}", self.message())
+            }
+            DdsError::CreateTopic(_) => write!(f, "Error creating DDS Topic: {}", self.message()),
+            DdsError::CreateTopicDescriptor(r) => {
+                write!(f, "Error creating the topic descriptor - retcode={r}")
+            }
+            DdsError::CreateReader(_) => write!(f, "Error creating DDS Reader: {}", self.message()),
+            DdsError::CreateWriter(_) => write!(f, "Error creating DDS Writer: {}", self.message()),
+            DdsError::Delete(r) => write!(f, "Error deleting DDS entity - retcode={r}"),
+            DdsError::GetGuid(r) => write!(f, "Error getting GUID of DDS entity - retcode={r}"),
+            DdsError::GetInstanceHandle(r) => write!(
+                f,
+                "Error getting instance handle of DDS Participant - retcode={r}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DdsError {}
+
+// For the callers reporting the errors as strings (e.g. in the routes statuses)
+impl From<DdsError> for String {
+    fn from(e: DdsError) -> Self {
+        e.to_string()
+    }
+}
+
+// The failure of the creation of a route: of its DDS entity (with the DDS retcode), or of its zenoh entity
+#[derive(Debug)]
+pub(crate) enum RouteError {
+    Dds(DdsError),
+    Zenoh(String),
+}
+
+impl RouteError {
+    // Return true if the route creation can't succeed if retried
+    pub(crate) fn is_permanent(&self) -> bool {
+        match self {
+            RouteError::Dds(e) => e.is_permanent(),
+            RouteError::Zenoh(_) => false,
+        }
+    }
+
+    // The DDS retcode of the failure, if it's a DDS failure
+    pub(crate) fn retcode(&self) -> Option<dds_return_t> {
+        match self {
+            RouteError::Dds(e) => Some(e.retcode()),
+            RouteError::Zenoh(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::Dds(e) => write!(f, "{e}"),
+            RouteError::Zenoh(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<DdsError> for RouteError {
+    fn from(e: DdsError) -> Self {
+        RouteError::Dds(e)
+    }
+}
+
+impl From<String> for RouteError {
+    fn from(e: String) -> Self {
+        RouteError::Zenoh(e)
+    }
+}
+
+// Marker at the beginning of a zenoh payload containing a DDS coherent set (i.e. several serialized samples).
+// It can't be confused with a single serialized sample, since those start with a CDR encapsulation
+// identifier having 0x00 or 0x80 as first byte.
+const COHERENT_SET_MAGIC: &[u8; 4] = b"ZDCS";
+
+// Marker at the beginning of a zenoh payload notifying a change of state of a DDS instance (dispose or unregister)
+const INSTANCE_STATE_MAGIC: &[u8; 4] = b"ZDIS";
+
+// Marker at the beginning of a zenoh payload containing a batch of samples (see 'batch' configuration)
+const BATCH_MAGIC: &[u8; 4] = b"ZDSB";
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub(crate) enum RouteStatus {
+pub enum RouteStatus {
     Routed(OwnedKeyExpr), // Routing is active, with the zenoh key expression used for the route
     NotAllowed,           // Routing was not allowed per configuration
-    CreationFailure(String), // The route creation failed
-    _QoSConflict,         // A route was already established but with conflicting QoS
+    // The route creation failed (retried with a backoff if not permanent, see route_creation_max_attempts)
+    CreationFailure {
+        error: String,
+        permanent: bool,
+        attempts: u32,
+    },
+    // A route was already established but with conflicting QoS (it's still used, with its own QoS)
+    QoSConflict {
+        route: OwnedKeyExpr,
+        conflicts: String,
+    },
+    // A route was already established for the topic but with another type (it's not used for this entity)
+    TypeConflict {
+        route: OwnedKeyExpr,
+        route_type: String,
+        type_name: String,
+    },
+    // The route was not created (or was evicted) as the max number of routes is reached (see max_routes):
+    // it's created once some routes are deleted
+    Throttled,
+    // The route creation was delayed as per route_creation_limit: it's created once the rate allows it
+    RateLimited,
+    // The route would be created with this zenoh key expression, but dry_run is configured (nothing is routed)
+    DryRun(OwnedKeyExpr),
+}
+
+impl RouteStatus {
+    // The status of an entity whose route creation failed (at its 1st attempt)
+    pub(crate) fn creation_failure(e: &RouteError) -> RouteStatus {
+        RouteStatus::CreationFailure {
+            error: e.to_string(),
+            permanent: e.is_permanent(),
+            attempts: 1,
+        }
+    }
+
+    // Return the key expression of the route serving the entity (if any)
+    pub(crate) fn route_key(&self) -> Option<&OwnedKeyExpr> {
+        match self {
+            RouteStatus::Routed(ke) | RouteStatus::QoSConflict { route: ke, .. } => Some(ke),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -69,6 +309,8 @@ pub(crate) struct DdsEntity {
     pub(crate) key: String,
     pub(crate) participant_key: String,
     pub(crate) topic_name: String,
+    // the topic name after normalization, if different (see 'topic_normalization' config)
+    pub(crate) normalized_topic_name: Option<String>,
     pub(crate) type_name: String,
     #[serde(skip)]
     pub(crate) type_info: Option<TypeInfo>,
@@ -124,6 +366,31 @@ impl IoxChunk {
     }
 }
 
+// The size of the encapsulation header of a serialized sample. Whatever the representation identifier
+// (CDR, PL_CDR, XCDR2...), it's made of 2 bytes for the identifier and 2 bytes for the options
+// (see DDS-XTypes 7.6.3.1.2).
+const CDR_HEADER_SIZE: usize = 4;
+
+// Return the bytes referenced by an iovec (its length being an u32 on Windows, and an usize elsewhere)
+fn iovec_as_slice(iov: &ddsrt_iovec_t) -> &[u8] {
+    if iov.iov_base.is_null() {
+        return &[];
+    }
+    #[cfg(not(target_os = "windows"))]
+    unsafe {
+        slice::from_raw_parts(iov.iov_base as *const u8, iov.iov_len)
+    }
+    #[cfg(target_os = "windows")]
+    unsafe {
+        slice::from_raw_parts(iov.iov_base as *const u8, iov.iov_len as usize)
+    }
+}
+
+// Return the payload of a serialized sample, without its encapsulation header (empty if the sample is truncated)
+fn cdr_payload(data: &[u8]) -> &[u8] {
+    data.get(CDR_HEADER_SIZE..).unwrap_or_default()
+}
+
 pub(crate) struct DDSRawSample {
     sdref: *mut ddsi_serdata,
     data: ddsrt_iovec_t,
@@ -189,35 +456,33 @@ impl DDSRawSample {
         return DDSRawSample { sdref, data };
     }
 
-    fn data_as_slice(&self) -> &[u8] {
-        #[cfg(not(target_os = "windows"))]
-        unsafe {
-            slice::from_raw_parts(self.data.iov_base as *const u8, self.data.iov_len)
-        }
-        #[cfg(target_os = "windows")]
-        unsafe {
-            slice::from_raw_parts(self.data.iov_base as *const u8, self.data.iov_len as usize)
+    // Return a copy of the full serialized sample (including the CDR header)
+    pub(crate) fn to_vec(&self) -> Vec<u8> {
+        #[allow(unused_mut)]
+        let mut v = self.data_as_slice().to_vec();
+        #[cfg(feature = "dds_shm")]
+        {
+            if let Some(iox_chunk) = self.iox_chunk.as_ref() {
+                v.extend_from_slice(iox_chunk.as_slice());
+            }
         }
+        v
     }
 
+    fn data_as_slice(&self) -> &[u8] {
+        iovec_as_slice(&self.data)
+    }
+
+    // Return the serialized sample without its CDR header, for the payload filters and the fields decoding only:
+    // the samples are routed to zenoh with their CDR header (see forwarded_value())
     pub(crate) fn payload_as_slice(&self) -> &[u8] {
-        unsafe {
-            #[cfg(feature = "dds_shm")]
-            {
-                if let Some(iox_chunk) = self.iox_chunk.as_ref() {
-                    return iox_chunk.as_slice();
-                }
-            }
-            #[cfg(not(target_os = "windows"))]
-            {
-                &slice::from_raw_parts(self.data.iov_base as *const u8, self.data.iov_len)[4..]
-            }
-            #[cfg(target_os = "windows")]
-            {
-                &slice::from_raw_parts(self.data.iov_base as *const u8, self.data.iov_len as usize)
-                    [4..]
+        #[cfg(feature = "dds_shm")]
+        {
+            if let Some(iox_chunk) = self.iox_chunk.as_ref() {
+                return iox_chunk.as_slice();
             }
         }
+        cdr_payload(self.data_as_slice())
     }
 
     pub(crate) fn hex_encode(&self) -> String {
@@ -254,62 +519,396 @@ impl fmt::Debug for DDSRawSample {
             if let Some(iox_chunk) = self.iox_chunk {
                 return write!(
                     f,
-                    "[{:02x?}, {:02x?}]",
+                    "[{:02x?}, {:?}]",
                     self.data_as_slice(),
-                    iox_chunk.as_slice()
+                    PayloadLog(iox_chunk.as_slice())
                 );
             }
         }
-        write!(f, "{:02x?}", self.data_as_slice())
+        write!(f, "{:?}", PayloadLog(self.data_as_slice()))
+    }
+}
+
+// A payload to be logged, truncated as configured with the Z_LOG_PAYLOAD_MAX_BYTES environment variable
+pub(crate) struct PayloadLog<'a>(pub(crate) &'a [u8]);
+
+impl fmt::Debug for PayloadLog<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *crate::LOG_PAYLOAD_MAX_BYTES {
+            Some((head, tail)) => fmt_truncated_payload(f, self.0, head, tail),
+            None => write!(f, "{:02x?}", self.0),
+        }
+    }
+}
+
+// Write the payload, with only its "head" first bytes and "tail" last bytes if it's larger than head+tail
+fn fmt_truncated_payload(
+    f: &mut fmt::Formatter<'_>,
+    payload: &[u8],
+    head: usize,
+    tail: usize,
+) -> fmt::Result {
+    let len = payload.len();
+    if len <= head + tail {
+        write!(f, "{payload:02x?}")
+    } else if tail == 0 {
+        write!(f, "{:02x?}... ({len} bytes)", &payload[..head])
+    } else {
+        write!(
+            f,
+            "{:02x?}...{:02x?} ({len} bytes)",
+            &payload[..head],
+            &payload[len - tail..]
+        )
+    }
+}
+
+// The zenoh shared memory segment in which a route publishes the samples received via Iceoryx, if enabled
+// (see zenoh_shm module). It's used only by the route's DDS Reader listener (or downsampling task).
+pub(crate) struct RouteShm {
+    #[cfg(feature = "zenoh_shm")]
+    segment: Option<Mutex<crate::zenoh_shm::ZenohShmSegment>>,
+}
+
+impl RouteShm {
+    fn new() -> Self {
+        RouteShm {
+            #[cfg(feature = "zenoh_shm")]
+            segment: crate::zenoh_shm::route_segment().map(Mutex::new),
+        }
     }
 }
 
-impl From<DDSRawSample> for Value {
-    fn from(buf: DDSRawSample) -> Self {
+impl DDSRawSample {
+    // Return the sample as a zenoh value, without copy unless received via Iceoryx
+    #[cfg_attr(not(feature = "zenoh_shm"), allow(unused_variables))]
+    fn into_value(self, shm: &RouteShm) -> Value {
         #[cfg(feature = "dds_shm")]
         {
-            // Where data was received via Iceoryx return both the header (contained in buf.data) and
-            // payload (contained in buf.iox_chunk) in a buffer.
-            if let Some(iox_chunk) = buf.iox_chunk {
+            // Where data was received via Iceoryx return both the header (contained in self.data) and
+            // payload (contained in self.iox_chunk) in a buffer.
+            if let Some(iox_chunk) = self.iox_chunk {
+                // If zenoh shared memory is available, copy them once in the route's zenoh shared memory segment
+                #[cfg(feature = "zenoh_shm")]
+                if let Some(value) = shm.segment.as_ref().and_then(|segment| {
+                    segment
+                        .lock()
+                        .unwrap()
+                        .to_value(&[self.data_as_slice(), iox_chunk.as_slice()])
+                }) {
+                    return value;
+                }
                 let mut zbuf = ZBuf::default();
-                zbuf.push_zslice(ZSlice::from(buf.data_as_slice().to_vec()));
+                zbuf.push_zslice(ZSlice::from(self.data_as_slice().to_vec()));
                 zbuf.push_zslice(ZSlice::from(iox_chunk.as_slice().to_vec()));
                 return zbuf.into();
             }
         }
-        buf.data_as_slice().into()
+        self.data_as_slice().into()
+    }
+}
+
+// Encode a set of serialized samples as a coherent set payload:
+// COHERENT_SET_MAGIC, then the number of samples (u32 LE), then for each sample its length (u32 LE) and its bytes.
+pub(crate) fn encode_coherent_set<T: AsRef<[u8]>>(samples: &[T]) -> Vec<u8> {
+    let len = samples.iter().map(|s| 4 + s.as_ref().len()).sum::<usize>() + 8;
+    let mut buf = Vec::with_capacity(len);
+    buf.extend_from_slice(COHERENT_SET_MAGIC);
+    buf.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+    for s in samples {
+        let s = s.as_ref();
+        buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+        buf.extend_from_slice(s);
+    }
+    buf
+}
+
+// Decode a coherent set payload into the list of serialized samples.
+// Return None if the payload is not a coherent set, or Some(Err) if it's malformed.
+pub(crate) fn decode_coherent_set(payload: &[u8]) -> Option<Result<Vec<&[u8]>, String>> {
+    fn read_u32(buf: &[u8], offset: usize) -> Result<usize, String> {
+        buf.get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(|| format!("truncated coherent set (at offset {offset})"))
+    }
+
+    if !payload.starts_with(COHERENT_SET_MAGIC) {
+        return None;
+    }
+    let decode = || -> Result<Vec<&[u8]>, String> {
+        let count = read_u32(payload, 4)?;
+        let mut samples = Vec::with_capacity(count.min(MAX_SAMPLES));
+        let mut offset = 8;
+        for _ in 0..count {
+            let len = read_u32(payload, offset)?;
+            offset += 4;
+            let sample = payload
+                .get(offset..offset + len)
+                .ok_or_else(|| format!("truncated coherent set (at offset {offset})"))?;
+            samples.push(sample);
+            offset += len;
+        }
+        Ok(samples)
+    };
+    Some(decode())
+}
+
+// Encode a batch of serialized samples with their source timestamps (-1 if unknown or not preserved) as a payload:
+// BATCH_MAGIC, then the number of samples (u32 LE), then for each sample its source timestamp (i64 LE),
+// its length (u32 LE) and its bytes.
+pub(crate) fn encode_batch(samples: &[(i64, Vec<u8>)]) -> Vec<u8> {
+    let len = samples.iter().map(|(_, s)| 12 + s.len()).sum::<usize>() + 8;
+    let mut buf = Vec::with_capacity(len);
+    buf.extend_from_slice(BATCH_MAGIC);
+    buf.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+    for (source_timestamp, s) in samples {
+        buf.extend_from_slice(&source_timestamp.to_le_bytes());
+        buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+        buf.extend_from_slice(s);
+    }
+    buf
+}
+
+// Decode a batch payload into the list of serialized samples, in their original order, with their source timestamps.
+// Return None if the payload is not a batch, or Some(Err) if it's malformed.
+#[allow(clippy::type_complexity)]
+pub(crate) fn decode_batch(payload: &[u8]) -> Option<Result<Vec<(i64, &[u8])>, String>> {
+    fn read<const N: usize>(buf: &[u8], offset: usize) -> Result<[u8; N], String> {
+        buf.get(offset..offset + N)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| format!("truncated batch (at offset {offset})"))
+    }
+
+    if !payload.starts_with(BATCH_MAGIC) {
+        return None;
+    }
+    let decode = || -> Result<Vec<(i64, &[u8])>, String> {
+        let count = u32::from_le_bytes(read(payload, 4)?) as usize;
+        let mut samples = Vec::with_capacity(count.min(MAX_SAMPLES));
+        let mut offset = 8;
+        for _ in 0..count {
+            let source_timestamp = i64::from_le_bytes(read(payload, offset)?);
+            let len = u32::from_le_bytes(read(payload, offset + 8)?) as usize;
+            offset += 12;
+            let sample = payload
+                .get(offset..offset + len)
+                .ok_or_else(|| format!("truncated batch (at offset {offset})"))?;
+            samples.push((source_timestamp, sample));
+            offset += len;
+        }
+        Ok(samples)
+    };
+    Some(decode())
+}
+
+// A change of state of a DDS instance, routed from DDS to zenoh for the remote bridges to re-inject it in DDS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InstanceStateChange {
+    Disposed,
+    Unregistered,
+}
+
+impl InstanceStateChange {
+    // Return the change of state notified by a sample without valid data (if any)
+    fn from_sample_info(si: &dds_sample_info_t) -> Option<Self> {
+        if si.valid_data {
+            return None;
+        }
+        #[allow(non_upper_case_globals)]
+        match si.instance_state {
+            dds_instance_state_DDS_IST_NOT_ALIVE_DISPOSED => Some(InstanceStateChange::Disposed),
+            dds_instance_state_DDS_IST_NOT_ALIVE_NO_WRITERS => {
+                Some(InstanceStateChange::Unregistered)
+            }
+            _ => None,
+        }
+    }
+}
+
+// Encode a change of state of a DDS instance as a payload:
+// INSTANCE_STATE_MAGIC, then the kind of change (1 byte: 0 for disposed, 1 for unregistered), then the serialized key.
+pub(crate) fn encode_instance_state(change: InstanceStateChange, key: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(INSTANCE_STATE_MAGIC.len() + 1 + key.len());
+    buf.extend_from_slice(INSTANCE_STATE_MAGIC);
+    buf.push(match change {
+        InstanceStateChange::Disposed => 0,
+        InstanceStateChange::Unregistered => 1,
+    });
+    buf.extend_from_slice(key);
+    buf
+}
+
+// Decode a change of state of a DDS instance from a payload, with the serialized key of the instance.
+// Return None if the payload is not an instance state change, or Some(Err) if it's malformed.
+pub(crate) fn decode_instance_state(
+    payload: &[u8],
+) -> Option<Result<(InstanceStateChange, &[u8]), String>> {
+    let payload = payload.strip_prefix(INSTANCE_STATE_MAGIC)?;
+    Some(match payload.split_first() {
+        Some((0, key)) => Ok((InstanceStateChange::Disposed, key)),
+        Some((1, key)) => Ok((InstanceStateChange::Unregistered, key)),
+        Some((kind, _)) => Err(format!("unknown instance state change {kind}")),
+        None => Err("truncated instance state change".into()),
+    })
+}
+
+// The set of DDS Participants created by the bridge, whose entities must be ignored by the discovery
+#[derive(Default)]
+pub(crate) struct LocalParticipants {
+    // the participants' instance handles
+    handles: RwLock<HashSet<dds_instance_handle_t>>,
+    // the participants' GUIDs (hex-encoded)
+    guids: RwLock<HashSet<String>>,
+}
+
+impl LocalParticipants {
+    pub(crate) fn add(&self, handle: dds_instance_handle_t, guid: String) {
+        self.handles.write().unwrap().insert(handle);
+        self.guids.write().unwrap().insert(guid);
+    }
+
+    pub(crate) fn contains_handle(&self, handle: dds_instance_handle_t) -> bool {
+        self.handles.read().unwrap().contains(&handle)
+    }
+
+    pub(crate) fn contains_guid(&self, guid: &str) -> bool {
+        self.guids.read().unwrap().contains(guid)
+    }
+
+    // Add a DDS Participant created by the bridge
+    pub(crate) fn register(&self, dp: dds_entity_t) -> Result<(), DdsError> {
+        let mut handle: dds_instance_handle_t = 0;
+        let ret = unsafe { dds_get_instance_handle(dp, &mut handle) };
+        if ret != 0 {
+            return Err(DdsError::GetInstanceHandle(ret));
+        }
+        self.add(handle, get_guid(&dp)?);
+        Ok(())
+    }
+}
+
+// The number of failures to return the loan of the discovery samples to CycloneDDS
+// (each failure possibly leaking the memory of the samples)
+static DISCOVERY_LOAN_RETURN_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn discovery_loan_return_failures() -> u64 {
+    DISCOVERY_LOAN_RETURN_FAILURES.load(Ordering::Relaxed)
+}
+
+// The queue of the DDS discovery events, from the discovery Listeners to the plugin's main loop.
+// Its channel is bounded if discovery_queue_capacity is configured: when it's full, the events are kept in
+// an overflow backlog (in order) rather than dropped, as a lost undiscovery would leak a route. The backlog
+// is moved to the channel as soon as there is room, i.e. at the next event or after each received event.
+pub(crate) struct DiscoveryQueue {
+    sender: Sender<DiscoveryEvent>,
+    overflow: Mutex<VecDeque<DiscoveryEvent>>,
+    // the max number of events pending in the queue (channel and backlog) since startup
+    high_water_mark: AtomicUsize,
+}
+
+impl DiscoveryQueue {
+    pub(crate) fn new(capacity: Option<usize>) -> (Arc<DiscoveryQueue>, Receiver<DiscoveryEvent>) {
+        let (sender, receiver) = match capacity {
+            Some(capacity) => flume::bounded(capacity),
+            None => flume::unbounded(),
+        };
+        let queue = DiscoveryQueue {
+            sender,
+            overflow: Mutex::new(VecDeque::new()),
+            high_water_mark: AtomicUsize::new(0),
+        };
+        (Arc::new(queue), receiver)
+    }
+
+    pub(crate) fn send(&self, event: DiscoveryEvent) {
+        let mut overflow = self.overflow.lock().unwrap();
+        overflow.push_back(event);
+        self.flush_overflow(&mut overflow);
+    }
+
+    // Move the events of the overflow backlog (if any) to the channel, as far as there is room
+    pub(crate) fn flush(&self) {
+        let mut overflow = self.overflow.lock().unwrap();
+        if !overflow.is_empty() {
+            self.flush_overflow(&mut overflow);
+        }
+    }
+
+    fn flush_overflow(&self, overflow: &mut VecDeque<DiscoveryEvent>) {
+        while let Some(event) = overflow.pop_front() {
+            match self.sender.try_send(event) {
+                Ok(()) => (),
+                Err(TrySendError::Full(event)) => {
+                    if overflow.is_empty() {
+                        warn!(
+                            "DDS discovery events queue is full (capacity: {:?}): keep the next events in a backlog - consider increasing 'discovery_queue_capacity'",
+                            self.sender.capacity()
+                        );
+                    }
+                    overflow.push_front(event);
+                    break;
+                }
+                Err(TrySendError::Disconnected(event)) => {
+                    // the plugin is stopping
+                    debug!(
+                        "Drop DiscoveryEvent as the internal channel is closed: {:?}",
+                        event
+                    );
+                    overflow.clear();
+                    break;
+                }
+            }
+        }
+        self.high_water_mark
+            .fetch_max(self.sender.len() + overflow.len(), Ordering::Relaxed);
+    }
+
+    // The number of events pending in the queue (channel and backlog)
+    pub(crate) fn pending(&self) -> usize {
+        self.sender.len() + self.overflow.lock().unwrap().len()
+    }
+
+    pub(crate) fn high_water_mark(&self) -> usize {
+        self.high_water_mark.load(Ordering::Relaxed)
     }
 }
 
+// The argument of the discovery Listeners: the discovery type, the queue of the discovery events,
+// the local participants and the max number of samples taken at once (see discovery_max_samples configuration)
+type DiscoveryListenerArg = (
+    DiscoveryType,
+    Arc<DiscoveryQueue>,
+    Arc<LocalParticipants>,
+    usize,
+);
+
 unsafe extern "C" fn on_data(dr: dds_entity_t, arg: *mut std::os::raw::c_void) {
-    let btx = Box::from_raw(arg as *mut (DiscoveryType, Sender<DiscoveryEvent>));
+    let btx = Box::from_raw(arg as *mut DiscoveryListenerArg);
     let discovery_type = btx.0;
-    let sender = &btx.1;
-    let dp = dds_get_participant(dr);
-    let mut dpih: dds_instance_handle_t = 0;
-    let _ = dds_get_instance_handle(dp, &mut dpih);
+    let queue = &btx.1;
+    let local_participants = &btx.2;
+    let max_samples = btx.3;
 
-    #[allow(clippy::uninit_assumed_init)]
-    let mut si = MaybeUninit::<[dds_sample_info_t; MAX_SAMPLES]>::uninit();
-    let mut samples: [*mut ::std::os::raw::c_void; MAX_SAMPLES] =
-        [std::ptr::null_mut(); MAX_SAMPLES];
-    samples[0] = std::ptr::null_mut();
+    // the arrays are allocated on heap, as their size is configurable
+    let mut si: Vec<dds_sample_info_t> = Vec::with_capacity(max_samples);
+    let mut samples: Vec<*mut ::std::os::raw::c_void> = vec![std::ptr::null_mut(); max_samples];
 
     let n = dds_take(
         dr,
         samples.as_mut_ptr(),
-        si.as_mut_ptr() as *mut dds_sample_info_t,
-        MAX_SAMPLES,
-        MAX_SAMPLES as u32,
+        si.as_mut_ptr(),
+        max_samples,
+        max_samples as u32,
     );
-    let si = si.assume_init();
+    // dds_take() initialized the first n sample infos
+    si.set_len(n.max(0) as usize);
 
     for i in 0..n {
         match discovery_type {
             DiscoveryType::Publication | DiscoveryType::Subscription => {
                 let sample = samples[i as usize] as *mut dds_builtintopic_endpoint_t;
-                if (*sample).participant_instance_handle == dpih {
-                    // Ignore discovery of entities created by our own participant
+                if local_participants.contains_handle((*sample).participant_instance_handle) {
+                    // Ignore discovery of entities created by our own participants
                     continue;
                 }
                 let is_alive = si[i as usize].instance_state == dds_instance_state_DDS_IST_ALIVE;
@@ -373,6 +972,7 @@ unsafe extern "C" fn on_data(dr: dds_entity_t, arg: *mut std::os::raw::c_void) {
                         key: key.clone(),
                         participant_key: participant_key.clone(),
                         topic_name: String::from(topic_name),
+                        normalized_topic_name: None,
                         type_name: String::from(type_name),
                         keyless,
                         type_info,
@@ -381,20 +981,14 @@ unsafe extern "C" fn on_data(dr: dds_entity_t, arg: *mut std::os::raw::c_void) {
                     };
 
                     if let DiscoveryType::Publication = discovery_type {
-                        send_discovery_event(
-                            sender,
-                            DiscoveryEvent::DiscoveredPublication { entity },
-                        );
+                        queue.send(DiscoveryEvent::DiscoveredPublication { entity });
                     } else {
-                        send_discovery_event(
-                            sender,
-                            DiscoveryEvent::DiscoveredSubscription { entity },
-                        );
+                        queue.send(DiscoveryEvent::DiscoveredSubscription { entity });
                     }
                 } else if let DiscoveryType::Publication = discovery_type {
-                    send_discovery_event(sender, DiscoveryEvent::UndiscoveredPublication { key });
+                    queue.send(DiscoveryEvent::UndiscoveredPublication { key });
                 } else {
-                    send_discovery_event(sender, DiscoveryEvent::UndiscoveredSubscription { key });
+                    queue.send(DiscoveryEvent::UndiscoveredSubscription { key });
                 }
             }
             DiscoveryType::Participant => {
@@ -402,12 +996,8 @@ unsafe extern "C" fn on_data(dr: dds_entity_t, arg: *mut std::os::raw::c_void) {
                 let is_alive = si[i as usize].instance_state == dds_instance_state_DDS_IST_ALIVE;
                 let key = hex::encode((*sample).key.v);
 
-                let mut guid = dds_builtintopic_guid { v: [0; 16] };
-                let _ = dds_get_guid(dp, &mut guid);
-                let guid = hex::encode(guid.v);
-
-                if key == guid {
-                    // Ignore discovery of entities created by our own participant
+                if local_participants.contains_guid(&key) {
+                    // Ignore discovery of our own participants
                     continue;
                 }
 
@@ -420,49 +1010,82 @@ unsafe extern "C" fn on_data(dr: dds_entity_t, arg: *mut std::os::raw::c_void) {
                         qos: Qos::from_qos_native((*sample).qos),
                     };
 
-                    send_discovery_event(sender, DiscoveryEvent::DiscoveredParticipant { entity });
+                    queue.send(DiscoveryEvent::DiscoveredParticipant { entity });
                 } else {
-                    send_discovery_event(sender, DiscoveryEvent::UndiscoveredParticipant { key });
+                    queue.send(DiscoveryEvent::UndiscoveredParticipant { key });
                 }
             }
         }
     }
-    dds_return_loan(dr, samples.as_mut_ptr(), MAX_SAMPLES as i32);
-    Box::into_raw(btx);
-}
-
-fn send_discovery_event(sender: &Sender<DiscoveryEvent>, event: DiscoveryEvent) {
-    if let Err(e) = sender.try_send(event) {
+    let ret = dds_return_loan(dr, samples.as_mut_ptr(), max_samples as i32);
+    if ret < 0 {
+        DISCOVERY_LOAN_RETURN_FAILURES.fetch_add(1, Ordering::Relaxed);
         error!(
-            "INTERNAL ERROR sending DiscoveryEvent to internal channel: {:?}",
-            e
+            "Failed to return the loan of {:?} discovery samples: {} - their memory may be leaked",
+            discovery_type,
+            CStr::from_ptr(dds_strretcode(-ret))
+                .to_str()
+                .unwrap_or("unrecoverable DDS retcode")
         );
     }
+    Box::into_raw(btx);
 }
 
-pub(crate) fn run_discovery(dp: dds_entity_t, tx: Sender<DiscoveryEvent>) {
+// Create the readers on the builtin discovery topics. The DCPSPublication reader is only required
+// to route from DDS (discovering the DDS Writers), and the DCPSSubscription reader to route to DDS
+// (discovering the DDS Readers). The DCPSParticipant reader is always required to identify the local participants.
+pub(crate) fn run_discovery(
+    dp: dds_entity_t,
+    tx: Arc<DiscoveryQueue>,
+    local_participants: Arc<LocalParticipants>,
+    directions: &[RouteDirection],
+    max_samples: usize,
+) {
     unsafe {
-        let ptx = Box::new((DiscoveryType::Publication, tx.clone()));
-        let stx = Box::new((DiscoveryType::Subscription, tx.clone()));
-        let dptx = Box::new((DiscoveryType::Participant, tx));
-        let sub_listener = dds_create_listener(Box::into_raw(ptx) as *mut std::os::raw::c_void);
-        dds_lset_data_available(sub_listener, Some(on_data));
+        if directions.contains(&RouteDirection::FromDds) {
+            let ptx: Box<DiscoveryListenerArg> = Box::new((
+                DiscoveryType::Publication,
+                tx.clone(),
+                local_participants.clone(),
+                max_samples,
+            ));
+            let sub_listener = dds_create_listener(Box::into_raw(ptx) as *mut std::os::raw::c_void);
+            dds_lset_data_available(sub_listener, Some(on_data));
+            let _pr = dds_create_reader(
+                dp,
+                DDS_BUILTIN_TOPIC_DCPSPUBLICATION,
+                std::ptr::null(),
+                sub_listener,
+            );
+        } else {
+            debug!("Routing from DDS is disabled: don't discover the DDS Writers");
+        }
 
-        let _pr = dds_create_reader(
-            dp,
-            DDS_BUILTIN_TOPIC_DCPSPUBLICATION,
-            std::ptr::null(),
-            sub_listener,
-        );
+        if directions.contains(&RouteDirection::ToDds) {
+            let stx: Box<DiscoveryListenerArg> = Box::new((
+                DiscoveryType::Subscription,
+                tx.clone(),
+                local_participants.clone(),
+                max_samples,
+            ));
+            let sub_listener = dds_create_listener(Box::into_raw(stx) as *mut std::os::raw::c_void);
+            dds_lset_data_available(sub_listener, Some(on_data));
+            let _sr = dds_create_reader(
+                dp,
+                DDS_BUILTIN_TOPIC_DCPSSUBSCRIPTION,
+                std::ptr::null(),
+                sub_listener,
+            );
+        } else {
+            debug!("Routing to DDS is disabled: don't discover the DDS Readers");
+        }
 
-        let sub_listener = dds_create_listener(Box::into_raw(stx) as *mut std::os::raw::c_void);
-        dds_lset_data_available(sub_listener, Some(on_data));
-        let _sr = dds_create_reader(
-            dp,
-            DDS_BUILTIN_TOPIC_DCPSSUBSCRIPTION,
-            std::ptr::null(),
-            sub_listener,
-        );
+        let dptx: Box<DiscoveryListenerArg> = Box::new((
+            DiscoveryType::Participant,
+            tx,
+            local_participants,
+            max_samples,
+        ));
 
         let sub_listener = dds_create_listener(Box::into_raw(dptx) as *mut std::os::raw::c_void);
         dds_lset_data_available(sub_listener, Some(on_data));
@@ -475,97 +1098,1724 @@ pub(crate) fn run_discovery(dp: dds_entity_t, tx: Sender<DiscoveryEvent>) {
     }
 }
 
-unsafe extern "C" fn data_forwarder_listener(dr: dds_entity_t, arg: *mut std::os::raw::c_void) {
-    let pa = arg as *mut (String, KeyExpr, Arc<Session>, CongestionControl);
-    let mut zp: *mut ddsi_serdata = std::ptr::null_mut();
-    #[allow(clippy::uninit_assumed_init)]
-    let mut si = MaybeUninit::<[dds_sample_info_t; 1]>::uninit();
-    while dds_takecdr(
-        dr,
-        &mut zp,
-        1,
-        si.as_mut_ptr() as *mut dds_sample_info_t,
-        DDS_ANY_STATE,
-    ) > 0
-    {
-        let si = si.assume_init();
-        if si[0].valid_data {
-            let raw_sample = DDSRawSample::create(zp);
+// The time of the last activity of a route (i.e. of its last routed sample, or of its creation), in milliseconds
+// since the UNIX epoch, to evict the least recently active route when max_routes is reached
+#[derive(Debug, Clone)]
+pub(crate) struct RouteActivity(Arc<AtomicU64>);
 
-            if *crate::LOG_PAYLOAD {
-                tracing::trace!(
-                    "Route data from DDS {} to zenoh key={} - payload: {:02x?}",
-                    &(*pa).0,
-                    &(*pa).1,
-                    raw_sample
-                );
-            } else {
-                tracing::trace!("Route data from DDS {} to zenoh key={}", &(*pa).0, &(*pa).1);
-            }
-            let _ = (*pa)
-                .2
-                .put(&(*pa).1, raw_sample)
-                .congestion_control((*pa).3)
-                .res_sync();
-        }
-        ddsi_serdata_unref(zp);
+impl Default for RouteActivity {
+    fn default() -> Self {
+        let activity = RouteActivity(Arc::new(AtomicU64::new(0)));
+        activity.touch();
+        activity
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-pub(crate) fn create_forwarding_dds_reader(
-    dp: dds_entity_t,
-    topic_name: String,
-    type_name: String,
-    type_info: &Option<TypeInfo>,
-    keyless: bool,
-    mut qos: Qos,
-    z_key: KeyExpr,
-    z: Arc<Session>,
-    read_period: Option<Duration>,
-    congestion_ctrl: CongestionControl,
-) -> Result<dds_entity_t, String> {
-    unsafe {
-        let t = create_topic(dp, &topic_name, &type_name, type_info, keyless);
+impl RouteActivity {
+    pub(crate) fn touch(&self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_millis() as u64);
+        self.0.store(now, Ordering::Relaxed);
+    }
 
-        match read_period {
-            None => {
+    pub(crate) fn last(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Serialize for RouteActivity {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_u64(self.last())
+    }
+}
+
+// The counters updated when routing the data received by a DDS Reader to zenoh
+#[derive(Clone, Default)]
+pub(crate) struct ForwardingCounters {
+    // the number of samples (or coherent sets, or batches) routed to zenoh, and their size in bytes
+    routed_samples: Arc<AtomicU64>,
+    routed_bytes: Arc<AtomicU64>,
+    // the number of samples received by a downsampling Reader since the last period,
+    // and of those overwritten in its history before being routed
+    received_samples: Arc<AtomicU64>,
+    downsampled_samples: Arc<AtomicU64>,
+    // the number of samples that failed to be routed to zenoh
+    put_failures: Arc<AtomicU64>,
+    // the number of samples dropped as their routing to zenoh didn't complete within the put timeout
+    put_timeouts: Arc<AtomicU64>,
+    // the number of samples dropped as the queue of the route was full (see async_forward configuration)
+    async_forward_dropped: Arc<AtomicU64>,
+    // the number of samples dropped on congestion by a route with CongestionControl::Drop and a queue
+    // (without queue, zenoh drops them silently)
+    congestion_drops: Arc<AtomicU64>,
+    // the number of samples dropped as matching the configured payload filter, or not matching the content filter
+    filtered_samples: Arc<AtomicU64>,
+    // the number of samples dropped as expired per the LIFESPAN QoS of their DDS Writer
+    expired_samples: Arc<AtomicU64>,
+    // the number of samples dropped or remapped to another key expression by the sample hook
+    hook_dropped_samples: Arc<AtomicU64>,
+    hook_remapped_samples: Arc<AtomicU64>,
+    // the number of deadlines missed by the DDS Writers, per the DEADLINE QoS requested by the DDS Reader
+    deadline_missed: Arc<AtomicU64>,
+    // the number of samples reported as lost by the DDS Reader (i.e. never delivered by DDS)
+    dds_samples_lost: Arc<AtomicU64>,
+    // the size of the compressed payloads before and after their compression (see compress configuration)
+    uncompressed_bytes: Arc<AtomicU64>,
+    compressed_bytes: Arc<AtomicU64>,
+    // the metrics for SLA evaluation (only if SLA thresholds are configured for the route)
+    sla_monitor: Option<Arc<SlaMonitor>>,
+    // the preview of the last routed sample (only if last_sample_preview is configured for the route)
+    last_sample: Option<Arc<LastSamplePreview>>,
+    // the selection of the DDS Writer whose samples are routed (only if coalesce_writers is configured for the route)
+    writer_selection: Option<Arc<WriterSelection>>,
+    // the bridge-level metrics, counting the samples routed by all the routes
+    bridge_metrics: Arc<BridgeMetrics>,
+    // the time of the last routed sample (or of the creation of the route)
+    last_activity: RouteActivity,
+}
+
+impl ForwardingCounters {
+    pub(crate) fn new(
+        sla_monitor: Option<Arc<SlaMonitor>>,
+        last_sample_preview_max_bytes: Option<usize>,
+        last_sample_decodable: bool,
+        coalesce_writers: bool,
+        bridge_metrics: Arc<BridgeMetrics>,
+    ) -> Self {
+        ForwardingCounters {
+            sla_monitor,
+            last_sample: last_sample_preview_max_bytes.map(|max_bytes| {
+                Arc::new(LastSamplePreview::new(max_bytes, last_sample_decodable))
+            }),
+            writer_selection: coalesce_writers.then(Default::default),
+            bridge_metrics,
+            ..Default::default()
+        }
+    }
+
+    // Return true if a sample published by the DDS Writer with this instance handle must be routed,
+    // i.e. if not coalescing the Writers, or if the Writer is the selected one
+    fn is_from_selected_writer(
+        &self,
+        dr: dds_entity_t,
+        publication_handle: dds_instance_handle_t,
+    ) -> bool {
+        match &self.writer_selection {
+            Some(selection) => selection.accept(dr, publication_handle),
+            None => true,
+        }
+    }
+
+    pub(crate) fn release_selected_writer(&self, guid: &str) -> bool {
+        match &self.writer_selection {
+            Some(selection) => selection.release(guid),
+            None => false,
+        }
+    }
+
+    fn on_sample(&self, si: &dds_sample_info_t, sample: &DDSRawSample) {
+        if let Some(monitor) = &self.sla_monitor {
+            monitor.on_sample(si.source_timestamp);
+        }
+        if let Some(last_sample) = &self.last_sample {
+            last_sample.update(si.source_timestamp, sample.payload_as_slice(), || {
+                sample.to_vec()
+            });
+        }
+    }
+
+    fn on_routed(&self, bytes: usize) {
+        self.last_activity.touch();
+        self.routed_samples.fetch_add(1, Ordering::Relaxed);
+        self.routed_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.bridge_metrics
+            .samples_from_dds
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Called when publications are dropped as the queue of a route with async_forward is full. For a route with
+    // CongestionControl::Drop, the queue stands in for the drops of zenoh on congestion, and counts them as such.
+    // Return true for the first drop of the route.
+    fn on_queue_overflow(&self, congestion_ctrl: CongestionControl, dropped: u64) -> bool {
+        let counter = if congestion_ctrl == CongestionControl::Drop {
+            &self.congestion_drops
+        } else {
+            &self.async_forward_dropped
+        };
+        counter.fetch_add(dropped, Ordering::Relaxed) == 0
+    }
+
+    // Called at each period of a downsampling route, with the number of samples taken from the Reader:
+    // the samples received since the previous period and not taken were overwritten in the Reader's history.
+    fn on_downsampling_period(&self, taken: u64) {
+        let received = self.received_samples.swap(0, Ordering::Relaxed);
+        self.downsampled_samples
+            .fetch_add(received.saturating_sub(taken), Ordering::Relaxed);
+    }
+
+    // Called for each payload compressed (or not, if not smaller once compressed) before its routing
+    fn on_compressed(&self, uncompressed: usize, compressed: usize) {
+        self.uncompressed_bytes
+            .fetch_add(uncompressed as u64, Ordering::Relaxed);
+        self.compressed_bytes
+            .fetch_add(compressed as u64, Ordering::Relaxed);
+    }
+
+    // The ratio of the uncompressed size to the compressed size of the payloads, if compressing
+    fn compression_ratio(&self) -> Option<f64> {
+        match self.compressed_bytes.load(Ordering::Relaxed) {
+            0 => None,
+            compressed => {
+                Some(self.uncompressed_bytes.load(Ordering::Relaxed) as f64 / compressed as f64)
+            }
+        }
+    }
+
+    // Called for each sample dropped by a route downsampling with the "first" strategy
+    fn on_downsampled(&self) {
+        self.downsampled_samples.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Return the source timestamp and the full serialized sample of the last routed sample
+    pub(crate) fn last_sample(&self) -> Result<(i64, Vec<u8>), String> {
+        let last_sample = self
+            .last_sample
+            .as_ref()
+            .ok_or("'last_sample_preview' is not configured for the route")?;
+        let last = last_sample.last.lock().unwrap();
+        let last = last.as_ref().ok_or("no sample routed yet")?;
+        Ok((last.source_timestamp, last.data.clone()))
+    }
+
+    pub(crate) fn throughput(&self) -> RouteThroughput {
+        RouteThroughput {
+            routed_samples: self.routed_samples.load(Ordering::Relaxed),
+            routed_bytes: self.routed_bytes.load(Ordering::Relaxed),
+            dropped_samples: self.put_failures.load(Ordering::Relaxed)
+                + self.put_timeouts.load(Ordering::Relaxed)
+                + self.async_forward_dropped.load(Ordering::Relaxed)
+                + self.congestion_drops.load(Ordering::Relaxed)
+                + self.downsampled_samples.load(Ordering::Relaxed),
+            dds_samples_lost: self.dds_samples_lost.load(Ordering::Relaxed),
+            compression_ratio: self.compression_ratio(),
+        }
+    }
+
+    // The time of the last routed sample (or of the creation of the route), in milliseconds since the UNIX epoch
+    pub(crate) fn last_activity(&self) -> u64 {
+        self.last_activity.last()
+    }
+
+    // The counters to be persisted (see stats_state_file configuration), with the same names than in admin space
+    pub(crate) fn stats(&self) -> RouteStats {
+        let mut stats = RouteStats::from([
+            (
+                "routed_samples".to_string(),
+                self.routed_samples.load(Ordering::Relaxed),
+            ),
+            (
+                "routed_bytes".to_string(),
+                self.routed_bytes.load(Ordering::Relaxed),
+            ),
+            (
+                "downsampled_samples".to_string(),
+                self.downsampled_samples.load(Ordering::Relaxed),
+            ),
+            (
+                "uncompressed_bytes".to_string(),
+                self.uncompressed_bytes.load(Ordering::Relaxed),
+            ),
+            (
+                "compressed_bytes".to_string(),
+                self.compressed_bytes.load(Ordering::Relaxed),
+            ),
+            (
+                "zenoh_put_failures".to_string(),
+                self.put_failures.load(Ordering::Relaxed),
+            ),
+            (
+                "zenoh_put_timeouts".to_string(),
+                self.put_timeouts.load(Ordering::Relaxed),
+            ),
+            (
+                "async_forward_dropped".to_string(),
+                self.async_forward_dropped.load(Ordering::Relaxed),
+            ),
+            (
+                "zenoh_congestion_drops".to_string(),
+                self.congestion_drops.load(Ordering::Relaxed),
+            ),
+            (
+                "filtered_samples".to_string(),
+                self.filtered_samples.load(Ordering::Relaxed),
+            ),
+            (
+                "expired_samples".to_string(),
+                self.expired_samples.load(Ordering::Relaxed),
+            ),
+            (
+                "hook_dropped_samples".to_string(),
+                self.hook_dropped_samples.load(Ordering::Relaxed),
+            ),
+            (
+                "hook_remapped_samples".to_string(),
+                self.hook_remapped_samples.load(Ordering::Relaxed),
+            ),
+            (
+                "deadline_missed".to_string(),
+                self.deadline_missed.load(Ordering::Relaxed),
+            ),
+            (
+                "dds_samples_lost".to_string(),
+                self.dds_samples_lost.load(Ordering::Relaxed),
+            ),
+        ]);
+        if let Some(selection) = &self.writer_selection {
+            stats.insert(
+                "coalesced_samples".to_string(),
+                selection.coalesced_samples.load(Ordering::Relaxed),
+            );
+        }
+        stats
+    }
+
+    // Add the persisted counters to the current ones
+    pub(crate) fn restore_stats(&self, stats: &RouteStats) {
+        if let Some(n) = stats.get("routed_samples") {
+            self.routed_samples.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("routed_bytes") {
+            self.routed_bytes.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("downsampled_samples") {
+            self.downsampled_samples.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("uncompressed_bytes") {
+            self.uncompressed_bytes.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("compressed_bytes") {
+            self.compressed_bytes.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let (Some(selection), Some(n)) = (&self.writer_selection, stats.get("coalesced_samples"))
+        {
+            selection.coalesced_samples.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("zenoh_put_failures") {
+            self.put_failures.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("zenoh_put_timeouts") {
+            self.put_timeouts.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("async_forward_dropped") {
+            self.async_forward_dropped.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("zenoh_congestion_drops") {
+            self.congestion_drops.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("filtered_samples") {
+            self.filtered_samples.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("expired_samples") {
+            self.expired_samples.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("hook_dropped_samples") {
+            self.hook_dropped_samples.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("hook_remapped_samples") {
+            self.hook_remapped_samples.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("deadline_missed") {
+            self.deadline_missed.fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("dds_samples_lost") {
+            self.dds_samples_lost.fetch_add(*n, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Serialize for ForwardingCounters {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("ForwardingCounters", 2)?;
+        s.serialize_field(
+            "routed_samples",
+            &self.routed_samples.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field("routed_bytes", &self.routed_bytes.load(Ordering::Relaxed))?;
+        s.serialize_field(
+            "downsampled_samples",
+            &self.downsampled_samples.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "zenoh_put_failures",
+            &self.put_failures.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "zenoh_put_timeouts",
+            &self.put_timeouts.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "async_forward_dropped",
+            &self.async_forward_dropped.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "zenoh_congestion_drops",
+            &self.congestion_drops.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "filtered_samples",
+            &self.filtered_samples.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "expired_samples",
+            &self.expired_samples.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "hook_dropped_samples",
+            &self.hook_dropped_samples.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "hook_remapped_samples",
+            &self.hook_remapped_samples.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "deadline_missed",
+            &self.deadline_missed.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "dds_samples_lost",
+            &self.dds_samples_lost.load(Ordering::Relaxed),
+        )?;
+        if let Some(ratio) = self.compression_ratio() {
+            s.serialize_field("compression_ratio", &ratio)?;
+        }
+        s.serialize_field("last_activity", &self.last_activity)?;
+        if let Some(last_sample) = &self.last_sample {
+            s.serialize_field("last_sample", &*last_sample.last.lock().unwrap())?;
+        }
+        if let Some(selection) = &self.writer_selection {
+            s.serialize_field("selected_writer", &*selection.guid.lock().unwrap())?;
+            s.serialize_field(
+                "coalesced_samples",
+                &selection.coalesced_samples.load(Ordering::Relaxed),
+            )?;
+        }
+        s.end()
+    }
+}
+
+// The throughput of a route from DDS to zenoh, as exposed in admin space under "route/from_dds/<zenoh_key>/stats".
+// The dropped samples are those that failed to be routed or were overwritten in the history of a downsampling Reader
+// (the samples intentionally not routed per the configuration, e.g. filtered out, are not counted).
+// The DDS samples lost are those that DDS never delivered to the route's Reader.
+// The compression ratio is only present if the route compresses its payloads.
+#[derive(Debug, Serialize, PartialEq)]
+pub(crate) struct RouteThroughput {
+    pub(crate) routed_samples: u64,
+    pub(crate) routed_bytes: u64,
+    pub(crate) dropped_samples: u64,
+    pub(crate) dds_samples_lost: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) compression_ratio: Option<f64>,
+}
+
+// The selection of the single DDS Writer whose samples are routed, when coalescing the Writers of a topic.
+// The first Writer from which a sample is received is selected, and kept until it's undiscovered.
+// The next Writer from which a sample is received is then selected (failover).
+#[derive(Debug, Default)]
+pub(crate) struct WriterSelection {
+    // the instance handle of the selected Writer (0 if none)
+    handle: AtomicU64,
+    // the GUID of the selected Writer (hex-encoded)
+    guid: Mutex<Option<String>>,
+    // the number of samples dropped as not published by the selected Writer
+    coalesced_samples: AtomicU64,
+}
+
+impl WriterSelection {
+    fn accept(&self, dr: dds_entity_t, publication_handle: dds_instance_handle_t) -> bool {
+        let selected = self.handle.load(Ordering::Acquire);
+        if selected == 0 {
+            let mut guid = self.guid.lock().unwrap();
+            // check again under lock, as another sample might have selected its Writer meanwhile
+            if self
+                .handle
+                .compare_exchange(0, publication_handle, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                *guid = get_matched_publication_guid(dr, publication_handle);
+                debug!(
+                    "Coalescing DDS Writers: selected Writer {:?}",
+                    guid.as_deref().unwrap_or("(unknown)")
+                );
+                return true;
+            }
+        }
+        if self.handle.load(Ordering::Acquire) == publication_handle {
+            true
+        } else {
+            self.coalesced_samples.fetch_add(1, Ordering::Relaxed);
+            false
+        }
+    }
+
+    // Unselect the Writer with this GUID if it's the selected one. Return true if it was.
+    fn release(&self, guid: &str) -> bool {
+        let mut selected = self.guid.lock().unwrap();
+        if selected.as_deref() == Some(guid) {
+            *selected = None;
+            self.handle.store(0, Ordering::Release);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Return the GUID of a DDS Writer matched with a Reader, from its instance handle
+fn get_matched_publication_guid(
+    dr: dds_entity_t,
+    publication_handle: dds_instance_handle_t,
+) -> Option<String> {
+    unsafe {
+        let endpoint = dds_get_matched_publication_data(dr, publication_handle);
+        if endpoint.is_null() {
+            return None;
+        }
+        let guid = hex::encode((*endpoint).key.v);
+        dds_builtintopic_free_endpoint(endpoint);
+        Some(guid)
+    }
+}
+
+// The preview of the last sample routed to zenoh, bounded to its first bytes
+#[derive(Debug)]
+pub(crate) struct LastSamplePreview {
+    max_bytes: usize,
+    // true if the type information is known: the full serialized sample is kept to be decoded on admin query
+    decodable: bool,
+    last: Mutex<Option<LastSample>>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct LastSample {
+    // the source timestamp of the sample (in nanoseconds since UNIX epoch, or negative if unknown)
+    source_timestamp: i64,
+    // the size of the payload (without the 4 bytes CDR header)
+    size: usize,
+    // the first bytes of the payload, hex-encoded
+    preview: String,
+    // the full serialized sample (with the CDR header), to be decoded on admin query (empty if not decodable)
+    #[serde(skip)]
+    data: Vec<u8>,
+}
+
+impl LastSamplePreview {
+    fn new(max_bytes: usize, decodable: bool) -> Self {
+        LastSamplePreview {
+            max_bytes,
+            decodable,
+            last: Mutex::new(None),
+        }
+    }
+
+    // Update the last sample with its payload (without CDR header), the full serialized sample being copied
+    // only if decodable
+    fn update(&self, source_timestamp: i64, payload: &[u8], full_sample: impl FnOnce() -> Vec<u8>) {
+        let preview = hex::encode(&payload[..payload.len().min(self.max_bytes)]);
+        let data = if self.decodable {
+            full_sample()
+        } else {
+            Vec::new()
+        };
+        *self.last.lock().unwrap() = Some(LastSample {
+            source_timestamp,
+            size: payload.len(),
+            preview,
+            data,
+        });
+    }
+}
+
+// A zenoh liveliness token declared as long as a route's DDS Reader or Writer
+// is matched with at least 1 DDS Writer or Reader
+pub(crate) struct MatchedLiveliness {
+    z: Arc<Session>,
+    ke: OwnedKeyExpr,
+    token: Mutex<Option<LivelinessToken<'static>>>,
+}
+
+impl MatchedLiveliness {
+    pub(crate) fn new(z: Arc<Session>, ke: OwnedKeyExpr) -> Self {
+        MatchedLiveliness {
+            z,
+            ke,
+            token: Mutex::new(None),
+        }
+    }
+
+    fn update(&self, current_count: u32) {
+        let mut token = self.token.lock().unwrap();
+        if current_count > 0 && token.is_none() {
+            match self.z.liveliness().declare_token(&self.ke).res_sync() {
+                Ok(t) => {
+                    debug!("Matched: declared liveliness token {}", self.ke);
+                    *token = Some(t);
+                }
+                Err(e) => warn!("Failed to declare liveliness token {}: {}", self.ke, e),
+            }
+        } else if current_count == 0 && token.take().is_some() {
+            debug!("Unmatched: undeclared liveliness token {}", self.ke);
+        }
+    }
+
+    // Undeclare the liveliness token (to be called when the DDS entity is deleted)
+    pub(crate) fn clear(&self) {
+        self.update(0);
+    }
+}
+
+// Wait for the historical data of the TRANSIENT_LOCAL DDS Writers matching a newly created DDS Reader,
+// logging the number of matched Writers at the end of the wait
+fn wait_for_historical_data(reader: dds_entity_t, max_wait: Duration, route: &str) {
+    unsafe {
+        let res = dds_reader_wait_for_historical_data(
+            reader,
+            i64::try_from(max_wait.as_nanos()).unwrap_or(qos::DDS_INFINITE_TIME),
+        );
+        if res < 0 && res != -(DDS_RETCODE_TIMEOUT as i32) {
+            tracing::error!(
+                retcode = res,
+                "{}: error calling dds_reader_wait_for_historical_data(): {}",
+                route,
+                CStr::from_ptr(dds_strretcode(-res))
+                    .to_str()
+                    .unwrap_or("unrecoverable DDS retcode")
+            );
+            return;
+        }
+        let mut status = MaybeUninit::<dds_subscription_matched_status_t>::uninit();
+        if dds_get_subscription_matched_status(reader, status.as_mut_ptr()) == DDS_RETCODE_OK as i32
+        {
+            tracing::debug!(
+                "{}: waited for historical data (max {:?}) from {} matched DDS Writer(s)",
+                route,
+                max_wait,
+                status.assume_init().current_count
+            );
+        }
+    }
+}
+
+// Count the samples received by a downsampling Reader (the listener is invoked for each received sample)
+unsafe extern "C" fn count_received_samples(_dr: dds_entity_t, arg: *mut std::os::raw::c_void) {
+    let received = &*(arg as *const Arc<AtomicU64>);
+    received.fetch_add(1, Ordering::Relaxed);
+}
+
+unsafe extern "C" fn on_subscription_matched(
+    _dr: dds_entity_t,
+    status: dds_subscription_matched_status_t,
+    arg: *mut std::os::raw::c_void,
+) {
+    let matched = &*(arg as *const Arc<MatchedLiveliness>);
+    matched.update(status.current_count);
+}
+
+unsafe extern "C" fn on_publication_matched(
+    _dw: dds_entity_t,
+    status: dds_publication_matched_status_t,
+    arg: *mut std::os::raw::c_void,
+) {
+    let matched = &*(arg as *const Arc<MatchedLiveliness>);
+    matched.update(status.current_count);
+}
+
+// The context of a Listener notified of the deadlines missed by the DDS Writers matching a route's DDS Reader
+struct DeadlineMonitor {
+    route: String,
+    // the DEADLINE period requested by the DDS Reader, in nanoseconds
+    deadline: i64,
+    deadline_missed: Arc<AtomicU64>,
+    // the zenoh session and the key expression to publish the missed deadlines on (if deadline_missed_events is configured)
+    events: Option<(Arc<Session>, OwnedKeyExpr)>,
+}
+
+impl DeadlineMonitor {
+    fn on_deadline_missed(&self, total_count: u32, total_count_change: i32) {
+        self.deadline_missed
+            .fetch_add(total_count_change.max(0) as u64, Ordering::Relaxed);
+        warn!(
+            "{}: missed deadline of {:?} ({} missed deadlines in total)",
+            self.route,
+            Duration::from_nanos(self.deadline as u64),
+            total_count
+        );
+        if let Some((z, ke)) = &self.events {
+            let event = deadline_missed_event(&self.route, self.deadline, total_count);
+            if let Err(e) = z.put(ke, event.to_string()).res_sync() {
+                warn!(
+                    "{}: failed to publish missed deadline on {}: {}",
+                    self.route, ke, e
+                );
+            }
+        }
+    }
+}
+
+fn deadline_missed_event(route: &str, deadline: i64, total_count: u32) -> serde_json::Value {
+    serde_json::json!({
+        "route": route,
+        "deadline": deadline,
+        "total_count": total_count,
+    })
+}
+
+unsafe extern "C" fn on_requested_deadline_missed(
+    _dr: dds_entity_t,
+    status: dds_requested_deadline_missed_status_t,
+    arg: *mut std::os::raw::c_void,
+) {
+    let monitor = &*(arg as *const DeadlineMonitor);
+    monitor.on_deadline_missed(status.total_count, status.total_count_change);
+}
+
+// The context of a Listener notified of the samples lost by a route's DDS Reader, i.e. never delivered by DDS
+// (e.g. a gap in the sequence numbers of a best-effort DDS Writer)
+struct SampleLostMonitor {
+    route: String,
+    dds_samples_lost: Arc<AtomicU64>,
+}
+
+impl SampleLostMonitor {
+    fn on_sample_lost(&self, total_count: u32, total_count_change: i32) {
+        self.dds_samples_lost
+            .fetch_add(total_count_change.max(0) as u64, Ordering::Relaxed);
+        debug!(
+            "{}: DDS reported {} lost samples ({} lost samples in total)",
+            self.route, total_count_change, total_count
+        );
+    }
+}
+
+unsafe extern "C" fn on_sample_lost(
+    _dr: dds_entity_t,
+    status: dds_sample_lost_status_t,
+    arg: *mut std::os::raw::c_void,
+) {
+    let monitor = &*(arg as *const SampleLostMonitor);
+    monitor.on_sample_lost(status.total_count, status.total_count_change);
+}
+
+// The downsampling of the data routed from DDS to zenoh
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Downsampling {
+    // the period of the routing
+    pub(crate) period: Duration,
+    // the history depth (per instance) of the DDS Reader, i.e. the max number of samples routed per instance per period
+    pub(crate) depth: i32,
+    // "latest": a periodic task takes the samples from a KEEP_LAST <depth> Reader at each period
+    // "first": the listener routes the first sample per instance per period, and drops the others
+    pub(crate) strategy: DownsamplingStrategy,
+}
+
+// The context of a Listener routing the data received by a DDS Reader to zenoh
+struct DataForwarder {
+    topic_name: String,
+    type_name: String,
+    keyless: bool,
+    z_key: KeyExpr<'static>,
+    z: Arc<Session>,
+    congestion_ctrl: CongestionControl,
+    priority: Priority,
+    express: bool,
+    // if each instance is routed on its own "<z_key>/<instance-keyhash>" sub-key (see keyed_instance_routing)
+    instance_routing: bool,
+    put_timeout: Option<Duration>,
+    preserve_source_timestamp: bool,
+    lifespan: Option<i64>,
+    coherent: bool,
+    payload_filter: Option<PayloadMatcher>,
+    content_filter: Option<ContentFilter>,
+    hook_context: HookContext,
+    profile: ForwardingProfile,
+    counters: ForwardingCounters,
+    // the gate dropping the samples in excess of the max frequency, if downsampling with the "first" strategy
+    interval_gate: Option<Mutex<IntervalGate>>,
+    // the samples accumulated before being published as a single zenoh payload, if batching
+    batch: Option<RouteBatch>,
+    // the zenoh shared memory segment of the route (for the samples received via Iceoryx)
+    zenoh_shm: RouteShm,
+    // the queue of the publications to zenoh, published by a dedicated thread (see async_forward configuration)
+    async_queue: Option<ForwardQueue<QueuedPut>>,
+    // the compression of the payloads published to zenoh (see compress configuration)
+    compression: Option<Compression>,
+}
+
+// The batch of a route (see 'batch' configuration)
+struct RouteBatch {
+    samples: Mutex<SampleBatch>,
+    // held while publishing a batch, so the batches are published in order without holding the samples lock
+    publishing: Mutex<()>,
+    // notified when a sample is added to an empty batch, waking up the flush task
+    started: (Sender<()>, Receiver<()>),
+}
+
+impl RouteBatch {
+    fn new(config: BatchConfig) -> Self {
+        RouteBatch {
+            samples: Mutex::new(SampleBatch::new(config)),
+            publishing: Mutex::new(()),
+            started: flume::bounded(1),
+        }
+    }
+}
+
+// A publication to zenoh queued by a DataForwarder: the key expression, the (uncompressed) value, its source
+// timestamp to be attached and the time it was queued (see put_timeout)
+type QueuedPut = (KeyExpr<'static>, Value, Option<i64>, Instant);
+
+// The period of the check that the DDS Reader of a route with async_forward still exists, while no sample is queued
+const ASYNC_FORWARD_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+// The period of the check that the DDS Reader of a route with batch still exists, while its batch is empty
+const BATCH_IDLE_CHECK_PERIOD: Duration = Duration::from_secs(1);
+
+impl DataForwarder {
+    // Put a value to zenoh, with the DDS source timestamp attached if preserve_source_timestamp is configured,
+    // and its payload compressed if compress is configured
+    fn put<V: Into<Value>>(
+        &self,
+        z_key: &KeyExpr<'static>,
+        value: V,
+        source_timestamp: Option<i64>,
+    ) {
+        let source_timestamp = source_timestamp.filter(|_| self.preserve_source_timestamp);
+        match &self.async_queue {
+            Some(queue) => {
+                let dropped = queue.push((
+                    z_key.clone(),
+                    value.into(),
+                    source_timestamp,
+                    Instant::now(),
+                ));
+                if dropped > 0
+                    && self
+                        .counters
+                        .on_queue_overflow(self.congestion_ctrl, dropped)
+                {
+                    warn!(
+                        topic = %self.topic_name,
+                        keyexpr = %z_key,
+                        direction = "from_dds",
+                        "Route data from DDS to zenoh: the publications to zenoh don't keep up - drop samples per 'async_forward_overflow' (next drops will be only counted in admin space)"
+                    );
+                }
+            }
+            None => self.put_now(z_key, value.into(), source_timestamp, self.congestion_ctrl),
+        }
+    }
+
+    // Compress a value (if compress is configured) and put it to zenoh from the calling thread: for a route with
+    // async_forward, it's the thread publishing the queue, keeping the compression off the DDS listener thread.
+    // The publications queued for a route with async_forward are put with CongestionControl::Block, the congestion
+    // making the queue overflow (and being counted) rather than zenoh silently dropping the publications.
+    fn put_now(
+        &self,
+        z_key: &KeyExpr<'static>,
+        value: Value,
+        source_timestamp: Option<i64>,
+        congestion_ctrl: CongestionControl,
+    ) {
+        let (value, compression) = compress_value(self.compression, value, &self.counters);
+        let attachment = sample_attachment(source_timestamp, compression);
+        put_to_zenoh(
+            &self.z,
+            z_key,
+            value,
+            attachment,
+            congestion_ctrl,
+            self.priority,
+            self.express,
+            &self.counters,
+            &self.topic_name,
+        );
+    }
+
+    // Add a sample to the batch, publishing the batch if full
+    fn add_to_batch(&self, batch: &RouteBatch, source_timestamp: i64, data: Vec<u8>) {
+        let source_timestamp = if self.preserve_source_timestamp {
+            source_timestamp
+        } else {
+            -1
+        };
+        let mut samples = batch.samples.lock().unwrap();
+        let started = samples.deadline().is_none();
+        if samples.push(source_timestamp, data, Instant::now()) {
+            self.put_batch(batch, samples);
+        } else if started {
+            // the flush task might already be notified of a previous batch
+            let _ = batch.started.0.try_send(());
+        }
+    }
+
+    // Publish the pending samples of the batch (if any) as a single zenoh payload, before routing another
+    // publication on the same key expression or when the batch's max latency is reached.
+    // The samples are taken under their lock, but published once released (the samples received meanwhile
+    // starting a new batch), the publishing lock being taken first for the batches to be published in order.
+    fn put_batch(&self, batch: &RouteBatch, mut pending: MutexGuard<SampleBatch>) {
+        let samples = pending.take();
+        if samples.is_empty() {
+            return;
+        }
+        let _publishing = batch.publishing.lock().unwrap();
+        drop(pending);
+        tracing::trace!(
+            "Route batch of {} samples from DDS {} to zenoh key={}",
+            samples.len(),
+            self.topic_name,
+            self.z_key
+        );
+        self.put(&self.z_key, encode_batch(&samples), None);
+    }
+
+    // Publish the pending samples of the batch if its max latency is reached at time "now",
+    // and return the time the next check is due (None if the batch is empty, or if not batching)
+    fn flush_due_batch(&self, now: Instant) -> Option<Instant> {
+        let batch = self.batch.as_ref()?;
+        let pending = batch.samples.lock().unwrap();
+        if pending.is_due(now) {
+            self.put_batch(batch, pending);
+            return None;
+        }
+        pending.deadline()
+    }
+
+    // Publish the pending samples of the batch (if any)
+    fn flush_batch(&self) {
+        if let Some(batch) = &self.batch {
+            self.put_batch(batch, batch.samples.lock().unwrap());
+        }
+    }
+}
+
+// Compress the payload of a value routed from DDS to zenoh, returning the value to publish and the compression
+// to be named in its attachment. The payload is published uncompressed if not smaller once compressed
+// (e.g. a small or already compressed payload), or if empty (e.g. an event of an "event_only" forwarding profile).
+fn compress_value(
+    compression: Option<Compression>,
+    value: Value,
+    counters: &ForwardingCounters,
+) -> (Value, Option<Compression>) {
+    let compression = match compression {
+        Some(compression) => compression,
+        None => return (value, None),
+    };
+    let payload = value.payload.contiguous();
+    if payload.is_empty() {
+        return (value, None);
+    }
+    match compress(compression, &payload) {
+        Ok(compressed) if compressed.len() < payload.len() => {
+            counters.on_compressed(payload.len(), compressed.len());
+            let encoding = value.encoding.clone();
+            (
+                Value::from(compressed).encoding(encoding),
+                Some(compression),
+            )
+        }
+        Ok(_) => {
+            counters.on_compressed(payload.len(), payload.len());
+            (value, None)
+        }
+        Err(e) => {
+            debug!(
+                "Failed to compress a payload with {}, route it uncompressed: {}",
+                compression, e
+            );
+            counters.on_compressed(payload.len(), payload.len());
+            (value, None)
+        }
+    }
+}
+
+// Put a value routed from DDS to zenoh
+#[allow(clippy::too_many_arguments)]
+fn put_to_zenoh(
+    z: &Arc<Session>,
+    z_key: &KeyExpr<'static>,
+    value: Value,
+    attachment: Option<Attachment>,
+    congestion_ctrl: CongestionControl,
+    priority: Priority,
+    express: bool,
+    counters: &ForwardingCounters,
+    topic_name: &str,
+) {
+    let len = value.payload.contiguous().len();
+    let put = z
+        .put(z_key, value)
+        .congestion_control(congestion_ctrl)
+        .priority(priority)
+        .express(express);
+    let result = match attachment {
+        Some(attachment) => put.with_attachment(attachment).res_sync(),
+        None => put.res_sync(),
+    };
+    match result {
+        Ok(()) => counters.on_routed(len),
+        Err(e) => report_put_failure(&counters.put_failures, topic_name, z_key, &e),
+    }
+}
+
+// Return true if a publication pending since "since" (i.e. queued, or taken from the DDS Reader) can no longer be
+// put to zenoh within the put timeout (if any): it's then discarded without being put, and counted as dropped.
+// The puts themselves are never abandoned, so that a publication counted as dropped is never published.
+fn is_put_timed_out(
+    put_timeout: Option<Duration>,
+    since: Instant,
+    counters: &ForwardingCounters,
+    topic_name: &str,
+    z_key: &KeyExpr,
+) -> bool {
+    match put_timeout {
+        Some(timeout) if since.elapsed() > timeout => {
+            if counters.put_timeouts.fetch_add(1, Ordering::Relaxed) == 0 {
+                warn!(
+                    topic = topic_name,
+                    keyexpr = %z_key,
+                    direction = "from_dds",
+                    "Route data from DDS to zenoh: put not completed within {:?} - dropped (next timeouts will be only counted in admin space)",
+                    timeout
+                );
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+// Return true if a sample with this source timestamp is expired at time 'now', as per the Writer's LIFESPAN (if any)
+fn is_expired(lifespan: Option<i64>, source_timestamp: i64, now: i64) -> bool {
+    match lifespan {
+        Some(lifespan) if source_timestamp >= 0 => source_timestamp.saturating_add(lifespan) < now,
+        _ => false,
+    }
+}
+
+// Return true if the sample is expired as per the Writer's LIFESPAN (if any) and must be dropped, counting it
+fn is_lifespan_expired(
+    lifespan: Option<i64>,
+    counters: &ForwardingCounters,
+    si: &dds_sample_info_t,
+) -> bool {
+    if is_expired(lifespan, si.source_timestamp, unsafe { dds_time() }) {
+        counters.expired_samples.fetch_add(1, Ordering::Relaxed);
+        true
+    } else {
+        false
+    }
+}
+
+// Return true if the sample matches the payload filter (if any) or doesn't match the content filter (if any)
+// and must be dropped, counting it
+fn is_filtered_out(
+    payload_filter: &Option<PayloadMatcher>,
+    content_filter: &Option<ContentFilter>,
+    counters: &ForwardingCounters,
+    sample: &DDSRawSample,
+) -> bool {
+    let payload = sample.payload_as_slice();
+    let filtered_out = payload_filter
+        .as_ref()
+        .map_or(false, |matcher| matcher.is_match(payload))
+        || content_filter.as_ref().map_or(false, |filter| {
+            !filter.is_match(sample.data_as_slice(), payload)
+        });
+    if filtered_out {
+        counters.filtered_samples.fetch_add(1, Ordering::Relaxed);
+    }
+    filtered_out
+}
+
+// The context of the sample hook for a route from DDS: the layout of the fields of its samples (derived from the
+// type information of its topic, if known), and the filter of the key expressions its samples can be remapped to
+pub(crate) struct HookContext {
+    pub(crate) fields_layout: Option<FieldsLayout>,
+    pub(crate) remap_filter: KeyFilter,
+}
+
+// Invoke the registered sample hook (if any) on a sample, counting the dropped and remapped samples.
+// A remapping is ignored if not allowed (i.e. for the samples of a coherent set, routed as one publication).
+// A sample remapped to a key expression not allowed by the 'allow' and 'deny' configuration is dropped.
+fn run_sample_hook(
+    topic_name: &str,
+    type_name: &str,
+    z_key: &KeyExpr,
+    hook_context: &HookContext,
+    allow_remap: bool,
+    counters: &ForwardingCounters,
+    sample: &DDSRawSample,
+) -> HookDecision {
+    let hook = match get_sample_hook() {
+        Some(hook) => hook,
+        None => return HookDecision::Forward,
+    };
+    let payload = sample.payload_as_slice();
+    let fields = hook_context
+        .fields_layout
+        .as_ref()
+        .and_then(|layout| layout.decode(sample.data_as_slice(), payload));
+    let decision = hook.on_sample(&HookSample {
+        topic_name,
+        type_name,
+        key_expr: z_key,
+        payload,
+        fields: fields.as_deref(),
+    });
+    match &decision {
+        HookDecision::Forward => (),
+        HookDecision::Drop => {
+            counters
+                .hook_dropped_samples
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        HookDecision::Remap(ke) if !allow_remap => {
+            debug!(
+                "Route data from DDS {} to zenoh key={}: ignore the remapping to {} by the sample hook for a sample of a coherent set",
+                topic_name, z_key, ke
+            );
+            return HookDecision::Forward;
+        }
+        HookDecision::Remap(ke) if !hook_context.remap_filter.is_allowed(ke, type_name) => {
+            debug!(
+                "Route data from DDS {} to zenoh key={}: drop a sample remapped to {} by the sample hook, as not allowed (see your 'allow' or 'deny' configuration)",
+                topic_name, z_key, ke
+            );
+            counters
+                .hook_dropped_samples
+                .fetch_add(1, Ordering::Relaxed);
+            return HookDecision::Drop;
+        }
+        HookDecision::Remap(_) => {
+            counters
+                .hook_remapped_samples
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    decision
+}
+
+// The 2 least significant bits of the encapsulation options (i.e. of the 4th byte of the CDR header)
+// give the number of padding bytes at the end of the serialized payload (see DDS-XTypes 7.6.3.1.2).
+// Remove those bytes and reset the bits accordingly.
+fn trim_cdr_padding(mut buf: Vec<u8>) -> Vec<u8> {
+    if buf.len() >= CDR_HEADER_SIZE {
+        let padding = (buf[3] & 0x03) as usize;
+        if padding > 0 && buf.len() >= CDR_HEADER_SIZE + padding {
+            buf[3] &= !0x03;
+            buf.truncate(buf.len() - padding);
+        }
+    }
+    buf
+}
+
+// Return the bytes of a sample to be routed to zenoh, transformed as per the forwarding profile
+fn forwarded_bytes(profile: &ForwardingProfile, sample: &DDSRawSample) -> Vec<u8> {
+    if profile.event_only {
+        Vec::new()
+    } else if profile.trim_padding {
+        trim_cdr_padding(sample.to_vec())
+    } else {
+        sample.to_vec()
+    }
+}
+
+// Return the "<z_key>/<instance-keyhash>" key expression the instance of a sample of a keyed topic is routed on
+// (see keyed_instance_routing). None if the serdata doesn't provide a keyhash.
+unsafe fn instance_key(
+    z_key: &KeyExpr<'static>,
+    serdata: *const ddsi_serdata,
+) -> Option<KeyExpr<'static>> {
+    let get_keyhash = (*(*serdata).ops).get_keyhash?;
+    let mut keyhash = ddsi_keyhash_t { value: [0; 16] };
+    get_keyhash(serdata, &mut keyhash, false);
+    instance_keyexpr(z_key, &keyhash.value)
+}
+
+// Return the "<z_key>/<keyhash>" key expression of an instance, the keyhash being hex-encoded
+fn instance_keyexpr(z_key: &KeyExpr<'static>, keyhash: &[u8]) -> Option<KeyExpr<'static>> {
+    z_key.join(&hex::encode(keyhash)).ok()
+}
+
+// Return the value of a sample to be routed to zenoh, transformed as per the forwarding profile
+// (without copy if there is no transformation)
+fn forwarded_value(profile: &ForwardingProfile, sample: DDSRawSample, shm: &RouteShm) -> Value {
+    if profile.event_only {
+        Vec::<u8>::new().into()
+    } else if profile.trim_padding {
+        trim_cdr_padding(sample.to_vec()).into()
+    } else {
+        sample.into_value(shm)
+    }
+}
+
+// Count a failed routing of a sample to zenoh, logging only the 1st failure as a warning to not flood the logs
+fn report_put_failure(
+    put_failures: &AtomicU64,
+    topic_name: &str,
+    z_key: &KeyExpr,
+    e: &dyn fmt::Display,
+) {
+    if put_failures.fetch_add(1, Ordering::Relaxed) == 0 {
+        warn!(
+            topic = topic_name,
+            keyexpr = %z_key,
+            direction = "from_dds",
+            "Route data from DDS to zenoh failed: {} (next failures will be only counted in admin space)",
+            e
+        );
+    } else {
+        debug!(
+            topic = topic_name,
+            keyexpr = %z_key,
+            direction = "from_dds",
+            "Route data from DDS to zenoh failed: {}",
+            e
+        );
+    }
+}
+
+unsafe extern "C" fn data_forwarder_listener(dr: dds_entity_t, arg: *mut std::os::raw::c_void) {
+    let fwd = &*(arg as *const DataForwarder);
+    if fwd.coherent {
+        return coherent_data_forwarder(dr, fwd);
+    }
+    let mut zp: *mut ddsi_serdata = std::ptr::null_mut();
+    #[allow(clippy::uninit_assumed_init)]
+    let mut si = MaybeUninit::<[dds_sample_info_t; 1]>::uninit();
+    while dds_takecdr(
+        dr,
+        &mut zp,
+        1,
+        si.as_mut_ptr() as *mut dds_sample_info_t,
+        DDS_ANY_STATE,
+    ) > 0
+    {
+        let si = si.assume_init();
+        if si[0].valid_data
+            && fwd
+                .counters
+                .is_from_selected_writer(dr, si[0].publication_handle)
+        {
+            if is_lifespan_expired(fwd.lifespan, &fwd.counters, &si[0]) {
+                tracing::trace!(
+                    "Drop data from DDS {} expired as per its Writer's LIFESPAN",
+                    fwd.topic_name
+                );
+                ddsi_serdata_unref(zp);
+                continue;
+            }
+            let raw_sample = DDSRawSample::create(zp);
+            if is_filtered_out(
+                &fwd.payload_filter,
+                &fwd.content_filter,
+                &fwd.counters,
+                &raw_sample,
+            ) {
+                tracing::trace!(
+                    "Drop data from DDS {} filtered out by payload or content filter",
+                    fwd.topic_name
+                );
+                ddsi_serdata_unref(zp);
+                continue;
+            }
+            // the max frequency applies to the samples passing the filters
+            if let Some(gate) = &fwd.interval_gate {
+                if !gate
+                    .lock()
+                    .unwrap()
+                    .try_pass(si[0].instance_handle, Instant::now())
+                {
+                    tracing::trace!(
+                        "Drop data from DDS {} in excess of its max frequency",
+                        fwd.topic_name
+                    );
+                    fwd.counters.on_downsampled();
+                    ddsi_serdata_unref(zp);
+                    continue;
+                }
+            }
+            let remapped_key: KeyExpr<'static>;
+            let z_key = match run_sample_hook(
+                &fwd.topic_name,
+                &fwd.type_name,
+                &fwd.z_key,
+                &fwd.hook_context,
+                true,
+                &fwd.counters,
+                &raw_sample,
+            ) {
+                HookDecision::Forward => match &fwd.batch {
+                    // the samples of a routed instance are published on the instance's sub-key
+                    None if fwd.instance_routing => match instance_key(&fwd.z_key, zp) {
+                        Some(ke) => {
+                            remapped_key = ke;
+                            &remapped_key
+                        }
+                        None => &fwd.z_key,
+                    },
+                    // the samples not remapped by the sample hook are routed in batches, if configured
+                    Some(batch) => {
+                        fwd.counters.on_sample(&si[0], &raw_sample);
+                        payload_log::log(
+                            PayloadDirection::FromDds,
+                            &fwd.topic_name,
+                            fwd.z_key.as_str(),
+                            raw_sample.data_as_slice(),
+                        );
+                        tracing::trace!(
+                            "Route data from DDS {} to zenoh key={} in a batch",
+                            fwd.topic_name,
+                            fwd.z_key
+                        );
+                        fwd.add_to_batch(
+                            batch,
+                            si[0].source_timestamp,
+                            forwarded_bytes(&fwd.profile, &raw_sample),
+                        );
+                        ddsi_serdata_unref(zp);
+                        continue;
+                    }
+                    None => &fwd.z_key,
+                },
+                HookDecision::Drop => {
+                    tracing::trace!("Drop data from DDS {} per sample hook", fwd.topic_name);
+                    ddsi_serdata_unref(zp);
+                    continue;
+                }
+                HookDecision::Remap(ke) => {
+                    remapped_key = ke.into();
+                    &remapped_key
+                }
+            };
+            fwd.counters.on_sample(&si[0], &raw_sample);
+
+            let logged_to_file = payload_log::log(
+                PayloadDirection::FromDds,
+                &fwd.topic_name,
+                z_key.as_str(),
+                raw_sample.data_as_slice(),
+            );
+            if *crate::LOG_PAYLOAD && !logged_to_file {
+                tracing::trace!(
+                    "Route data from DDS {} to zenoh key={} - payload: {:02x?}",
+                    fwd.topic_name,
+                    z_key,
+                    raw_sample
+                );
+            } else {
+                tracing::trace!(
+                    "Route data from DDS {} to zenoh key={}",
+                    fwd.topic_name,
+                    z_key
+                );
+            }
+            fwd.put(
+                z_key,
+                forwarded_value(&fwd.profile, raw_sample, &fwd.zenoh_shm),
+                Some(si[0].source_timestamp),
+            );
+        } else if let Some(change) = InstanceStateChange::from_sample_info(&si[0]) {
+            // the instance is no longer alive: its gate state is evicted (not to grow with the instances)
+            if let Some(gate) = &fwd.interval_gate {
+                gate.lock().unwrap().remove(si[0].instance_handle);
+            }
+            if !fwd.keyless
+                && fwd
+                    .counters
+                    .is_from_selected_writer(dr, si[0].publication_handle)
+            {
+                let key = DDSRawSample::create(zp).to_vec();
+                let z_key = if fwd.instance_routing {
+                    instance_key(&fwd.z_key, zp).unwrap_or_else(|| fwd.z_key.clone())
+                } else {
+                    fwd.z_key.clone()
+                };
+                tracing::trace!(
+                    "Route instance {:?} from DDS {} to zenoh key={}",
+                    change,
+                    fwd.topic_name,
+                    z_key
+                );
+                // publish the pending batch first, so the change of state is routed after the preceding samples
+                fwd.flush_batch();
+                fwd.put(
+                    &z_key,
+                    encode_instance_state(change, &key),
+                    Some(si[0].source_timestamp),
+                );
+            }
+        }
+        ddsi_serdata_unref(zp);
+    }
+}
+
+// Route all the samples available in the Reader as one zenoh publication, so the coherent set(s)
+// delivered by a Reader with PRESENTATION coherent_access are routed atomically.
+unsafe fn coherent_data_forwarder(dr: dds_entity_t, fwd: &DataForwarder) {
+    let mut zp: *mut ddsi_serdata = std::ptr::null_mut();
+    #[allow(clippy::uninit_assumed_init)]
+    let mut si = MaybeUninit::<[dds_sample_info_t; 1]>::uninit();
+    let mut samples: Vec<Vec<u8>> = Vec::new();
+    while dds_takecdr(
+        dr,
+        &mut zp,
+        1,
+        si.as_mut_ptr() as *mut dds_sample_info_t,
+        DDS_ANY_STATE,
+    ) > 0
+    {
+        let si = si.assume_init();
+        if si[0].valid_data
+            && fwd
+                .counters
+                .is_from_selected_writer(dr, si[0].publication_handle)
+        {
+            let raw_sample = DDSRawSample::create(zp);
+            if !is_lifespan_expired(fwd.lifespan, &fwd.counters, &si[0])
+                && !is_filtered_out(
+                    &fwd.payload_filter,
+                    &fwd.content_filter,
+                    &fwd.counters,
+                    &raw_sample,
+                )
+                && run_sample_hook(
+                    &fwd.topic_name,
+                    &fwd.type_name,
+                    &fwd.z_key,
+                    &fwd.hook_context,
+                    false,
+                    &fwd.counters,
+                    &raw_sample,
+                ) != HookDecision::Drop
+            {
+                fwd.counters.on_sample(&si[0], &raw_sample);
+                samples.push(forwarded_bytes(&fwd.profile, &raw_sample));
+            }
+        }
+        ddsi_serdata_unref(zp);
+    }
+    if samples.is_empty() {
+        return;
+    }
+
+    tracing::trace!(
+        "Route coherent set of {} samples from DDS {} to zenoh key={}",
+        samples.len(),
+        fwd.topic_name,
+        fwd.z_key
+    );
+    fwd.put(&fwd.z_key, encode_coherent_set(&samples), None);
+}
+
+// If the forwarder is batching, spawn the task publishing the partial batches once their max latency is reached,
+// while the Reader exists. The pending batch is published when the Reader is deleted.
+unsafe fn spawn_batch_flush(reader: dds_entity_t, fwd: Arc<DataForwarder>) {
+    if fwd.batch.is_none() {
+        return;
+    }
+    task::spawn(async move {
+        let batch = match &fwd.batch {
+            Some(batch) => batch,
+            None => return,
+        };
+        // loop while reader's instance handle remain the same
+        // (if reader was deleted, its dds_entity_t value might have been
+        // reused by a new entity... don't trust it! Only trust instance handle)
+        let mut original_handle: dds_instance_handle_t = 0;
+        dds_get_instance_handle(reader, &mut original_handle);
+        let mut handle: dds_instance_handle_t = 0;
+        while dds_get_instance_handle(reader, &mut handle) == DDS_RETCODE_OK as i32 {
+            if handle != original_handle {
+                break;
+            }
+            let now = Instant::now();
+            match fwd.flush_due_batch(now) {
+                Some(next_check) => {
+                    async_std::task::sleep(next_check.saturating_duration_since(now)).await
+                }
+                // the batch is empty: wait for the next one to start (checking that the DDS Reader still exists)
+                None => {
+                    let _ = async_std::future::timeout(
+                        BATCH_IDLE_CHECK_PERIOD,
+                        batch.started.1.recv_async(),
+                    )
+                    .await;
+                }
+            }
+        }
+        fwd.flush_batch();
+    });
+}
+
+// Start the thread publishing to zenoh the publications queued by the DataForwarder of a route with async_forward,
+// until the DDS Reader is deleted
+unsafe fn spawn_async_forward(reader: dds_entity_t, fwd: Arc<DataForwarder>) {
+    if fwd.async_queue.is_none() {
+        return;
+    }
+    let topic_name = fwd.topic_name.clone();
+    let spawned = std::thread::Builder::new()
+        .name("dds-async-fwd".into())
+        .spawn(move || {
+            let queue = match &fwd.async_queue {
+                Some(queue) => queue,
+                None => return,
+            };
+            // loop while reader's instance handle remain the same
+            // (if reader was deleted, its dds_entity_t value might have been
+            // reused by a new entity... don't trust it! Only trust instance handle)
+            let mut original_handle: dds_instance_handle_t = 0;
+            dds_get_instance_handle(reader, &mut original_handle);
+            let mut handle: dds_instance_handle_t = 0;
+            loop {
+                match queue.pop(ASYNC_FORWARD_CHECK_PERIOD) {
+                    Some((z_key, value, source_timestamp, queued_at)) => {
+                        if !is_put_timed_out(
+                            fwd.put_timeout,
+                            queued_at,
+                            &fwd.counters,
+                            &fwd.topic_name,
+                            &z_key,
+                        ) {
+                            fwd.put_now(&z_key, value, source_timestamp, CongestionControl::Block)
+                        }
+                    }
+                    None => {
+                        if dds_get_instance_handle(reader, &mut handle) != DDS_RETCODE_OK as i32
+                            || handle != original_handle
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        error!(
+            "Route DDS->Zenoh ({}): failed to start the thread publishing the queued samples: {}",
+            topic_name, e
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_forwarding_dds_reader(
+    dp: dds_entity_t,
+    topic_name: String,
+    type_name: String,
+    type_info: &Option<TypeInfo>,
+    keyless: bool,
+    mut qos: Qos,
+    z_key: KeyExpr,
+    z: Arc<Session>,
+    downsampling: Option<Downsampling>,
+    batch: Option<BatchConfig>,
+    congestion_ctrl: CongestionControl,
+    priority: Priority,
+    express: bool,
+    instance_routing: bool,
+    async_forward: Option<AsyncForward>,
+    compression: Option<Compression>,
+    put_timeout: Option<Duration>,
+    preserve_source_timestamp: bool,
+    lifespan: Option<i64>,
+    payload_filter: Option<PayloadMatcher>,
+    content_filter: Option<ContentFilter>,
+    hook_context: HookContext,
+    profile: ForwardingProfile,
+    counters: ForwardingCounters,
+    matched_liveliness: Option<Arc<MatchedLiveliness>>,
+    deadline_missed_ke: Option<OwnedKeyExpr>,
+    historical_data_timeout: Duration,
+) -> Result<dds_entity_t, DdsError> {
+    let route = format!("Route DDS->Zenoh ({topic_name} -> {z_key})");
+    // if the DDS Reader requests a DEADLINE, monitor the deadlines missed by the DDS Writers
+    let deadline_monitor = get_deadline(&qos).map(|deadline| DeadlineMonitor {
+        route: route.clone(),
+        deadline,
+        deadline_missed: counters.deadline_missed.clone(),
+        events: deadline_missed_ke.map(|ke| (z.clone(), ke)),
+    });
+    // count the samples that DDS reports as lost, to tell them apart from the ones dropped by the route
+    let sample_lost_monitor = SampleLostMonitor {
+        route: route.clone(),
+        dds_samples_lost: counters.dds_samples_lost.clone(),
+    };
+    let zenoh_shm = RouteShm::new();
+    // the "first" downsampling strategy is implemented by the listener, dropping the samples in excess
+    let (downsampling, interval_gate) = match downsampling {
+        Some(Downsampling {
+            period,
+            strategy: DownsamplingStrategy::First,
+            ..
+        }) => (None, Some(Mutex::new(IntervalGate::new(period)))),
+        downsampling => (downsampling, None),
+    };
+    unsafe {
+        let t = create_topic(dp, &topic_name, &type_name, type_info, keyless)?;
+
+        match downsampling {
+            None => {
                 // Use a Listener to route data as soon as it arrives
-                let arg = Box::new((topic_name, z_key, z, congestion_ctrl));
+                // (the topics routed with a max frequency are not routed as coherent sets)
+                let coherent = interval_gate.is_none() && is_coherent_access(&qos);
+                if coherent {
+                    tracing::debug!(
+                        "Route DDS {} to zenoh key={}: PRESENTATION with coherent_access => route coherent sets atomically",
+                        topic_name,
+                        z_key
+                    );
+                }
+                let batch = match batch {
+                    Some(_) if coherent => {
+                        tracing::debug!(
+                            "{}: coherent sets are routed as such - ignore 'batch' configuration",
+                            route
+                        );
+                        None
+                    }
+                    batch => batch,
+                };
+                if coherent && instance_routing {
+                    tracing::debug!(
+                        "{}: coherent sets are routed as such - ignore 'keyed_instance_routing' configuration",
+                        route
+                    );
+                }
+                let fwd = Arc::new(DataForwarder {
+                    topic_name,
+                    type_name,
+                    keyless,
+                    z_key: z_key.into_owned(),
+                    z,
+                    congestion_ctrl,
+                    priority,
+                    express,
+                    instance_routing: instance_routing && !coherent,
+                    put_timeout,
+                    preserve_source_timestamp,
+                    lifespan,
+                    coherent,
+                    payload_filter,
+                    content_filter,
+                    hook_context,
+                    profile,
+                    counters,
+                    interval_gate,
+                    batch: batch.map(RouteBatch::new),
+                    zenoh_shm,
+                    async_queue: async_forward.map(ForwardQueue::new),
+                    compression,
+                });
                 let sub_listener =
-                    dds_create_listener(Box::into_raw(arg) as *mut std::os::raw::c_void);
+                    dds_create_listener(Arc::into_raw(fwd.clone()) as *mut std::os::raw::c_void);
                 dds_lset_data_available(sub_listener, Some(data_forwarder_listener));
+                if let Some(matched) = matched_liveliness {
+                    dds_lset_subscription_matched_arg(
+                        sub_listener,
+                        Some(on_subscription_matched),
+                        Box::into_raw(Box::new(matched)) as *mut std::os::raw::c_void,
+                        false,
+                    );
+                }
+                if let Some(monitor) = deadline_monitor {
+                    dds_lset_requested_deadline_missed_arg(
+                        sub_listener,
+                        Some(on_requested_deadline_missed),
+                        Box::into_raw(Box::new(monitor)) as *mut std::os::raw::c_void,
+                        false,
+                    );
+                }
+                dds_lset_sample_lost_arg(
+                    sub_listener,
+                    Some(on_sample_lost),
+                    Box::into_raw(Box::new(sample_lost_monitor)) as *mut std::os::raw::c_void,
+                    false,
+                );
                 let qos_native = qos.to_qos_native();
                 let reader = dds_create_reader(dp, t, qos_native, sub_listener);
                 Qos::delete_qos_native(qos_native);
                 if reader >= 0 {
-                    let res = dds_reader_wait_for_historical_data(reader, qos::DDS_100MS_DURATION);
-                    if res < 0 {
-                        tracing::error!(
-                            "Error calling dds_reader_wait_for_historical_data(): {}",
-                            CStr::from_ptr(dds_strretcode(-res))
-                                .to_str()
-                                .unwrap_or("unrecoverable DDS retcode")
-                        );
-                    }
+                    // wait for the historical data in a blocking task, not to delay the discovery loop
+                    task::spawn_blocking(move || {
+                        wait_for_historical_data(reader, historical_data_timeout, &route)
+                    });
+                    spawn_async_forward(reader, fwd.clone());
+                    spawn_batch_flush(reader, fwd);
                     Ok(reader)
                 } else {
-                    Err(format!(
-                        "Error creating DDS Reader: {}",
-                        CStr::from_ptr(dds_strretcode(-reader))
-                            .to_str()
-                            .unwrap_or("unrecoverable DDS retcode")
-                    ))
+                    Err(DdsError::CreateReader(reader))
                 }
             }
-            Some(period) => {
-                // Use a periodic task that takes data to route from a Reader with KEEP_LAST 1
+            Some(Downsampling { period, depth, .. }) => {
+                if batch.is_some() {
+                    tracing::debug!(
+                        "{}: downsampled with the \"latest\" strategy - ignore 'batch' configuration",
+                        route
+                    );
+                }
+                if async_forward.is_some() {
+                    tracing::debug!(
+                        "{}: downsampled with the \"latest\" strategy, routed by a periodic task - ignore 'async_forward' configuration",
+                        route
+                    );
+                }
+                // Use a periodic task that takes data to route from a Reader with KEEP_LAST <depth> (1 by default)
                 qos.history = Some(History {
                     kind: HistoryKind::KEEP_LAST,
-                    depth: 1,
+                    depth,
                 });
+                // a Listener counts the received samples, to deduce the ones overwritten in the Reader's history
+                let sub_listener =
+                    dds_create_listener(Box::into_raw(Box::new(counters.received_samples.clone()))
+                        as *mut std::os::raw::c_void);
+                dds_lset_data_available(sub_listener, Some(count_received_samples));
+                if let Some(matched) = matched_liveliness {
+                    dds_lset_subscription_matched_arg(
+                        sub_listener,
+                        Some(on_subscription_matched),
+                        Box::into_raw(Box::new(matched)) as *mut std::os::raw::c_void,
+                        false,
+                    );
+                }
+                if let Some(monitor) = deadline_monitor {
+                    dds_lset_requested_deadline_missed_arg(
+                        sub_listener,
+                        Some(on_requested_deadline_missed),
+                        Box::into_raw(Box::new(monitor)) as *mut std::os::raw::c_void,
+                        false,
+                    );
+                }
+                dds_lset_sample_lost_arg(
+                    sub_listener,
+                    Some(on_sample_lost),
+                    Box::into_raw(Box::new(sample_lost_monitor)) as *mut std::os::raw::c_void,
+                    false,
+                );
                 let qos_native = qos.to_qos_native();
-                let reader = dds_create_reader(dp, t, qos_native, std::ptr::null());
+                let reader = dds_create_reader(dp, t, qos_native, sub_listener);
+                Qos::delete_qos_native(qos_native);
+                if reader < 0 {
+                    return Err(DdsError::CreateReader(reader));
+                }
                 let z_key = z_key.into_owned();
                 task::spawn(async move {
                     // loop while reader's instance handle remain the same
@@ -574,38 +2824,151 @@ pub(crate) fn create_forwarding_dds_reader(
                     let mut original_handle: dds_instance_handle_t = 0;
                     dds_get_instance_handle(reader, &mut original_handle);
                     let mut handle: dds_instance_handle_t = 0;
+                    // time spent spreading the samples routed in the previous period
+                    let mut spread_time = Duration::ZERO;
                     while dds_get_instance_handle(reader, &mut handle) == DDS_RETCODE_OK as i32 {
                         if handle != original_handle {
                             break;
                         }
 
-                        async_std::task::sleep(period).await;
-                        let mut zp: *mut ddsi_serdata = std::ptr::null_mut();
-                        #[allow(clippy::uninit_assumed_init)]
-                        let mut si = MaybeUninit::<[dds_sample_info_t; 1]>::uninit();
-                        while dds_takecdr(
-                            reader,
-                            &mut zp,
-                            1,
-                            si.as_mut_ptr() as *mut dds_sample_info_t,
-                            DDS_ANY_STATE,
-                        ) > 0
+                        async_std::task::sleep(period.saturating_sub(spread_time)).await;
+                        // the samples to route, with the key expression they're remapped to by the sample hook (if any)
+                        // and their source timestamp (if preserve_source_timestamp is configured)
+                        let taken_at = Instant::now();
+                        let samples: Vec<(Value, Option<KeyExpr<'static>>, Option<i64>)> = {
+                            let mut samples = Vec::new();
+                            let mut taken: u64 = 0;
+                            let mut zp: *mut ddsi_serdata = std::ptr::null_mut();
+                            #[allow(clippy::uninit_assumed_init)]
+                            let mut si = MaybeUninit::<[dds_sample_info_t; 1]>::uninit();
+                            while dds_takecdr(
+                                reader,
+                                &mut zp,
+                                1,
+                                si.as_mut_ptr() as *mut dds_sample_info_t,
+                                DDS_ANY_STATE,
+                            ) > 0
+                            {
+                                taken += 1;
+                                let si = si.assume_init();
+                                let source_timestamp = if preserve_source_timestamp {
+                                    Some(si[0].source_timestamp)
+                                } else {
+                                    None
+                                };
+                                if si[0].valid_data
+                                    && counters
+                                        .is_from_selected_writer(reader, si[0].publication_handle)
+                                {
+                                    tracing::trace!(
+                                        "Route (periodic) data to zenoh resource with rid={}",
+                                        z_key
+                                    );
+
+                                    let raw_sample = DDSRawSample::create(zp);
+                                    if !is_lifespan_expired(lifespan, &counters, &si[0])
+                                        && !is_filtered_out(
+                                            &payload_filter,
+                                            &content_filter,
+                                            &counters,
+                                            &raw_sample,
+                                        )
+                                    {
+                                        let remapped_key = match run_sample_hook(
+                                            &topic_name,
+                                            &type_name,
+                                            &z_key,
+                                            &hook_context,
+                                            true,
+                                            &counters,
+                                            &raw_sample,
+                                        ) {
+                                            HookDecision::Forward if instance_routing => {
+                                                Some(instance_key(&z_key, zp))
+                                            }
+                                            HookDecision::Forward => Some(None),
+                                            HookDecision::Drop => None,
+                                            HookDecision::Remap(ke) => Some(Some(ke.into())),
+                                        };
+                                        if let Some(remapped_key) = remapped_key {
+                                            counters.on_sample(&si[0], &raw_sample);
+                                            samples.push((
+                                                forwarded_value(&profile, raw_sample, &zenoh_shm),
+                                                remapped_key,
+                                                source_timestamp,
+                                            ));
+                                        }
+                                    }
+                                } else if let Some(change) =
+                                    InstanceStateChange::from_sample_info(&si[0])
+                                {
+                                    if !keyless
+                                        && counters.is_from_selected_writer(
+                                            reader,
+                                            si[0].publication_handle,
+                                        )
+                                    {
+                                        let key = DDSRawSample::create(zp).to_vec();
+                                        let instance_ke = if instance_routing {
+                                            instance_key(&z_key, zp)
+                                        } else {
+                                            None
+                                        };
+                                        samples.push((
+                                            encode_instance_state(change, &key).into(),
+                                            instance_ke,
+                                            source_timestamp,
+                                        ));
+                                    }
+                                }
+                                ddsi_serdata_unref(zp);
+                            }
+                            counters.on_downsampling_period(taken);
+                            samples
+                        };
+
+                        // With a depth > 1, the samples taken at once are spread over the period
+                        // (with a depth of 1, there is at most 1 sample per instance: route them at once)
+                        let interval = if depth > 1 && samples.len() > 1 {
+                            period / samples.len() as u32
+                        } else {
+                            Duration::ZERO
+                        };
+                        spread_time = Duration::ZERO;
+                        for (i, (value, remapped_key, source_timestamp)) in
+                            samples.into_iter().enumerate()
                         {
-                            let si = si.assume_init();
-                            if si[0].valid_data {
-                                tracing::trace!(
-                                    "Route (periodic) data to zenoh resource with rid={}",
-                                    z_key
-                                );
-
-                                let raw_sample = DDSRawSample::create(zp);
-
-                                let _ = z
-                                    .put(&z_key, raw_sample)
-                                    .congestion_control(congestion_ctrl)
-                                    .res_sync();
+                            if i > 0 && !interval.is_zero() {
+                                async_std::task::sleep(interval).await;
+                                spread_time += interval;
+                            }
+                            let key = remapped_key.as_ref().unwrap_or(&z_key);
+                            // the samples are not put after the put timeout (not counting the spreading delay)
+                            if congestion_ctrl == CongestionControl::Block
+                                && is_put_timed_out(
+                                    put_timeout,
+                                    taken_at + spread_time,
+                                    &counters,
+                                    &topic_name,
+                                    key,
+                                )
+                            {
+                                continue;
                             }
-                            ddsi_serdata_unref(zp);
+                            let (value, compression) =
+                                compress_value(compression, value, &counters);
+                            let attachment = sample_attachment(source_timestamp, compression);
+                            put_to_zenoh(
+                                &z,
+                                key,
+                                value,
+                                attachment,
+                                congestion_ctrl,
+                                priority,
+                                express,
+                                &counters,
+                                &topic_name,
+                            );
                         }
                     }
                 });
@@ -621,11 +2984,11 @@ unsafe fn create_topic(
     type_name: &str,
     type_info: &Option<TypeInfo>,
     keyless: bool,
-) -> dds_entity_t {
+) -> Result<dds_entity_t, DdsError> {
     let cton = CString::new(topic_name.to_owned()).unwrap().into_raw();
     let ctyn = CString::new(type_name.to_owned()).unwrap().into_raw();
 
-    match type_info {
+    let topic = match type_info {
         None => cdds_create_blob_topic(dp, cton, ctyn, keyless),
         Some(type_info) => {
             let mut descriptor: *mut dds_topic_descriptor_t = std::ptr::null_mut();
@@ -637,75 +3000,163 @@ unsafe fn create_topic(
                 500000000,
                 &mut descriptor,
             );
-            let mut topic: dds_entity_t = 0;
-            if ret == (DDS_RETCODE_OK as i32) {
-                topic = dds_create_topic(dp, descriptor, cton, std::ptr::null(), std::ptr::null());
-                assert!(topic >= 0);
-                dds_delete_topic_descriptor(descriptor);
+            if ret != (DDS_RETCODE_OK as i32) {
+                return Err(DdsError::CreateTopicDescriptor(ret));
             }
+            let topic = dds_create_topic(dp, descriptor, cton, std::ptr::null(), std::ptr::null());
+            dds_delete_topic_descriptor(descriptor);
             topic
         }
+    };
+    if topic < 0 {
+        return Err(DdsError::CreateTopic(topic));
+    }
+    Ok(topic)
+}
+
+// Return the serialization instructions of a type (see dds_opcodes.h in Cyclone DDS),
+// copied from the topic descriptor created from its type information
+pub(crate) fn get_type_ops(dp: dds_entity_t, type_info: &TypeInfo) -> Result<Vec<u32>, DdsError> {
+    unsafe {
+        let mut descriptor: *mut dds_topic_descriptor_t = std::ptr::null_mut();
+        let ret = dds_create_topic_descriptor(
+            dds_find_scope_DDS_FIND_SCOPE_GLOBAL,
+            dp,
+            type_info.ptr,
+            500000000,
+            &mut descriptor,
+        );
+        if ret != (DDS_RETCODE_OK as i32) {
+            return Err(DdsError::CreateTopicDescriptor(ret));
+        }
+        let ops =
+            slice::from_raw_parts((*descriptor).m_ops, (*descriptor).m_nops as usize).to_vec();
+        dds_delete_topic_descriptor(descriptor);
+        Ok(ops)
     }
 }
 
+// Return the QoS of a DDS Writer created by a route to DDS
+pub(crate) fn forwarding_writer_qos(
+    mut qos: Qos,
+    allow_best_effort: bool,
+    qos_override: Option<&Qos>,
+) -> Qos {
+    // force RELIABLE QoS for Writers (#165), unless BEST_EFFORT is explicitly allowed for the topic
+    if let Some(qos::Reliability {
+        kind: qos::ReliabilityKind::BEST_EFFORT,
+        ..
+    }) = &qos.reliability
+    {
+        if !allow_best_effort {
+            // Per DDS specification, the default Reliability value for DataWriters is RELIABLE with max_blocking_time=100ms
+            // Thus just use default value.
+            qos.reliability = None;
+        }
+    }
+    // apply the configured override after, so it can also force BEST_EFFORT
+    if let Some(qos_override) = qos_override {
+        apply_qos_override(&mut qos, qos_override);
+    }
+    qos
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn create_forwarding_dds_writer(
     dp: dds_entity_t,
     topic_name: String,
     type_name: String,
     keyless: bool,
-    mut qos: Qos,
-) -> Result<dds_entity_t, String> {
+    qos: Qos,
+    allow_best_effort: bool,
+    qos_override: Option<&Qos>,
+    entity_name: Option<&str>,
+    matched_liveliness: Option<Arc<MatchedLiveliness>>,
+) -> Result<dds_entity_t, DdsError> {
     let cton = CString::new(topic_name).unwrap().into_raw();
     let ctyn = CString::new(type_name).unwrap().into_raw();
 
     unsafe {
         let t = cdds_create_blob_topic(dp, cton, ctyn, keyless);
 
-        // force RELIABLE QoS for Writers (#165)
-        if let Some(qos::Reliability {
-            kind: qos::ReliabilityKind::BEST_EFFORT,
-            ..
-        }) = &mut qos.reliability
-        {
-            // Per DDS specification, the default Reliability value for DataWriters is RELIABLE with max_blocking_time=100ms
-            // Thus just use default value.
-            qos.reliability = None;
-        }
-
+        let mut qos = forwarding_writer_qos(qos, allow_best_effort, qos_override);
+        qos.entity_name = entity_name.map(|name| EntityName {
+            name: name.to_string(),
+        });
+
+        let listener = match matched_liveliness {
+            Some(matched) => {
+                let l = dds_create_listener(std::ptr::null_mut());
+                dds_lset_publication_matched_arg(
+                    l,
+                    Some(on_publication_matched),
+                    Box::into_raw(Box::new(matched)) as *mut std::os::raw::c_void,
+                    false,
+                );
+                l
+            }
+            None => std::ptr::null_mut(),
+        };
         let qos_native = qos.to_qos_native();
-        let writer: i32 = dds_create_writer(dp, t, qos_native, std::ptr::null_mut());
+        let writer: i32 = dds_create_writer(dp, t, qos_native, listener);
         Qos::delete_qos_native(qos_native);
         if writer >= 0 {
             Ok(writer)
         } else {
-            Err(format!(
-                "Error creating DDS Writer: {}",
-                CStr::from_ptr(dds_strretcode(-writer))
-                    .to_str()
-                    .unwrap_or("unrecoverable DDS retcode")
-            ))
+            Err(DdsError::CreateWriter(writer))
         }
     }
 }
 
-pub fn delete_dds_entity(entity: dds_entity_t) -> Result<(), String> {
+pub fn delete_dds_entity(entity: dds_entity_t) -> Result<(), DdsError> {
     unsafe {
         let r = dds_delete(entity);
         match r {
             0 | DDS_RETCODE_ALREADY_DELETED => Ok(()),
-            e => Err(format!("Error deleting DDS entity - retcode={e}")),
+            e => Err(DdsError::Delete(e)),
         }
     }
 }
 
-pub fn get_guid(entity: &dds_entity_t) -> Result<String, String> {
+// Check the DDS Participant created at startup is valid and usable, returning its GUID.
+// Otherwise, return an actionable error: the failure is either due to the CycloneDDS configuration,
+// either to a CycloneDDS library which is not the one the bridge was built with (e.g. another version
+// found at runtime via LD_LIBRARY_PATH).
+pub(crate) fn check_dds_participant(dp: dds_entity_t, domain: u32) -> Result<String, String> {
+    if dp < 0 {
+        let reason = DdsError::CreateParticipant(dp).message();
+        return Err(format!(
+            "failed to create DDS Participant on domain {domain}: {reason}. Check the CycloneDDS configuration (CYCLONEDDS_URI='{}')",
+            std::env::var("CYCLONEDDS_URI").unwrap_or_default()
+        ));
+    }
+    get_guid(&dp).map_err(|e| {
+        format!(
+            "the DDS Participant created on domain {domain} is not usable ({e}). \
+            The CycloneDDS library loaded at runtime might be incompatible with the one this bridge was built with: \
+            check that no other CycloneDDS library is found first in the libraries path (e.g. via LD_LIBRARY_PATH)"
+        )
+    })
+}
+
+// Check the DDS Participant is still alive, i.e. it was not deleted nor became unusable
+pub(crate) fn check_dds_participant_alive(dp: dds_entity_t) -> Result<(), DdsError> {
+    let mut handle: dds_instance_handle_t = 0;
+    let ret = unsafe { dds_get_instance_handle(dp, &mut handle) };
+    if ret != 0 {
+        return Err(DdsError::GetInstanceHandle(ret));
+    }
+    Ok(())
+}
+
+pub fn get_guid(entity: &dds_entity_t) -> Result<String, DdsError> {
     unsafe {
         let mut guid = dds_guid_t { v: [0; 16] };
         let r = dds_get_guid(*entity, &mut guid);
         if r == 0 {
             Ok(hex::encode(guid.v))
         } else {
-            Err(format!("Error getting GUID of DDS entity - retcode={r}"))
+            Err(DdsError::GetGuid(r))
         }
     }
 }
@@ -719,3 +3170,525 @@ where
         Err(_) => s.serialize_str("UNKOWN_GUID"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cdr_payload, compress_value, deadline_missed_event, decode_batch, decode_coherent_set,
+        decode_instance_state, encode_batch, encode_coherent_set, encode_instance_state,
+        fmt_truncated_payload, forwarding_writer_qos, get_source_timestamp, instance_keyexpr,
+        iovec_as_slice, is_expired, is_put_timed_out, sample_attachment, trim_cdr_padding,
+        DdsError, DeadlineMonitor, DiscoveryEvent, DiscoveryQueue, ForwardingCounters,
+        InstanceStateChange, LastSample, LastSamplePreview, LocalParticipants, RouteError,
+        RouteThroughput, SampleLostMonitor,
+    };
+    use crate::compression::{decompress, get_compression};
+    use crate::config::{Compression, Config};
+    use crate::qos_helpers::{get_deadline, get_lifespan};
+    use cyclors::qos::{Deadline, Lifespan, Qos, Reliability, ReliabilityKind};
+    use cyclors::{
+        ddsrt_iovec_t, DDS_RETCODE_BAD_PARAMETER, DDS_RETCODE_INCONSISTENT_POLICY,
+        DDS_RETCODE_OUT_OF_RESOURCES, DDS_RETCODE_PRECONDITION_NOT_MET, DDS_RETCODE_TIMEOUT,
+    };
+    use std::fmt;
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+    use zenoh::prelude::KeyExpr;
+    use zenoh::publication::CongestionControl;
+
+    #[test]
+    fn test_instance_keyexpr() {
+        let z_key = KeyExpr::try_from("rt/chatter").unwrap();
+        let mut keyhash = [0u8; 16];
+        keyhash[0] = 0x0a;
+        keyhash[15] = 0xff;
+        let ke = instance_keyexpr(&z_key, &keyhash).unwrap();
+        assert_eq!(ke.as_str(), "rt/chatter/0a0000000000000000000000000000ff");
+        // a single chunk below the routing key expression: matched by "rt/chatter/*" but not by a nested topic
+        assert!(KeyExpr::try_from("rt/chatter/*").unwrap().includes(&ke));
+        assert!(!KeyExpr::try_from("rt/chatter/*/*").unwrap().intersects(&ke));
+    }
+
+    #[test]
+    fn test_lifespan_expiration() {
+        let mut qos = Qos::default();
+        assert_eq!(get_lifespan(&qos), None);
+        qos.lifespan = Some(Lifespan { duration: i64::MAX });
+        assert_eq!(get_lifespan(&qos), None);
+
+        // 10ms lifespan
+        qos.lifespan = Some(Lifespan {
+            duration: 10_000_000,
+        });
+        let lifespan = get_lifespan(&qos);
+        assert_eq!(lifespan, Some(10_000_000));
+        let now = 1_700_000_000_000_000_000;
+        assert!(is_expired(lifespan, now - 20_000_000, now));
+        assert!(!is_expired(lifespan, now - 5_000_000, now));
+        assert!(!is_expired(lifespan, now - 10_000_000, now));
+        // no lifespan, or invalid source timestamp (DDS_TIME_INVALID)
+        assert!(!is_expired(None, now - 20_000_000, now));
+        assert!(!is_expired(lifespan, -1, now));
+    }
+
+    #[test]
+    fn test_deadline_missed() {
+        let mut qos = Qos::default();
+        assert_eq!(get_deadline(&qos), None);
+        qos.deadline = Some(Deadline { period: i64::MAX });
+        assert_eq!(get_deadline(&qos), None);
+        qos.deadline = Some(Deadline {
+            period: 100_000_000,
+        });
+        assert_eq!(get_deadline(&qos), Some(100_000_000));
+
+        let counters = ForwardingCounters::default();
+        let monitor = DeadlineMonitor {
+            route: "Route DDS->Zenoh (rt/scan -> rt/scan)".into(),
+            deadline: 100_000_000,
+            deadline_missed: counters.deadline_missed.clone(),
+            events: None,
+        };
+        monitor.on_deadline_missed(2, 2);
+        monitor.on_deadline_missed(3, 1);
+        assert_eq!(counters.deadline_missed.load(Ordering::Relaxed), 3);
+        assert_eq!(counters.stats().get("deadline_missed"), Some(&3));
+
+        assert_eq!(
+            deadline_missed_event("Route DDS->Zenoh (rt/scan -> rt/scan)", 100_000_000, 3),
+            serde_json::json!({
+                "route": "Route DDS->Zenoh (rt/scan -> rt/scan)",
+                "deadline": 100_000_000,
+                "total_count": 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_congestion_drops() {
+        let counters = ForwardingCounters::default();
+        // the queue overflows of a route with CongestionControl::Drop are counted as congestion drops
+        assert!(counters.on_queue_overflow(CongestionControl::Drop, 2));
+        assert!(!counters.on_queue_overflow(CongestionControl::Drop, 1));
+        // and those of a route with CongestionControl::Block as async_forward drops
+        assert!(counters.on_queue_overflow(CongestionControl::Block, 1));
+        assert_eq!(counters.stats().get("zenoh_congestion_drops"), Some(&3));
+        assert_eq!(counters.stats().get("async_forward_dropped"), Some(&1));
+        assert_eq!(counters.throughput().dropped_samples, 4);
+        let admin = serde_json::to_value(&counters).unwrap();
+        assert_eq!(admin["zenoh_congestion_drops"], 3);
+
+        let restored = ForwardingCounters::default();
+        restored.restore_stats(&counters.stats());
+        assert_eq!(restored.congestion_drops.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_put_timeout() {
+        let counters = ForwardingCounters::default();
+        let z_key = KeyExpr::try_from("rt/scan").unwrap();
+        let timeout = Some(Duration::from_millis(100));
+        let now = Instant::now();
+        // only the publications pending for longer than the timeout are dropped, and counted
+        assert!(!is_put_timed_out(
+            timeout, now, &counters, "rt/scan", &z_key
+        ));
+        assert!(!is_put_timed_out(
+            None,
+            now - Duration::from_secs(1),
+            &counters,
+            "rt/scan",
+            &z_key
+        ));
+        assert!(is_put_timed_out(
+            timeout,
+            now - Duration::from_secs(1),
+            &counters,
+            "rt/scan",
+            &z_key
+        ));
+        assert!(is_put_timed_out(
+            timeout,
+            now - Duration::from_millis(200),
+            &counters,
+            "rt/scan",
+            &z_key
+        ));
+        assert_eq!(counters.stats().get("zenoh_put_timeouts"), Some(&2));
+        assert_eq!(counters.throughput().dropped_samples, 2);
+    }
+
+    #[test]
+    fn test_samples_lost() {
+        let counters = ForwardingCounters::default();
+        let monitor = SampleLostMonitor {
+            route: "Route DDS->Zenoh (rt/scan -> rt/scan)".into(),
+            dds_samples_lost: counters.dds_samples_lost.clone(),
+        };
+        monitor.on_sample_lost(2, 2);
+        monitor.on_sample_lost(5, 3);
+        assert_eq!(counters.throughput().dds_samples_lost, 5);
+        assert_eq!(counters.stats().get("dds_samples_lost"), Some(&5));
+
+        // the lost samples are not counted as dropped by the route
+        assert_eq!(counters.throughput().dropped_samples, 0);
+        let restored = ForwardingCounters::default();
+        restored.restore_stats(&counters.stats());
+        assert_eq!(restored.dds_samples_lost.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_source_timestamp_attachment() {
+        let attachment = sample_attachment(Some(1_700_000_000_123_456_789), None).unwrap();
+        assert_eq!(
+            get_source_timestamp(&attachment),
+            Some(1_700_000_000_123_456_789)
+        );
+        assert!(get_compression(&attachment).is_none());
+        // invalid timestamp (DDS_TIME_INVALID)
+        assert!(sample_attachment(Some(-1), None).is_none());
+        assert!(sample_attachment(None, None).is_none());
+
+        let attachment = sample_attachment(Some(-1), Some(Compression::Zstd)).unwrap();
+        assert_eq!(get_source_timestamp(&attachment), None);
+        assert_eq!(get_compression(&attachment), Some(Ok(Compression::Zstd)));
+    }
+
+    #[test]
+    fn test_compress_value() {
+        let counters = ForwardingCounters::default();
+        let data = vec![0u8; 1000];
+        let (value, compression) =
+            compress_value(Some(Compression::Lz4), data.clone().into(), &counters);
+        assert_eq!(compression, Some(Compression::Lz4));
+        let compressed = value.payload.contiguous();
+        assert!(compressed.len() < 100);
+        assert_eq!(
+            decompress(Compression::Lz4, &compressed, data.len()).unwrap(),
+            data
+        );
+
+        // a payload not smaller once compressed is routed uncompressed
+        let (value, compression) =
+            compress_value(Some(Compression::Lz4), vec![1u8, 2, 3].into(), &counters);
+        assert_eq!(compression, None);
+        assert_eq!(&value.payload.contiguous()[..], &[1u8, 2, 3]);
+        // so as an empty payload
+        let (_, compression) =
+            compress_value(Some(Compression::Zstd), Vec::<u8>::new().into(), &counters);
+        assert_eq!(compression, None);
+
+        let ratio = counters.throughput().compression_ratio.unwrap();
+        assert_eq!(ratio, 1003.0 / (compressed.len() + 3) as f64);
+
+        let (_, compression) = compress_value(None, data.into(), &counters);
+        assert_eq!(compression, None);
+        assert_eq!(
+            ForwardingCounters::default().throughput().compression_ratio,
+            None
+        );
+    }
+
+    #[test]
+    fn test_route_throughput() {
+        let counters = ForwardingCounters::default();
+        counters.on_routed(10);
+        counters.on_routed(5);
+        // 4 samples received in the period, 1 taken: 3 were overwritten
+        counters.received_samples.fetch_add(4, Ordering::Relaxed);
+        counters.on_downsampling_period(1);
+        // no sample received in the period
+        counters.on_downsampling_period(0);
+        counters.put_failures.fetch_add(1, Ordering::Relaxed);
+        assert_eq!(
+            counters.throughput(),
+            RouteThroughput {
+                routed_samples: 2,
+                routed_bytes: 15,
+                dropped_samples: 4,
+                dds_samples_lost: 0,
+                compression_ratio: None,
+            }
+        );
+
+        // all the counters are persisted
+        let restored = ForwardingCounters::default();
+        restored.restore_stats(&counters.stats());
+        assert_eq!(restored.throughput(), counters.throughput());
+    }
+
+    #[test]
+    fn test_last_sample_preview() {
+        let preview = LastSamplePreview::new(4, true);
+        assert_eq!(*preview.last.lock().unwrap(), None);
+        let data = vec![0x00, 0x01, 0x00, 0x00, 0xde, 0xad, 0xbe, 0xef, 0x01, 0x02];
+        preview.update(42, cdr_payload(&data), || data.clone());
+        assert_eq!(
+            *preview.last.lock().unwrap(),
+            Some(LastSample {
+                source_timestamp: 42,
+                size: 6,
+                preview: "deadbeef".into(),
+                data,
+            })
+        );
+        preview.update(-1, &[0x2a], || vec![0x00, 0x01, 0x00, 0x00, 0x2a]);
+        assert_eq!(preview.last.lock().unwrap().as_ref().unwrap().preview, "2a");
+
+        // without type information, the full sample is not copied
+        let preview = LastSamplePreview::new(4, false);
+        preview.update(42, cdr_payload(&data), || unreachable!());
+        let last = preview.last.lock().unwrap();
+        assert_eq!(last.as_ref().unwrap().preview, "deadbeef");
+        assert!(last.as_ref().unwrap().data.is_empty());
+    }
+
+    #[test]
+    fn test_large_sample_payload() {
+        // serialized samples around the 64KB boundary, and larger than it
+        for size in [4, 5, 65_535, 65_536, 65_539, 65_540, 65_541, 1_000_003] {
+            let mut data: Vec<u8> = (0..size).map(|i| i as u8).collect();
+            data[0..4].copy_from_slice(&[0x00, 0x01, 0x00, 0x00]);
+            let iov = ddsrt_iovec_t {
+                iov_base: data.as_mut_ptr() as *mut std::ffi::c_void,
+                iov_len: size as _,
+            };
+            let slice = iovec_as_slice(&iov);
+            assert_eq!(slice.len(), size);
+            let payload = cdr_payload(slice);
+            assert_eq!(payload.len(), slice.len() - 4);
+            assert_eq!(payload, &data[4..]);
+        }
+
+        // the encapsulation header has the same size for all the representation identifiers
+        for encapsulation in [0x00u8, 0x01, 0x02, 0x03, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b] {
+            let data = [0x00, encapsulation, 0x00, 0x00, 0x2a];
+            assert_eq!(cdr_payload(&data), &[0x2a]);
+        }
+
+        // truncated sample, or unset iovec
+        assert!(cdr_payload(&[0x00, 0x01]).is_empty());
+        let iov = ddsrt_iovec_t {
+            iov_base: std::ptr::null_mut(),
+            iov_len: 0,
+        };
+        assert!(iovec_as_slice(&iov).is_empty());
+    }
+
+    #[test]
+    fn test_trim_cdr_padding() {
+        // XCDR2 little endian, 3 padding bytes
+        assert_eq!(
+            trim_cdr_padding(vec![0x00, 0x07, 0x00, 0x03, 0x2a, 0x00, 0x00, 0x00]),
+            vec![0x00, 0x07, 0x00, 0x00, 0x2a]
+        );
+        // no padding: unchanged
+        assert_eq!(
+            trim_cdr_padding(vec![0x00, 0x01, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00]),
+            vec![0x00, 0x01, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00]
+        );
+        // inconsistent padding or truncated header: unchanged
+        assert_eq!(
+            trim_cdr_padding(vec![0x00, 0x07, 0x00, 0x03, 0x2a]),
+            vec![0x00, 0x07, 0x00, 0x03, 0x2a]
+        );
+        assert_eq!(trim_cdr_padding(vec![0x00, 0x07]), vec![0x00, 0x07]);
+    }
+
+    #[test]
+    fn test_instance_state_encoding() {
+        let key = [0x00, 0x01, 0x00, 0x00, 0x2a, 0x00, 0x00, 0x00];
+        for change in [
+            InstanceStateChange::Disposed,
+            InstanceStateChange::Unregistered,
+        ] {
+            let buf = encode_instance_state(change, &key);
+            assert_eq!(
+                decode_instance_state(&buf).unwrap().unwrap(),
+                (change, &key[..])
+            );
+        }
+        // a serialized sample or a coherent set is not an instance state change
+        assert!(decode_instance_state(&key).is_none());
+        assert!(decode_instance_state(&encode_coherent_set(&[key])).is_none());
+        // malformed
+        assert!(decode_instance_state(b"ZDIS").unwrap().is_err());
+        assert!(decode_instance_state(b"ZDIS\x07").unwrap().is_err());
+    }
+
+    #[test]
+    fn test_coherent_set_encoding() {
+        let samples: Vec<Vec<u8>> = vec![vec![0, 1, 0, 0, 42], vec![], vec![0, 1, 0, 0, 1, 2, 3]];
+        let buf = encode_coherent_set(&samples);
+        let decoded = decode_coherent_set(&buf).unwrap().unwrap();
+        assert_eq!(decoded, samples);
+
+        // a single serialized sample is not a coherent set
+        assert!(decode_coherent_set(&samples[0]).is_none());
+
+        // a truncated coherent set is an error
+        assert!(decode_coherent_set(&buf[..buf.len() - 1]).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_discovery_queue_overflow() {
+        let (queue, receiver) = DiscoveryQueue::new(Some(2));
+        let key = |i: usize| format!("writer-{i}");
+        for i in 0..5 {
+            queue.send(DiscoveryEvent::UndiscoveredPublication { key: key(i) });
+        }
+        // the events exceeding the capacity are kept in the backlog, not dropped
+        assert_eq!(receiver.len(), 2);
+        assert_eq!(queue.pending(), 5);
+        assert_eq!(queue.high_water_mark(), 5);
+
+        // they are received in order, the backlog being flushed after each received event
+        let mut received = Vec::new();
+        while let Ok(event) = receiver.try_recv() {
+            queue.flush();
+            match event {
+                DiscoveryEvent::UndiscoveredPublication { key } => received.push(key),
+                e => panic!("unexpected event: {e:?}"),
+            }
+        }
+        assert_eq!(received, (0..5).map(key).collect::<Vec<_>>());
+        assert_eq!(queue.pending(), 0);
+        assert_eq!(queue.high_water_mark(), 5);
+    }
+
+    #[test]
+    fn test_batch_encoding() {
+        let samples: Vec<(i64, Vec<u8>)> = vec![
+            (1_700_000_000_000_000_000, vec![0, 1, 0, 0, 42]),
+            (-1, vec![]),
+            (1_700_000_000_000_000_001, vec![0, 1, 0, 0, 1, 2, 3]),
+        ];
+        let buf = encode_batch(&samples);
+        let decoded = decode_batch(&buf).unwrap().unwrap();
+        assert_eq!(
+            decoded,
+            samples
+                .iter()
+                .map(|(t, s)| (*t, s.as_slice()))
+                .collect::<Vec<_>>()
+        );
+
+        // neither a single serialized sample nor a coherent set is a batch
+        assert!(decode_batch(&samples[0].1).is_none());
+        assert!(decode_batch(&encode_coherent_set(&[&samples[0].1])).is_none());
+        assert!(decode_coherent_set(&buf).is_none());
+
+        // a truncated batch is an error
+        assert!(decode_batch(&buf[..buf.len() - 1]).unwrap().is_err());
+        assert!(decode_batch(&buf[..10]).unwrap().is_err());
+        assert!(decode_batch(&encode_batch(&[]))
+            .unwrap()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_truncated_payload() {
+        struct Truncated<'a>(&'a [u8], usize, usize);
+        impl fmt::Debug for Truncated<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt_truncated_payload(f, self.0, self.1, self.2)
+            }
+        }
+        let payload: Vec<u8> = (0..10).collect();
+        assert_eq!(
+            format!("{:?}", Truncated(&payload, 8, 2)),
+            "[00, 01, 02, 03, 04, 05, 06, 07, 08, 09]"
+        );
+        assert_eq!(
+            format!("{:?}", Truncated(&payload, 3, 0)),
+            "[00, 01, 02]... (10 bytes)"
+        );
+        assert_eq!(
+            format!("{:?}", Truncated(&payload, 2, 2)),
+            "[00, 01]...[08, 09] (10 bytes)"
+        );
+    }
+
+    #[test]
+    fn test_dds_error() {
+        let e = DdsError::Delete(DDS_RETCODE_PRECONDITION_NOT_MET);
+        assert_eq!(e.retcode(), DDS_RETCODE_PRECONDITION_NOT_MET);
+        assert!(matches!(
+            e,
+            DdsError::Delete(DDS_RETCODE_PRECONDITION_NOT_MET)
+        ));
+        assert_eq!(
+            e.to_string(),
+            format!("Error deleting DDS entity - retcode={DDS_RETCODE_PRECONDITION_NOT_MET}")
+        );
+        assert_eq!(
+            String::from(DdsError::GetGuid(-3)),
+            "Error getting GUID of DDS entity - retcode=-3"
+        );
+
+        // the failures due to invalid parameters are permanent, not the ones due to resources exhaustion
+        assert!(e.is_permanent());
+        assert!(DdsError::CreateReader(DDS_RETCODE_INCONSISTENT_POLICY).is_permanent());
+        assert!(!DdsError::CreateWriter(DDS_RETCODE_OUT_OF_RESOURCES).is_permanent());
+        assert!(!DdsError::CreateWriter(DDS_RETCODE_TIMEOUT).is_permanent());
+        let route_error = RouteError::from(DdsError::CreateReader(DDS_RETCODE_BAD_PARAMETER));
+        assert!(route_error.is_permanent());
+        assert!(!RouteError::from("failed to declare KeyExpr".to_string()).is_permanent());
+    }
+
+    #[test]
+    fn test_local_participants() {
+        let local_participants = LocalParticipants::default();
+        local_participants.add(1, "0110a3b5c1d2e3f400000000000001c1".into());
+        local_participants.add(2, "0110a3b5c1d2e3f500000000000001c1".into());
+
+        // the entities of all the local participants are recognized, not the others
+        assert!(local_participants.contains_handle(1));
+        assert!(local_participants.contains_handle(2));
+        assert!(!local_participants.contains_handle(3));
+        assert!(local_participants.contains_guid("0110a3b5c1d2e3f400000000000001c1"));
+        assert!(local_participants.contains_guid("0110a3b5c1d2e3f500000000000001c1"));
+        assert!(!local_participants.contains_guid("0110a3b5c1d2e3f600000000000001c1"));
+    }
+
+    #[test]
+    fn test_forwarding_writer_qos() {
+        let config =
+            serde_json::from_str::<Config>(r#"{"allow_best_effort": "rt/camera/.*"}"#).unwrap();
+        let allow_best_effort = |topic: &str| {
+            config
+                .allow_best_effort
+                .as_ref()
+                .map_or(false, |re| re.is_match(topic))
+        };
+        let mut qos = Qos::default();
+        qos.reliability = Some(Reliability {
+            kind: ReliabilityKind::BEST_EFFORT,
+            max_blocking_time: 0,
+        });
+
+        // a matching topic keeps its BEST_EFFORT reliability
+        let matching =
+            forwarding_writer_qos(qos.clone(), allow_best_effort("rt/camera/image"), None);
+        assert_eq!(
+            matching.reliability.map(|r| r.kind),
+            Some(ReliabilityKind::BEST_EFFORT)
+        );
+        // a non-matching topic is upgraded to RELIABLE (i.e. the default reliability of the DDS Writers)
+        let other = forwarding_writer_qos(qos.clone(), allow_best_effort("rt/cmd_vel"), None);
+        assert!(other.reliability.is_none());
+
+        // a configured override still applies after
+        let mut qos_override = Qos::default();
+        qos_override.reliability = Some(Reliability {
+            kind: ReliabilityKind::BEST_EFFORT,
+            max_blocking_time: 0,
+        });
+        let overridden = forwarding_writer_qos(qos, false, Some(&qos_override));
+        assert_eq!(
+            overridden.reliability.map(|r| r.kind),
+            Some(ReliabilityKind::BEST_EFFORT)
+        );
+    }
+}
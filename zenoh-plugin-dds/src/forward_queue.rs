@@ -0,0 +1,111 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The bounded queue decoupling the DDS listener of a route from DDS from its publications to zenoh (see
+// async_forward configuration): the DDS listener pushes the publications and returns immediately, while a
+// dedicated thread pops and publishes them. When the queue is full, either the oldest queued publication or
+// the pushed one is dropped, as per the configured overflow policy.
+use flume::{Receiver, Sender, TrySendError};
+use std::time::Duration;
+
+use crate::config::QueueOverflow;
+
+// The settings of the queue of a route from DDS
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AsyncForward {
+    pub(crate) capacity: usize,
+    pub(crate) overflow: QueueOverflow,
+}
+
+pub(crate) struct ForwardQueue<T> {
+    tx: Sender<T>,
+    rx: Receiver<T>,
+    overflow: QueueOverflow,
+}
+
+impl<T> ForwardQueue<T> {
+    pub(crate) fn new(config: AsyncForward) -> Self {
+        let (tx, rx) = flume::bounded(config.capacity);
+        ForwardQueue {
+            tx,
+            rx,
+            overflow: config.overflow,
+        }
+    }
+
+    // Push an item, returning the number of items dropped as the queue is full
+    pub(crate) fn push(&self, item: T) -> u64 {
+        let item = match self.tx.try_send(item) {
+            Ok(()) => return 0,
+            Err(TrySendError::Full(item)) => item,
+            // the queue holds its receiver: it can't be disconnected
+            Err(TrySendError::Disconnected(_)) => return 1,
+        };
+        match self.overflow {
+            QueueOverflow::DropNewest => 1,
+            QueueOverflow::DropOldest => {
+                let dropped = u64::from(self.rx.try_recv().is_ok());
+                // another DDS listener thread might have filled the freed slot meanwhile
+                match self.tx.try_send(item) {
+                    Ok(()) => dropped,
+                    Err(_) => dropped + 1,
+                }
+            }
+        }
+    }
+
+    // Pop the oldest item, waiting at most for timeout
+    pub(crate) fn pop(&self, timeout: Duration) -> Option<T> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsyncForward, ForwardQueue};
+    use crate::config::QueueOverflow;
+    use std::time::Duration;
+
+    fn drain(queue: &ForwardQueue<u32>) -> Vec<u32> {
+        std::iter::from_fn(|| queue.pop(Duration::ZERO)).collect()
+    }
+
+    #[test]
+    fn test_forward_queue() {
+        let queue = ForwardQueue::new(AsyncForward {
+            capacity: 2,
+            overflow: QueueOverflow::DropOldest,
+        });
+        assert_eq!(queue.push(1), 0);
+        assert_eq!(queue.push(2), 0);
+        assert_eq!(queue.push(3), 1);
+        assert_eq!(queue.push(4), 1);
+        assert_eq!(drain(&queue), vec![3, 4]);
+
+        let queue = ForwardQueue::new(AsyncForward {
+            capacity: 2,
+            overflow: QueueOverflow::DropNewest,
+        });
+        assert_eq!(queue.push(1), 0);
+        assert_eq!(queue.push(2), 0);
+        assert_eq!(queue.push(3), 1);
+        assert_eq!(queue.push(4), 1);
+        assert_eq!(drain(&queue), vec![1, 2]);
+
+        // once popped, the queue accepts new items
+        assert_eq!(queue.push(5), 0);
+        assert_eq!(queue.pop(Duration::from_millis(10)), Some(5));
+        assert_eq!(queue.pop(Duration::from_millis(10)), None);
+    }
+}
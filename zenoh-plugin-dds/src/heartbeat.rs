@@ -0,0 +1,178 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The heartbeat periodically published by the bridge on a zenoh key (see heartbeat_key and heartbeat_period
+// configuration), as a JSON object allowing to detect remotely a stalled bridge or a bridge not keeping up
+// with the DDS discovery.
+use async_std::task::{self, JoinHandle};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use zenoh::prelude::r#async::AsyncResolve;
+use zenoh::prelude::*;
+use zenoh::publication::CongestionControl;
+use zenoh::Session;
+
+use crate::dds_mgt::DiscoveryQueue;
+use crate::metrics::BridgeMetrics;
+
+// The age in microseconds of the latest report of the watchdog monitoring the async runtime
+// (see report_watchdog()), or u64::MAX if no watchdog reported
+pub(crate) static WATCHDOG_REPORT_AGE: AtomicU64 = AtomicU64::new(u64::MAX);
+
+// The number of stalls of the async runtime reported by the watchdog (see report_watchdog_stall()), per severity
+pub(crate) static WATCHDOG_STALLS: [AtomicU64; 3] =
+    [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+// The severity of a stall of the async runtime detected by a watchdog, per the duration without task scheduling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallSeverity {
+    // more than the 1st threshold
+    Info,
+    // more than the 2nd threshold
+    Warn,
+    // more than the watchdog period: the async runtime is stalled
+    Error,
+}
+
+// A stall of the async runtime detected by a watchdog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StallReport {
+    // the duration without task scheduling
+    pub elapsed: Duration,
+    pub severity: StallSeverity,
+}
+
+#[derive(Serialize)]
+struct WatchdogStalls {
+    info: u64,
+    warn: u64,
+    error: u64,
+}
+
+#[derive(Serialize)]
+struct HeartbeatMessage {
+    uptime: f64,
+    routes_from_dds: u64,
+    routes_to_dds: u64,
+    discovery_backlog: usize,
+    discovery_backlog_growing: bool,
+    watchdog_report_age: Option<f64>,
+    watchdog_stalls: Option<WatchdogStalls>,
+}
+
+// The number of consecutive heartbeats with a growing discovery backlog, from which it's reported as growing
+const BACKLOG_GROWTH_THRESHOLD: u32 = 2;
+
+// The tracking of the DDS discovery events backlog between the heartbeats
+#[derive(Default)]
+struct BacklogTracker {
+    previous: usize,
+    growth: u32,
+}
+
+impl BacklogTracker {
+    // Record the current backlog, returning true if it grew for BACKLOG_GROWTH_THRESHOLD consecutive heartbeats
+    fn update(&mut self, backlog: usize) -> bool {
+        if backlog > self.previous {
+            self.growth += 1;
+        } else {
+            self.growth = 0;
+        }
+        self.previous = backlog;
+        self.growth >= BACKLOG_GROWTH_THRESHOLD
+    }
+}
+
+// Spawn the task publishing the heartbeat on "ke" every "period"
+pub(crate) fn spawn_heartbeat(
+    zsession: Arc<Session>,
+    ke: OwnedKeyExpr,
+    period: Duration,
+    start_time: Instant,
+    metrics: Arc<BridgeMetrics>,
+    discovery_queue: Arc<DiscoveryQueue>,
+) -> JoinHandle<()> {
+    task::spawn(async move {
+        tracing::info!(
+            "Publish heartbeat on {} every {} sec",
+            ke,
+            period.as_secs_f32()
+        );
+        let mut tracker = BacklogTracker::default();
+        loop {
+            task::sleep(period).await;
+            let backlog = discovery_queue.pending();
+            let growing = tracker.update(backlog);
+            if growing {
+                tracing::warn!(
+                    "The DDS discovery events backlog keeps growing ({} pending events): the bridge doesn't keep up with the DDS discovery",
+                    backlog
+                );
+            }
+            let watchdog_report_age = match WATCHDOG_REPORT_AGE.load(Ordering::Relaxed) {
+                u64::MAX => None,
+                micros => Some(Duration::from_micros(micros).as_secs_f64()),
+            };
+            let watchdog_stalls = watchdog_report_age.map(|_| WatchdogStalls {
+                info: WATCHDOG_STALLS[StallSeverity::Info as usize].load(Ordering::Relaxed),
+                warn: WATCHDOG_STALLS[StallSeverity::Warn as usize].load(Ordering::Relaxed),
+                error: WATCHDOG_STALLS[StallSeverity::Error as usize].load(Ordering::Relaxed),
+            });
+            let msg = HeartbeatMessage {
+                uptime: start_time.elapsed().as_secs_f64(),
+                routes_from_dds: metrics.routes_from_dds.load(Ordering::Relaxed),
+                routes_to_dds: metrics.routes_to_dds.load(Ordering::Relaxed),
+                discovery_backlog: backlog,
+                discovery_backlog_growing: growing,
+                watchdog_report_age,
+                watchdog_stalls,
+            };
+            match serde_json::to_string(&msg) {
+                Ok(json) => {
+                    if let Err(e) = zsession
+                        .put(&ke, json)
+                        .congestion_control(CongestionControl::Drop)
+                        .res_async()
+                        .await
+                    {
+                        tracing::warn!("Failed to publish heartbeat on {}: {}", ke, e);
+                    }
+                }
+                Err(e) => tracing::error!("INTERNAL ERROR: failed to serialize heartbeat: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BacklogTracker;
+
+    #[test]
+    fn test_backlog_tracker() {
+        let mut tracker = BacklogTracker::default();
+        assert!(!tracker.update(0));
+        // a single growth is not reported
+        assert!(!tracker.update(5));
+        assert!(!tracker.update(3));
+        // consecutive growths are reported until the backlog stops growing
+        assert!(!tracker.update(10));
+        assert!(tracker.update(20));
+        assert!(tracker.update(30));
+        assert!(!tracker.update(30));
+        assert!(!tracker.update(0));
+    }
+}
@@ -13,8 +13,8 @@
 //
 use async_trait::async_trait;
 use cyclors::qos::{
-    DurabilityService, History, IgnoreLocal, IgnoreLocalKind, Qos, Reliability, ReliabilityKind,
-    DDS_100MS_DURATION, DDS_1S_DURATION,
+    Durability, DurabilityKind, DurabilityService, History, HistoryKind, IgnoreLocal,
+    IgnoreLocalKind, Qos, Reliability, ReliabilityKind, DDS_100MS_DURATION, DDS_1S_DURATION,
 };
 use cyclors::*;
 use flume::{unbounded, Receiver, Sender};
@@ -23,20 +23,22 @@ use route_dds_zenoh::RouteDDSZenoh;
 use serde::ser::SerializeStruct;
 use serde::{Serialize, Serializer};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::env;
 use std::mem::ManuallyDrop;
-use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
-use std::time::Duration;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, trace, warn};
 use zenoh::liveliness::LivelinessToken;
 use zenoh::plugins::{RunningPlugin, RunningPluginTrait, ZenohPlugin};
 use zenoh::prelude::r#async::AsyncResolve;
 use zenoh::prelude::r#sync::SyncResolve;
 use zenoh::prelude::*;
-use zenoh::publication::CongestionControl;
+use zenoh::publication::{CongestionControl, Priority};
 use zenoh::query::{ConsolidationMode, QueryTarget};
 use zenoh::queryable::{Query, Queryable};
 use zenoh::runtime::Runtime;
@@ -47,20 +49,47 @@ use zenoh_ext::{SessionExt, SubscriberBuilderExt};
 use zenoh_plugin_trait::{plugin_long_version, plugin_version, Plugin, PluginControl};
 use zenoh_util::{Timed, TimedEvent, Timer};
 
+mod batch;
+mod cdr_json;
+mod compression;
 pub mod config;
 mod dds_mgt;
+mod forward_queue;
+mod heartbeat;
+mod metrics;
+mod ordered_writes;
+mod payload_log;
 mod qos_helpers;
+mod rate_limit;
 mod ros_discovery;
 mod route_dds_zenoh;
 mod route_zenoh_dds;
-use config::Config;
+mod routing;
+pub mod sample_hook;
+mod self_test;
+mod sla;
+mod stats_state;
+#[cfg(feature = "zenoh_shm")]
+mod zenoh_shm;
+use config::{
+    congestion_control_to_str, priority_to_str, BatchConfig, Compression, Config, ContentFilter,
+    DdsInterface, DeliveryMode, DownsamplingStrategy, EntityMatcher, ForwardingProfile,
+    KeylessConflict, PayloadMatcher, RouteDirection, RouteEviction, SlaThresholds,
+    ROUTE_SETTINGS_FIELDS, ROUTING_FIELDS, SESSION_FIELDS, STARTUP_FIELDS,
+};
+pub use dds_mgt::RouteStatus;
 use dds_mgt::*;
+use forward_queue::AsyncForward;
+use heartbeat::{spawn_heartbeat, WATCHDOG_REPORT_AGE, WATCHDOG_STALLS};
+pub use heartbeat::{StallReport, StallSeverity};
+use metrics::{spawn_metrics_server, BridgeMetrics};
+use rate_limit::TokenBucket;
+use stats_state::StatsState;
 
 use crate::qos_helpers::*;
-use crate::ros_discovery::{
-    NodeEntitiesInfo, ParticipantEntitiesInfo, RosDiscoveryInfoMgr, ROS_DISCOVERY_INFO_TOPIC_NAME,
-};
-use crate::route_zenoh_dds::RouteZenohDDS;
+use crate::ros_discovery::{NodeEntitiesInfo, ParticipantEntitiesInfo, RosDiscoveryInfoMgr};
+use crate::route_zenoh_dds::{HistoryQueryRetry, RouteZenohDDS};
+use crate::routing::{is_route_allowed, route_local_entity, RouteFactory};
 
 macro_rules! ke_for_sure {
     ($val:expr) => {
@@ -76,18 +105,52 @@ macro_rules! member_id {
 
 lazy_static::lazy_static!(
     static ref LOG_PAYLOAD: bool = std::env::var("Z_LOG_PAYLOAD").is_ok();
+    // the max number of bytes logged at head and at tail of a payload: "<head>[,<tail>]"
+    static ref LOG_PAYLOAD_MAX_BYTES: Option<(usize, usize)> =
+        std::env::var("Z_LOG_PAYLOAD_MAX_BYTES").ok().and_then(|s| parse_log_payload_max_bytes(&s));
 
     static ref KE_PREFIX_ADMIN_SPACE: &'static keyexpr = ke_for_sure!("@dds");
     static ref KE_PREFIX_ROUTE_TO_DDS: &'static keyexpr = ke_for_sure!("route/to_dds");
     static ref KE_PREFIX_ROUTE_FROM_DDS: &'static keyexpr = ke_for_sure!("route/from_dds");
     static ref KE_PREFIX_PUB_CACHE: &'static keyexpr = ke_for_sure!("@dds_pub_cache");
     static ref KE_PREFIX_FWD_DISCO: &'static keyexpr = ke_for_sure!("@dds_fwd_disco");
+    static ref KE_PREFIX_FWD_ACK: &'static keyexpr = ke_for_sure!("@dds_fwd_ack");
+    static ref KE_PREFIX_SLA_EVENTS: &'static keyexpr = ke_for_sure!("sla");
+    static ref KE_PREFIX_MATCHED: &'static keyexpr = ke_for_sure!("matched");
+    static ref KE_PREFIX_DEADLINE_MISSED: &'static keyexpr = ke_for_sure!("deadline_missed");
+    static ref KE_PREFIX_ZENOH_ADMIN: &'static keyexpr = ke_for_sure!("@");
+    static ref KE_DDS_ROUTE: &'static keyexpr = ke_for_sure!("dds/route");
+    static ref KE_EFFECTIVE_QOS: &'static keyexpr = ke_for_sure!("effective-qos");
+    static ref KE_STATS: &'static keyexpr = ke_for_sure!("stats");
+    static ref KE_CONFIG_DENY: &'static keyexpr = ke_for_sure!("config/deny");
+    static ref KE_KEYLESS: &'static keyexpr = ke_for_sure!("keyless");
+    static ref KE_PREFIX_HEARTBEAT: &'static keyexpr = ke_for_sure!("@");
+    static ref KE_SUFFIX_HEARTBEAT: &'static keyexpr = ke_for_sure!("dds/heartbeat");
+    static ref KE_SUFFIX_INFO: &'static keyexpr = ke_for_sure!("dds/info");
+    static ref KE_SUFFIX_GRAPH: &'static keyexpr = ke_for_sure!("dds/graph");
+    static ref KE_KEYED: &'static keyexpr = ke_for_sure!("keyed");
     static ref KE_PREFIX_LIVELINESS_GROUP: &'static keyexpr = ke_for_sure!("zenoh-plugin-dds");
 
     static ref KE_ANY_1_SEGMENT: &'static keyexpr = ke_for_sure!("*");
     static ref KE_ANY_N_SEGMENT: &'static keyexpr = ke_for_sure!("**");
 
     static ref LOG_ROS2_DEPRECATION_WARNING_FLAG: AtomicBool = AtomicBool::new(false);
+
+    // the sender of the requests to shutdown the running plugin, each with the sender notifying its completion.
+    // It's registered while the plugin runs (see PluginRequests): a request not served by a run is not left for the next one.
+    static ref SHUTDOWN_REQUESTS: RwLock<Option<Sender<Sender<()>>>> = RwLock::new(None);
+
+    // the sender of the requests to reload the plugin's configuration, each with the sender notifying its completion.
+    // It's registered while the plugin runs (see PluginRequests).
+    static ref RELOAD_REQUESTS: RwLock<Option<Sender<(Config, Sender<()>)>>> = RwLock::new(None);
+
+    // the sender of the requests for a snapshot of the plugin's routes, each with the sender of the snapshot.
+    // It's registered while the plugin runs (see PluginRequests).
+    static ref ROUTES_REQUESTS: RwLock<Option<Sender<Sender<RoutesSnapshot>>>> = RwLock::new(None);
+
+    // the sender of the requests for a self-test of the plugin, each with its deadline and the sender of its result.
+    // It's registered while the plugin runs (see PluginRequests).
+    static ref SELF_TEST_REQUESTS: RwLock<Option<Sender<(Instant, Sender<Result<(), String>>)>>> = RwLock::new(None);
 );
 
 // CycloneDDS' localhost-only: set network interface address (shortened form of config would be
@@ -101,6 +164,24 @@ const CYCLONEDDS_CONFIG_ENABLE_SHM: &str = r#"<CycloneDDS><Domain><SharedMemory>
 
 const ROS_DISCOVERY_INFO_POLL_INTERVAL_MS: u64 = 500;
 
+// The max number of DDS entities (participants, publications and subscriptions) in a reply to a query on
+// "@/<zid>/dds/graph" without a limit, unless admin_query_default_limit is configured
+const GRAPH_DEFAULT_LIMIT: usize = 1000;
+
+// The delay before the 1st retry of a failed route creation, doubled at each attempt up to the max delay
+const ROUTE_CREATION_RETRY_INITIAL_DELAY: Duration = Duration::from_secs(1);
+const ROUTE_CREATION_RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+// The max number of pending requests of each kind to the running plugin (see PluginRequests)
+const PLUGIN_REQUESTS_CAPACITY: usize = 16;
+
+// The extra delay for the result of a self-test failing at its deadline (see self_test())
+const SELF_TEST_RESULT_MARGIN: Duration = Duration::from_secs(1);
+
+// Version of the schema used to represent the DDS QoS in admin space (see README.md).
+// Must be incremented on any incompatible change of this representation.
+const ADMIN_QOS_SCHEMA_VERSION: u32 = 1;
+
 #[cfg(feature = "dynamic_plugin")]
 zenoh_plugin_trait::declare_plugin!(DDSPlugin);
 
@@ -139,7 +220,10 @@ impl Plugin for DDSPlugin {
         let plugin_conf = runtime_conf
             .plugin(name)
             .ok_or_else(|| zerror!("Plugin `{}`: missing config", name))?;
-        let config: Config = serde_json::from_value(plugin_conf.clone())
+        let mut config: Config = serde_json::from_value(plugin_conf.clone())
+            .map_err(|e| zerror!("Plugin `{}` configuration error: {}", name, e))?;
+        config.apply_case_insensitive_matching();
+        check_generalise_overlaps(&config)
             .map_err(|e| zerror!("Plugin `{}` configuration error: {}", name, e))?;
         async_std::task::spawn(run(runtime.clone(), config));
         Ok(Box::new(DDSPlugin))
@@ -147,6 +231,204 @@ impl Plugin for DDSPlugin {
 }
 impl RunningPluginTrait for DDSPlugin {}
 
+// When the plugin is stopped by zenohd, request its shutdown (deleting its DDS entities and persisting its statistics)
+impl Drop for DDSPlugin {
+    fn drop(&mut self) {
+        if let Some(requests) = plugin_requests(&*SHUTDOWN_REQUESTS) {
+            let (done_tx, _) = flume::bounded(1);
+            let _ = requests.try_send(done_tx);
+        }
+    }
+}
+
+// Return the sender of a kind of requests to the running plugin, or None if it's not running
+fn plugin_requests<T>(requests: &RwLock<Option<Sender<T>>>) -> Option<Sender<T>> {
+    requests.read().unwrap().clone()
+}
+
+// The channel of a kind of requests to the plugin, registered for the time of a run: the requests are bounded,
+// and fail right away if the plugin is not running. The requests still pending at the end of the run are returned
+// by unregister(), or dropped with the channel (their requesters being notified as their response sender is dropped).
+struct PluginRequests<T: 'static> {
+    registry: &'static RwLock<Option<Sender<T>>>,
+    rcv: Receiver<T>,
+}
+
+impl<T> PluginRequests<T> {
+    fn register(registry: &'static RwLock<Option<Sender<T>>>) -> Self {
+        let (tx, rcv) = flume::bounded(PLUGIN_REQUESTS_CAPACITY);
+        *registry.write().unwrap() = Some(tx);
+        PluginRequests { registry, rcv }
+    }
+
+    // Unregister the channel, returning the pending requests
+    fn unregister(&self) -> Vec<T> {
+        *self.registry.write().unwrap() = None;
+        self.rcv.drain().collect()
+    }
+}
+
+impl<T> Drop for PluginRequests<T> {
+    fn drop(&mut self) {
+        *self.registry.write().unwrap() = None;
+    }
+}
+
+// Shutdown the running DDS plugin gracefully: delete the routes' DDS Readers and Writers and the DDS Participant,
+// for the remote DDS participants to be notified rather than keeping stale entities until the lease expiry.
+// Return false if the shutdown didn't complete within the timeout (e.g. a DDS deletion is stuck, or the plugin
+// is not running): the caller might then just exit.
+pub async fn shutdown(timeout: Duration) -> bool {
+    let requests = match plugin_requests(&*SHUTDOWN_REQUESTS) {
+        Some(requests) => requests,
+        None => return false,
+    };
+    let (done_tx, done_rcv) = flume::bounded(1);
+    async_std::future::timeout(timeout, async {
+        requests.send_async(done_tx).await.is_ok() && done_rcv.recv_async().await.is_ok()
+    })
+    .await
+    .unwrap_or(false)
+}
+
+// Reload the configuration of the running DDS plugin from its (new) JSON configuration, applying the changes
+// without restarting it: the routes that are no longer allowed or whose settings changed are deleted, and the
+// newly allowed routes are created. The changes of the settings used only at startup are ignored (with a warning),
+// while the changes of the generalised key expressions restart the plugin with a new zenoh Session.
+// Return an error if the configuration is invalid (the running plugin being left unchanged), or if the reload
+// didn't complete within the timeout (or the plugin is not running).
+pub async fn reload(plugin_conf: &serde_json::Value, timeout: Duration) -> Result<(), String> {
+    let mut config: Config = serde_json::from_value(plugin_conf.clone())
+        .map_err(|e| format!("invalid configuration: {e}"))?;
+    config.apply_case_insensitive_matching();
+    check_generalise_overlaps(&config).map_err(|e| format!("invalid configuration: {e}"))?;
+    let requests = plugin_requests(&*RELOAD_REQUESTS)
+        .ok_or_else(|| "the DDS plugin is not running".to_string())?;
+    let (done_tx, done_rcv) = flume::bounded(1);
+    match async_std::future::timeout(timeout, async {
+        requests.send_async((config, done_tx)).await.is_ok() && done_rcv.recv_async().await.is_ok()
+    })
+    .await
+    {
+        Ok(true) => Ok(()),
+        Ok(false) => Err("the DDS plugin is not running".into()),
+        Err(_) => Err(format!("not completed within {timeout:?}")),
+    }
+}
+
+// Warn about the overlapping key expressions of generalise_subs and of generalise_pubs (see
+// Config::generalise_overlaps()), or return an error if "generalise_overlap_error" is set
+fn check_generalise_overlaps(config: &Config) -> Result<(), String> {
+    let overlaps = config.generalise_overlaps();
+    if overlaps.is_empty() {
+        return Ok(());
+    }
+    if config.generalise_overlap_error {
+        return Err(format!(
+            "overlapping generalised key expressions: {}",
+            overlaps
+                .iter()
+                .map(|(field, ke1, ke2)| format!("'{ke1}' and '{ke2}' in '{field}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+    for (field, ke1, ke2) in overlaps {
+        warn!(
+            field,
+            %ke1,
+            %ke2,
+            "Overlapping generalised key expressions: the topics matching both might be routed twice"
+        );
+    }
+    Ok(())
+}
+
+// A route (or route attempt) of the running DDS plugin, for a discovered DDS Writer (route from DDS) or
+// Reader (route to DDS) in one of its partitions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteInfo {
+    pub direction: RouteDirection,
+    // the key of the DDS Writer or Reader served by the route
+    pub entity_key: String,
+    pub topic_name: String,
+    pub type_name: String,
+    // the partition of the DDS entity ("*" if it has no partition)
+    pub partition: String,
+    // the zenoh key expression of the route serving the entity (if any, see RouteStatus)
+    pub key_expr: Option<OwnedKeyExpr>,
+    pub status: RouteStatus,
+}
+
+// A snapshot of the routes of the running DDS plugin (see DDSPlugin::routes())
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoutesSnapshot {
+    // the routes from DDS, then the routes to DDS, each ordered by DDS entity key and partition
+    pub routes: Vec<RouteInfo>,
+}
+
+impl DDSPlugin {
+    // Return a snapshot of the routes of the running DDS plugin, for an application embedding it to introspect its
+    // state without querying the admin space. All the route statuses are listed (including the routes not allowed
+    // or whose creation failed): filter on RouteStatus::Routed for the active routes only.
+    // Return an error if the snapshot wasn't taken within the timeout (or the plugin is not running).
+    pub async fn routes(timeout: Duration) -> Result<RoutesSnapshot, String> {
+        let requests = plugin_requests(&*ROUTES_REQUESTS)
+            .ok_or_else(|| "the DDS plugin is not running".to_string())?;
+        let (snapshot_tx, snapshot_rcv) = flume::bounded(1);
+        match async_std::future::timeout(timeout, async {
+            requests.send_async(snapshot_tx).await.ok()?;
+            snapshot_rcv.recv_async().await.ok()
+        })
+        .await
+        {
+            Ok(Some(snapshot)) => Ok(snapshot),
+            Ok(None) => Err("the DDS plugin is not running".into()),
+            Err(_) => Err(format!("not completed within {timeout:?}")),
+        }
+    }
+}
+
+// Run a self-test of the running DDS plugin, checking it can actually route data: a temporary DDS Writer and
+// DDS Reader are created on a temporary topic ("zenoh_bridge_dds_self_test_<zid>", that must be allowed by the
+// configuration), the plugin must create their routes, and the samples of the DDS Writer must be received via
+// zenoh (see self_test.rs). Return an error describing the failed step, or if the self-test didn't complete within
+// the timeout (or the plugin is not running).
+pub async fn self_test(timeout: Duration) -> Result<(), String> {
+    let requests = plugin_requests(&*SELF_TEST_REQUESTS)
+        .ok_or_else(|| "the DDS plugin is not running".to_string())?;
+    let (result_tx, result_rcv) = flume::bounded(1);
+    // the self-test itself fails at the deadline: leave it the time to report which step failed
+    match async_std::future::timeout(timeout + SELF_TEST_RESULT_MARGIN, async {
+        requests
+            .send_async((Instant::now() + timeout, result_tx))
+            .await
+            .ok()?;
+        result_rcv.recv_async().await.ok()
+    })
+    .await
+    {
+        Ok(Some(result)) => result,
+        Ok(None) => Err("the DDS plugin is not running".into()),
+        Err(_) => Err(format!("not completed within {timeout:?}")),
+    }
+}
+
+// Report the age of the latest report of a watchdog monitoring the async runtime (as the one of
+// zenoh-bridge-dds), to be published in the bridge heartbeat (see heartbeat_key configuration)
+pub fn report_watchdog(report_age: Duration) {
+    WATCHDOG_REPORT_AGE.store(
+        report_age.as_micros().try_into().unwrap_or(u64::MAX - 1),
+        Ordering::Relaxed,
+    );
+}
+
+// Report a stall of the async runtime detected by a watchdog (as the one of zenoh-bridge-dds),
+// counted per severity in the bridge heartbeat (see heartbeat_key configuration)
+pub fn report_watchdog_stall(report: StallReport) {
+    WATCHDOG_STALLS[report.severity as usize].fetch_add(1, Ordering::Relaxed);
+}
+
 pub async fn run(runtime: Runtime, config: Config) {
     // Try to initiate login.
     // Required in case of dynamic lib, otherwise no logs.
@@ -155,8 +437,107 @@ pub async fn run(runtime: Runtime, config: Config) {
     debug!("DDS plugin {}", DDSPlugin::PLUGIN_LONG_VERSION);
     debug!("DDS plugin {:?}", config);
 
+    // CycloneDDS merges the elements of "$CYCLONEDDS_URI" in order, the later ones taking precedence: the
+    // "cyclonedds_xml" fragment is appended (overriding "$CYCLONEDDS_URI"), while the settings below are prepended
+    if let Some(xml) = &config.cyclonedds_xml {
+        let uri = match env::var("CYCLONEDDS_URI") {
+            Ok(uri) if !uri.trim().is_empty() => format!("{uri},{xml}"),
+            _ => xml.clone(),
+        };
+        env::set_var("CYCLONEDDS_URI", uri);
+    }
+
+    // if "localhost_only" is set, configure CycloneDDS to use only localhost interface,
+    // otherwise to use the "interface" network interface (if set, and if it exists on this host).
+    // If "ipv6" is set, configure CycloneDDS to use the UDP/IPv6 transport.
+    if config.localhost_only {
+        if let Some(interface) = &config.interface {
+            warn!(
+                "'localhost_only' is set: ignore the '{}' network interface configured by 'interface'",
+                interface
+            );
+        }
+    } else if let Some(interface) = &config.interface {
+        if let Err(e) = check_dds_interface(interface, config.ipv6) {
+            tracing::error!("Unable to start DDS plugin: {}", e);
+            return;
+        }
+    }
+    if let Some(network_config) = cyclonedds_network_config(
+        config.localhost_only,
+        config.interface.as_ref(),
+        config.ipv6,
+    ) {
+        env::set_var(
+            "CYCLONEDDS_URI",
+            format!(
+                "{}{}",
+                network_config,
+                env::var("CYCLONEDDS_URI").unwrap_or_default()
+            ),
+        );
+    }
+
+    // if "domain_tag" is set, configure CycloneDDS to discover only the participants with the same domain tag
+    if let Some(tag) = &config.domain_tag {
+        env::set_var(
+            "CYCLONEDDS_URI",
+            format!(
+                "<CycloneDDS><Domain><Discovery><Tag>{}</Tag></Discovery></Domain></CycloneDDS>,{}",
+                xml_escape(tag),
+                env::var("CYCLONEDDS_URI").unwrap_or_default()
+            ),
+        );
+    }
+
+    // if "enable_shm" is set, configure CycloneDDS to use Iceoryx shared memory
+    #[cfg(feature = "dds_shm")]
+    {
+        if config.shm_enabled {
+            env::set_var(
+                "CYCLONEDDS_URI",
+                format!(
+                    "{}{}",
+                    CYCLONEDDS_CONFIG_ENABLE_SHM,
+                    env::var("CYCLONEDDS_URI").unwrap_or_default()
+                ),
+            );
+            if config.forward_discovery {
+                warn!("DDS shared memory support enabled but will not be used as forward discovery mode is active.");
+            }
+        }
+    }
+
+    // log the routed payloads to a dedicated file, if configured
+    if let Some(path) = &config.payload_log_file {
+        if let Err(e) = payload_log::init(path, config.payload_log_max_size) {
+            tracing::error!("Payloads won't be logged: {}", e);
+        }
+    }
+
+    // the plugin is restarted with a new zenoh Session if a reload of the configuration changes its settings
+    let mut config = config;
+    while let Some(reloaded) = run_with_session(&runtime, config).await {
+        info!("Restart the DDS plugin with the reloaded configuration");
+        config = reloaded;
+    }
+}
+
+// Create the zenoh Session and the DDS Participant of the plugin and run it, returning the configuration to restart
+// it with if a reload of the configuration requires a new zenoh Session
+async fn run_with_session(runtime: &Runtime, config: Config) -> Option<Config> {
+    // zenoh shared memory is used for the samples received via Iceoryx only if enabled in zenoh transport
+    #[cfg(feature = "zenoh_shm")]
+    let zenoh_shm_enabled = config.shm_enabled
+        && *runtime
+            .config()
+            .lock()
+            .transport()
+            .shared_memory()
+            .enabled();
+
     // open zenoh-net Session
-    let zsession = match zenoh::init(runtime)
+    let zsession = match zenoh::init(runtime.clone())
         .aggregated_subscribers(config.generalise_subs.clone())
         .aggregated_publishers(config.generalise_pubs.clone())
         .res_async()
@@ -165,7 +546,7 @@ pub async fn run(runtime: Runtime, config: Config) {
         Ok(session) => Arc::new(session),
         Err(e) => {
             tracing::error!("Unable to init zenoh session for DDS plugin : {:?}", e);
-            return;
+            return None;
         }
     };
 
@@ -174,6 +555,14 @@ pub async fn run(runtime: Runtime, config: Config) {
         Some(ref id) => id.clone(),
         None => zsession.zid().into_keyexpr(),
     };
+
+    #[cfg(feature = "zenoh_shm")]
+    if zenoh_shm_enabled {
+        zenoh_shm::init_zenoh_shm(
+            format!("zenoh-dds-{}", zsession.zid()),
+            config.zenoh_shm_size,
+        );
+    }
     let member = match zsession
         .liveliness()
         .declare_token(*KE_PREFIX_LIVELINESS_GROUP / &member_id)
@@ -186,68 +575,95 @@ pub async fn run(runtime: Runtime, config: Config) {
                 "Unable to declare liveliness token for DDS plugin : {:?}",
                 e
             );
-            return;
+            return None;
         }
     };
 
-    // if "localhost_only" is set, configure CycloneDDS to use only localhost interface
-    if config.localhost_only {
-        env::set_var(
-            "CYCLONEDDS_URI",
-            format!(
-                "{}{}",
-                CYCLONEDDS_CONFIG_LOCALHOST_ONLY,
-                env::var("CYCLONEDDS_URI").unwrap_or_default()
-            ),
-        );
-    }
-
-    // if "enable_shm" is set, configure CycloneDDS to use Iceoryx shared memory
-    #[cfg(feature = "dds_shm")]
-    {
-        if config.shm_enabled {
-            env::set_var(
-                "CYCLONEDDS_URI",
-                format!(
-                    "{}{}",
-                    CYCLONEDDS_CONFIG_ENABLE_SHM,
-                    env::var("CYCLONEDDS_URI").unwrap_or_default()
-                ),
-            );
-            if config.forward_discovery {
-                warn!("DDS shared memory support enabled but will not be used as forward discovery mode is active.");
-            }
-        }
-    }
-
     // create DDS Participant
     debug!(
         "Create DDS Participant with CYCLONEDDS_URI='{}'",
         env::var("CYCLONEDDS_URI").unwrap_or_default()
     );
     let dp = unsafe { dds_create_participant(config.domain, std::ptr::null(), std::ptr::null()) };
-    debug!(
-        "DDS plugin {} with member_id={} and using DDS Participant {}",
+    // check CycloneDDS is operational before going further, to report an actionable error
+    let dp_guid = match check_dds_participant(dp, config.domain) {
+        Ok(guid) => guid,
+        Err(e) => {
+            tracing::error!("Unable to start DDS plugin: {}", e);
+            return None;
+        }
+    };
+    info!(
+        domain = config.domain,
+        participant_guid = %dp_guid,
+        zid = %zsession.zid(),
+        "DDS plugin {} with member_id={} created DDS Participant {} on domain {}",
         zsession.zid(),
         member_id,
-        get_guid(&dp).unwrap()
+        dp_guid,
+        config.domain
     );
+    let participant_info = Arc::new(RwLock::new(ParticipantInfo {
+        domain: config.domain,
+        participant_guid: dp_guid,
+        zid: zsession.zid().to_string(),
+        member_id: member_id.to_string(),
+    }));
+
+    // the entities of the DDS Participants created by the bridge must be ignored by the discovery
+    let local_participants = Arc::new(LocalParticipants::default());
+    if let Err(e) = local_participants.register(dp) {
+        tracing::error!("Unable to register DDS Participant for DDS plugin : {}", e);
+        return None;
+    }
+
+    // load the statistics persisted by a previous run, if configured
+    let stats_state = config.stats_state_file.as_deref().map(StatsState::load);
+
+    // limit the rate of route creations, if configured
+    let route_creation_limiter = config
+        .route_creation_limit
+        .map(|limit| TokenBucket::new(limit.max_routes, limit.period));
 
     let mut dds_plugin = DdsPluginRuntime {
         config,
+        configured_deny: None,
         zsession: &zsession,
         _member: member,
         member_id,
         dp,
+        participant_info,
+        local_participants,
         discovered_participants: HashMap::<String, DdsParticipant>::new(),
         discovered_writers: HashMap::<String, DdsEntity>::new(),
         discovered_readers: HashMap::<String, DdsEntity>::new(),
         routes_from_dds: HashMap::<OwnedKeyExpr, RouteDDSZenoh>::new(),
         routes_to_dds: HashMap::<OwnedKeyExpr, RouteZenohDDS>::new(),
         admin_space: HashMap::<OwnedKeyExpr, AdminRef>::new(),
+        stats_state,
+        start_time: Instant::now(),
+        route_creation_limiter,
+        discovery_queue: None,
+        unconfirmed_entities: UnconfirmedEntities::default(),
+        route_creation_attempts: HashMap::new(),
+        route_retry_tx: None,
+        history_retry_tx: None,
+        remote_route_statuses: HashMap::new(),
+        max_routes_reached: false,
+        metrics: Arc::new(BridgeMetrics::default()),
     };
 
-    dds_plugin.run().await;
+    let restart = dds_plugin.run().await;
+    // the plugin (and its routes) must be dropped before its zenoh Session
+    drop(dds_plugin);
+    restart
+}
+
+// The end of a run of the plugin, with the sender notifying the completion of the request that ended it
+enum RunEnd {
+    Shutdown(Sender<()>),
+    // a reload of the configuration changed the fields of the zenoh Session (see SESSION_FIELDS)
+    Restart(Box<Config>, Sender<()>),
 }
 
 // An reference used in admin space to point to a struct (DdsEntity or Route) stored in another map
@@ -257,19 +673,251 @@ enum AdminRef {
     DdsWriterEntity(String),
     DdsReaderEntity(String),
     FromDdsRoute(OwnedKeyExpr),
+    // the throughput of a route from DDS (not stored in admin space, but derived from the route's entry)
+    FromDdsRouteStats(OwnedKeyExpr),
     ToDdsRoute(OwnedKeyExpr),
     Config,
+    ConfigDeny,
     Version,
+    Uptime,
+    Discovery,
+    // the list of all the DDS Writers or Readers discovered, whatever their routing status
+    DiscoveredPublications,
+    DiscoveredSubscriptions,
+}
+
+// The filtering and pagination of the replies to an admin space query, as specified by its parameters
+#[derive(Debug, Default)]
+struct AdminQueryPage {
+    // the regex that the topic name of the DDS entities and routes must match (excluding the other keys)
+    topic: Option<regex::Regex>,
+    // the number of replies to skip (in the order of the admin keys)
+    offset: usize,
+    // the max number of replies
+    limit: Option<usize>,
+}
+
+impl AdminQueryPage {
+    fn parse(parameters: &str, default_limit: Option<usize>) -> Result<Self, String> {
+        let mut page = AdminQueryPage {
+            limit: default_limit,
+            ..Default::default()
+        };
+        for param in parameters.split('&').filter(|p| !p.is_empty()) {
+            let (name, value) = param.split_once('=').unwrap_or((param, ""));
+            match name {
+                "topic" => {
+                    page.topic = Some(
+                        regex::Regex::new(value)
+                            .map_err(|e| format!("invalid topic regex '{value}': {e}"))?,
+                    )
+                }
+                "offset" => {
+                    page.offset = value
+                        .parse::<usize>()
+                        .map_err(|e| format!("invalid offset '{value}': {e}"))?
+                }
+                "limit" => {
+                    page.limit = Some(
+                        value
+                            .parse::<usize>()
+                            .map_err(|e| format!("invalid limit '{value}': {e}"))?,
+                    )
+                }
+                // ignore zenoh's reserved parameters
+                _ if name.starts_with('_') => (),
+                _ => return Err(format!("unknown parameter '{name}'")),
+            }
+        }
+        Ok(page)
+    }
+
+    fn is_paginated(&self) -> bool {
+        self.offset > 0 || self.limit.is_some()
+    }
+}
+
+// The identity of the bridge's DDS Participant, to correlate the bridge with DDS captures (e.g. Wireshark or ddsperf)
+#[derive(Clone, Debug, Serialize)]
+struct ParticipantInfo {
+    domain: u32,
+    participant_guid: String,
+    zid: String,
+    member_id: String,
+}
+
+// The keys of the DDS entities known before a recovery of the DDS Participant and not discovered again since,
+// with the time of the recovery
+#[derive(Debug, Default)]
+struct UnconfirmedEntities {
+    keys: HashSet<String>,
+    since: Option<Instant>,
+}
+
+impl UnconfirmedEntities {
+    fn reset(&mut self, keys: HashSet<String>, now: Instant) {
+        self.keys = keys;
+        self.since = Some(now);
+    }
+
+    fn confirm(&mut self, key: &str) {
+        self.keys.remove(key);
+    }
+
+    // Take the keys of the DDS entities still not discovered again once the timeout after the recovery elapsed
+    fn take_expired(&mut self, timeout: Duration, now: Instant) -> Vec<String> {
+        match self.since {
+            Some(since) if now.saturating_duration_since(since) >= timeout => {
+                self.since = None;
+                self.keys.drain().collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+// The acknowledgments by the remote bridges of the routes they created for a DDS entity advertised in
+// "forward discovery" mode
+#[derive(Debug)]
+struct RemoteRouteStatus {
+    // the time of the advertisement of the DDS entity
+    advertised: Instant,
+    // the route statuses per partition ("*" only if no partition), indexed by the uuid of the remote bridge
+    acks: HashMap<String, HashMap<String, RouteStatus>>,
+}
+
+// The encoding of a RouteStatus in the acknowledgments of routes between bridges. bincode encodes an enum by the index
+// of its variant, which changes if the variants are reordered: the status is rather encoded with an explicit code,
+// stable across the versions of the bridge (a new status must take a new code).
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct RouteStatusAck {
+    code: u32,
+    route: Option<OwnedKeyExpr>,
+    details: Vec<String>,
+    permanent: bool,
+    attempts: u32,
+}
+
+impl RouteStatusAck {
+    const ROUTED: u32 = 0;
+    const NOT_ALLOWED: u32 = 1;
+    const CREATION_FAILURE: u32 = 2;
+    const QOS_CONFLICT: u32 = 3;
+    const TYPE_CONFLICT: u32 = 4;
+    const THROTTLED: u32 = 5;
+    const RATE_LIMITED: u32 = 6;
+    const DRY_RUN: u32 = 7;
+
+    fn new(code: u32, route: Option<OwnedKeyExpr>, details: Vec<String>) -> Self {
+        RouteStatusAck {
+            code,
+            route,
+            details,
+            permanent: false,
+            attempts: 0,
+        }
+    }
+
+    // Decode the status, or None if its code is unknown (i.e. sent by a newer bridge)
+    fn into_status(self) -> Option<RouteStatus> {
+        let mut details = self.details.into_iter();
+        let status = match (self.code, self.route) {
+            (Self::ROUTED, Some(ke)) => RouteStatus::Routed(ke),
+            (Self::NOT_ALLOWED, _) => RouteStatus::NotAllowed,
+            (Self::CREATION_FAILURE, _) => RouteStatus::CreationFailure {
+                error: details.next().unwrap_or_default(),
+                permanent: self.permanent,
+                attempts: self.attempts,
+            },
+            (Self::QOS_CONFLICT, Some(route)) => RouteStatus::QoSConflict {
+                route,
+                conflicts: details.next().unwrap_or_default(),
+            },
+            (Self::TYPE_CONFLICT, Some(route)) => RouteStatus::TypeConflict {
+                route,
+                route_type: details.next().unwrap_or_default(),
+                type_name: details.next().unwrap_or_default(),
+            },
+            (Self::THROTTLED, _) => RouteStatus::Throttled,
+            (Self::RATE_LIMITED, _) => RouteStatus::RateLimited,
+            (Self::DRY_RUN, Some(ke)) => RouteStatus::DryRun(ke),
+            _ => return None,
+        };
+        Some(status)
+    }
+}
+
+impl From<&RouteStatus> for RouteStatusAck {
+    fn from(status: &RouteStatus) -> Self {
+        match status {
+            RouteStatus::Routed(ke) => Self::new(Self::ROUTED, Some(ke.clone()), vec![]),
+            RouteStatus::NotAllowed => Self::new(Self::NOT_ALLOWED, None, vec![]),
+            RouteStatus::CreationFailure {
+                error,
+                permanent,
+                attempts,
+            } => RouteStatusAck {
+                permanent: *permanent,
+                attempts: *attempts,
+                ..Self::new(Self::CREATION_FAILURE, None, vec![error.clone()])
+            },
+            RouteStatus::QoSConflict { route, conflicts } => Self::new(
+                Self::QOS_CONFLICT,
+                Some(route.clone()),
+                vec![conflicts.clone()],
+            ),
+            RouteStatus::TypeConflict {
+                route,
+                route_type,
+                type_name,
+            } => Self::new(
+                Self::TYPE_CONFLICT,
+                Some(route.clone()),
+                vec![route_type.clone(), type_name.clone()],
+            ),
+            RouteStatus::Throttled => Self::new(Self::THROTTLED, None, vec![]),
+            RouteStatus::RateLimited => Self::new(Self::RATE_LIMITED, None, vec![]),
+            RouteStatus::DryRun(ke) => Self::new(Self::DRY_RUN, Some(ke.clone()), vec![]),
+        }
+    }
+}
+
+impl RemoteRouteStatus {
+    fn new() -> Self {
+        RemoteRouteStatus {
+            advertised: Instant::now(),
+            acks: HashMap::new(),
+        }
+    }
+
+    // The value of the "remote_route_status" field in admin space: the statuses acknowledged by the remote bridges,
+    // or "pending" if none acknowledged yet. After the timeout, "unconfirmed" means that no remote bridge created
+    // a route (e.g. no bridge is interested, or the remote bridges run an older version without acknowledgments).
+    fn to_value(&self, timeout: Duration) -> Value {
+        if !self.acks.is_empty() {
+            serde_json::to_value(&self.acks).unwrap_or_default()
+        } else if self.advertised.elapsed() < timeout {
+            "pending".into()
+        } else {
+            "unconfirmed".into()
+        }
+    }
 }
 
 pub(crate) struct DdsPluginRuntime<'a> {
     config: Config,
+    // the 'deny' of the loaded configuration, while overridden by a 'deny' set via admin space (see update_deny())
+    configured_deny: Option<Option<EntityMatcher>>,
     // Note: &'a Arc<Session> here to keep the ownership of Session outside this struct
     // and be able to store the publishers/subscribers it creates in this same struct.
     zsession: &'a Arc<Session>,
     _member: LivelinessToken<'a>,
     member_id: OwnedKeyExpr,
     dp: dds_entity_t,
+    // the identity of dp, served on @/<zid>/dds/info (updated if dp is re-created)
+    participant_info: Arc<RwLock<ParticipantInfo>>,
+    // the DDS Participants created by the bridge (including dp)
+    local_participants: Arc<LocalParticipants>,
     // maps of all discovered DDS entities (indexed by DDS key)
     discovered_participants: HashMap<String, DdsParticipant>,
     discovered_writers: HashMap<String, DdsEntity>,
@@ -280,6 +928,28 @@ pub(crate) struct DdsPluginRuntime<'a> {
     // admin space: index is the admin_keyexpr (relative to admin_prefix)
     // value is the JSon string to return to queries.
     admin_space: HashMap<OwnedKeyExpr, AdminRef>,
+    // the persisted statistics (if stats_state_file is configured)
+    stats_state: Option<StatsState>,
+    start_time: Instant,
+    // the limiter of the rate of route creations (if route_creation_limit is configured)
+    route_creation_limiter: Option<TokenBucket>,
+    // the queue of the DDS discovery events, to report the number of pending events
+    discovery_queue: Option<Arc<DiscoveryQueue>>,
+    // the DDS entities known before a recovery of the DDS Participant, and not discovered again since
+    unconfirmed_entities: UnconfirmedEntities,
+    // the number of failed attempts to create the routes of the discovered DDS entities (indexed by DDS key)
+    route_creation_attempts: HashMap<String, u32>,
+    // the sender of the retries of the route creations, with the DDS key and true if it's a Writer
+    route_retry_tx: Option<Sender<(String, bool)>>,
+    // the sender of the retries of the failed queries for historical publications (see queries_max_retries)
+    history_retry_tx: Option<Sender<HistoryQueryRetry>>,
+    // the statuses of the routes created by the remote bridges for the advertised DDS entities, in "forward discovery"
+    // mode with route_ack_timeout configured (indexed by DDS key)
+    remote_route_statuses: HashMap<String, RemoteRouteStatus>,
+    // true while the max number of routes is reached (see max_routes configuration), to log it only once
+    max_routes_reached: bool,
+    // the bridge-level metrics (served over HTTP if metrics_port is configured)
+    metrics: Arc<BridgeMetrics>,
 }
 
 impl Serialize for DdsPluginRuntime<'_> {
@@ -290,22 +960,59 @@ impl Serialize for DdsPluginRuntime<'_> {
         // return the plugin's config as a JSON struct
         let mut s = serializer.serialize_struct("dds", 3)?;
         s.serialize_field("domain", &self.config.domain)?;
+        s.serialize_field(
+            "interface",
+            &self.config.interface.as_ref().map(|i| i.to_string()),
+        )?;
+        s.serialize_field("ipv6", &self.config.ipv6)?;
+        s.serialize_field("domain_tag", &self.config.domain_tag)?;
+        s.serialize_field("cyclonedds_xml", &self.config.cyclonedds_xml)?;
         s.serialize_field("scope", &self.config.scope)?;
+        match &self.config.allow {
+            Some(allow) => s.serialize_field("allow", allow)?,
+            None => s.serialize_field("allow", ".*")?,
+        }
+        match &self.config.deny {
+            Some(deny) => s.serialize_field("deny", deny)?,
+            None => s.serialize_field("deny", "")?,
+        }
+        s.serialize_field("filter_precedence", &self.config.filter_precedence)?;
+        s.serialize_field(
+            "case_insensitive_matching",
+            &self.config.case_insensitive_matching,
+        )?;
+        s.serialize_field("partition_filter", &self.config.partition_filter)?;
         s.serialize_field(
-            "allow",
+            "partition_rewrite",
             &self
                 .config
-                .allow
+                .partition_rewrite
                 .as_ref()
-                .map_or_else(|| ".*".to_string(), |re| re.to_string()),
+                .map(|(re, replacement)| format!("{re}={replacement}")),
         )?;
+        s.serialize_field("topic_normalization", &self.config.topic_normalization)?;
         s.serialize_field(
-            "deny",
+            "partition_keyexpr_template",
             &self
                 .config
-                .deny
+                .partition_keyexpr_template
                 .as_ref()
-                .map_or_else(|| "".to_string(), |re| re.to_string()),
+                .map(|t| t.to_string()),
+        )?;
+        s.serialize_field("route_setup", &self.config.route_setup)?;
+        s.serialize_field("route_creation_limit", &self.config.route_creation_limit)?;
+        s.serialize_field(
+            "route_creation_max_attempts",
+            &self.config.route_creation_max_attempts,
+        )?;
+        s.serialize_field("max_routes", &self.config.max_routes)?;
+        s.serialize_field("max_routes_eviction", &self.config.max_routes_eviction)?;
+        s.serialize_field("keyless_conflict", &self.config.keyless_conflict)?;
+        s.serialize_field("directions", &self.config.directions)?;
+        s.serialize_field("discovery_max_samples", &self.config.discovery_max_samples)?;
+        s.serialize_field(
+            "discovery_queue_capacity",
+            &self.config.discovery_queue_capacity,
         )?;
         s.serialize_field(
             "max-frequencies",
@@ -313,316 +1020,2218 @@ impl Serialize for DdsPluginRuntime<'_> {
                 .config
                 .max_frequencies
                 .iter()
-                .map(|(re, freq)| format!("{re}={freq}"))
+                .map(|(re, freq, strategy)| match strategy {
+                    DownsamplingStrategy::Latest => format!("{re}={freq}"),
+                    DownsamplingStrategy::First => format!("{re}={freq}:{strategy}"),
+                })
                 .collect::<Vec<String>>(),
         )?;
-        s.serialize_field("forward_discovery", &self.config.forward_discovery)?;
         s.serialize_field(
-            "reliable_routes_blocking",
-            &self.config.reliable_routes_blocking,
+            "put_timeout",
+            &self.config.put_timeout.map(|d| d.as_secs_f32()),
         )?;
-        s.end()
-    }
-}
-
-lazy_static::lazy_static! {
-    static ref JSON_NULL_VALUE: Value = serde_json::json!(null);
-}
-
-impl<'a> DdsPluginRuntime<'a> {
-    fn is_allowed(&self, ke: &keyexpr) -> bool {
-        if ke.ends_with(ROS_DISCOVERY_INFO_TOPIC_NAME) {
-            log_ros2_deprecation_warning();
-        }
-
-        if self.config.forward_discovery && ke.ends_with(ROS_DISCOVERY_INFO_TOPIC_NAME) {
-            // If fwd-discovery mode is enabled, don't route "ros_discovery_info"
-            return false;
-        }
-        match (&self.config.allow, &self.config.deny) {
-            (Some(allow), None) => allow.is_match(ke),
-            (None, Some(deny)) => !deny.is_match(ke),
-            (Some(allow), Some(deny)) => allow.is_match(ke) && !deny.is_match(ke),
-            (None, None) => true,
-        }
-    }
-
-    // Return the read period if keyexpr matches one of the --dds-periodic-topics option
-    fn get_read_period(&self, ke: &keyexpr) -> Option<Duration> {
-        for (re, freq) in &self.config.max_frequencies {
-            if re.is_match(ke) {
-                return Some(Duration::from_secs_f32(1f32 / freq));
-            }
-        }
-        None
-    }
-
-    fn get_participant_admin_keyexpr(e: &DdsParticipant) -> OwnedKeyExpr {
-        format!("participant/{}", e.key,).try_into().unwrap()
-    }
-
-    fn get_entity_admin_keyexpr(e: &DdsEntity, is_writer: bool) -> OwnedKeyExpr {
-        format!(
-            "participant/{}/{}/{}/{}",
-            e.participant_key,
-            if is_writer { "writer" } else { "reader" },
-            e.key,
-            e.topic_name
+        s.serialize_field(
+            "put_timeouts",
+            &self
+                .config
+                .put_timeouts
+                .iter()
+                .map(|(re, timeout)| format!("{re}={timeout}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "topic_keyexpr_map",
+            &self
+                .config
+                .topic_keyexpr_map
+                .iter()
+                .map(|(re, template)| format!("{re}={template}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "compatible_types",
+            &self
+                .config
+                .compatible_types
+                .iter()
+                .map(|(a, b)| format!("{a}={b}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "transient_local_max_samples",
+            &self
+                .config
+                .transient_local_max_samples
+                .iter()
+                .map(|(re, max_samples)| format!("{re}={max_samples}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "downsampling_depths",
+            &self
+                .config
+                .downsampling_depths
+                .iter()
+                .map(|(re, depth)| format!("{re}={depth}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "batch",
+            &self
+                .config
+                .batch
+                .iter()
+                .map(|(re, batch)| format!("{re}={batch}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "express",
+            &self.config.express.as_ref().map(|re| re.to_string()),
+        )?;
+        s.serialize_field(
+            "keyed_instance_routing",
+            &self
+                .config
+                .keyed_instance_routing
+                .as_ref()
+                .map(|re| re.to_string()),
+        )?;
+        s.serialize_field(
+            "async_forward",
+            &self.config.async_forward.as_ref().map(|re| re.to_string()),
+        )?;
+        s.serialize_field(
+            "async_forward_queue_size",
+            &self.config.async_forward_queue_size,
+        )?;
+        s.serialize_field(
+            "async_forward_overflow",
+            &self.config.async_forward_overflow,
+        )?;
+        s.serialize_field(
+            "compress",
+            &self
+                .config
+                .compress
+                .iter()
+                .map(|(re, compression)| format!("{re}={compression}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field("max_decompressed_size", &self.config.max_decompressed_size)?;
+        s.serialize_field(
+            "delivery_modes",
+            &self
+                .config
+                .delivery_modes
+                .iter()
+                .map(|(re, mode)| format!("{re}={mode}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "priorities",
+            &self
+                .config
+                .priorities
+                .iter()
+                .map(|(re, priority)| format!("{re}={}", priority_to_str(*priority)))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "congestion_control",
+            &self
+                .config
+                .congestion_control
+                .iter()
+                .map(|(re, cc)| format!("{re}={}", congestion_control_to_str(*cc)))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "transport_priority_mapping",
+            &self
+                .config
+                .transport_priority_mapping
+                .iter()
+                .map(|(threshold, priority)| format!("{threshold}={}", priority_to_str(*priority)))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field("forwarding_profiles", &self.config.forwarding_profiles)?;
+        s.serialize_field(
+            "topic_forwarding_profiles",
+            &self
+                .config
+                .topic_forwarding_profiles
+                .iter()
+                .map(|(re, name)| format!("{re}={name}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "payload_filters",
+            &self
+                .config
+                .payload_filters
+                .iter()
+                .map(|(re, matcher)| format!("{re}: {matcher}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "content_filter",
+            &self
+                .config
+                .content_filter
+                .iter()
+                .map(|(re, filter)| format!("{re}={filter}"))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "qos_overrides",
+            &self
+                .config
+                .qos_overrides
+                .iter()
+                .map(|(re, qos)| format!("{re}={}", serde_json::to_string(qos).unwrap_or_default()))
+                .collect::<Vec<String>>(),
+        )?;
+        s.serialize_field(
+            "sla_thresholds",
+            &self
+                .config
+                .sla_thresholds
+                .iter()
+                .map(|t| {
+                    serde_json::json!({
+                        "topic": t.topic.as_str(),
+                        "max_latency": t.max_latency.map(|d| d.as_secs_f32()),
+                        "min_rate": t.min_rate,
+                        "max_jitter": t.max_jitter.map(|d| d.as_secs_f32()),
+                    })
+                })
+                .collect::<Vec<Value>>(),
+        )?;
+        s.serialize_field(
+            "sla_check_period",
+            &self.config.sla_check_period.as_secs_f32(),
+        )?;
+        s.serialize_field(
+            "last_sample_preview",
+            &self
+                .config
+                .last_sample_preview
+                .as_ref()
+                .map(|re| re.to_string()),
+        )?;
+        s.serialize_field(
+            "last_sample_preview_max_bytes",
+            &self.config.last_sample_preview_max_bytes,
+        )?;
+        s.serialize_field(
+            "coalesce_writers",
+            &self
+                .config
+                .coalesce_writers
+                .as_ref()
+                .map(|re| re.to_string()),
+        )?;
+        s.serialize_field(
+            "force_keyless",
+            &self.config.force_keyless.as_ref().map(|re| re.to_string()),
+        )?;
+        s.serialize_field(
+            "force_keyed",
+            &self.config.force_keyed.as_ref().map(|re| re.to_string()),
+        )?;
+        s.serialize_field(
+            "allow_best_effort",
+            &self
+                .config
+                .allow_best_effort
+                .as_ref()
+                .map(|re| re.to_string()),
+        )?;
+        s.serialize_field("forward_discovery", &self.config.forward_discovery)?;
+        s.serialize_field("allowed_peers", &self.config.allowed_peers)?;
+        s.serialize_field(
+            "reliable_routes_blocking",
+            &self.config.reliable_routes_blocking,
+        )?;
+        s.serialize_field("queries_max_retries", &self.config.queries_max_retries)?;
+        s.serialize_field(
+            "queries_retry_backoff",
+            &self.config.queries_retry_backoff.as_secs_f32(),
+        )?;
+        s.serialize_field(
+            "historical_data_timeout",
+            &self.config.historical_data_timeout.as_secs_f32(),
+        )?;
+        s.serialize_field("admin_route_qos", &self.config.admin_route_qos)?;
+        s.serialize_field(
+            "validate_forwarded_qos",
+            &self.config.validate_forwarded_qos,
+        )?;
+        s.serialize_field(
+            "admin_query_default_limit",
+            &self.config.admin_query_default_limit,
+        )?;
+        s.serialize_field("matched_liveliness", &self.config.matched_liveliness)?;
+        s.serialize_field("route_liveliness", &self.config.route_liveliness)?;
+        s.serialize_field(
+            "deadline_missed_events",
+            &self.config.deadline_missed_events,
+        )?;
+        s.serialize_field("dead_letters_key", &self.config.dead_letters_key)?;
+        s.serialize_field(
+            "check_qos_compatibility",
+            &self.config.check_qos_compatibility,
+        )?;
+        s.serialize_field(
+            "preserve_source_timestamp",
+            &self.config.preserve_source_timestamp,
+        )?;
+        s.serialize_field("admin_deny_updates", &self.config.admin_deny_updates)?;
+        s.serialize_field("dry_run", &self.config.dry_run)?;
+        s.serialize_field("manifest", &self.config.manifest)?;
+        s.serialize_field(
+            "prime_writers",
+            &self.config.prime_writers.as_ref().map(|re| re.to_string()),
+        )?;
+        s.serialize_field("topic_types", &self.config.topic_types)?;
+        s.serialize_field("stats_state_file", &self.config.stats_state_file)?;
+        s.serialize_field(
+            "stats_state_save_period",
+            &self.config.stats_state_save_period.as_secs_f32(),
+        )?;
+        s.serialize_field("metrics_port", &self.config.metrics_port)?;
+        s.serialize_field("metrics_address", &self.config.metrics_address)?;
+        s.serialize_field("heartbeat_key", &self.config.heartbeat_key)?;
+        s.serialize_field("payload_log_file", &self.config.payload_log_file)?;
+        s.serialize_field("payload_log_max_size", &self.config.payload_log_max_size)?;
+        s.serialize_field(
+            "heartbeat_period",
+            &self.config.heartbeat_period.as_secs_f32(),
+        )?;
+        s.serialize_field(
+            "participant_check_period",
+            &self.config.participant_check_period.as_secs_f32(),
+        )?;
+        s.serialize_field(
+            "participant_rediscovery_timeout",
+            &self.config.participant_rediscovery_timeout.as_secs_f32(),
+        )?;
+        s.serialize_field("entity_name_prefix", &self.config.entity_name_prefix)?;
+        s.serialize_field(
+            "route_ack_timeout",
+            &self.config.route_ack_timeout.map(|d| d.as_secs_f32()),
+        )?;
+        s.end()
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref JSON_NULL_VALUE: Value = serde_json::json!(null);
+}
+
+impl<'a> DdsPluginRuntime<'a> {
+    // See routing::is_allowed()
+    fn is_allowed(&self, ke: &keyexpr, type_name: &str) -> bool {
+        routing::is_allowed(&self.config, ke, type_name)
+    }
+
+    // Return the topic name normalized as configured, or None if unchanged by the normalization
+    fn normalized_topic_name(&self, topic_name: &str) -> Option<String> {
+        match self.config.topic_normalization.normalize(topic_name) {
+            Cow::Borrowed(_) => None,
+            Cow::Owned(name) => Some(name),
+        }
+    }
+
+    // Return the alias configured for the first of the identifiers (DDS entity key or topic name) having one
+    fn get_alias(&self, ids: &[&str]) -> Option<&String> {
+        ids.iter().find_map(|id| self.config.topic_aliases.get(*id))
+    }
+
+    // Return a suffix to be appended to logs, with the alias of the identifiers (if any)
+    fn alias_suffix(&self, ids: &[&str]) -> String {
+        match self.get_alias(ids) {
+            Some(alias) => format!(" (alias: '{alias}')"),
+            None => String::new(),
+        }
+    }
+
+    // Return the downsampling if keyexpr matches one of the --max-frequency option,
+    // with the history depth from downsampling_depths configuration (1 by default, unused by the "first" strategy)
+    fn get_downsampling(&self, ke: &keyexpr) -> Option<Downsampling> {
+        for (re, freq, strategy) in &self.config.max_frequencies {
+            if re.is_match(ke) {
+                let depth = self
+                    .config
+                    .downsampling_depths
+                    .iter()
+                    .find(|(re, _)| re.is_match(ke))
+                    .map_or(1, |(_, depth)| *depth);
+                return Some(Downsampling {
+                    period: Duration::from_secs_f32(1f32 / freq),
+                    depth,
+                    strategy: *strategy,
+                });
+            }
+        }
+        None
+    }
+
+    // Return the batching configuration if keyexpr matches one of the batch configuration
+    fn get_batch(&self, ke: &keyexpr) -> Option<BatchConfig> {
+        self.config
+            .batch
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+            .map(|(_, batch)| *batch)
+    }
+
+    // Return true if the data routed from DDS on keyexpr are published with the zenoh express flag
+    // (see express configuration)
+    fn is_express(&self, ke: &keyexpr) -> bool {
+        self.config
+            .express
+            .as_ref()
+            .map_or(false, |re| re.is_match(ke))
+    }
+
+    // Return true if the instances of the keyed topic routed on keyexpr are routed each on its own
+    // "<keyexpr>/<instance-keyhash>" sub-key (see keyed_instance_routing configuration)
+    fn is_keyed_instance_routed(&self, ke: &keyexpr, keyless: bool) -> bool {
+        !keyless
+            && self
+                .config
+                .keyed_instance_routing
+                .as_ref()
+                .map_or(false, |re| re.is_match(ke))
+    }
+
+    // Return the settings of the queue decoupling the DDS listener of a route from DDS on topic_name from its
+    // publications to zenoh, if the topic matches async_forward
+    fn get_async_forward(&self, topic_name: &str) -> Option<AsyncForward> {
+        self.config
+            .async_forward
+            .as_ref()
+            .filter(|re| re.is_match(topic_name))
+            .map(|_| self.async_forward_settings())
+    }
+
+    // Return the settings of the queue of a route from DDS (see async_forward_queue_size and async_forward_overflow)
+    fn async_forward_settings(&self) -> AsyncForward {
+        AsyncForward {
+            capacity: self.config.async_forward_queue_size,
+            overflow: self.config.async_forward_overflow,
+        }
+    }
+
+    // Return the compression of the payloads routed from DDS for a topic, if it matches one of the compress entries
+    fn get_compression(&self, topic_name: &str) -> Option<Compression> {
+        self.config
+            .compress
+            .iter()
+            .find(|(re, _)| re.is_match(topic_name))
+            .map(|(_, compression)| *compression)
+    }
+
+    // Return the zenoh put timeout for keyexpr: the first matching put_timeouts override (a 0 timeout meaning none),
+    // or the global put_timeout
+    fn get_put_timeout(&self, ke: &keyexpr) -> Option<Duration> {
+        match self
+            .config
+            .put_timeouts
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+        {
+            Some((_, timeout)) if *timeout > 0.0 => Some(Duration::from_secs_f32(*timeout)),
+            Some(_) => None,
+            None => self.config.put_timeout,
+        }
+    }
+
+    // Return the delivery mode if keyexpr matches one of the delivery_modes configuration
+    fn get_delivery_mode(&self, ke: &keyexpr) -> Option<DeliveryMode> {
+        self.config
+            .delivery_modes
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+            .map(|(_, mode)| *mode)
+    }
+
+    // Return the max number of samples cached for the late joiners by the route from DDS on keyexpr (see
+    // transient_local_max_samples configuration), or None if unbounded
+    fn get_transient_local_max_samples(&self, ke: &keyexpr) -> Option<usize> {
+        self.config
+            .transient_local_max_samples
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+            .map(|(_, max_samples)| *max_samples)
+    }
+
+    // Return the zenoh priority for the data routed from DDS on keyexpr: either from the priorities configuration
+    // if keyexpr matches, either from the transport_priority_mapping of the DDS Writer's TRANSPORT_PRIORITY (0 if unknown)
+    fn get_priority(&self, ke: &keyexpr, transport_priority: Option<i32>) -> Priority {
+        if let Some((_, priority)) = self
+            .config
+            .priorities
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+        {
+            return *priority;
+        }
+        let value = transport_priority.unwrap_or(0);
+        self.config
+            .transport_priority_mapping
+            .iter()
+            .find(|(threshold, _)| value >= *threshold)
+            .map_or(Priority::default(), |(_, priority)| *priority)
+    }
+
+    // Return the zenoh congestion control for the data routed from DDS on keyexpr: either from the congestion_control
+    // configuration if keyexpr matches, either the default one (depending on reliable_routes_blocking)
+    fn get_congestion_control(
+        &self,
+        ke: &keyexpr,
+        default: CongestionControl,
+    ) -> CongestionControl {
+        self.config
+            .congestion_control
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+            .map_or(default, |(_, cc)| *cc)
+    }
+
+    // Return true if the remote bridge with this id is allowed to exchange forwarded discovery with this bridge
+    fn is_peer_allowed(&self, id: &str) -> bool {
+        self.config.allowed_peers.is_empty() || self.config.allowed_peers.contains(id)
+    }
+
+    // Return the forwarding profile assigned to keyexpr in topic_forwarding_profiles configuration
+    // (the default passthrough profile if none, or if the assigned profile is not defined)
+    fn get_forwarding_profile(&self, ke: &keyexpr) -> ForwardingProfile {
+        match self
+            .config
+            .topic_forwarding_profiles
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+        {
+            Some((_, name)) => match self.config.forwarding_profiles.get(name) {
+                Some(profile) => profile.clone(),
+                None => {
+                    warn!(
+                        "Forwarding profile '{}' assigned to {} is not defined in 'forwarding_profiles' configuration - ignore it",
+                        name, ke
+                    );
+                    ForwardingProfile::default()
+                }
+            },
+            None => ForwardingProfile::default(),
+        }
+    }
+
+    // Return the payload filter if keyexpr matches one of the payload_filters configuration
+    fn get_payload_filter(&self, ke: &keyexpr) -> Option<PayloadMatcher> {
+        self.config
+            .payload_filters
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+            .map(|(_, matcher)| matcher.clone())
+    }
+
+    // Return the content filter if keyexpr matches one of the content_filter configuration
+    fn get_content_filter(&self, ke: &keyexpr) -> Option<ContentFilter> {
+        self.config
+            .content_filter
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+            .map(|(_, filter)| filter.clone())
+    }
+
+    // Return the QoS override if keyexpr matches one of the qos_overrides configuration
+    fn get_qos_override(&self, ke: &keyexpr) -> Option<Qos> {
+        self.config
+            .qos_overrides
+            .iter()
+            .find(|(re, _)| re.is_match(ke))
+            .map(|(_, qos)| qos.clone())
+    }
+
+    // Return the SLA thresholds if keyexpr matches one of the sla_thresholds configuration
+    fn get_sla_thresholds(&self, ke: &keyexpr) -> Option<&SlaThresholds> {
+        self.config
+            .sla_thresholds
+            .iter()
+            .find(|t| t.topic.is_match(ke))
+    }
+
+    // Return the max number of bytes of the last sample preview for keyexpr, if last_sample_preview is configured for it
+    fn get_last_sample_preview_max_bytes(&self, ke: &keyexpr) -> Option<usize> {
+        match &self.config.last_sample_preview {
+            Some(re) if re.is_match(ke) => Some(self.config.last_sample_preview_max_bytes),
+            _ => None,
+        }
+    }
+
+    // Override the discovered keyless-ness of a DDS entity if its topic matches force_keyless or force_keyed
+    fn override_keyless(&self, entity: &mut DdsEntity) {
+        let force_keyless = self
+            .config
+            .force_keyless
+            .as_ref()
+            .map_or(false, |re| re.is_match(&entity.topic_name));
+        let force_keyed = self
+            .config
+            .force_keyed
+            .as_ref()
+            .map_or(false, |re| re.is_match(&entity.topic_name));
+        let keyless = match (force_keyless, force_keyed) {
+            (true, false) => true,
+            (false, true) => false,
+            (false, false) => return,
+            (true, true) => {
+                warn!(
+                    "DDS entity {} on {}: topic matches both 'force_keyless' and 'force_keyed' - keep the discovered keyless={}",
+                    entity.key, entity.topic_name, entity.keyless
+                );
+                return;
+            }
+        };
+        if entity.keyless != keyless {
+            warn!(
+                "DDS entity {} on {} with type {}: discovered keyless={} overridden with keyless={} (see your '{}' configuration)",
+                entity.key,
+                entity.topic_name,
+                entity.type_name,
+                entity.keyless,
+                keyless,
+                if keyless { "force_keyless" } else { "force_keyed" }
+            );
+            entity.keyless = keyless;
+        }
+    }
+
+    // Return true if the DDS Writers routed on keyexpr must be coalesced (see coalesce_writers configuration)
+    fn is_coalescing_writers(&self, ke: &keyexpr) -> bool {
+        self.config
+            .coalesce_writers
+            .as_ref()
+            .map_or(false, |re| re.is_match(ke))
+    }
+
+    // Return the key expression on which the SLA events of the route for keyexpr are published
+    fn get_sla_events_keyexpr(&self, ke: &keyexpr) -> OwnedKeyExpr {
+        *KE_PREFIX_ADMIN_SPACE / &self.zsession.zid().into_keyexpr() / *KE_PREFIX_SLA_EVENTS / ke
+    }
+
+    // Return the EntityName QoS of the DDS Reader (route_kind "from_dds") or Writer (route_kind "to_dds") created
+    // for a route on topic_name, or None if entity_name_prefix is empty
+    fn get_entity_name(&self, route_kind: &str, topic_name: &str) -> Option<String> {
+        dds_entity_name(&self.config.entity_name_prefix, route_kind, topic_name)
+    }
+
+    // Return the key expression on which the missed deadlines of the route for keyexpr are published,
+    // if deadline_missed_events is configured
+    fn get_deadline_missed_keyexpr(&self, ke: &keyexpr) -> Option<OwnedKeyExpr> {
+        self.config.deadline_missed_events.then(|| {
+            *KE_PREFIX_ADMIN_SPACE
+                / &self.zsession.zid().into_keyexpr()
+                / *KE_PREFIX_DEADLINE_MISSED
+                / ke
+        })
+    }
+
+    // Return the liveliness token to be declared while the DDS entity serving the route on keyexpr is matched,
+    // if matched_liveliness is configured. route_kind is either "from_dds" or "to_dds".
+    fn get_matched_liveliness(
+        &self,
+        route_kind: &str,
+        ke: &keyexpr,
+    ) -> Option<Arc<MatchedLiveliness>> {
+        if self.config.matched_liveliness {
+            let liveliness_ke = *KE_PREFIX_ADMIN_SPACE
+                / &self.zsession.zid().into_keyexpr()
+                / *KE_PREFIX_MATCHED
+                / ke_for_sure!(route_kind)
+                / ke;
+            Some(Arc::new(MatchedLiveliness::new(
+                self.zsession.clone(),
+                liveliness_ke,
+            )))
+        } else {
+            None
+        }
+    }
+
+    // Declare the liveliness token advertising a route as long as it exists, on "@/<zid>/dds/route/<route_kind>/<ke>"
+    // if route_liveliness is configured. route_kind is either "from_dds" or "to_dds".
+    async fn declare_route_liveliness(
+        &self,
+        route_kind: &str,
+        ke: &keyexpr,
+    ) -> Option<LivelinessToken<'a>> {
+        if !self.config.route_liveliness {
+            return None;
+        }
+        let liveliness_ke = *KE_PREFIX_ZENOH_ADMIN
+            / &self.zsession.zid().into_keyexpr()
+            / *KE_DDS_ROUTE
+            / ke_for_sure!(route_kind)
+            / ke;
+        match self
+            .zsession
+            .liveliness()
+            .declare_token(liveliness_ke.clone())
+            .res_async()
+            .await
+        {
+            Ok(token) => {
+                debug!("Declared route liveliness token {}", liveliness_ke);
+                Some(token)
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to declare route liveliness token {}: {}",
+                    liveliness_ke, e
+                );
+                None
+            }
+        }
+    }
+
+    fn get_participant_admin_keyexpr(e: &DdsParticipant) -> OwnedKeyExpr {
+        format!("participant/{}", e.key,).try_into().unwrap()
+    }
+
+    fn get_entity_admin_keyexpr(e: &DdsEntity, is_writer: bool) -> OwnedKeyExpr {
+        format!(
+            "participant/{}/{}/{}/{}",
+            e.participant_key,
+            if is_writer { "writer" } else { "reader" },
+            e.key,
+            e.topic_name
+        )
+        .try_into()
+        .unwrap()
+    }
+
+    fn insert_dds_participant(&mut self, admin_keyexpr: OwnedKeyExpr, e: DdsParticipant) {
+        // insert reference in admin space
+        self.admin_space
+            .insert(admin_keyexpr, AdminRef::DdsParticipant(e.key.clone()));
+
+        // insert DdsParticipant in discovered_participants map
+        self.discovered_participants.insert(e.key.clone(), e);
+    }
+
+    fn remove_dds_participant(&mut self, dds_key: &str) -> Option<(OwnedKeyExpr, DdsParticipant)> {
+        // remove fron participants map
+        if let Some(e) = self.discovered_participants.remove(dds_key) {
+            // remove from admin_space
+            let admin_keyexpr = DdsPluginRuntime::get_participant_admin_keyexpr(&e);
+            self.admin_space.remove(&admin_keyexpr);
+            Some((admin_keyexpr, e))
+        } else {
+            None
+        }
+    }
+
+    fn insert_dds_writer(&mut self, admin_keyexpr: OwnedKeyExpr, mut e: DdsEntity) {
+        // record the normalized topic name (if different) for traceability
+        e.normalized_topic_name = self.normalized_topic_name(&e.topic_name);
+
+        // insert reference in admin_space
+        self.admin_space
+            .insert(admin_keyexpr, AdminRef::DdsWriterEntity(e.key.clone()));
+
+        // insert DdsEntity in dds_writer map
+        self.discovered_writers.insert(e.key.clone(), e);
+    }
+
+    fn remove_dds_writer(&mut self, dds_key: &str) -> Option<(OwnedKeyExpr, DdsEntity)> {
+        // remove from dds_writer map
+        if let Some(e) = self.discovered_writers.remove(dds_key) {
+            self.remote_route_statuses.remove(dds_key);
+            // remove from admin_space
+            let admin_keyexpr = DdsPluginRuntime::get_entity_admin_keyexpr(&e, true);
+            self.admin_space.remove(&admin_keyexpr);
+            Some((admin_keyexpr, e))
+        } else {
+            None
+        }
+    }
+
+    fn insert_dds_reader(&mut self, admin_keyexpr: OwnedKeyExpr, mut e: DdsEntity) {
+        // record the normalized topic name (if different) for traceability
+        e.normalized_topic_name = self.normalized_topic_name(&e.topic_name);
+
+        // insert reference in admin_space
+        self.admin_space
+            .insert(admin_keyexpr, AdminRef::DdsReaderEntity(e.key.clone()));
+
+        // insert DdsEntity in dds_reader map
+        self.discovered_readers.insert(e.key.clone(), e);
+    }
+
+    fn remove_dds_reader(&mut self, dds_key: &str) -> Option<(OwnedKeyExpr, DdsEntity)> {
+        // remove from dds_reader map
+        if let Some(e) = self.discovered_readers.remove(dds_key) {
+            self.remote_route_statuses.remove(dds_key);
+            // remove from admin space
+            let admin_keyexpr = DdsPluginRuntime::get_entity_admin_keyexpr(&e, false);
+            self.admin_space.remove(&admin_keyexpr);
+            Some((admin_keyexpr, e))
+        } else {
+            None
+        }
+    }
+
+    fn insert_route_from_dds(&mut self, ke: OwnedKeyExpr, r: RouteDDSZenoh<'a>) {
+        // insert reference in admin_space
+        let admin_ke = *KE_PREFIX_ROUTE_FROM_DDS / &ke;
+        self.admin_space
+            .insert(admin_ke, AdminRef::FromDdsRoute(ke.clone()));
+
+        // restore the route's counters persisted by a previous run (if any)
+        if let Some(stats) = self
+            .stats_state
+            .as_ref()
+            .and_then(|s| s.route_from_dds(&ke))
+        {
+            r.restore_stats(stats);
+        }
+
+        // insert route in routes_from_dds map
+        self.routes_from_dds.insert(ke, r);
+    }
+
+    fn insert_route_to_dds(&mut self, ke: OwnedKeyExpr, mut r: RouteZenohDDS<'a>) {
+        // insert reference in admin_space
+        let admin_ke: OwnedKeyExpr = *KE_PREFIX_ROUTE_TO_DDS / &ke;
+        self.admin_space
+            .insert(admin_ke, AdminRef::ToDdsRoute(ke.clone()));
+
+        // restore the route's counters persisted by a previous run (if any)
+        if let Some(stats) = self.stats_state.as_ref().and_then(|s| s.route_to_dds(&ke)) {
+            r.restore_stats(stats);
+        }
+
+        // insert route in routes_from_dds map
+        self.routes_to_dds.insert(ke, r);
+    }
+
+    // The uptime of the bridge, cumulated with the one persisted by the previous runs (if stats_state_file is configured)
+    fn uptime(&self) -> Duration {
+        self.stats_state
+            .as_ref()
+            .map_or(Duration::ZERO, |s| s.uptime())
+            + self.start_time.elapsed()
+    }
+
+    // Run a self-test in a dedicated task, as it relies on the routes snapshots served by the discovery loop
+    fn spawn_self_test(&self, deadline: Instant, result_tx: Sender<Result<(), String>>) {
+        info!("Self-test requested");
+        let zsession = self.zsession.clone();
+        let domain = self.config.domain;
+        async_std::task::spawn(async move {
+            let _ = result_tx.send(self_test::run_self_test(zsession, domain, deadline).await);
+        });
+    }
+
+    // Persist the counters of the current routes and the uptime in the stats_state_file (if configured).
+    // The counters of the routes that no longer exist are kept as last persisted.
+    fn save_stats_state(&mut self) {
+        let uptime = self.uptime();
+        if let (Some(path), Some(state)) = (&self.config.stats_state_file, &mut self.stats_state) {
+            for (ke, route) in &self.routes_from_dds {
+                state.set_route_from_dds(ke.to_string(), route.stats());
+            }
+            for (ke, route) in &self.routes_to_dds {
+                state.set_route_to_dds(ke.to_string(), route.stats());
+            }
+            if let Err(e) = state.save(path, uptime) {
+                warn!("Failed to persist statistics: {}", e);
+            }
+        }
+    }
+
+    // Return true if a new route can be created now as per route_creation_limit configuration. Otherwise its
+    // creation is to be retried later (see schedule_route_retry()), without blocking the discovery loop.
+    fn check_route_creation_limit(&mut self, ke: &keyexpr) -> bool {
+        match &mut self.route_creation_limiter {
+            Some(limiter) if !limiter.try_acquire() => {
+                debug!(
+                    "Creation of route for {} delayed by 'route_creation_limit' configuration",
+                    ke
+                );
+                false
+            }
+            _ => true,
+        }
+    }
+
+    // Return true if a new route can be created as per max_routes configuration. If the max number of routes is
+    // reached, the least recently active route is evicted if max_routes_eviction is configured (its DDS entities
+    // being throttled), otherwise the new route is throttled.
+    fn make_room_for_route(&mut self, ke: &keyexpr) -> bool {
+        let max_routes = match self.config.max_routes {
+            Some(max_routes) => max_routes,
+            None => return true,
+        };
+        if self.routes_from_dds.len() + self.routes_to_dds.len() < max_routes {
+            self.max_routes_reached = false;
+            return true;
+        }
+        if !self.max_routes_reached {
+            warn!(
+                "The max number of routes ({}) is reached (see your 'max_routes' configuration)",
+                max_routes
+            );
+            self.max_routes_reached = true;
+        }
+        if self.config.max_routes_eviction == RouteEviction::LeastRecentlyActive {
+            // the routes created from the startup manifest are never evicted
+            let evicted = least_recently_active_route(
+                self.routes_from_dds
+                    .iter()
+                    .filter(|(_, route)| !route.is_from_manifest())
+                    .map(|(ke, route)| (RouteDirection::FromDds, ke, route.last_activity()))
+                    .chain(
+                        self.routes_to_dds
+                            .iter()
+                            .filter(|(_, route)| !route.is_from_manifest())
+                            .map(|(ke, route)| (RouteDirection::ToDds, ke, route.last_activity())),
+                    ),
+            )
+            .map(|(direction, ke)| (direction, ke.clone()));
+            if let Some((direction, evicted)) = evicted {
+                info!(
+                    "Evict the least recently active route for {} to create the route for {} (see your 'max_routes_eviction' configuration)",
+                    evicted, ke
+                );
+                let evicted = [evicted];
+                match direction {
+                    RouteDirection::FromDds => {
+                        self.remove_routes(&evicted, &[], "evicted", RouteStatus::Throttled)
+                    }
+                    RouteDirection::ToDds => {
+                        self.remove_routes(&[], &evicted, "evicted", RouteStatus::Throttled)
+                    }
+                }
+                return true;
+            }
+        }
+        info!(
+            "Route for {} throttled as the max number of routes ({}) is reached (see your 'max_routes' configuration)",
+            ke, max_routes
+        );
+        false
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn try_add_route_from_dds(
+        &mut self,
+        mut ke: OwnedKeyExpr,
+        topic_name: &str,
+        topic_type: &str,
+        type_info: &Option<TypeInfo>,
+        keyless: bool,
+        reader_qos: Qos,
+        congestion_ctrl: CongestionControl,
+        transport_priority: Option<i32>,
+        lifespan: Option<i64>,
+    ) -> RouteStatus {
+        if !is_route_allowed(&self.config, &ke, topic_type, RouteDirection::FromDds) {
+            return RouteStatus::NotAllowed;
+        }
+        if self.config.dry_run {
+            info!(
+                "Dry run: route DDS topic {} (type {}, partitions {:?}) to zenoh key {} => Routed",
+                topic_name, topic_type, reader_qos.partition, ke
+            );
+            return RouteStatus::DryRun(ke);
+        }
+
+        if let Some(route) = self.routes_from_dds.get(&ke) {
+            if !self
+                .config
+                .are_compatible_types(route.topic_type(), topic_type)
+            {
+                warn!(
+                    "{}: a DDS Writer on {} has the type '{}' conflicting with the route's type '{}' - not routed (see your 'compatible_types' configuration)",
+                    route, topic_name, topic_type, route.topic_type()
+                );
+                return RouteStatus::TypeConflict {
+                    route: ke,
+                    route_type: route.topic_type().into(),
+                    type_name: topic_type.into(),
+                };
+            }
+        }
+
+        // a route replaced because of a keyless conflict: its routed entities are transferred to the new route
+        let mut replaced = None;
+        let mut keyless_conflict = false;
+        if let Some(route) = self.routes_from_dds.get_mut(&ke) {
+            if route.is_keyless() != keyless {
+                keyless_conflict = true;
+                route.set_keyless_conflict();
+                match (self.config.keyless_conflict, keyless) {
+                    (KeylessConflict::PreferKeyed, false)
+                    | (KeylessConflict::PreferKeyless, true) => {
+                        warn!(
+                            "{}: DDS Writers on {} disagree on keyless-ness - re-create the route with keyless={} (see your 'keyless_conflict' configuration)",
+                            route, topic_name, keyless
+                        );
+                        replaced = Some((route.take_routed_entities(), route.is_from_manifest()));
+                    }
+                    (KeylessConflict::SeparateRoutes, _) => {
+                        let suffix = if keyless { *KE_KEYLESS } else { *KE_KEYED };
+                        warn!(
+                            "{}: DDS Writers on {} disagree on keyless-ness - route the ones with keyless={} to {} (see your 'keyless_conflict' configuration)",
+                            route, topic_name, keyless, &ke / suffix
+                        );
+                        ke = &ke / suffix;
+                    }
+                    _ => {
+                        warn!(
+                            "{}: DDS Writers on {} disagree on keyless-ness - keep the route with keyless={} (see your 'keyless_conflict' configuration)",
+                            route, topic_name, route.is_keyless()
+                        );
+                        return RouteStatus::Routed(ke);
+                    }
+                }
+            }
+        }
+        if replaced.is_some() {
+            // delete the replaced route before creating the new one, that uses the same DDS topic and zenoh key expression
+            self.routes_from_dds.remove(&ke);
+            self.admin_space.remove(&(*KE_PREFIX_ROUTE_FROM_DDS / &ke));
+        } else if let Some(route) = self.routes_from_dds.get(&ke) {
+            debug!(
+                "Route from DDS to resource {} already exists -- ignoring",
+                ke
+            );
+            let route_status = get_existing_route_status(ke, route.dds_reader_qos(), &reader_qos);
+            if let RouteStatus::QoSConflict { conflicts, .. } = &route_status {
+                warn!(
+                    "{}: a DDS Writer on {} has a QoS conflicting with the route's QoS: {}",
+                    route, topic_name, conflicts
+                );
+            }
+            return route_status;
+        }
+
+        // a replacing route doesn't change the number of routes
+        if replaced.is_none() && !self.make_room_for_route(&ke) {
+            return RouteStatus::Throttled;
+        }
+
+        // create route DDS->Zenoh
+        match RouteDDSZenoh::new(
+            self,
+            topic_name.into(),
+            topic_type.into(),
+            type_info,
+            keyless,
+            reader_qos,
+            ke.clone(),
+            self.get_congestion_control(&ke, congestion_ctrl),
+            self.get_priority(&ke, transport_priority),
+            lifespan,
         )
-        .try_into()
-        .unwrap()
+        .await
+        {
+            Ok(mut route) => {
+                info!(
+                    topic = topic_name,
+                    keyexpr = %ke,
+                    direction = "from_dds",
+                    "Route DDS->Zenoh created with topic_type={}{}",
+                    topic_type,
+                    self.alias_suffix(&[topic_name])
+                );
+                if keyless_conflict {
+                    route.set_keyless_conflict();
+                }
+                if let Some(((remote_readers, local_writers), from_manifest)) = replaced {
+                    for admin_ke in remote_readers {
+                        route.add_remote_routed_reader(admin_ke);
+                    }
+                    for entity_key in local_writers {
+                        route.add_local_routed_writer(entity_key);
+                    }
+                    if from_manifest {
+                        route.set_from_manifest();
+                    }
+                }
+                self.insert_route_from_dds(ke.clone(), route);
+                RouteStatus::Routed(ke)
+            }
+            Err(e) => {
+                error!(
+                    topic = topic_name,
+                    keyexpr = %ke,
+                    direction = "from_dds",
+                    retcode = e.retcode(),
+                    "Route DDS->Zenoh creation failed: {}",
+                    e
+                );
+                RouteStatus::creation_failure(&e)
+            }
+        }
+    }
+
+    async fn try_add_route_to_dds(
+        &mut self,
+        ke: OwnedKeyExpr,
+        topic_name: &str,
+        topic_type: &str,
+        keyless: bool,
+        is_transient: bool,
+        writer_qos: Option<Qos>,
+    ) -> RouteStatus {
+        if !is_route_allowed(&self.config, &ke, topic_type, RouteDirection::ToDds) {
+            return RouteStatus::NotAllowed;
+        }
+        if self.config.dry_run {
+            info!(
+                "Dry run: route zenoh key {} to DDS topic {} (type {}, partitions {:?}) => Routed",
+                ke,
+                topic_name,
+                topic_type,
+                writer_qos.as_ref().and_then(|qos| qos.partition.as_ref())
+            );
+            return RouteStatus::DryRun(ke);
+        }
+
+        if let Some(route) = self.routes_to_dds.get(&ke) {
+            if !self
+                .config
+                .are_compatible_types(route.topic_type(), topic_type)
+            {
+                warn!(
+                    "{}: a DDS Reader on {} has the type '{}' conflicting with the route's type '{}' - not routed (see your 'compatible_types' configuration)",
+                    route, topic_name, topic_type, route.topic_type()
+                );
+                return RouteStatus::TypeConflict {
+                    route: ke,
+                    route_type: route.topic_type().into(),
+                    type_name: topic_type.into(),
+                };
+            }
+            debug!(
+                "Route from resource {} to DDS already exists -- ignoring",
+                ke
+            );
+            // #102: in forwarding mode, it might happen that the route have been created but without DDS Writer
+            //       (just to declare the Zenoh Subscriber). Thus, try to set a DDS Writer to the route here.
+            //       If already set, nothing will happen.
+            if let Some(qos) = writer_qos {
+                if let Some(route_qos) = route.dds_writer_qos() {
+                    let route_status = get_existing_route_status(ke, &route_qos, &qos);
+                    if let RouteStatus::QoSConflict { conflicts, .. } = &route_status {
+                        warn!(
+                            "{}: a DDS Reader on {} has a QoS conflicting with the route's QoS: {}",
+                            route, topic_name, conflicts
+                        );
+                    }
+                    return route_status;
+                }
+                if self.config.check_qos_compatibility {
+                    route.set_qos_incompatibilities(self.check_writer_qos(topic_name, &qos));
+                }
+                if let Err(e) = route.set_dds_writer(self.dp, qos) {
+                    error!(
+                        "{}: failed to set a DDS Writer after creation: {}",
+                        route, e
+                    );
+                    return RouteStatus::creation_failure(&e);
+                }
+            }
+            return RouteStatus::Routed(ke);
+        }
+
+        let qos_incompatibilities = match &writer_qos {
+            Some(qos) if self.config.check_qos_compatibility => {
+                self.check_writer_qos(topic_name, qos)
+            }
+            _ => Vec::new(),
+        };
+
+        if !self.make_room_for_route(&ke) {
+            return RouteStatus::Throttled;
+        }
+
+        // create route Zenoh->DDS (with a DDS Writer if writer_qos is set)
+        match RouteZenohDDS::new(
+            self,
+            ke.clone(),
+            is_transient,
+            topic_name.into(),
+            topic_type.into(),
+            keyless,
+            writer_qos,
+        )
+        .await
+        {
+            Ok(route) => {
+                route.set_qos_incompatibilities(qos_incompatibilities);
+                info!(
+                    topic = topic_name,
+                    keyexpr = %ke,
+                    direction = "to_dds",
+                    "Route Zenoh->DDS created with topic_type={}{}",
+                    topic_type,
+                    self.alias_suffix(&[topic_name])
+                );
+                self.insert_route_to_dds(ke.clone(), route);
+                RouteStatus::Routed(ke)
+            }
+            Err(e) => {
+                error!(
+                    topic = topic_name,
+                    keyexpr = %ke,
+                    direction = "to_dds",
+                    retcode = e.retcode(),
+                    "Route Zenoh->DDS creation failed: {}",
+                    e
+                );
+                RouteStatus::creation_failure(&e)
+            }
+        }
+    }
+
+    // Check the QoS of a DDS Writer to be created against the QoS of the discovered local DDS Readers
+    // on the same topic and partitions, warning about and returning the incompatibilities.
+    fn check_writer_qos(&self, topic_name: &str, writer_qos: &Qos) -> Vec<String> {
+        let mut result = Vec::new();
+        for reader in self.discovered_readers.values() {
+            if reader.topic_name != topic_name
+                || !partitions_overlap(&reader.qos.partition, &writer_qos.partition)
+            {
+                continue;
+            }
+            for incompatibility in get_qos_incompatibilities(writer_qos, &reader.qos) {
+                warn!(
+                    "DDS Writer for topic {} will not match DDS Reader {}{} - incompatible QoS: {}",
+                    topic_name,
+                    reader.key,
+                    self.alias_suffix(&[&reader.key]),
+                    incompatibility
+                );
+                result.push(format!("{}: {}", reader.key, incompatibility));
+            }
+        }
+        result
+    }
+
+    // Pre-create the routes listed in the startup manifest and the primed DDS Writers (see prime_writers configuration),
+    // independently of the DDS discovery. Those routes are never removed, and are shared with the discovered DDS entities
+    // on the same topics.
+    async fn create_manifest_routes(&mut self) {
+        let primed_writers = self.config.primed_writers();
+        for entry in self
+            .config
+            .manifest
+            .clone()
+            .into_iter()
+            .chain(primed_writers)
+        {
+            if !self
+                .config
+                .partition_filter
+                .is_allowed(&entry.qos.partition)
+            {
+                info!(
+                    "Ignoring manifest route for {} as its partitions {:?} are not allowed (see your 'partition_filter' configuration)",
+                    entry.topic, entry.qos.partition
+                );
+                continue;
+            }
+            // create 1 route per partition, or just 1 if no partition
+            let partitions: Vec<Option<&str>> = if partition_is_empty(&entry.qos.partition) {
+                vec![None]
+            } else {
+                entry
+                    .qos
+                    .partition
+                    .as_deref()
+                    .unwrap()
+                    .iter()
+                    .map(|p| Some(p.as_str()))
+                    .collect()
+            };
+            for p in partitions {
+                let ke = match self.topic_to_keyexpr(&entry.topic, &self.config.scope, p) {
+                    Ok(ke) => ke,
+                    Err(e) => {
+                        error!(
+                            "Invalid topic '{}' in manifest (partition: {:?}): {}",
+                            entry.topic, p, e
+                        );
+                        continue;
+                    }
+                };
+                // the type information is not known until discovery: the route uses a blob type
+                let route_status = match entry.direction {
+                    RouteDirection::FromDds => {
+                        let qos = resolve_route_qos(RouteDirection::FromDds, false, &entry.qos);
+                        let congestion_ctrl = match (
+                            self.config.reliable_routes_blocking,
+                            is_writer_reliable(&entry.qos.reliability),
+                        ) {
+                            (true, true) => CongestionControl::Block,
+                            _ => CongestionControl::Drop,
+                        };
+                        let route_status = self
+                            .try_add_route_from_dds(
+                                ke,
+                                &entry.topic,
+                                &entry.type_name,
+                                &None,
+                                entry.keyless,
+                                qos,
+                                congestion_ctrl,
+                                entry.qos.transport_priority.as_ref().map(|p| p.value),
+                                get_lifespan(&entry.qos),
+                            )
+                            .await;
+                        if let Some(route_key) = route_status.route_key() {
+                            if let Some(r) = self.routes_from_dds.get_mut(route_key) {
+                                r.set_from_manifest();
+                            }
+                        }
+                        route_status
+                    }
+                    RouteDirection::ToDds => {
+                        let qos = resolve_route_qos(RouteDirection::ToDds, false, &entry.qos);
+                        let route_status = self
+                            .try_add_route_to_dds(
+                                ke,
+                                &entry.topic,
+                                &entry.type_name,
+                                entry.keyless,
+                                is_transient_local(&qos),
+                                Some(qos),
+                            )
+                            .await;
+                        if let Some(route_key) = route_status.route_key() {
+                            if let Some(r) = self.routes_to_dds.get_mut(route_key) {
+                                r.set_from_manifest();
+                            }
+                        }
+                        route_status
+                    }
+                };
+                debug!(
+                    "Manifest route for {} (partition: {:?}): {:?}",
+                    entry.topic, p, route_status
+                );
+            }
+        }
+    }
+
+    // The "remote_route_status" of an advertised DDS entity, if route_ack_timeout is configured
+    fn entity_remote_route_status(&self, dds_key: &str) -> Option<Value> {
+        let timeout = self.config.route_ack_timeout?;
+        self.remote_route_statuses
+            .get(dds_key)
+            .map(|s| s.to_value(timeout))
+    }
+
+    // The "remote_route_status" of a route, if route_ack_timeout is configured: the statuses acknowledged by the
+    // remote bridges for the advertised DDS entities served by a route with the same key expression
+    fn route_remote_route_status(&self, zkey: &keyexpr) -> Option<Value> {
+        if self.config.route_ack_timeout.is_none() {
+            return None;
+        }
+        let mut statuses = serde_json::Map::new();
+        for remote in self.remote_route_statuses.values() {
+            for (uuid, routes) in &remote.acks {
+                for status in routes.values() {
+                    if status.route_key().map(|ke| ke.as_str()) == Some(zkey.as_str()) {
+                        statuses.insert(
+                            uuid.clone(),
+                            serde_json::to_value(status).unwrap_or_default(),
+                        );
+                    }
+                }
+            }
+        }
+        Some(Value::Object(statuses))
+    }
+
+    // Return the DDS topic name of an admin space entry, if it's a DDS entity (other than a participant) or a route
+    fn get_admin_topic_name(&self, admin_ref: &AdminRef) -> Option<&str> {
+        match admin_ref {
+            AdminRef::DdsReaderEntity(key) => self
+                .discovered_readers
+                .get(key)
+                .map(|e| e.topic_name.as_str()),
+            AdminRef::DdsWriterEntity(key) => self
+                .discovered_writers
+                .get(key)
+                .map(|e| e.topic_name.as_str()),
+            AdminRef::FromDdsRoute(zkey) | AdminRef::FromDdsRouteStats(zkey) => {
+                self.routes_from_dds.get(zkey).map(|r| r.topic_name())
+            }
+            AdminRef::ToDdsRoute(zkey) => self.routes_to_dds.get(zkey).map(|r| r.topic_name()),
+            _ => None,
+        }
+    }
+
+    fn get_admin_value(&self, admin_ref: &AdminRef) -> Result<Option<Value>, serde_json::Error> {
+        match admin_ref {
+            AdminRef::DdsParticipant(key) => self
+                .discovered_participants
+                .get(key)
+                .map(serde_json::to_value)
+                .map(remove_null_qos_values)
+                .map(add_qos_schema_version)
+                .transpose(),
+            AdminRef::DdsReaderEntity(key) => self
+                .discovered_readers
+                .get(key)
+                .map(|e| {
+                    let value = insert_alias(
+                        add_qos_schema_version(remove_null_qos_values(serde_json::to_value(e))),
+                        self.get_alias(&[&e.key, &e.topic_name]),
+                    );
+                    insert_remote_route_status(value, self.entity_remote_route_status(key))
+                })
+                .transpose(),
+            AdminRef::DdsWriterEntity(key) => self
+                .discovered_writers
+                .get(key)
+                .map(|e| {
+                    let value = insert_alias(
+                        add_qos_schema_version(remove_null_qos_values(serde_json::to_value(e))),
+                        self.get_alias(&[&e.key, &e.topic_name]),
+                    );
+                    insert_remote_route_status(value, self.entity_remote_route_status(key))
+                })
+                .transpose(),
+            AdminRef::FromDdsRoute(zkey) => self
+                .routes_from_dds
+                .get(zkey)
+                .map(|route| {
+                    let value = serde_json::to_value(route);
+                    let value = if self.config.admin_route_qos {
+                        insert_qos_value(value, Some(route.dds_reader_qos()))
+                    } else {
+                        value
+                    };
+                    let value = insert_alias(value, self.get_alias(&[route.topic_name()]));
+                    insert_remote_route_status(value, self.route_remote_route_status(zkey))
+                })
+                .transpose(),
+            AdminRef::FromDdsRouteStats(zkey) => self
+                .routes_from_dds
+                .get(zkey)
+                .map(|route| serde_json::to_value(route.throughput()))
+                .transpose(),
+            AdminRef::ToDdsRoute(zkey) => self
+                .routes_to_dds
+                .get(zkey)
+                .map(|route| {
+                    let value = serde_json::to_value(route);
+                    let value = if self.config.admin_route_qos {
+                        insert_qos_value(value, route.dds_writer_qos().as_ref())
+                    } else {
+                        value
+                    };
+                    let value = insert_alias(value, self.get_alias(&[route.topic_name()]));
+                    insert_remote_route_status(value, self.route_remote_route_status(zkey))
+                })
+                .transpose(),
+            AdminRef::Config => Some(serde_json::to_value(self)).transpose(),
+            AdminRef::ConfigDeny => Some(serde_json::to_value(&self.config.deny)).transpose(),
+            AdminRef::Version => Ok(Some(DDSPlugin::PLUGIN_LONG_VERSION.into())),
+            AdminRef::Uptime => Ok(Some(self.uptime().as_secs_f64().into())),
+            AdminRef::Discovery => Ok(Some(serde_json::json!({
+                "loan_return_failures": discovery_loan_return_failures(),
+                "pending_events": self.discovery_queue.as_ref().map_or(0, |q| q.pending()),
+                "pending_events_high_water_mark": self.discovery_queue.as_ref().map_or(0, |q| q.high_water_mark()),
+            }))),
+            AdminRef::DiscoveredPublications => {
+                discovered_entities_summary(&self.discovered_writers).map(Some)
+            }
+            AdminRef::DiscoveredSubscriptions => {
+                discovered_entities_summary(&self.discovered_readers).map(Some)
+            }
+        }
     }
 
-    fn insert_dds_participant(&mut self, admin_keyexpr: OwnedKeyExpr, e: DdsParticipant) {
-        // insert reference in admin space
-        self.admin_space
-            .insert(admin_keyexpr, AdminRef::DdsParticipant(e.key.clone()));
+    // Compute the effective QoS of the route that would be created for a hypothetical DDS entity, described by the
+    // parameters of an "effective-qos" admin query:
+    //  - "topic" and "direction" ("from_dds" or "to_dds"): mandatory
+    //  - "partition", "type", "reliability" ("reliable" or "best_effort"), "durability" ("volatile" or "transient_local")
+    //    and "history_depth": the QoS of the DDS entity served by the route (the DDS defaults if not set)
+    fn get_effective_qos(&self, parameters: &str) -> Result<Value, String> {
+        let mut topic = None;
+        let mut direction = None;
+        let mut partition = None;
+        let mut type_name = "";
+        let mut qos = Qos::default();
+        for param in parameters.split('&').filter(|p| !p.is_empty()) {
+            let (name, value) = param.split_once('=').unwrap_or((param, ""));
+            match name {
+                "topic" => topic = Some(value),
+                "direction" => {
+                    direction = Some(match value {
+                        "from_dds" => RouteDirection::FromDds,
+                        "to_dds" => RouteDirection::ToDds,
+                        _ => return Err(format!("invalid direction '{value}' (expected 'from_dds' or 'to_dds')")),
+                    })
+                }
+                "partition" => partition = Some(value),
+                "type" => type_name = value,
+                "reliability" => {
+                    qos.reliability = Some(Reliability {
+                        kind: match value {
+                            "reliable" => ReliabilityKind::RELIABLE,
+                            "best_effort" => ReliabilityKind::BEST_EFFORT,
+                            _ => return Err(format!("invalid reliability '{value}' (expected 'reliable' or 'best_effort')")),
+                        },
+                        max_blocking_time: DDS_100MS_DURATION,
+                    })
+                }
+                "durability" => {
+                    qos.durability = Some(Durability {
+                        kind: match value {
+                            "volatile" => DurabilityKind::VOLATILE,
+                            "transient_local" => DurabilityKind::TRANSIENT_LOCAL,
+                            _ => return Err(format!("invalid durability '{value}' (expected 'volatile' or 'transient_local')")),
+                        },
+                    })
+                }
+                "history_depth" => {
+                    qos.history = Some(History {
+                        kind: HistoryKind::KEEP_LAST,
+                        depth: value
+                            .parse::<i32>()
+                            .map_err(|e| format!("invalid history_depth '{value}': {e}"))?,
+                    })
+                }
+                // ignore zenoh's reserved parameters
+                _ if name.starts_with('_') => (),
+                _ => return Err(format!("unknown parameter '{name}'")),
+            }
+        }
+        let topic = topic.ok_or("missing 'topic' parameter")?;
+        let direction = direction.ok_or("missing 'direction' parameter")?;
+        if let Some(p) = partition {
+            qos.partition = Some(vec![p.to_string()]);
+        }
 
-        // insert DdsParticipant in discovered_participants map
-        self.discovered_participants.insert(e.key.clone(), e);
+        let ke = self
+            .topic_to_keyexpr(topic, &self.config.scope, partition)
+            .map_err(|e| format!("invalid topic '{topic}': {e}"))?;
+        let allowed = self.is_allowed(&ke, type_name)
+            && self.config.partition_filter.is_allowed(&qos.partition);
+        let route_qos = resolve_route_qos(direction, self.config.forward_discovery, &qos);
+        let mut value = serde_json::json!({
+            "topic": topic,
+            "direction": direction,
+            "key_expr": ke.as_str(),
+            "allowed": allowed,
+        });
+        if direction == RouteDirection::FromDds {
+            // the zenoh publication's settings, as for the routes created on discovery
+            let reliable = if self.config.forward_discovery {
+                is_reader_reliable(&qos.reliability)
+            } else {
+                is_writer_reliable(&qos.reliability)
+            };
+            let default_congestion_ctrl = if self.config.reliable_routes_blocking && reliable {
+                CongestionControl::Block
+            } else {
+                CongestionControl::Drop
+            };
+            value["congestion_control"] = congestion_control_to_str(
+                self.get_congestion_control(&ke, default_congestion_ctrl),
+            )
+            .into();
+            let transport_priority = qos.transport_priority.as_ref().map(|p| p.value);
+            value["priority"] = priority_to_str(self.get_priority(&ke, transport_priority)).into();
+            value["express"] = self.is_express(&ke).into();
+        }
+        insert_qos_value(Ok(value), Some(&route_qos)).map_err(|e| e.to_string())
     }
 
-    fn remove_dds_participant(&mut self, dds_key: &str) -> Option<(OwnedKeyExpr, DdsParticipant)> {
-        // remove fron participants map
-        if let Some(e) = self.discovered_participants.remove(dds_key) {
-            // remove from admin_space
-            let admin_keyexpr = DdsPluginRuntime::get_participant_admin_keyexpr(&e);
-            self.admin_space.remove(&admin_keyexpr);
-            Some((admin_keyexpr, e))
-        } else {
-            None
+    // Update the 'deny' regex from the payload of a PUT or a query on the "config/deny" admin key (an empty payload
+    // removing it), and delete the routes that are no longer allowed. Refused unless admin_deny_updates is configured.
+    // Note that the routes for the topics no longer denied are only created on the next discovery of their DDS entities.
+    fn update_deny(&mut self, payload: &[u8]) -> Result<(), String> {
+        if !self.config.admin_deny_updates {
+            return Err(
+                "updates of 'deny' via admin space are disabled (see your 'admin_deny_updates' configuration)"
+                    .into(),
+            );
+        }
+        let mut deny = parse_deny_payload(payload)?;
+        if self.config.case_insensitive_matching {
+            deny.iter_mut()
+                .for_each(EntityMatcher::make_case_insensitive);
+        }
+        info!(
+            "Update 'deny' configuration via admin space: {}",
+            serde_json::to_string(&deny).unwrap_or_default()
+        );
+        if self.configured_deny.is_none() {
+            self.configured_deny = Some(self.config.deny.clone());
         }
+        self.config.deny = deny;
+        self.delete_denied_routes();
+        Ok(())
     }
 
-    fn insert_dds_writer(&mut self, admin_keyexpr: OwnedKeyExpr, e: DdsEntity) {
-        // insert reference in admin_space
-        self.admin_space
-            .insert(admin_keyexpr, AdminRef::DdsWriterEntity(e.key.clone()));
-
-        // insert DdsEntity in dds_writer map
-        self.discovered_writers.insert(e.key.clone(), e);
+    // Delete the routes that are no longer allowed, and set the status of the DDS entities they were serving accordingly
+    fn delete_denied_routes(&mut self) {
+        let denied_from_dds: Vec<OwnedKeyExpr> = self
+            .routes_from_dds
+            .iter()
+            .filter(|(ke, route)| !self.is_allowed(ke, route.topic_type()))
+            .map(|(ke, _)| ke.clone())
+            .collect();
+        let denied_to_dds: Vec<OwnedKeyExpr> = self
+            .routes_to_dds
+            .iter()
+            .filter(|(ke, route)| !self.is_allowed(ke, route.topic_type()))
+            .map(|(ke, _)| ke.clone())
+            .collect();
+        self.remove_routes(
+            &denied_from_dds,
+            &denied_to_dds,
+            "no longer allowed",
+            RouteStatus::NotAllowed,
+        );
     }
 
-    fn remove_dds_writer(&mut self, dds_key: &str) -> Option<(OwnedKeyExpr, DdsEntity)> {
-        // remove from dds_writer map
-        if let Some(e) = self.discovered_writers.remove(dds_key) {
-            // remove from admin_space
-            let admin_keyexpr = DdsPluginRuntime::get_entity_admin_keyexpr(&e, true);
-            self.admin_space.remove(&admin_keyexpr);
-            Some((admin_keyexpr, e))
-        } else {
-            None
+    // Delete routes from and to DDS, and set the status of the DDS entities they were serving
+    fn remove_routes(
+        &mut self,
+        from_dds: &[OwnedKeyExpr],
+        to_dds: &[OwnedKeyExpr],
+        reason: &str,
+        status: RouteStatus,
+    ) {
+        for ke in from_dds {
+            if let Some(route) = self.routes_from_dds.remove(ke) {
+                info!("{}: remove it as {}", route, reason);
+            }
+            self.admin_space.remove(&(*KE_PREFIX_ROUTE_FROM_DDS / ke));
+        }
+        for ke in to_dds {
+            if let Some(route) = self.routes_to_dds.remove(ke) {
+                info!("{}: remove it as {}", route, reason);
+            }
+            self.admin_space.remove(&(*KE_PREFIX_ROUTE_TO_DDS / ke));
+        }
+        for (entities, removed) in [
+            (&mut self.discovered_writers, from_dds),
+            (&mut self.discovered_readers, to_dds),
+        ] {
+            for entity in entities.values_mut() {
+                for s in entity.routes.values_mut() {
+                    if s.route_key().map_or(false, |ke| removed.contains(ke)) {
+                        *s = status.clone();
+                    }
+                }
+            }
         }
     }
 
-    fn insert_dds_reader(&mut self, admin_keyexpr: OwnedKeyExpr, e: DdsEntity) {
-        // insert reference in admin_space
-        self.admin_space
-            .insert(admin_keyexpr, AdminRef::DdsReaderEntity(e.key.clone()));
+    // Apply a reloaded configuration (see reload()), keeping the fields used only at startup, and update the routes:
+    //  - the routes no longer allowed are deleted
+    //  - the routes whose parameters or key expression changed (see route_from_dds_settings() and
+    //    route_to_dds_settings()) are re-created
+    //  - the discovered DDS entities without route (e.g. newly allowed) are routed again
+    // The 'deny' set via admin space (see update_deny()) is kept, unless the reloaded 'deny' changed.
+    // In "forward discovery" mode, the routes serving remote DDS entities can't be re-created before their next
+    // discovery: only the routes no longer allowed are deleted, the fields determining the routes are kept, and the
+    // changes of the routes parameters only apply to the routes created afterwards. In "dry run" mode, the routes are
+    // only logged at the discovery of the DDS entities: none is re-created.
+    // Return the configuration to restart the plugin with, if the fields of the zenoh Session changed.
+    async fn reload_config(&mut self, mut config: Config) -> Option<Config> {
+        let keep_admin_deny = self.configured_deny.as_ref().map_or(false, |configured| {
+            format!("{configured:?}") == format!("{:?}", config.deny)
+        });
+        if keep_admin_deny {
+            config.deny = self.config.deny.clone();
+        } else if self.configured_deny.take().is_some() {
+            info!("Reload of the configuration: the 'deny' set via admin space is replaced by the reloaded one");
+        }
 
-        // insert DdsEntity in dds_reader map
-        self.discovered_readers.insert(e.key.clone(), e);
-    }
+        let changed = self.config.changed_fields(&config);
+        let (mut ignored, mut applied): (Vec<&str>, Vec<&str>) = changed
+            .into_iter()
+            .partition(|field| STARTUP_FIELDS.contains(field));
+        config.keep_startup_fields(&self.config);
+        let mut new_routes_only = Vec::new();
+        if self.config.forward_discovery {
+            let (kept, others): (Vec<&str>, Vec<&str>) = applied
+                .into_iter()
+                .partition(|field| ROUTING_FIELDS.contains(field));
+            ignored.extend(kept);
+            (new_routes_only, applied) = others
+                .into_iter()
+                .partition(|field| ROUTE_SETTINGS_FIELDS.contains(field));
+            config.keep_routing_fields(&self.config);
+        }
+        if !ignored.is_empty() {
+            warn!(
+                "Reload of the configuration: the changes of {:?} require a restart - ignored",
+                ignored
+            );
+        }
+        if applied.is_empty() && new_routes_only.is_empty() {
+            info!("Reload of the configuration: no change to apply");
+            return None;
+        }
+        if applied.iter().any(|field| SESSION_FIELDS.contains(field)) {
+            warn!(
+                "Reload of the configuration: the changes of {:?} require a new zenoh Session - restart the plugin",
+                applied
+            );
+            return Some(config);
+        }
 
-    fn remove_dds_reader(&mut self, dds_key: &str) -> Option<(OwnedKeyExpr, DdsEntity)> {
-        // remove from dds_reader map
-        if let Some(e) = self.discovered_readers.remove(dds_key) {
-            // remove from admin space
-            let admin_keyexpr = DdsPluginRuntime::get_entity_admin_keyexpr(&e, false);
-            self.admin_space.remove(&admin_keyexpr);
-            Some((admin_keyexpr, e))
-        } else {
-            None
+        let previous_from_dds: HashMap<OwnedKeyExpr, String> = self
+            .routes_from_dds
+            .iter()
+            .map(|(ke, route)| (ke.clone(), self.route_from_dds_settings(ke, route)))
+            .collect();
+        let previous_to_dds: HashMap<OwnedKeyExpr, String> = self
+            .routes_to_dds
+            .iter()
+            .map(|(ke, route)| (ke.clone(), self.route_to_dds_settings(ke, route)))
+            .collect();
+        self.config = config;
+        if applied.contains(&"route_creation_limit") {
+            self.route_creation_limiter = self
+                .config
+                .route_creation_limit
+                .map(|limit| TokenBucket::new(limit.max_routes, limit.period));
         }
-    }
 
-    fn insert_route_from_dds(&mut self, ke: OwnedKeyExpr, r: RouteDDSZenoh<'a>) {
-        // insert reference in admin_space
-        let admin_ke = *KE_PREFIX_ROUTE_FROM_DDS / &ke;
-        self.admin_space
-            .insert(admin_ke, AdminRef::FromDdsRoute(ke.clone()));
+        let recreate = !self.config.forward_discovery;
+        let removed_from_dds: Vec<OwnedKeyExpr> = self
+            .routes_from_dds
+            .iter()
+            .filter(|(ke, route)| {
+                !self.is_allowed(ke, route.topic_type())
+                    || (recreate
+                        && previous_from_dds.get(*ke)
+                            != Some(&self.route_from_dds_settings(ke, route)))
+            })
+            .map(|(ke, _)| ke.clone())
+            .collect();
+        let removed_to_dds: Vec<OwnedKeyExpr> = self
+            .routes_to_dds
+            .iter()
+            .filter(|(ke, route)| {
+                !self.is_allowed(ke, route.topic_type())
+                    || (recreate
+                        && previous_to_dds.get(*ke) != Some(&self.route_to_dds_settings(ke, route)))
+            })
+            .map(|(ke, _)| ke.clone())
+            .collect();
+        self.remove_routes(
+            &removed_from_dds,
+            &removed_to_dds,
+            "affected by the reloaded configuration",
+            RouteStatus::NotAllowed,
+        );
 
-        // insert route in routes_from_dds map
-        self.routes_from_dds.insert(ke, r);
+        // create the manifest routes (if any) that no longer exist, then route again the DDS entities without route
+        let rerouted = if self.config.dry_run {
+            0
+        } else {
+            self.create_manifest_routes().await;
+            if recreate {
+                self.reroute_local_entities().await
+            } else {
+                0
+            }
+        };
+        if !applied.is_empty() {
+            info!(
+                "Reload of the configuration: applied the changes of {:?} - {} route(s) removed or re-created, {} DDS entities routed again",
+                applied,
+                removed_from_dds.len() + removed_to_dds.len(),
+                rerouted
+            );
+        }
+        if !new_routes_only.is_empty() {
+            warn!(
+                "Reload of the configuration: the changes of {:?} only apply to the routes created from now on (the existing routes can't be re-created in \"forward discovery\" mode)",
+                new_routes_only
+            );
+        }
+        None
     }
 
-    fn insert_route_to_dds(&mut self, ke: OwnedKeyExpr, r: RouteZenohDDS<'a>) {
-        // insert reference in admin_space
-        let admin_ke: OwnedKeyExpr = *KE_PREFIX_ROUTE_TO_DDS / &ke;
-        self.admin_space
-            .insert(admin_ke, AdminRef::ToDdsRoute(ke.clone()));
-
-        // insert route in routes_from_dds map
-        self.routes_to_dds.insert(ke, r);
+    // Route again the discovered DDS entities that are not served by all their routes, returning their number
+    async fn reroute_local_entities(&mut self) -> usize {
+        let mut rerouted = 0;
+        let writers: Vec<String> = self
+            .discovered_writers
+            .values()
+            .filter(|e| !self.has_all_routes(e, true))
+            .map(|e| e.key.clone())
+            .collect();
+        for key in writers {
+            if self.reroute_local_entity(&key, true).await {
+                rerouted += 1;
+            }
+        }
+        let readers: Vec<String> = self
+            .discovered_readers
+            .values()
+            .filter(|e| !self.has_all_routes(e, false))
+            .map(|e| e.key.clone())
+            .collect();
+        for key in readers {
+            if self.reroute_local_entity(&key, false).await {
+                rerouted += 1;
+            }
+        }
+        rerouted
     }
 
-    #[allow(clippy::too_many_arguments)]
-    async fn try_add_route_from_dds(
-        &mut self,
-        ke: OwnedKeyExpr,
-        topic_name: &str,
-        topic_type: &str,
-        type_info: &Option<TypeInfo>,
-        keyless: bool,
-        reader_qos: Qos,
-        congestion_ctrl: CongestionControl,
-    ) -> RouteStatus {
-        if !self.is_allowed(&ke) {
-            info!(
-                "Ignoring Publication for resource {} as it is not allowed (see your 'allow' or 'deny' configuration)",
-                ke
+    // Route again the discovered DDS entities throttled (or evicted) as per max_routes configuration, as long as the
+    // max number of routes is not reached. To be called once some routes are deleted, or once a route creation
+    // following an eviction failed. In "forward discovery" mode, the DDS entities are only routed at their discovery.
+    async fn reroute_throttled_entities(&mut self) {
+        let max_routes = match self.config.max_routes {
+            Some(max_routes) if !self.config.forward_discovery => max_routes,
+            _ => return,
+        };
+        if self.routes_from_dds.len() + self.routes_to_dds.len() >= max_routes {
+            return;
+        }
+        let throttled: Vec<(String, bool)> = self
+            .discovered_writers
+            .values()
+            .map(|e| (e, true))
+            .chain(self.discovered_readers.values().map(|e| (e, false)))
+            .filter(|(e, _)| e.routes.values().any(|s| *s == RouteStatus::Throttled))
+            .map(|(e, is_writer)| (e.key.clone(), is_writer))
+            .collect();
+        for (key, is_writer) in throttled {
+            if self.routes_from_dds.len() + self.routes_to_dds.len() >= max_routes {
+                break;
+            }
+            debug!(
+                "Route again the DDS {} {} throttled by 'max_routes' configuration",
+                if is_writer { "Writer" } else { "Reader" },
+                key
             );
-            return RouteStatus::NotAllowed;
+            self.reroute_local_entity(&key, is_writer).await;
         }
+    }
 
-        if self.routes_from_dds.contains_key(&ke) {
-            // TODO: check if there is no QoS conflict with existing route
+    // Route again the discovered DDS Writers (or Readers) not routed because of a type conflict with one of the removed
+    // routes: the route can now be created with their type. To be called once some routes are deleted.
+    async fn reroute_type_conflicts(&mut self, removed: &[OwnedKeyExpr], is_writer: bool) {
+        let entities = if is_writer {
+            &self.discovered_writers
+        } else {
+            &self.discovered_readers
+        };
+        for key in type_conflicts_with(entities, removed) {
             debug!(
-                "Route from DDS to resource {} already exists -- ignoring",
-                ke
+                "Route again the DDS {} {} whose type conflicted with a removed route",
+                if is_writer { "Writer" } else { "Reader" },
+                key
             );
-            return RouteStatus::Routed(ke);
+            self.reroute_local_entity(&key, is_writer).await;
         }
+    }
 
-        // create route DDS->Zenoh
-        match RouteDDSZenoh::new(
-            self,
-            topic_name.into(),
-            topic_type.into(),
-            type_info,
-            keyless,
-            reader_qos,
-            ke.clone(),
-            congestion_ctrl,
-        )
-        .await
+    // Route again a discovered DDS Writer (or Reader), returning false if it's not discovered
+    async fn reroute_local_entity(&mut self, key: &str, is_writer: bool) -> bool {
+        let removed = if is_writer {
+            self.remove_dds_writer(key)
+        } else {
+            self.remove_dds_reader(key)
+        };
+        let (admin_keyexpr, mut entity) = match removed {
+            Some(removed) => removed,
+            None => return false,
+        };
+        entity.routes.clear();
+        if is_writer {
+            route_local_entity(self, &mut entity, RouteDirection::FromDds).await;
+            self.schedule_route_retry(&mut entity, true);
+            self.insert_dds_writer(admin_keyexpr, entity);
+        } else {
+            route_local_entity(self, &mut entity, RouteDirection::ToDds).await;
+            self.schedule_route_retry(&mut entity, false);
+            self.insert_dds_reader(admin_keyexpr, entity);
+        }
+        true
+    }
+
+    // If the creation of some routes of a discovered DDS Writer (or Reader) failed, schedule a new attempt with an
+    // exponential backoff, unless the failures are permanent or route_creation_max_attempts is reached.
+    // If it was delayed as per route_creation_limit, schedule a new attempt at the next refill of the limiter.
+    fn schedule_route_retry(&mut self, entity: &mut DdsEntity, is_writer: bool) {
+        let kind = if is_writer { "Writer" } else { "Reader" };
+        // the delayed creations are not counted as failed attempts
+        if entity
+            .routes
+            .values()
+            .any(|status| *status == RouteStatus::RateLimited)
         {
-            Ok(route) => {
-                info!("{}: created with topic_type={}", route, topic_type);
-                self.insert_route_from_dds(ke.clone(), route);
-                RouteStatus::Routed(ke)
+            if let (Some(tx), Some(limiter)) = (&self.route_retry_tx, &self.route_creation_limiter)
+            {
+                let delay = limiter.refill_delay();
+                debug!(
+                    "DDS {} {} on {}: retry to create its route(s) in {} sec (see your 'route_creation_limit' configuration)",
+                    kind,
+                    entity.key,
+                    entity.topic_name,
+                    delay.as_secs_f32()
+                );
+                let tx = tx.clone();
+                let key = entity.key.clone();
+                async_std::task::spawn(async move {
+                    async_std::task::sleep(delay).await;
+                    let _ = tx.send_async((key, is_writer)).await;
+                });
             }
-            Err(e) => {
-                error!(
-                    "Route DDS->Zenoh ({} -> {}): creation failed: {}",
-                    topic_name, ke, e
+        }
+        let mut failed = false;
+        let mut retriable = false;
+        for status in entity.routes.values() {
+            if let RouteStatus::CreationFailure { permanent, .. } = status {
+                failed = true;
+                retriable |= !permanent;
+            }
+        }
+        if !failed {
+            if let Some(attempts) = self.route_creation_attempts.remove(&entity.key) {
+                info!(
+                    "DDS {} {} on {}: route(s) created after {} failed attempt(s)",
+                    kind, entity.key, entity.topic_name, attempts
                 );
-                RouteStatus::CreationFailure(e)
             }
+            return;
         }
-    }
 
-    async fn try_add_route_to_dds(
-        &mut self,
-        ke: OwnedKeyExpr,
-        topic_name: &str,
-        topic_type: &str,
-        keyless: bool,
-        is_transient: bool,
-        writer_qos: Option<Qos>,
-    ) -> RouteStatus {
-        if !self.is_allowed(&ke) {
+        let attempts = self
+            .route_creation_attempts
+            .entry(entity.key.clone())
+            .or_insert(0);
+        *attempts += 1;
+        let attempts = *attempts;
+        for status in entity.routes.values_mut() {
+            if let RouteStatus::CreationFailure { attempts: a, .. } = status {
+                *a = attempts;
+            }
+        }
+        if !retriable {
+            debug!(
+                "DDS {} {} on {}: the route creation failure is permanent - not retried",
+                kind, entity.key, entity.topic_name
+            );
+            return;
+        }
+        if attempts >= self.config.route_creation_max_attempts {
+            if self.config.route_creation_max_attempts > 1 {
+                warn!(
+                    "DDS {} {} on {}: give up creating its route(s) after {} attempts (see your 'route_creation_max_attempts' configuration)",
+                    kind, entity.key, entity.topic_name, attempts
+                );
+            }
+            return;
+        }
+        if let Some(tx) = &self.route_retry_tx {
+            let delay = route_creation_retry_delay(attempts);
             info!(
-                "Ignoring Subscription for resource {} as it is not allowed (see your 'allow' or 'deny' configuration)",
-                ke
+                "DDS {} {} on {}: retry to create its route(s) in {} sec (attempt {}/{})",
+                kind,
+                entity.key,
+                entity.topic_name,
+                delay.as_secs_f32(),
+                attempts + 1,
+                self.config.route_creation_max_attempts
             );
-            return RouteStatus::NotAllowed;
+            let tx = tx.clone();
+            let key = entity.key.clone();
+            async_std::task::spawn(async move {
+                async_std::task::sleep(delay).await;
+                let _ = tx.send_async((key, is_writer)).await;
+            });
         }
+    }
 
-        if let Some(route) = self.routes_to_dds.get(&ke) {
-            // TODO: check if there is no type or QoS conflict with existing route
-            debug!(
-                "Route from resource {} to DDS already exists -- ignoring",
-                ke
-            );
-            // #102: in forwarding mode, it might happen that the route have been created but without DDS Writer
-            //       (just to declare the Zenoh Subscriber). Thus, try to set a DDS Writer to the route here.
-            //       If already set, nothing will happen.
-            if let Some(qos) = writer_qos {
-                if let Err(e) = route.set_dds_writer(self.dp, qos) {
-                    error!(
-                        "{}: failed to set a DDS Writer after creation: {}",
-                        route, e
-                    );
-                    return RouteStatus::CreationFailure(e);
+    // Make the routes to DDS query the historical publications of a remote bridge (for those using a FetchingSubscriber)
+    async fn query_historical_publications(&self, member_id: &keyexpr) {
+        if let Some(tx) = &self.history_retry_tx {
+            for (zkey, route) in &self.routes_to_dds {
+                for query_ke in route.history_query_keyexprs(member_id) {
+                    route
+                        .query_historical_publications(zkey, query_ke, 0, &self.config, tx)
+                        .await;
                 }
             }
-            return RouteStatus::Routed(ke);
         }
+    }
 
-        // create route Zenoh->DDS
-        match RouteZenohDDS::new(
-            self,
-            ke.clone(),
-            is_transient,
-            topic_name.into(),
-            topic_type.into(),
-            keyless,
-        )
-        .await
-        {
-            Ok(route) => {
-                // if writer_qos is set, add a DDS Writer to the route
-                if let Some(qos) = writer_qos {
-                    if let Err(e) = route.set_dds_writer(self.dp, qos) {
-                        error!(
-                            "Route Zenoh->DDS ({} -> {}): creation failed: {}",
-                            ke, topic_name, e
-                        );
-                        return RouteStatus::CreationFailure(e);
-                    }
+    // Retry a failed query for historical publications, if its route to DDS still exists
+    async fn retry_history_query(&self, retry: HistoryQueryRetry) {
+        if let (Some(route), Some(tx)) = (
+            self.routes_to_dds.get(&retry.route_ke),
+            &self.history_retry_tx,
+        ) {
+            route
+                .query_historical_publications(
+                    &retry.route_ke,
+                    retry.query_ke,
+                    retry.retries,
+                    &self.config,
+                    tx,
+                )
+                .await;
+        }
+    }
+
+    // Retry to create the routes of a DDS Writer (or Reader), if still discovered and not served by all its routes
+    async fn retry_routes(&mut self, key: String, is_writer: bool) {
+        let entity = if is_writer {
+            self.discovered_writers.get(&key)
+        } else {
+            self.discovered_readers.get(&key)
+        };
+        match entity {
+            Some(entity) if !self.has_all_routes(entity, is_writer) => {
+                self.reroute_local_entity(&key, is_writer).await;
+            }
+            _ => {
+                self.route_creation_attempts.remove(&key);
+            }
+        }
+    }
+
+    // Check the DDS Participant is alive, or recover from its death (see recover_participant())
+    async fn check_participant(&mut self) {
+        if let Err(e) = check_dds_participant_alive(self.dp) {
+            self.recover_participant(e).await;
+            return;
+        }
+        // the known DDS entities not discovered again within participant_rediscovery_timeout after a recovery of the
+        // DDS Participant no longer exist
+        let expired = self
+            .unconfirmed_entities
+            .take_expired(self.config.participant_rediscovery_timeout, Instant::now());
+        if !expired.is_empty() {
+            info!(
+                "{} DDS entities were not discovered again within {} sec after the recovery of the DDS Participant: consider them as undiscovered",
+                expired.len(),
+                self.config.participant_rediscovery_timeout.as_secs_f32()
+            );
+            if let Some(queue) = &self.discovery_queue {
+                for key in expired {
+                    let evt = if self.discovered_writers.contains_key(&key) {
+                        DiscoveryEvent::UndiscoveredPublication { key }
+                    } else if self.discovered_readers.contains_key(&key) {
+                        DiscoveryEvent::UndiscoveredSubscription { key }
+                    } else {
+                        DiscoveryEvent::UndiscoveredParticipant { key }
+                    };
+                    queue.send(evt);
                 }
+            }
+        }
+    }
+
+    // Recover from the death of the DDS Participant: re-create it, restart the DDS discovery and re-create the
+    // routes of the known DDS entities. Those are kept until discovered again (or undiscovered at the first check
+    // after participant_rediscovery_timeout)
+    async fn recover_participant(&mut self, reason: DdsError) {
+        error!(
+            "The DDS Participant is no longer alive ({}): re-create it and all the routes",
+            reason
+        );
+        let from_dds: Vec<OwnedKeyExpr> = self.routes_from_dds.keys().cloned().collect();
+        let to_dds: Vec<OwnedKeyExpr> = self.routes_to_dds.keys().cloned().collect();
+        self.remove_routes(
+            &from_dds,
+            &to_dds,
+            "its DDS Participant is dead",
+            RouteStatus::NotAllowed,
+        );
+        if let Err(e) = delete_dds_entity(self.dp) {
+            debug!("Failed to delete the dead DDS Participant: {}", e);
+        }
 
-                info!("{}: created with topic_type={}", route, topic_type);
-                self.insert_route_to_dds(ke.clone(), route);
-                RouteStatus::Routed(ke)
-            }
+        self.dp = unsafe {
+            dds_create_participant(self.config.domain, std::ptr::null(), std::ptr::null())
+        };
+        let dp_guid = match check_dds_participant(self.dp, self.config.domain) {
+            Ok(guid) => guid,
             Err(e) => {
                 error!(
-                    "Route Zenoh->DDS ({} -> {}): creation failed: {}",
-                    ke, topic_name, e
+                    "Failed to re-create the DDS Participant: {} - retry in {} sec",
+                    e,
+                    self.config.participant_check_period.as_secs_f32()
                 );
-                RouteStatus::CreationFailure(e)
+                return;
             }
+        };
+        self.participant_info.write().unwrap().participant_guid = dp_guid.clone();
+        if let Err(e) = self.local_participants.register(self.dp) {
+            error!(
+                "Failed to register the re-created DDS Participant: {} - its own entities might be routed",
+                e
+            );
+        }
+        if let Some(queue) = &self.discovery_queue {
+            run_discovery(
+                self.dp,
+                queue.clone(),
+                self.local_participants.clone(),
+                &self.config.directions,
+                self.config.discovery_max_samples,
+            );
         }
+
+        self.unconfirmed_entities.reset(
+            self.discovered_participants
+                .keys()
+                .chain(self.discovered_writers.keys())
+                .chain(self.discovered_readers.keys())
+                .cloned()
+                .collect(),
+            Instant::now(),
+        );
+        self.create_manifest_routes().await;
+        let rerouted = self.reroute_local_entities().await;
+        warn!(
+            "Re-created the DDS Participant {} and restarted the DDS discovery: {} DDS entities routed again",
+            dp_guid, rerouted
+        );
     }
 
-    fn get_admin_value(&self, admin_ref: &AdminRef) -> Result<Option<Value>, serde_json::Error> {
-        match admin_ref {
-            AdminRef::DdsParticipant(key) => self
-                .discovered_participants
-                .get(key)
-                .map(serde_json::to_value)
-                .map(remove_null_qos_values)
-                .transpose(),
-            AdminRef::DdsReaderEntity(key) => self
-                .discovered_readers
-                .get(key)
-                .map(serde_json::to_value)
-                .map(remove_null_qos_values)
-                .transpose(),
-            AdminRef::DdsWriterEntity(key) => self
-                .discovered_writers
-                .get(key)
-                .map(serde_json::to_value)
-                .map(remove_null_qos_values)
-                .transpose(),
-            AdminRef::FromDdsRoute(zkey) => self
-                .routes_from_dds
-                .get(zkey)
-                .map(serde_json::to_value)
-                .transpose(),
-            AdminRef::ToDdsRoute(zkey) => self
-                .routes_to_dds
-                .get(zkey)
-                .map(serde_json::to_value)
-                .transpose(),
-            AdminRef::Config => Some(serde_json::to_value(self)).transpose(),
-            AdminRef::Version => Ok(Some(DDSPlugin::PLUGIN_LONG_VERSION.into())),
+    // The key expression of a route on a DDS topic with the partition of its DDS Reader or Writer QoS (the routes being
+    // created per partition), with the current configuration. None if its partition is no longer allowed.
+    fn route_keyexpr(&self, topic_name: &str, qos: Option<&Qos>) -> Option<OwnedKeyExpr> {
+        let partition = qos.map_or(&None, |qos| &qos.partition);
+        if !self.config.partition_filter.is_allowed(partition) {
+            return None;
         }
+        let partition = partition.as_ref().and_then(|p| p.first());
+        self.topic_to_keyexpr(
+            topic_name,
+            &self.config.scope,
+            partition.map(String::as_str),
+        )
+        .ok()
+    }
+
+    // The parameters of a route from DDS that are set at its creation (see RouteDDSZenoh::new()), and its key
+    // expression, with the current configuration
+    fn route_from_dds_settings(&self, ke: &keyexpr, route: &RouteDDSZenoh) -> String {
+        let topic_name = route.topic_name();
+        let qos = route.dds_reader_qos();
+        let congestion_ctrl =
+            if self.config.reliable_routes_blocking && is_writer_reliable(&qos.reliability) {
+                CongestionControl::Block
+            } else {
+                CongestionControl::Drop
+            };
+        [
+            format!("{:?}", self.route_keyexpr(topic_name, Some(qos))),
+            format!("{:?}", self.config.route_setup),
+            format!("{:?}", self.get_downsampling(ke)),
+            format!("{:?}", self.get_batch(ke)),
+            format!("{}", self.is_express(ke)),
+            format!("{}", self.is_keyed_instance_routed(ke, route.is_keyless())),
+            format!(
+                "{:?}",
+                route
+                    .has_keyless_conflict()
+                    .then(|| self.config.keyless_conflict)
+            ),
+            format!("{:?}", self.get_congestion_control(ke, congestion_ctrl)),
+            format!(
+                "{:?}",
+                self.get_priority(ke, qos.transport_priority.as_ref().map(|p| p.value))
+            ),
+            format!("{:?}", self.get_put_timeout(ke)),
+            format!(
+                "{:?} {:?}",
+                self.get_async_forward(topic_name),
+                self.async_forward_settings()
+            ),
+            format!("{:?}", self.get_compression(topic_name)),
+            format!("{:?}", self.get_payload_filter(ke)),
+            format!("{:?}", self.get_content_filter(ke)),
+            format!("{:?}", self.get_forwarding_profile(ke)),
+            format!(
+                "{:?} {:?}",
+                self.get_sla_thresholds(ke),
+                self.config.sla_check_period
+            ),
+            format!("{:?}", self.get_last_sample_preview_max_bytes(ke)),
+            format!("{}", self.is_coalescing_writers(ke)),
+            format!("{:?}", self.get_qos_override(ke)),
+            format!("{:?}", self.get_entity_name("from_dds", topic_name)),
+            format!("{:?}", self.get_delivery_mode(ke)),
+            format!("{:?}", self.get_transient_local_max_samples(ke)),
+            format!(
+                "{} {} {} {} {:?}",
+                self.config.preserve_source_timestamp,
+                self.config.matched_liveliness,
+                self.config.route_liveliness,
+                self.config.deadline_missed_events,
+                self.config.historical_data_timeout
+            ),
+        ]
+        .join(" ")
+    }
+
+    // The parameters of a route to DDS that are set at its creation (see RouteZenohDDS::new()), and its key
+    // expression, with the current configuration
+    fn route_to_dds_settings(&self, ke: &keyexpr, route: &RouteZenohDDS) -> String {
+        let topic_name = route.topic_name();
+        [
+            format!(
+                "{:?}",
+                self.route_keyexpr(topic_name, route.dds_writer_qos().as_ref())
+            ),
+            format!("{:?}", self.config.route_setup),
+            format!("{}", self.is_keyed_instance_routed(ke, route.is_keyless())),
+            format!("{}", self.config.max_decompressed_size),
+            format!("{:?}", self.config.dead_letters_key),
+            format!("{:?}", self.get_qos_override(ke)),
+            format!("{:?}", self.get_entity_name("to_dds", topic_name)),
+            format!(
+                "{} {} {}",
+                self.config.preserve_source_timestamp,
+                self.config.matched_liveliness,
+                self.config.route_liveliness
+            ),
+        ]
+        .join(" ")
+    }
+
+    // Return true if all the routes serving a discovered DDS Writer (or Reader) exist
+    fn has_all_routes(&self, entity: &DdsEntity, is_writer: bool) -> bool {
+        entity.routes.values().all(|status| {
+            status.route_key().map_or(false, |ke| {
+                if is_writer {
+                    self.routes_from_dds.contains_key(ke)
+                } else {
+                    self.routes_to_dds.contains_key(ke)
+                }
+            })
+        })
     }
 
-    async fn treat_admin_query(&self, query: Query, admin_keyexpr_prefix: &keyexpr) {
+    async fn treat_admin_query(&mut self, query: Query, admin_keyexpr_prefix: &keyexpr) {
         let selector = query.selector();
         debug!("Query on admin space: {:?}", selector);
 
@@ -634,36 +3243,160 @@ impl<'a> DdsPluginRuntime<'a> {
             return;
         }
 
+        // a query with a value on "config/deny" updates the 'deny' regex, replying with the new one
+        if sub_kes.len() == 1 && sub_kes[0] == *KE_CONFIG_DENY {
+            if let Some(value) = query.value() {
+                let reply = match self.update_deny(&value.payload.contiguous()) {
+                    Ok(()) => {
+                        let deny = serde_json::to_value(&self.config.deny).unwrap_or_default();
+                        query.reply(Ok(Sample::new(
+                            admin_keyexpr_prefix / *KE_CONFIG_DENY,
+                            deny,
+                        )))
+                    }
+                    Err(e) => {
+                        warn!("Invalid update of 'deny' configuration {}: {}", selector, e);
+                        query.reply(Err(e.into()))
+                    }
+                };
+                if let Err(e) = reply.res_async().await {
+                    warn!("Error replying to admin query {:?}: {}", query, e);
+                }
+                // the routes no longer allowed are deleted: route the throttled entities instead
+                self.reroute_throttled_entities().await;
+                return;
+            }
+        }
+
+        // the effective QoS is computed from the query's parameters (it's not stored in admin space)
+        if sub_kes.len() == 1 && sub_kes[0] == *KE_EFFECTIVE_QOS {
+            let admin_keyexpr = admin_keyexpr_prefix / *KE_EFFECTIVE_QOS;
+            let reply = match self.get_effective_qos(selector.parameters()) {
+                Ok(v) => query.reply(Ok(Sample::new(admin_keyexpr, v))),
+                Err(e) => {
+                    warn!("Invalid effective QoS query {}: {}", selector, e);
+                    query.reply(Err(e.into()))
+                }
+            };
+            if let Err(e) = reply.res_async().await {
+                warn!("Error replying to admin query {:?}: {}", query, e);
+            }
+            return;
+        }
+
+        // the last sample of a route from DDS is decoded on query (it's not stored in admin space)
+        if sub_kes.len() == 1 {
+            let route = sub_kes[0]
+                .as_str()
+                .strip_suffix("/sample")
+                .and_then(|route_ke| keyexpr::new(route_ke).ok())
+                .and_then(|route_ke| self.admin_space.get(route_ke))
+                .and_then(|admin_ref| match admin_ref {
+                    AdminRef::FromDdsRoute(zkey) => self.routes_from_dds.get(zkey),
+                    _ => None,
+                });
+            if let Some(route) = route {
+                let admin_keyexpr = admin_keyexpr_prefix / sub_kes[0];
+                let reply = match route.decode_last_sample() {
+                    Ok(v) => query.reply(Ok(Sample::new(admin_keyexpr, v))),
+                    Err(e) => {
+                        warn!("{}: failed to decode the last sample: {}", route, e);
+                        query.reply(Err(format!("{route}: {e}").into()))
+                    }
+                };
+                if let Err(e) = reply.res_async().await {
+                    warn!("Error replying to admin query {:?}: {}", query, e);
+                }
+                return;
+            }
+        }
+
+        let page = match AdminQueryPage::parse(
+            selector.parameters(),
+            self.config.admin_query_default_limit,
+        ) {
+            Ok(page) => page,
+            Err(e) => {
+                warn!("Invalid admin query {}: {}", selector, e);
+                if let Err(e) = query.reply(Err(e.into())).res_async().await {
+                    warn!("Error replying to admin query {:?}: {}", query, e);
+                }
+                return;
+            }
+        };
+        let is_topic_matching = |admin_ref: &AdminRef| match &page.topic {
+            Some(re) => self
+                .get_admin_topic_name(admin_ref)
+                .map_or(false, |topic| re.is_match(topic)),
+            None => true,
+        };
+
         // Get all matching keys/values
         let mut kvs: Vec<(KeyExpr, Value)> = Vec::with_capacity(sub_kes.len());
+        let mut push_admin_value =
+            |ke: KeyExpr<'static>, admin_ref: &AdminRef| match self.get_admin_value(admin_ref) {
+                Ok(Some(v)) => kvs.push((ke, v)),
+                Ok(None) => error!("INTERNAL ERROR: Dangling {:?}", admin_ref),
+                Err(e) => {
+                    error!("INTERNAL ERROR serializing admin value as JSON: {}", e)
+                }
+            };
         for sub_ke in sub_kes {
             if sub_ke.contains('*') {
                 // iterate over all admin space to find matching keys
                 for (ke, admin_ref) in self.admin_space.iter() {
-                    if sub_ke.intersects(ke) {
-                        match self.get_admin_value(admin_ref) {
-                            Ok(Some(v)) => kvs.push((ke.into(), v)),
-                            Ok(None) => error!("INTERNAL ERROR: Dangling {:?}", admin_ref),
-                            Err(e) => {
-                                error!("INTERNAL ERROR serializing admin value as JSON: {}", e)
-                            }
+                    if sub_ke.intersects(ke) && is_topic_matching(admin_ref) {
+                        push_admin_value(ke.clone().into(), admin_ref);
+                    }
+                    // the "stats" key of the routes from DDS is derived from the route's key
+                    if let AdminRef::FromDdsRoute(zkey) = admin_ref {
+                        let stats_ke = &**ke / *KE_STATS;
+                        let stats_ref = AdminRef::FromDdsRouteStats(zkey.clone());
+                        if sub_ke.intersects(&stats_ke) && is_topic_matching(&stats_ref) {
+                            push_admin_value(stats_ke.into(), &stats_ref);
                         }
                     }
                 }
             } else {
                 // sub_ke correspond to 1 key - just get it.
-                if let Some(admin_ref) = self.admin_space.get(sub_ke) {
-                    match self.get_admin_value(admin_ref) {
-                        Ok(Some(v)) => kvs.push((sub_ke.into(), v)),
-                        Ok(None) => error!("INTERNAL ERROR: Dangling {:?}", admin_ref),
-                        Err(e) => {
-                            error!("INTERNAL ERROR serializing admin value as JSON: {}", e)
-                        }
+                let stats_ref = match sub_ke
+                    .as_str()
+                    .strip_suffix("/stats")
+                    .and_then(|route_ke| keyexpr::new(route_ke).ok())
+                {
+                    Some(route_ke) => {
+                        self.admin_space
+                            .get(route_ke)
+                            .and_then(|admin_ref| match admin_ref {
+                                AdminRef::FromDdsRoute(zkey) => {
+                                    Some(AdminRef::FromDdsRouteStats(zkey.clone()))
+                                }
+                                _ => None,
+                            })
                     }
+                    None => None,
+                };
+                if let Some(admin_ref) = self
+                    .admin_space
+                    .get(sub_ke)
+                    .or(stats_ref.as_ref())
+                    .filter(|admin_ref| is_topic_matching(admin_ref))
+                {
+                    push_admin_value(sub_ke.to_owned().into(), admin_ref);
                 }
             }
         }
 
+        // paginate in the order of the admin keys, for the pages to be consistent across queries
+        if page.is_paginated() {
+            kvs.sort_unstable_by(|(ke1, _), (ke2, _)| ke1.as_str().cmp(ke2.as_str()));
+            kvs = kvs
+                .into_iter()
+                .skip(page.offset)
+                .take(page.limit.unwrap_or(usize::MAX))
+                .collect();
+        }
+
         // send replies
         for (ke, v) in kvs.drain(..) {
             let admin_keyexpr = admin_keyexpr_prefix / &ke;
@@ -677,7 +3410,42 @@ impl<'a> DdsPluginRuntime<'a> {
         }
     }
 
-    async fn run(&mut self) {
+    // Reply to a query on "@/<zid>/dds/graph" with the snapshot of the DDS discovery tables
+    async fn treat_graph_query(&self, query: Query) {
+        let graph_keyexpr =
+            &(*KE_PREFIX_HEARTBEAT / &self.zsession.zid().into_keyexpr()) / *KE_SUFFIX_GRAPH;
+        let graph = AdminQueryPage::parse(
+            query.selector().parameters(),
+            Some(
+                self.config
+                    .admin_query_default_limit
+                    .unwrap_or(GRAPH_DEFAULT_LIMIT),
+            ),
+        )
+        .and_then(|page| {
+            discovery_graph(
+                &self.discovered_participants,
+                &self.discovered_writers,
+                &self.discovered_readers,
+                &page,
+            )
+            .map_err(|e| format!("failed to serialize the DDS graph: {e}"))
+        });
+        let reply = match graph {
+            Ok(graph) => query.reply(Ok(Sample::new(graph_keyexpr, graph))),
+            Err(e) => {
+                warn!("Invalid query {}: {}", query.selector(), e);
+                query.reply(Err(e.into()))
+            }
+        };
+        if let Err(e) = reply.res_async().await {
+            warn!("Error replying to query {:?}: {}", query, e);
+        }
+    }
+
+    // Run the plugin until a shutdown request, or a reload requiring a restart: then return the configuration to
+    // restart it with
+    async fn run(&mut self) -> Option<Config> {
         let group_subscriber = self
             .zsession
             .liveliness()
@@ -689,8 +3457,19 @@ impl<'a> DdsPluginRuntime<'a> {
             .expect("Failed to create Liveliness Subscriber");
 
         // run DDS discovery
-        let (tx, dds_disco_rcv): (Sender<DiscoveryEvent>, Receiver<DiscoveryEvent>) = unbounded();
-        run_discovery(self.dp, tx);
+        let (disco_queue, dds_disco_rcv) =
+            DiscoveryQueue::new(self.config.discovery_queue_capacity);
+        run_discovery(
+            self.dp,
+            disco_queue.clone(),
+            self.local_participants.clone(),
+            &self.config.directions,
+            self.config.discovery_max_samples,
+        );
+        self.discovery_queue = Some(disco_queue.clone());
+        if self.config.dry_run {
+            info!("Dry run: the routes are only logged, without creating any DDS Reader or Writer nor zenoh publisher or subscriber");
+        }
 
         // declare admin space queryable
         let admin_keyexpr_prefix = *KE_PREFIX_ADMIN_SPACE / &self.zsession.zid().into_keyexpr();
@@ -703,186 +3482,396 @@ impl<'a> DdsPluginRuntime<'a> {
             .await
             .expect("Failed to create AdminSpace queryable");
 
+        // serve the identity of the DDS Participant, next to the heartbeat
+        let info_keyexpr =
+            &(*KE_PREFIX_HEARTBEAT / &self.zsession.zid().into_keyexpr()) / *KE_SUFFIX_INFO;
+        let participant_info = self.participant_info.clone();
+        let reply_keyexpr = info_keyexpr.clone();
+        let _info_queryable = self
+            .zsession
+            .declare_queryable(&info_keyexpr)
+            .callback(move |query| {
+                let info = participant_info.read().unwrap().clone();
+                match serde_json::to_value(info) {
+                    Ok(value) => {
+                        if let Err(e) = query
+                            .reply(Ok(Sample::new(reply_keyexpr.clone(), value)))
+                            .res_sync()
+                        {
+                            warn!("Error replying to query on {}: {}", reply_keyexpr, e);
+                        }
+                    }
+                    Err(e) => warn!("Error serializing the DDS Participant info: {}", e),
+                }
+            })
+            .res_async()
+            .await
+            .expect("Failed to create the DDS Participant info queryable");
+
+        // serve the snapshot of the DDS discovery tables, next to the heartbeat
+        let graph_keyexpr =
+            &(*KE_PREFIX_HEARTBEAT / &self.zsession.zid().into_keyexpr()) / *KE_SUFFIX_GRAPH;
+        let graph_queryable = self
+            .zsession
+            .declare_queryable(&graph_keyexpr)
+            .res_async()
+            .await
+            .expect("Failed to create the DDS graph queryable");
+
+        // subscribe to the updates of the 'deny' regex via admin space
+        let deny_subscriber = self
+            .zsession
+            .declare_subscriber(&admin_keyexpr_prefix / *KE_CONFIG_DENY)
+            .with(flume::unbounded())
+            .res_async()
+            .await
+            .expect("Failed to create 'deny' configuration Subscriber");
+
         // add plugin's config and version in admin space
         self.admin_space
             .insert("config".try_into().unwrap(), AdminRef::Config);
+        self.admin_space
+            .insert((*KE_CONFIG_DENY).to_owned(), AdminRef::ConfigDeny);
         self.admin_space
             .insert("version".try_into().unwrap(), AdminRef::Version);
+        self.admin_space
+            .insert("uptime".try_into().unwrap(), AdminRef::Uptime);
+        self.admin_space
+            .insert("discovery".try_into().unwrap(), AdminRef::Discovery);
+        self.admin_space.insert(
+            "discovered/publications".try_into().unwrap(),
+            AdminRef::DiscoveredPublications,
+        );
+        self.admin_space.insert(
+            "discovered/subscriptions".try_into().unwrap(),
+            AdminRef::DiscoveredSubscriptions,
+        );
 
-        if self.config.forward_discovery {
+        // serve the bridge-level metrics over HTTP, if configured
+        let metrics_server = self.config.metrics_port.map(|port| {
+            spawn_metrics_server(self.config.metrics_address, port, self.metrics.clone())
+        });
+
+        // periodically publish the bridge heartbeat, unless disabled with a null period
+        let heartbeat = (!self.config.heartbeat_period.is_zero()).then(|| {
+            let ke = match &self.config.heartbeat_key {
+                Some(ke) => ke.clone(),
+                None => {
+                    &(*KE_PREFIX_HEARTBEAT / &self.zsession.zid().into_keyexpr())
+                        / *KE_SUFFIX_HEARTBEAT
+                }
+            };
+            spawn_heartbeat(
+                self.zsession.clone(),
+                ke,
+                self.config.heartbeat_period,
+                self.start_time,
+                self.metrics.clone(),
+                disco_queue.clone(),
+            )
+        });
+
+        // pre-create the routes from the startup manifest, if any
+        self.create_manifest_routes().await;
+
+        // periodically persist the statistics, if configured
+        // (stats_tx is kept here for stats_timer_rcv to remain open even if not configured)
+        let timer = Timer::default();
+        let (stats_tx, stats_timer_rcv): (Sender<()>, Receiver<()>) = unbounded();
+        if self.stats_state.is_some() {
+            let stats_timer_event = TimedEvent::periodic(
+                self.config.stats_state_save_period,
+                ChannelEvent {
+                    tx: stats_tx.clone(),
+                },
+            );
+            timer.add_async(stats_timer_event).await;
+        }
+
+        // periodically check the DDS Participant is alive, to recover from its death (only in "local discovery" mode,
+        // as the routes of the "forward discovery" mode can't be rebuilt before the next discovery of the remote entities)
+        let (participant_check_tx, participant_check_rcv): (Sender<()>, Receiver<()>) = unbounded();
+        if !self.config.participant_check_period.is_zero() && !self.config.forward_discovery {
+            let participant_check_event = TimedEvent::periodic(
+                self.config.participant_check_period,
+                ChannelEvent {
+                    tx: participant_check_tx.clone(),
+                },
+            );
+            timer.add_async(participant_check_event).await;
+        }
+
+        // the retries of the failed route creations (only in "local discovery" mode)
+        let (route_retry_tx, route_retry_rcv): (Sender<(String, bool)>, Receiver<(String, bool)>) =
+            unbounded();
+        self.route_retry_tx = Some(route_retry_tx);
+
+        // the retries of the failed queries for historical publications
+        let (history_retry_tx, history_retry_rcv): (
+            Sender<HistoryQueryRetry>,
+            Receiver<HistoryQueryRetry>,
+        ) = unbounded();
+        self.history_retry_tx = Some(history_retry_tx);
+
+        // the shutdown, reload, routes snapshot and self-test requests, accepted only during this run
+        let shutdown_requests = PluginRequests::register(&*SHUTDOWN_REQUESTS);
+        let reload_requests = PluginRequests::register(&*RELOAD_REQUESTS);
+        let routes_requests = PluginRequests::register(&*ROUTES_REQUESTS);
+        let self_test_requests = PluginRequests::register(&*SELF_TEST_REQUESTS);
+
+        // the modes only return on a shutdown request or on a reload requiring a restart, with the sender notifying
+        // its completion
+        let run_end = if self.config.forward_discovery {
             self.run_fwd_discovery_mode(
                 &group_subscriber,
                 &dds_disco_rcv,
                 admin_keyexpr_prefix,
                 &admin_queryable,
+                &graph_queryable,
+                &deny_subscriber,
+                &stats_timer_rcv,
+                &history_retry_rcv,
+                &shutdown_requests.rcv,
+                &reload_requests.rcv,
+                &routes_requests.rcv,
+                &self_test_requests.rcv,
             )
-            .await;
+            .await
         } else {
             self.run_local_discovery_mode(
                 &group_subscriber,
                 &dds_disco_rcv,
                 admin_keyexpr_prefix,
                 &admin_queryable,
+                &graph_queryable,
+                &deny_subscriber,
+                &stats_timer_rcv,
+                &participant_check_rcv,
+                &route_retry_rcv,
+                &history_retry_rcv,
+                &shutdown_requests.rcv,
+                &reload_requests.rcv,
+                &routes_requests.rcv,
+                &self_test_requests.rcv,
             )
-            .await;
+            .await
+        };
+        // the next requests fail right away, and the pending shutdowns are served by this shutdown (if a shutdown is
+        // pending, the plugin is not restarted)
+        let pending_shutdowns = shutdown_requests.unregister();
+        drop(reload_requests);
+        drop(routes_requests);
+        drop(self_test_requests);
+        let (done, restart) = match run_end {
+            RunEnd::Shutdown(done) => (done, None),
+            RunEnd::Restart(config, done) if pending_shutdowns.is_empty() => (done, Some(*config)),
+            RunEnd::Restart(_, done) => (done, None),
+        };
+        drop(stats_tx);
+        drop(participant_check_tx);
+        if let Some(metrics_server) = metrics_server {
+            metrics_server.cancel().await;
+        }
+        if let Some(heartbeat) = heartbeat {
+            heartbeat.cancel().await;
+        }
+        self.delete_dds_entities();
+        // the requesters might have given up on timeout
+        for done in std::iter::once(done).chain(pending_shutdowns) {
+            let _ = done.send(());
+        }
+        restart
+    }
+
+    // Update the gauges of the bridge metrics (at each event processed by the discovery modes)
+    fn update_metrics(&self) {
+        let set = |gauge: &AtomicU64, value: usize| gauge.store(value as u64, Ordering::Relaxed);
+        set(
+            &self.metrics.discovered_publications,
+            self.discovered_writers.len(),
+        );
+        set(
+            &self.metrics.discovered_subscriptions,
+            self.discovered_readers.len(),
+        );
+        set(&self.metrics.routes_from_dds, self.routes_from_dds.len());
+        set(&self.metrics.routes_to_dds, self.routes_to_dds.len());
+        if let Some(queue) = &self.discovery_queue {
+            set(
+                &self.metrics.discovery_queue_high_water_mark,
+                queue.high_water_mark(),
+            );
+        }
+    }
+
+    // Delete all the routes (and thus their DDS Reader or Writer), then the DDS Participant
+    fn delete_dds_entities(&mut self) {
+        info!(
+            "Shutdown: delete {} route(s) from DDS, {} route(s) to DDS and the DDS Participant",
+            self.routes_from_dds.len(),
+            self.routes_to_dds.len()
+        );
+        self.save_stats_state();
+        self.admin_space.clear();
+        self.routes_from_dds.clear();
+        self.routes_to_dds.clear();
+        if let Err(e) = delete_dds_entity(self.dp) {
+            warn!("Shutdown: failed to delete the DDS Participant: {}", e);
         }
     }
 
+    // See routing::topic_to_keyexpr()
     fn topic_to_keyexpr(
         &self,
         topic_name: &str,
         scope: &Option<OwnedKeyExpr>,
         partition: Option<&str>,
     ) -> ZResult<OwnedKeyExpr> {
-        // key_expr for a topic is: "<scope>/<partition>/<topic_name>" with <scope> and <partition> being optional
-        match (scope, partition) {
-            (Some(scope), Some(part)) => scope.join(&format!("{part}/{topic_name}")),
-            (Some(scope), None) => scope.join(topic_name),
-            (None, Some(part)) => format!("{part}/{topic_name}").try_into(),
-            (None, None) => topic_name.try_into(),
+        routing::topic_to_keyexpr(&self.config, topic_name, scope, partition)
+    }
+
+    // Return the key expression of the route of a DDS entity in a partition (see topic_to_keyexpr()),
+    // or None with a warning if there is no valid one (e.g. if its topic name is empty once normalized)
+    fn entity_keyexpr(
+        &self,
+        entity: &DdsEntity,
+        scope: &Option<OwnedKeyExpr>,
+        partition: Option<&str>,
+    ) -> Option<OwnedKeyExpr> {
+        match self.topic_to_keyexpr(&entity.topic_name, scope, partition) {
+            Ok(ke) => Some(ke),
+            Err(e) => {
+                warn!(
+                    "Ignoring DDS entity {} on {}: no valid key expression for partition {:?}: {}",
+                    entity.key, entity.topic_name, partition, e
+                );
+                None
+            }
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn run_local_discovery_mode(
         &mut self,
         group_subscriber: &Receiver<Sample>,
         dds_disco_rcv: &Receiver<DiscoveryEvent>,
         admin_keyexpr_prefix: OwnedKeyExpr,
         admin_queryable: &Queryable<'_, flume::Receiver<Query>>,
-    ) {
+        graph_queryable: &Queryable<'_, flume::Receiver<Query>>,
+        deny_subscriber: &Receiver<Sample>,
+        stats_timer_rcv: &Receiver<()>,
+        participant_check_rcv: &Receiver<()>,
+        route_retry_rcv: &Receiver<(String, bool)>,
+        history_retry_rcv: &Receiver<HistoryQueryRetry>,
+        shutdown_rcv: &Receiver<Sender<()>>,
+        reload_rcv: &Receiver<(Config, Sender<()>)>,
+        routes_rcv: &Receiver<Sender<RoutesSnapshot>>,
+        self_test_rcv: &Receiver<(Instant, Sender<Result<(), String>>)>,
+    ) -> RunEnd {
         debug!(r#"Run in "local discovery" mode"#);
 
         loop {
+            self.update_metrics();
             select!(
                 evt = dds_disco_rcv.recv_async() => {
+                    self.metrics.discovery_events.fetch_add(1, Ordering::Relaxed);
+                    // there is room in the queue for the events kept in its backlog (if any)
+                    if let Some(queue) = &self.discovery_queue {
+                        queue.flush();
+                    }
                     match evt.unwrap() {
                         DiscoveryEvent::DiscoveredPublication {
                             mut entity
                         } => {
-                            debug!("Discovered DDS Writer {} on {} with type '{}' and QoS: {:?}", entity.key, entity.topic_name, entity.type_name, entity.qos);
+                            debug!(topic = %entity.topic_name, type_name = %entity.type_name, "Discovered DDS Writer {} with QoS: {:?}{}", entity.key, entity.qos, self.alias_suffix(&[&entity.key, &entity.topic_name]));
+                            self.unconfirmed_entities.confirm(&entity.key);
+                            self.override_keyless(&mut entity);
                             // get its admin_keyexpr
                             let admin_keyexpr = DdsPluginRuntime::get_entity_admin_keyexpr(&entity, true);
 
-                            let qos = adapt_writer_qos_for_reader(&entity.qos);
-                            // CongestionControl to be used when re-publishing over zenoh: Blocking if Writer is RELIABLE (since we don't know what is remote Reader's QoS)
-                            let congestion_ctrl = match (self.config.reliable_routes_blocking, is_writer_reliable(&entity.qos.reliability)) {
-                                (true, true) => CongestionControl::Block,
-                                _ => CongestionControl::Drop,
-                            };
-
-                            // create 1 route per partition, or just 1 if no partition
-                            if partition_is_empty(&entity.qos.partition) {
-                                let ke = self.topic_to_keyexpr(&entity.topic_name, &self.config.scope, None).unwrap();
-                                let route_status = self.try_add_route_from_dds(ke, &entity.topic_name, &entity.type_name, &entity.type_info, entity.keyless, qos, congestion_ctrl).await;
-                                if let RouteStatus::Routed(ref route_key) = route_status {
-                                    if let Some(r) = self.routes_from_dds.get_mut(route_key) {
-                                        // add Writer's key to the route
-                                        r.add_local_routed_writer(entity.key.clone());
-                                    }
-                                }
-                                entity.routes.insert("*".to_string(), route_status);
-                            } else {
-                                for p in entity.qos.partition.as_deref().unwrap() {
-                                    let ke = self.topic_to_keyexpr(&entity.topic_name, &self.config.scope, Some(p)).unwrap();
-                                    let route_status = self.try_add_route_from_dds(ke, &entity.topic_name, &entity.type_name, &entity.type_info, entity.keyless, qos.clone(), congestion_ctrl).await;
-                                    if let RouteStatus::Routed(ref route_key) = route_status {
-                                        if let Some(r) = self.routes_from_dds.get_mut(route_key) {
-                                            // if route has been created, add this Writer in its routed_writers list
-                                            r.add_local_routed_writer(entity.key.clone());
-                                        }
-                                    }
-                                    entity.routes.insert(p.clone(), route_status);
-                                }
-                            }
+                            route_local_entity(self, &mut entity, RouteDirection::FromDds).await;
+                            self.schedule_route_retry(&mut entity, true);
 
                             // store the writer
                             self.insert_dds_writer(admin_keyexpr, entity);
+                            // the route might have evicted another one, and then failed to be created
+                            self.reroute_throttled_entities().await;
                         }
 
                         DiscoveryEvent::UndiscoveredPublication {
                             key,
                         } => {
+                            self.route_creation_attempts.remove(&key);
                             if let Some((_, e)) = self.remove_dds_writer(&key) {
-                                debug!("Undiscovered DDS Writer {} on topic {}", key, e.topic_name);
+                                debug!(topic = %e.topic_name, "Undiscovered DDS Writer {}{}", key, self.alias_suffix(&[&key, &e.topic_name]));
                                 // remove it from all the active routes refering it (deleting the route if no longer used)
                                 let admin_space = &mut self.admin_space;
+                                let mut removed = Vec::new();
                                 self.routes_from_dds.retain(|zkey, route| {
                                         route.remove_local_routed_writer(&key);
-                                        if !route.has_local_routed_writer() {
+                                        if !route.has_local_routed_writer() && !route.is_from_manifest() {
                                             info!(
                                                 "{}: remove it as no longer unused (no local DDS Writer left)",
                                                 route
                                             );
                                             let ke = *KE_PREFIX_ROUTE_FROM_DDS / zkey;
                                             admin_space.remove(&ke);
+                                            removed.push(zkey.clone());
                                             false
                                         } else {
                                             true
                                         }
                                     }
                                 );
+                                self.reroute_type_conflicts(&removed, true).await;
+                                self.reroute_throttled_entities().await;
                             }
                         }
 
                         DiscoveryEvent::DiscoveredSubscription {
                             mut entity
                         } => {
-                            debug!("Discovered DDS Reader {} on {} with type '{}' and QoS: {:?}", entity.key, entity.topic_name, entity.type_name, entity.qos);
+                            debug!(topic = %entity.topic_name, type_name = %entity.type_name, "Discovered DDS Reader {} with QoS: {:?}{}", entity.key, entity.qos, self.alias_suffix(&[&entity.key, &entity.topic_name]));
+                            self.unconfirmed_entities.confirm(&entity.key);
+                            self.override_keyless(&mut entity);
                             let admin_keyexpr = DdsPluginRuntime::get_entity_admin_keyexpr(&entity, false);
 
-                            let qos = adapt_reader_qos_for_writer(&entity.qos);
-
-                            // create 1 route per partition, or just 1 if no partition
-                            if partition_is_empty(&entity.qos.partition) {
-                                let ke = self.topic_to_keyexpr(&entity.topic_name, &self.config.scope, None).unwrap();
-                                let route_status = self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&qos), Some(qos)).await;
-                                if let RouteStatus::Routed(ref route_key) = route_status {
-                                    if let Some(r) = self.routes_to_dds.get_mut(route_key) {
-                                        // if route has been created, add this Reader in its routed_readers list
-                                        r.add_local_routed_reader(entity.key.clone());
-                                    }
-                                }
-                                entity.routes.insert("*".to_string(), route_status);
-                            } else {
-                                for p in entity.qos.partition.as_deref().unwrap() {
-                                    let ke = self.topic_to_keyexpr(&entity.topic_name, &self.config.scope, Some(p)).unwrap();
-                                    let route_status = self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&qos), Some(qos.clone())).await;
-                                    if let RouteStatus::Routed(ref route_key) = route_status {
-                                        if let Some(r) = self.routes_to_dds.get_mut(route_key) {
-                                            // if route has been created, add this Reader in its routed_readers list
-                                            r.add_local_routed_reader(entity.key.clone());
-                                        }
-                                    }
-                                    entity.routes.insert(p.clone(), route_status);
-                                }
-                            }
+                            route_local_entity(self, &mut entity, RouteDirection::ToDds).await;
+                            self.schedule_route_retry(&mut entity, false);
 
                             // store the reader
                             self.insert_dds_reader(admin_keyexpr, entity);
+                            // the route might have evicted another one, and then failed to be created
+                            self.reroute_throttled_entities().await;
                         }
 
                         DiscoveryEvent::UndiscoveredSubscription {
                             key,
                         } => {
+                            self.route_creation_attempts.remove(&key);
                             if let Some((_, e)) = self.remove_dds_reader(&key) {
-                                debug!("Undiscovered DDS Reader {} on topic {}", key, e.topic_name);
+                                debug!(topic = %e.topic_name, "Undiscovered DDS Reader {}{}", key, self.alias_suffix(&[&key, &e.topic_name]));
                                 // remove it from all the active routes refering it (deleting the route if no longer used)
                                 let admin_space = &mut self.admin_space;
+                                let mut removed = Vec::new();
                                 self.routes_to_dds.retain(|zkey, route| {
                                         route.remove_local_routed_reader(&key);
-                                        if !route.has_local_routed_reader() {
+                                        if !route.has_local_routed_reader() && !route.is_from_manifest() {
                                             info!(
                                                 "{}: remove it as no longer unused (no local DDS Reader left)",
                                                 route
                                             );
                                             let ke = *KE_PREFIX_ROUTE_TO_DDS / zkey;
                                             admin_space.remove(&ke);
+                                            removed.push(zkey.clone());
                                             false
                                         } else {
                                             true
                                         }
                                     }
                                 );
+                                self.reroute_type_conflicts(&removed, false).await;
+                                self.reroute_throttled_entities().await;
                             }
                         }
 
@@ -890,6 +3879,7 @@ impl<'a> DdsPluginRuntime<'a> {
                             entity,
                         } => {
                             debug!("Discovered DDS Participant {}", entity.key);
+                            self.unconfirmed_entities.confirm(&entity.key);
                             let admin_keyexpr = DdsPluginRuntime::get_participant_admin_keyexpr(&entity);
 
                             // store the participant
@@ -913,9 +3903,7 @@ impl<'a> DdsPluginRuntime<'a> {
                             debug!("New zenoh_dds_plugin detected: {}", mid);
                             if let Ok(member_id) = keyexpr::new(mid) {
                                 // make all QueryingSubscriber to query this new member
-                                for (zkey, route) in &mut self.routes_to_dds {
-                                    route.query_historical_publications(|| (*KE_PREFIX_PUB_CACHE / member_id / zkey).into(), self.config.queries_timeout).await;
-                                }
+                                self.query_historical_publications(member_id).await;
                             } else {
                                 error!("Can't convert member id '{}' into a KeyExpr", mid);
                             }
@@ -932,23 +3920,103 @@ impl<'a> DdsPluginRuntime<'a> {
                         warn!("AdminSpace queryable was closed!");
                     }
                 }
+
+                get_request = graph_queryable.recv_async() => {
+                    if let Ok(query) = get_request {
+                        self.treat_graph_query(query).await;
+                    }
+                }
+
+                sample = deny_subscriber.recv_async() => {
+                    if let Ok(sample) = sample {
+                        if let Err(e) = self.update_deny(&sample.value.payload.contiguous()) {
+                            warn!("Invalid update of 'deny' configuration via admin space: {}", e);
+                        }
+                        // the routes no longer allowed are deleted: route the throttled entities instead
+                        self.reroute_throttled_entities().await;
+                    }
+                }
+
+                _ = stats_timer_rcv.recv_async() => {
+                    self.save_stats_state();
+                }
+
+                _ = participant_check_rcv.recv_async() => {
+                    self.check_participant().await;
+                }
+
+                retry = route_retry_rcv.recv_async() => {
+                    if let Ok((key, is_writer)) = retry {
+                        self.retry_routes(key, is_writer).await;
+                        self.reroute_throttled_entities().await;
+                    }
+                }
+
+                retry = history_retry_rcv.recv_async() => {
+                    if let Ok(retry) = retry {
+                        self.retry_history_query(retry).await;
+                    }
+                }
+
+                done = shutdown_rcv.recv_async() => {
+                    if let Ok(done) = done {
+                        debug!("Shutdown requested");
+                        return RunEnd::Shutdown(done);
+                    }
+                }
+
+                reload = reload_rcv.recv_async() => {
+                    if let Ok((config, done)) = reload {
+                        debug!("Reload of the configuration requested");
+                        if let Some(config) = self.reload_config(config).await {
+                            return RunEnd::Restart(Box::new(config), done);
+                        }
+                        let _ = done.send(());
+                    }
+                }
+
+                snapshot = routes_rcv.recv_async() => {
+                    if let Ok(snapshot) = snapshot {
+                        let _ = snapshot.send(routes_snapshot(
+                            &self.discovered_writers,
+                            &self.discovered_readers,
+                        ));
+                    }
+                }
+
+                self_test = self_test_rcv.recv_async() => {
+                    if let Ok((deadline, result_tx)) = self_test {
+                        self.spawn_self_test(deadline, result_tx);
+                    }
+                }
             )
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn run_fwd_discovery_mode(
         &mut self,
         group_subscriber: &Receiver<Sample>,
         dds_disco_rcv: &Receiver<DiscoveryEvent>,
         admin_keyexpr_prefix: OwnedKeyExpr,
         admin_queryable: &Queryable<'_, flume::Receiver<Query>>,
-    ) {
+        graph_queryable: &Queryable<'_, flume::Receiver<Query>>,
+        deny_subscriber: &Receiver<Sample>,
+        stats_timer_rcv: &Receiver<()>,
+        history_retry_rcv: &Receiver<HistoryQueryRetry>,
+        shutdown_rcv: &Receiver<Sender<()>>,
+        reload_rcv: &Receiver<(Config, Sender<()>)>,
+        routes_rcv: &Receiver<Sender<RoutesSnapshot>>,
+        self_test_rcv: &Receiver<(Instant, Sender<Result<(), String>>)>,
+    ) -> RunEnd {
         debug!(r#"Run in "forward discovery" mode"#);
 
         // The data space where all discovery info are fowarded:
         //   - writers discovery on <KE_PREFIX_FWD_DISCO>/<uuid>/[<scope>]/writer/<dds_entity_admin_key>
         //   - readers discovery on <KE_PREFIX_FWD_DISCO>/<uuid>/[<scope>]/reader/<dds_entity_admin_key>
         //   - ros_discovery_info on <KE_PREFIX_FWD_DISCO>/<uuid>/[<scope>]/ros_disco/<gid>
+        // The statuses of the routes created for a remote entity are acknowledged to its bridge on
+        //   <KE_PREFIX_FWD_ACK>/<remote_uuid>/<uuid>/<dds_entity_admin_key>
         // The PublicationCache is declared on <KE_PREFIX_FWD_DISCO>/<uuid>/[<scope>]/**
         // The QuerySubscriber is declared on  <KE_PREFIX_FWD_DISCO>/*/[<scope>]/**
         let uuid: OwnedKeyExpr = self.zsession.zid().into();
@@ -1007,6 +4075,27 @@ impl<'a> DdsPluginRuntime<'a> {
             .await
             .expect("Failed to declare QueryingSubscriber for Fwd Discovery");
 
+        // Subscribe to the acknowledgments of the routes created by the remote DDS plugins for my entities (if configured)
+        let fwd_ack_sub = if self.config.route_ack_timeout.is_some() {
+            Some(
+                self.zsession
+                    .declare_subscriber(*KE_PREFIX_FWD_ACK / &uuid / *KE_ANY_N_SEGMENT)
+                    .with(flume::unbounded())
+                    .allowed_origin(Locality::Remote)
+                    .res_async()
+                    .await
+                    .expect("Failed to declare Subscriber for Fwd Discovery acknowledgments"),
+            )
+        } else {
+            None
+        };
+        // without acknowledgments, a channel on which nothing is ever received
+        let (_no_ack_tx, no_ack_rcv): (Sender<Sample>, Receiver<Sample>) = unbounded();
+        let fwd_ack_rcv: &Receiver<Sample> = match &fwd_ack_sub {
+            Some(sub) => &**sub,
+            None => &no_ack_rcv,
+        };
+
         // Manage ros_discovery_info topic, reading it periodically
         let ros_disco_mgr =
             RosDiscoveryInfoMgr::create(self.dp).expect("Failed to create RosDiscoveryInfoMgr");
@@ -1025,13 +4114,20 @@ impl<'a> DdsPluginRuntime<'a> {
 
         let scope = self.config.scope.clone();
         loop {
+            self.update_metrics();
             select!(
                 evt = dds_disco_rcv.recv_async() => {
+                    self.metrics.discovery_events.fetch_add(1, Ordering::Relaxed);
+                    // there is room in the queue for the events kept in its backlog (if any)
+                    if let Some(queue) = &self.discovery_queue {
+                        queue.flush();
+                    }
                     match evt.unwrap() {
                         DiscoveryEvent::DiscoveredPublication {
-                            entity
+                            mut entity
                         } => {
-                            debug!("Discovered DDS Writer {} on {} with type '{}' and QoS: {:?} => advertise it", entity.key, entity.topic_name, entity.type_name, entity.qos);
+                            debug!(topic = %entity.topic_name, type_name = %entity.type_name, "Discovered DDS Writer {} with QoS: {:?}{} => advertise it", entity.key, entity.qos, self.alias_suffix(&[&entity.key, &entity.topic_name]));
+                            self.override_keyless(&mut entity);
                             // advertise the entity and its scope within admin space (bincode format)
                             let admin_keyexpr = DdsPluginRuntime::get_entity_admin_keyexpr(&entity, true);
                             let fwd_ke = &fwd_writers_key_prefix_key / &admin_keyexpr;
@@ -1044,6 +4140,11 @@ impl<'a> DdsPluginRuntime<'a> {
                                 error!("INTERNAL ERROR: failed to publish discovery message on {}: {}", fwd_ke, e);
                             }
 
+                            // wait for the acknowledgments of the routes created by the remote bridges (if configured)
+                            if self.config.route_ack_timeout.is_some() {
+                                self.remote_route_statuses.insert(entity.key.clone(), RemoteRouteStatus::new());
+                            }
+
                             // store the writer in admin space
                             self.insert_dds_writer(admin_keyexpr, entity);
                         }
@@ -1052,6 +4153,10 @@ impl<'a> DdsPluginRuntime<'a> {
                             key,
                         } => {
                             debug!("Undiscovered DDS Writer {} => advertise it", key);
+                            // if selected by a route coalescing the DDS Writers, select another one
+                            for route in self.routes_from_dds.values() {
+                                route.release_selected_writer(&key);
+                            }
                             if let Some((admin_keyexpr, _)) = self.remove_dds_writer(&key) {
                                 let fwd_ke = &fwd_writers_key_prefix_key / &admin_keyexpr;
                                 // publish its deletion from admin space
@@ -1064,14 +4169,20 @@ impl<'a> DdsPluginRuntime<'a> {
                         DiscoveryEvent::DiscoveredSubscription {
                             mut entity
                         } => {
-                            debug!("Discovered DDS Reader {} on {} with type '{}' and QoS: {:?} => advertise it", entity.key, entity.topic_name, entity.type_name, entity.qos);
+                            debug!(topic = %entity.topic_name, type_name = %entity.type_name, "Discovered DDS Reader {} with QoS: {:?}{} => advertise it", entity.key, entity.qos, self.alias_suffix(&[&entity.key, &entity.topic_name]));
+                            self.override_keyless(&mut entity);
 
                             // #102: create a local "to_dds" route, but only with the Zenoh Subscriber (not the DDS Writer)
                             // create 1 route per partition, or just 1 if no partition
-                            if partition_is_empty(&entity.qos.partition) {
-                                let ke = self.topic_to_keyexpr(&entity.topic_name, &self.config.scope, None).unwrap();
-                                let route_status = self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&entity.qos), None).await;
-                                if let RouteStatus::Routed(ref route_key) = route_status {
+                            if !self.config.partition_filter.is_allowed(&entity.qos.partition) {
+                                info!("Ignoring DDS entity {} on {} as its partitions {:?} are not allowed (see your 'partition_filter' configuration)", entity.key, entity.topic_name, entity.qos.partition);
+                                entity.routes.insert("*".to_string(), RouteStatus::NotAllowed);
+                            } else if partition_is_empty(&entity.qos.partition) {
+                                let route_status = match self.entity_keyexpr(&entity, &self.config.scope, None) {
+                                    Some(ke) => self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&entity.qos), None).await,
+                                    None => RouteStatus::NotAllowed,
+                                };
+                                if let Some(route_key) = route_status.route_key() {
                                     if let Some(r) = self.routes_to_dds.get_mut(route_key) {
                                         // if route has been created, add this Reader in its routed_readers list
                                         r.add_local_routed_reader(entity.key.clone());
@@ -1080,9 +4191,11 @@ impl<'a> DdsPluginRuntime<'a> {
                                 entity.routes.insert("*".to_string(), route_status);
                             } else {
                                 for p in entity.qos.partition.as_deref().unwrap() {
-                                    let ke = self.topic_to_keyexpr(&entity.topic_name, &self.config.scope, Some(p)).unwrap();
-                                    let route_status = self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&entity.qos), None).await;
-                                    if let RouteStatus::Routed(ref route_key) = route_status {
+                                    let route_status = match self.entity_keyexpr(&entity, &self.config.scope, Some(p)) {
+                                        Some(ke) => self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&entity.qos), None).await,
+                                        None => RouteStatus::NotAllowed,
+                                    };
+                                    if let Some(route_key) = route_status.route_key() {
                                         if let Some(r) = self.routes_to_dds.get_mut(route_key) {
                                             // if route has been created, add this Reader in its routed_readers list
                                             r.add_local_routed_reader(entity.key.clone());
@@ -1090,6 +4203,7 @@ impl<'a> DdsPluginRuntime<'a> {
                                     }
                                     entity.routes.insert(p.clone(), route_status);
                                 }
+                                self.attach_to_rewritten_routes(&mut entity);
                             }
 
                             // advertise the entity and its scope within admin space (bincode format)
@@ -1104,6 +4218,11 @@ impl<'a> DdsPluginRuntime<'a> {
                                 error!("INTERNAL ERROR: failed to publish discovery message on {}: {}", fwd_ke, e);
                             }
 
+                            // wait for the acknowledgments of the routes created by the remote bridges (if configured)
+                            if self.config.route_ack_timeout.is_some() {
+                                self.remote_route_statuses.insert(entity.key.clone(), RemoteRouteStatus::new());
+                            }
+
                             // store the reader
                             self.insert_dds_reader(admin_keyexpr, entity);
                         }
@@ -1124,7 +4243,7 @@ impl<'a> DdsPluginRuntime<'a> {
                             let admin_space = &mut self.admin_space;
                             self.routes_to_dds.retain(|zkey, route| {
                                     route.remove_local_routed_reader(&key);
-                                    if !route.has_local_routed_reader() && !route.has_remote_routed_writer(){
+                                    if !route.has_local_routed_reader() && !route.has_remote_routed_writer() && !route.is_from_manifest() {
                                         info!(
                                             "{}: remove it as no longer unused (no local DDS Reader nor remote DDS Writer left)",
                                             route
@@ -1166,6 +4285,10 @@ impl<'a> DdsPluginRuntime<'a> {
 
                     // parse fwd_ke and extract the remote uuid, the discovery kind (reader|writer|ros_disco) and the remaining of the keyexpr
                     if let Some((remote_uuid, disco_kind, remaining_ke)) = Self::parse_fwd_discovery_keyexpr(fwd_ke) {
+                        if !self.is_peer_allowed(remote_uuid) {
+                            debug!("Ignoring forwarded discovery message from {} as it's not an allowed peer (see your 'allowed_peers' configuration)", remote_uuid);
+                            continue;
+                        }
                         match disco_kind {
                             // it's a writer discovery message
                             "writer" => {
@@ -1177,16 +4300,42 @@ impl<'a> DdsPluginRuntime<'a> {
                                         Ok(x) => x,
                                         Err(e) => {
                                             warn!("Failed to deserialize discovery msg for {}: {}", full_admin_keyexpr, e);
+                                            let acks = HashMap::from([("*".to_string(), RouteStatus::CreationFailure {
+                                                error: format!("failed to deserialize discovery msg: {e}"),
+                                                permanent: true,
+                                                attempts: 1,
+                                            })]);
+                                            self.ack_fwd_routes(remote_uuid, remaining_ke, &acks).await;
                                             continue;
                                         }
                                     };
-                                    let qos = adapt_writer_qos_for_proxy_writer(&entity.qos);
+                                    if self.config.validate_forwarded_qos {
+                                        if let Err(e) = validate_qos(&entity.qos) {
+                                            warn!("Ignoring remote DDS entity {} on {} as its QoS is invalid: {} (see your 'validate_forwarded_qos' configuration)", full_admin_keyexpr, entity.topic_name, e);
+                                            let acks = HashMap::from([("*".to_string(), RouteStatus::NotAllowed)]);
+                                            self.ack_fwd_routes(remote_uuid, remaining_ke, &acks).await;
+                                            continue;
+                                        }
+                                    }
+                                    if !self.config.partition_filter.is_allowed(&entity.qos.partition) {
+                                        info!("Ignoring remote DDS entity {} on {} as its partitions {:?} are not allowed (see your 'partition_filter' configuration)", full_admin_keyexpr, entity.topic_name, entity.qos.partition);
+                                        let acks = HashMap::from([("*".to_string(), RouteStatus::NotAllowed)]);
+                                        self.ack_fwd_routes(remote_uuid, remaining_ke, &acks).await;
+                                        continue;
+                                    }
+                                    let mut qos = resolve_route_qos(RouteDirection::ToDds, true, &entity.qos);
+                                    // the DDS Writer joins the rewritten partitions (see partition_rewrite configuration),
+                                    // while the routes keep the key expressions of the original ones
+                                    self.config.rewrite_partitions(&mut qos);
 
                                     // create 1 "to_dds" route per partition, or just 1 if no partition
+                                    let mut acks = HashMap::new();
                                     if partition_is_empty(&entity.qos.partition) {
-                                        let ke = self.topic_to_keyexpr(&entity.topic_name, &scope, None).unwrap();
-                                        let route_status = self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&qos), Some(qos)).await;
-                                        if let RouteStatus::Routed(ref route_key) = route_status {
+                                        let route_status = match self.entity_keyexpr(&entity, &scope, None) {
+                                            Some(ke) => self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&qos), Some(qos)).await,
+                                            None => RouteStatus::NotAllowed,
+                                        };
+                                        if let Some(route_key) = route_status.route_key() {
                                             if let Some(r) = self.routes_to_dds.get_mut(route_key) {
                                                 // add the writer's admin keyexpr to the list of remote_routed_writers
                                                 r.add_remote_routed_writer(full_admin_keyexpr);
@@ -1199,32 +4348,48 @@ impl<'a> DdsPluginRuntime<'a> {
                                                 }
                                             }
                                         }
+                                        acks.insert("*".to_string(), route_status);
                                     } else {
                                         for p in entity.qos.partition.as_deref().unwrap() {
-                                            let ke = self.topic_to_keyexpr(&entity.topic_name, &scope, Some(p)).unwrap();
-                                            let route_status = self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&qos), Some(qos.clone())).await;
-                                            if let RouteStatus::Routed(ref route_key) = route_status {
+                                            let local_p = self.config.rewrite_partition(p).into_owned();
+                                            let route_status = match self.entity_keyexpr(&entity, &scope, Some(p)) {
+                                                Some(ke) => self.try_add_route_to_dds(ke, &entity.topic_name, &entity.type_name, entity.keyless, is_transient_local(&qos), Some(qos.clone())).await,
+                                                None => RouteStatus::NotAllowed,
+                                            };
+                                            if let Some(route_key) = route_status.route_key() {
                                                 if let Some(r) = self.routes_to_dds.get_mut(route_key) {
                                                     // add the writer's admin keyexpr to the list of remote_routed_writers
                                                     r.add_remote_routed_writer(full_admin_keyexpr.clone());
+                                                    // if the partition is rewritten, the route's key expression differs from the one of the
+                                                    // local Readers' own routes in the rewritten partition: their status for this route is
+                                                    // keyed by the route's key expression, not to overwrite their own one
+                                                    let status_key = if local_p != *p {
+                                                        r.set_rewritten_partition(local_p.clone());
+                                                        route_key.to_string()
+                                                    } else {
+                                                        local_p.clone()
+                                                    };
                                                     // check amongst local Readers is some are matching (only wrt. topic_name and partition. TODO: consider qos match also)
                                                     for reader in self.discovered_readers.values_mut() {
-                                                        if reader.topic_name == entity.topic_name && partition_contains(&reader.qos.partition, p) {
+                                                        if reader.topic_name == entity.topic_name && partition_contains(&reader.qos.partition, &local_p) {
                                                             r.add_local_routed_reader(reader.key.clone());
-                                                            reader.routes.insert(p.clone(), route_status.clone());
+                                                            reader.routes.insert(status_key.clone(), route_status.clone());
                                                         }
                                                     }
                                                 }
                                             }
+                                            acks.insert(p.clone(), route_status);
                                         }
                                     }
+                                    // acknowledge the routes to the bridge which advertised the writer
+                                    self.ack_fwd_routes(remote_uuid, remaining_ke, &acks).await;
                                 } else {
                                     // writer was deleted; remove it from all the active routes refering it (deleting the route if no longer used)
                                     let admin_space = &mut self.admin_space;
                                     self.routes_to_dds.retain(|zkey, route| {
                                             route.remove_remote_routed_writer(&full_admin_keyexpr);
-                                            if route.has_remote_routed_writer() {
-                                                // if there are still remote writers for this route, keep it
+                                            if route.has_remote_routed_writer() || route.is_from_manifest() {
+                                                // if there are still remote writers for this route, or if it's from the manifest, keep it
                                                 true
                                             } else {
                                                 // #102: Delete the DDS Writer of this route if there are no more remote Writers,
@@ -1257,22 +4422,46 @@ impl<'a> DdsPluginRuntime<'a> {
                                         Ok(x) => x,
                                         Err(e) => {
                                             warn!("Failed to deserialize discovery msg for {}: {}", full_admin_keyexpr, e);
+                                            let acks = HashMap::from([("*".to_string(), RouteStatus::CreationFailure {
+                                                error: format!("failed to deserialize discovery msg: {e}"),
+                                                permanent: true,
+                                                attempts: 1,
+                                            })]);
+                                            self.ack_fwd_routes(remote_uuid, remaining_ke, &acks).await;
                                             continue;
                                         }
                                     };
-                                    let qos = adapt_reader_qos_for_proxy_reader(&entity.qos);
+                                    if self.config.validate_forwarded_qos {
+                                        if let Err(e) = validate_qos(&entity.qos) {
+                                            warn!("Ignoring remote DDS entity {} on {} as its QoS is invalid: {} (see your 'validate_forwarded_qos' configuration)", full_admin_keyexpr, entity.topic_name, e);
+                                            let acks = HashMap::from([("*".to_string(), RouteStatus::NotAllowed)]);
+                                            self.ack_fwd_routes(remote_uuid, remaining_ke, &acks).await;
+                                            continue;
+                                        }
+                                    }
+                                    if !self.config.partition_filter.is_allowed(&entity.qos.partition) {
+                                        info!("Ignoring remote DDS entity {} on {} as its partitions {:?} are not allowed (see your 'partition_filter' configuration)", full_admin_keyexpr, entity.topic_name, entity.qos.partition);
+                                        let acks = HashMap::from([("*".to_string(), RouteStatus::NotAllowed)]);
+                                        self.ack_fwd_routes(remote_uuid, remaining_ke, &acks).await;
+                                        continue;
+                                    }
+                                    let qos = resolve_route_qos(RouteDirection::FromDds, true, &entity.qos);
 
                                     // CongestionControl to be used when re-publishing over zenoh: Blocking if Reader is RELIABLE (since Writer will also be, otherwise no matching)
                                     let congestion_ctrl = match (self.config.reliable_routes_blocking, is_reader_reliable(&entity.qos.reliability)) {
                                         (true, true) => CongestionControl::Block,
                                         _ => CongestionControl::Drop,
                                     };
+                                    // Note: the TRANSPORT_PRIORITY and LIFESPAN of the remote Writers are unknown here, only the priorities configuration applies
 
                                     // create 1 'from_dds" route per partition, or just 1 if no partition
+                                    let mut acks = HashMap::new();
                                     if partition_is_empty(&entity.qos.partition) {
-                                        let ke = self.topic_to_keyexpr(&entity.topic_name, &scope, None).unwrap();
-                                        let route_status = self.try_add_route_from_dds(ke, &entity.topic_name, &entity.type_name, &entity.type_info, entity.keyless, qos, congestion_ctrl).await;
-                                        if let RouteStatus::Routed(ref route_key) = route_status {
+                                        let route_status = match self.entity_keyexpr(&entity, &scope, None) {
+                                            Some(ke) => self.try_add_route_from_dds(ke, &entity.topic_name, &entity.type_name, &entity.type_info, entity.keyless, qos, congestion_ctrl, None, None).await,
+                                            None => RouteStatus::NotAllowed,
+                                        };
+                                        if let Some(route_key) = route_status.route_key() {
                                             if let Some(r) = self.routes_from_dds.get_mut(route_key) {
                                                 // add the reader's admin keyexpr to the list of remote_routed_writers
                                                 r.add_remote_routed_reader(full_admin_keyexpr);
@@ -1285,11 +4474,14 @@ impl<'a> DdsPluginRuntime<'a> {
                                                 }
                                             }
                                         }
+                                        acks.insert("*".to_string(), route_status);
                                     } else {
-                                        for p in &entity.qos.partition.unwrap() {
-                                            let ke = self.topic_to_keyexpr(&entity.topic_name, &scope, Some(p)).unwrap();
-                                            let route_status = self.try_add_route_from_dds(ke, &entity.topic_name, &entity.type_name, &entity.type_info, entity.keyless, qos.clone(), congestion_ctrl).await;
-                                            if let RouteStatus::Routed(ref route_key) = route_status {
+                                        for p in entity.qos.partition.as_deref().unwrap() {
+                                            let route_status = match self.entity_keyexpr(&entity, &scope, Some(p)) {
+                                                Some(ke) => self.try_add_route_from_dds(ke, &entity.topic_name, &entity.type_name, &entity.type_info, entity.keyless, qos.clone(), congestion_ctrl, None, None).await,
+                                                None => RouteStatus::NotAllowed,
+                                            };
+                                            if let Some(route_key) = route_status.route_key() {
                                                 if let Some(r) = self.routes_from_dds.get_mut(route_key) {
                                                     // add the reader's admin keyexpr to the list of remote_routed_writers
                                                     r.add_remote_routed_reader(full_admin_keyexpr.clone());
@@ -1302,14 +4494,17 @@ impl<'a> DdsPluginRuntime<'a> {
                                                     }
                                                 }
                                             }
+                                            acks.insert(p.clone(), route_status);
                                         }
                                     }
+                                    // acknowledge the routes to the bridge which advertised the reader
+                                    self.ack_fwd_routes(remote_uuid, remaining_ke, &acks).await;
                                 } else {
                                     // reader was deleted; remove it from all the active routes refering it (deleting the route if no longer used)
                                     let admin_space = &mut self.admin_space;
                                     self.routes_from_dds.retain(|zkey, route| {
                                             route.remove_remote_routed_reader(&full_admin_keyexpr);
-                                            if !route.has_remote_routed_reader() {
+                                            if !route.has_remote_routed_reader() && !route.is_from_manifest() {
                                                 info!(
                                                     "{}: remove it as no longer unused (no remote DDS Reader left)",
                                                     route
@@ -1355,11 +4550,42 @@ impl<'a> DdsPluginRuntime<'a> {
                     }
                 },
 
+                sample = fwd_ack_rcv.recv_async() => {
+                    let sample = sample.expect("Fwd Discovery acknowledgments subscriber was closed!");
+                    if let Some((remote_uuid, admin_keyexpr)) = Self::parse_fwd_ack_keyexpr(&sample.key_expr) {
+                        let dds_key = match self.admin_space.get(admin_keyexpr) {
+                            Some(AdminRef::DdsWriterEntity(key)) | Some(AdminRef::DdsReaderEntity(key)) => key,
+                            _ => {
+                                debug!("Ignoring acknowledgment of routes from {} for unknown DDS entity {}", remote_uuid, admin_keyexpr);
+                                continue;
+                            }
+                        };
+                        match bincode::deserialize::<HashMap<String, RouteStatusAck>>(&sample.payload.contiguous()) {
+                            Ok(acks) => {
+                                let acks: HashMap<String, RouteStatus> = acks.into_iter()
+                                    .filter_map(|(p, ack)| ack.into_status().map(|s| (p, s)))
+                                    .collect();
+                                debug!("Remote bridge {} acknowledged the routes for DDS entity {}: {:?}", remote_uuid, dds_key, acks);
+                                if let Some(status) = self.remote_route_statuses.get_mut(dds_key) {
+                                    status.acks.insert(remote_uuid.to_string(), acks);
+                                }
+                            }
+                            Err(e) => warn!("Failed to deserialize acknowledgment of routes from {} for {}: {}", remote_uuid, admin_keyexpr, e),
+                        }
+                    } else {
+                        error!("Unexpected acknowledgment of routes received on invalid key: {}", sample.key_expr);
+                    }
+                },
+
                 group_event = group_subscriber.recv_async() => {
                     match group_event.as_ref().map(|s|s.kind) {
                         Ok(SampleKind::Put) => {
                             let mid = member_id!(group_event.as_ref().unwrap());
                             debug!("New zenoh_dds_plugin detected: {}", mid);
+                            if !self.is_peer_allowed(mid) {
+                                info!("Ignoring zenoh_dds_plugin {} as it's not an allowed peer (see your 'allowed_peers' configuration)", mid);
+                                continue;
+                            }
                             // query for past publications of discocvery messages from this new member
                             let key = if let Some(scope) = &self.config.scope {
                                 *KE_PREFIX_FWD_DISCO / ke_for_sure!(mid) / scope / *KE_ANY_N_SEGMENT
@@ -1380,13 +4606,15 @@ impl<'a> DdsPluginRuntime<'a> {
                                 warn!("Query on {} for discovery messages failed: {}", key, e);
                             }
                             // make all QueryingSubscriber to query this new member
-                            for (zkey, route) in &mut self.routes_to_dds {
-                                route.query_historical_publications(|| (*KE_PREFIX_PUB_CACHE / ke_for_sure!(mid) / zkey).into(), self.config.queries_timeout).await;
-                            }
+                            self.query_historical_publications(ke_for_sure!(mid)).await;
                         }
                         Ok(SampleKind::Delete) => {
                             let mid = member_id!(group_event.as_ref().unwrap());
                             debug!("Remote zenoh_dds_plugin left: {}", mid);
+                            // forget the routes it acknowledged
+                            for status in self.remote_route_statuses.values_mut() {
+                                status.acks.remove(mid);
+                            }
                             // remove all the references to the plugin's enities, removing no longer used routes
                             // and updating/re-publishing ParticipantEntitiesInfo
                             let admin_space = &mut self.admin_space;
@@ -1394,7 +4622,7 @@ impl<'a> DdsPluginRuntime<'a> {
                             let mut participant_info_changed = false;
                             self.routes_to_dds.retain(|zkey, route| {
                                 route.remove_remote_routed_writers_containing(&admin_subke);
-                                if !route.has_remote_routed_writer() {
+                                if !route.has_remote_routed_writer() && !route.is_from_manifest() {
                                     info!(
                                         "{}: remove it as no longer unused (no remote DDS Writer left)",
                                         route
@@ -1414,7 +4642,7 @@ impl<'a> DdsPluginRuntime<'a> {
                             });
                             self.routes_from_dds.retain(|zkey, route| {
                                 route.remove_remote_routed_readers_containing(&admin_subke);
-                                if !route.has_remote_routed_reader() {
+                                if !route.has_remote_routed_reader() && !route.is_from_manifest() {
                                     info!(
                                         "{}: remove it as no longer unused (no remote DDS Reader left)",
                                         route
@@ -1452,6 +4680,56 @@ impl<'a> DdsPluginRuntime<'a> {
                     }
                 }
 
+                get_request = graph_queryable.recv_async() => {
+                    if let Ok(query) = get_request {
+                        self.treat_graph_query(query).await;
+                    }
+                }
+
+                sample = deny_subscriber.recv_async() => {
+                    if let Ok(sample) = sample {
+                        if let Err(e) = self.update_deny(&sample.value.payload.contiguous()) {
+                            warn!("Invalid update of 'deny' configuration via admin space: {}", e);
+                        }
+                    }
+                }
+
+                _ = stats_timer_rcv.recv_async() => {
+                    self.save_stats_state();
+                }
+
+                done = shutdown_rcv.recv_async() => {
+                    if let Ok(done) = done {
+                        debug!("Shutdown requested");
+                        return RunEnd::Shutdown(done);
+                    }
+                }
+
+                reload = reload_rcv.recv_async() => {
+                    if let Ok((config, done)) = reload {
+                        debug!("Reload of the configuration requested");
+                        if let Some(config) = self.reload_config(config).await {
+                            return RunEnd::Restart(Box::new(config), done);
+                        }
+                        let _ = done.send(());
+                    }
+                }
+
+                snapshot = routes_rcv.recv_async() => {
+                    if let Ok(snapshot) = snapshot {
+                        let _ = snapshot.send(routes_snapshot(
+                            &self.discovered_writers,
+                            &self.discovered_readers,
+                        ));
+                    }
+                }
+
+                self_test = self_test_rcv.recv_async() => {
+                    if let Ok((deadline, result_tx)) = self_test {
+                        self.spawn_self_test(deadline, result_tx);
+                    }
+                }
+
                 _ = ros_disco_timer_rcv.recv_async() => {
                     let infos = ros_disco_mgr.read();
                     for (gid, buf) in infos {
@@ -1467,6 +4745,67 @@ impl<'a> DdsPluginRuntime<'a> {
         }
     }
 
+    fn parse_fwd_ack_keyexpr(ack_ke: &keyexpr) -> Option<(&keyexpr, &keyexpr)> {
+        // parse ack_ke which have format: "KE_PREFIX_FWD_ACK/<uuid>/<remote_uuid>/<dds_entity_admin_key...>"
+        let remaining = ack_ke
+            .strip_prefix(KE_PREFIX_FWD_ACK.as_str())?
+            .strip_prefix('/')?;
+        let (_, remaining) = remaining.split_once('/')?;
+        let (remote_uuid, admin_keyexpr) = remaining.split_once('/')?;
+        Some((ke_for_sure!(remote_uuid), ke_for_sure!(admin_keyexpr)))
+    }
+
+    // Attach a local DDS Reader discovered in "forward discovery" mode to the routes serving remote DDS Writers whose
+    // partition is rewritten into one of the Reader's (see partition_rewrite configuration): their key expressions,
+    // with the original partitions, differ from the ones of the Reader's own routes. As when the remote DDS Writer is
+    // discovered after the Reader, its statuses for those routes are keyed by the routes' key expressions.
+    fn attach_to_rewritten_routes(&mut self, entity: &mut DdsEntity) {
+        for (ke, route) in self.routes_to_dds.iter_mut() {
+            if route.topic_name() == entity.topic_name
+                && route.has_remote_routed_writer()
+                && route
+                    .rewritten_partition()
+                    .map_or(false, |p| partition_contains(&entity.qos.partition, p))
+            {
+                route.add_local_routed_reader(entity.key.clone());
+                entity
+                    .routes
+                    .insert(ke.to_string(), RouteStatus::Routed(ke.clone()));
+            }
+        }
+    }
+
+    // Acknowledge to the bridge which advertised a DDS entity in "forward discovery" mode the statuses of the routes
+    // created for it (per partition, "*" only if no partition). Ignored by the bridges without route_ack_timeout
+    // configured, or running an older version.
+    async fn ack_fwd_routes(
+        &self,
+        remote_uuid: &keyexpr,
+        admin_keyexpr: &keyexpr,
+        acks: &HashMap<String, RouteStatus>,
+    ) {
+        let uuid: OwnedKeyExpr = self.zsession.zid().into();
+        let ack_ke = *KE_PREFIX_FWD_ACK / remote_uuid / &uuid / admin_keyexpr;
+        let acks: HashMap<&String, RouteStatusAck> =
+            acks.iter().map(|(p, s)| (p, s.into())).collect();
+        let ser_msg = match bincode::serialize(&acks) {
+            Ok(s) => s,
+            Err(e) => {
+                error!(
+                    "INTERNAL ERROR: failed to serialize acknowledgment of routes {:?}: {}",
+                    acks, e
+                );
+                return;
+            }
+        };
+        if let Err(e) = self.zsession.put(&ack_ke, ser_msg).res_async().await {
+            error!(
+                "Failed to publish acknowledgment of routes on {}: {}",
+                ack_ke, e
+            );
+        }
+    }
+
     fn parse_fwd_discovery_keyexpr(fwd_ke: &keyexpr) -> Option<(&keyexpr, &str, &keyexpr)> {
         // parse fwd_ke which have format: "KE_PREFIX_FWD_DISCO/<uuid>[/scope/possibly/multiple]/<disco_kind>/<remaining_ke...>"
         if !fwd_ke.starts_with(KE_PREFIX_FWD_DISCO.as_str()) {
@@ -1595,6 +4934,251 @@ fn remove_null_qos_values(
     }
 }
 
+// Add the version of the QoS representation schema to a serde_json::Value having a "qos" field
+fn add_qos_schema_version(
+    value: Result<Value, serde_json::Error>,
+) -> Result<Value, serde_json::Error> {
+    match value {
+        Ok(Value::Object(mut obj)) => {
+            if obj.contains_key("qos") {
+                obj.insert("qos_schema_version".into(), ADMIN_QOS_SCHEMA_VERSION.into());
+            }
+            Ok(Value::Object(obj))
+        }
+        other => other,
+    }
+}
+
+// Return the direction and key expression of the least recently active route, among routes given with their
+// last activity time
+fn least_recently_active_route<'k>(
+    routes: impl Iterator<Item = (RouteDirection, &'k OwnedKeyExpr, u64)>,
+) -> Option<(RouteDirection, &'k OwnedKeyExpr)> {
+    routes
+        .min_by_key(|(_, _, last_activity)| *last_activity)
+        .map(|(direction, ke, _)| (direction, ke))
+}
+
+// Return the keys of the DDS entities not routed because of a type conflict with one of the routes, ordered by key
+fn type_conflicts_with(
+    entities: &HashMap<String, DdsEntity>,
+    routes: &[OwnedKeyExpr],
+) -> Vec<String> {
+    let mut keys: Vec<String> = entities
+        .values()
+        .filter(|e| {
+            e.routes.values().any(|status| {
+                matches!(status, RouteStatus::TypeConflict { route, .. } if routes.contains(route))
+            })
+        })
+        .map(|e| e.key.clone())
+        .collect();
+    keys.sort();
+    keys
+}
+
+// Return the routes statuses of the discovered DDS Writers (routes from DDS) and Readers (routes to DDS)
+fn routes_snapshot(
+    writers: &HashMap<String, DdsEntity>,
+    readers: &HashMap<String, DdsEntity>,
+) -> RoutesSnapshot {
+    let mut routes = Vec::new();
+    for (direction, entities) in [
+        (RouteDirection::FromDds, writers),
+        (RouteDirection::ToDds, readers),
+    ] {
+        let mut entities: Vec<&DdsEntity> = entities.values().collect();
+        entities.sort_by(|a, b| a.key.cmp(&b.key));
+        for e in entities {
+            let mut statuses: Vec<(&String, &RouteStatus)> = e.routes.iter().collect();
+            statuses.sort_by(|a, b| a.0.cmp(b.0));
+            routes.extend(statuses.into_iter().map(|(partition, status)| RouteInfo {
+                direction,
+                entity_key: e.key.clone(),
+                topic_name: e.topic_name.clone(),
+                type_name: e.type_name.clone(),
+                partition: partition.clone(),
+                key_expr: status.route_key().cloned(),
+                status: status.clone(),
+            }));
+        }
+    }
+    RoutesSnapshot { routes }
+}
+
+// Return the snapshot of the DDS discovery tables: the discovered participants, publications and subscriptions
+// (with their routes statuses), each ordered by key, with their "total" number. Only the entities in the page are
+// returned (the participants first, then the publications and the subscriptions), with the offset of the next page
+// in "next_offset" if there are more, bounding the reply's size on a large graph. The page's topic regex filters the
+// publications and subscriptions.
+fn discovery_graph(
+    participants: &HashMap<String, DdsParticipant>,
+    writers: &HashMap<String, DdsEntity>,
+    readers: &HashMap<String, DdsEntity>,
+    page: &AdminQueryPage,
+) -> Result<Value, serde_json::Error> {
+    let mut participants: Vec<&DdsParticipant> = participants.values().collect();
+    participants.sort_by(|a, b| a.key.cmp(&b.key));
+    fn sorted_entities<'a>(
+        entities: &'a HashMap<String, DdsEntity>,
+        topic: &Option<regex::Regex>,
+    ) -> Vec<&'a DdsEntity> {
+        let mut entities: Vec<&DdsEntity> = entities
+            .values()
+            .filter(|e| topic.as_ref().map_or(true, |re| re.is_match(&e.topic_name)))
+            .collect();
+        entities.sort_by(|a, b| a.key.cmp(&b.key));
+        entities
+    }
+    let publications = sorted_entities(writers, &page.topic);
+    let subscriptions = sorted_entities(readers, &page.topic);
+
+    let total = participants.len() + publications.len() + subscriptions.len();
+    let start = page.offset.min(total);
+    let end = start
+        .saturating_add(page.limit.unwrap_or(usize::MAX))
+        .min(total);
+    // the range of a list in the page, given the index of its first element among all the entities
+    let range = |first: usize, len: usize| {
+        start.clamp(first, first + len) - first..end.clamp(first, first + len) - first
+    };
+    let mut graph = serde_json::json!({
+        "participants": serde_json::to_value(&participants[range(0, participants.len())])?,
+        "publications": serde_json::to_value(
+            &publications[range(participants.len(), publications.len())]
+        )?,
+        "subscriptions": serde_json::to_value(
+            &subscriptions[range(
+                participants.len() + publications.len(),
+                subscriptions.len()
+            )]
+        )?,
+        "total": total,
+    });
+    if end < total {
+        graph["next_offset"] = end.into();
+    }
+    Ok(graph)
+}
+
+// Return the list of discovered DDS entities (ordered by key) with their topic, type, partitions, keyless flag,
+// main QoS kinds and routes statuses
+fn discovered_entities_summary(
+    entities: &HashMap<String, DdsEntity>,
+) -> Result<Value, serde_json::Error> {
+    let mut entities: Vec<&DdsEntity> = entities.values().collect();
+    entities.sort_by(|a, b| a.key.cmp(&b.key));
+    entities
+        .into_iter()
+        .map(|e| {
+            let history = e.qos.history.as_ref().map(|h| {
+                serde_json::json!({
+                    "kind": h.kind,
+                    "depth": h.depth,
+                })
+            });
+            Ok(serde_json::json!({
+                "key": e.key,
+                "participant_key": e.participant_key,
+                "topic_name": e.topic_name,
+                "type_name": e.type_name,
+                "partition": e.qos.partition,
+                "keyless": e.keyless,
+                "reliability": e.qos.reliability.as_ref().map(|r| &r.kind),
+                "durability": e.qos.durability.as_ref().map(|d| &d.kind),
+                "history": history,
+                "routes": serde_json::to_value(&e.routes)?,
+            }))
+        })
+        .collect::<Result<Vec<Value>, serde_json::Error>>()
+        .map(Value::Array)
+}
+
+// Insert an "alias" field into a serde_json::Value, if an alias is set
+fn insert_alias(
+    value: Result<Value, serde_json::Error>,
+    alias: Option<&String>,
+) -> Result<Value, serde_json::Error> {
+    match (value, alias) {
+        (Ok(Value::Object(mut obj)), Some(alias)) => {
+            obj.insert("alias".into(), alias.clone().into());
+            Ok(Value::Object(obj))
+        }
+        (value, _) => value,
+    }
+}
+
+// Insert a "remote_route_status" field into a serde_json::Value, if any (see route_ack_timeout)
+fn insert_remote_route_status(
+    value: Result<Value, serde_json::Error>,
+    status: Option<Value>,
+) -> Result<Value, serde_json::Error> {
+    match (value, status) {
+        (Ok(Value::Object(mut obj)), Some(status)) => {
+            obj.insert("remote_route_status".into(), status);
+            Ok(Value::Object(obj))
+        }
+        (value, _) => value,
+    }
+}
+
+// Insert a "qos" field (without null values) and its schema version into a serde_json::Value.
+// If qos is None (e.g. route without DDS Writer yet), the "qos" field is set to null.
+fn insert_qos_value(
+    value: Result<Value, serde_json::Error>,
+    qos: Option<&Qos>,
+) -> Result<Value, serde_json::Error> {
+    match value {
+        Ok(Value::Object(mut obj)) => {
+            let qos = match qos {
+                Some(qos) => serde_json::to_value(qos)?,
+                None => Value::Null,
+            };
+            obj.insert("qos".into(), qos);
+            add_qos_schema_version(remove_null_qos_values(Ok(Value::Object(obj))))
+        }
+        other => other,
+    }
+}
+
+// Return the delay before a new attempt to create the routes of a DDS entity, after "attempts" failed attempts
+fn route_creation_retry_delay(attempts: u32) -> Duration {
+    ROUTE_CREATION_RETRY_INITIAL_DELAY
+        .saturating_mul(1 << attempts.saturating_sub(1).min(16))
+        .min(ROUTE_CREATION_RETRY_MAX_DELAY)
+}
+
+// Return the status of a DDS entity served by an already established route: QoSConflict if the QoS the route
+// would have for this entity conflicts with the route's QoS (the route is then still used, with its own QoS)
+fn get_existing_route_status(ke: OwnedKeyExpr, route_qos: &Qos, qos: &Qos) -> RouteStatus {
+    let conflicts = get_qos_conflicts(route_qos, qos);
+    if conflicts.is_empty() {
+        RouteStatus::Routed(ke)
+    } else {
+        RouteStatus::QoSConflict {
+            route: ke,
+            conflicts: conflicts.join(", "),
+        }
+    }
+}
+
+// Resolve the QoS of the DDS entity created by the bridge for a route, from the QoS of the DDS entity it serves:
+//  - from DDS: the QoS of a local Writer (or of a remote Reader in "forward discovery" mode) gives the QoS of the bridge's Reader
+//  - to DDS: the QoS of a local Reader (or of a remote Writer in "forward discovery" mode) gives the QoS of the bridge's Writer
+fn resolve_route_qos(direction: RouteDirection, forward_discovery: bool, qos: &Qos) -> Qos {
+    match (direction, forward_discovery) {
+        (RouteDirection::FromDds, false) => adapt_writer_qos_for_reader(qos),
+        (RouteDirection::FromDds, true) => adapt_reader_qos_for_proxy_reader(qos),
+        (RouteDirection::ToDds, false) => adapt_reader_qos_for_writer(qos),
+        (RouteDirection::ToDds, true) => adapt_writer_qos_for_proxy_writer(qos),
+    }
+}
+
+// The name of a DDS Reader or Writer created by the bridge, to identify it in the DDS tools (e.g. ddsls)
+fn dds_entity_name(prefix: &str, route_kind: &str, topic_name: &str) -> Option<String> {
+    (!prefix.is_empty()).then(|| format!("{prefix}:{route_kind}:{topic_name}"))
+}
+
 // Copy and adapt Writer's QoS for creation of a matching Reader
 fn adapt_writer_qos_for_reader(qos: &Qos) -> Qos {
     let mut reader_qos = qos.clone();
@@ -1701,6 +5285,73 @@ fn adapt_reader_qos_for_proxy_reader(qos: &Qos) -> Qos {
     reader_qos
 }
 
+// The CycloneDDS network configuration per the "localhost_only", "interface" and "ipv6" settings (with a trailing
+// comma, to be prepended to "$CYCLONEDDS_URI"), or None if none is set. "localhost_only" takes precedence over "interface".
+fn cyclonedds_network_config(
+    localhost_only: bool,
+    interface: Option<&DdsInterface>,
+    ipv6: bool,
+) -> Option<String> {
+    if localhost_only && !ipv6 {
+        return Some(CYCLONEDDS_CONFIG_LOCALHOST_ONLY.to_string());
+    }
+    let interface = if localhost_only {
+        Some(r#"<NetworkInterface address="::1"/>"#.to_string())
+    } else {
+        interface.map(|interface| match interface {
+            DdsInterface::Name(name) => {
+                format!(r#"<NetworkInterface name="{}"/>"#, xml_escape(name))
+            }
+            DdsInterface::Address(addr) => format!(r#"<NetworkInterface address="{addr}"/>"#),
+        })
+    };
+    if interface.is_none() && !ipv6 {
+        return None;
+    }
+    Some(format!(
+        "<CycloneDDS><Domain><General>{}{}</General></Domain></CycloneDDS>,",
+        if ipv6 {
+            "<Transport>udp6</Transport>"
+        } else {
+            ""
+        },
+        interface
+            .map(|interface| format!("<Interfaces>{interface}</Interfaces>"))
+            .unwrap_or_default()
+    ))
+}
+
+// Check that a network interface exists on this host (with an IPv6 address if "ipv6" is set),
+// to report an actionable error rather than CycloneDDS failing to create the DDS Participant
+fn check_dds_interface(interface: &DdsInterface, ipv6: bool) -> Result<(), String> {
+    let addresses = match interface {
+        DdsInterface::Name(name) => zenoh_util::net::get_local_addresses(Some(name)),
+        DdsInterface::Address(addr) => zenoh_util::net::get_local_addresses(None)
+            .map(|addresses| addresses.into_iter().filter(|a| a == addr).collect()),
+    }
+    .map_err(|e| {
+        format!("can't resolve the network interface '{interface}' configured by 'interface': {e}")
+    })?;
+    if addresses.is_empty() {
+        return Err(format!(
+            "the network interface '{interface}' configured by 'interface' doesn't exist on this host"
+        ));
+    }
+    if ipv6 && !addresses.iter().any(IpAddr::is_ipv6) {
+        return Err(format!(
+            "the network interface '{interface}' configured by 'interface' has no IPv6 address, while 'ipv6' is set"
+        ));
+    }
+    Ok(())
+}
+
+// Escape a string to be inserted as text in a XML element
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 //TODO replace when stable https://github.com/rust-lang/rust/issues/65816
 #[inline]
 pub(crate) fn vec_into_raw_parts<T>(v: Vec<T>) -> (*mut T, usize, usize) {
@@ -1708,6 +5359,118 @@ pub(crate) fn vec_into_raw_parts<T>(v: Vec<T>) -> (*mut T, usize, usize) {
     (me.as_mut_ptr(), me.len(), me.capacity())
 }
 
+fn parse_log_payload_max_bytes(s: &str) -> Option<(usize, usize)> {
+    let mut it = s.split(',');
+    let head = it.next()?.trim().parse::<usize>();
+    let tail = it.next().map_or(Ok(0), |t| t.trim().parse::<usize>());
+    match (head, tail) {
+        (Ok(head), Ok(tail)) => Some((head, tail)),
+        _ => {
+            warn!(
+                "Invalid Z_LOG_PAYLOAD_MAX_BYTES value '{}' (expected '<head>[,<tail>]'): payloads won't be truncated in logs",
+                s
+            );
+            None
+        }
+    }
+}
+
+// Parse the regex in the payload of an update of the 'deny' configuration (None if empty)
+// The payload is either a single expression, or a JSON list of expressions (as replied for "config/deny")
+fn parse_deny_payload(payload: &[u8]) -> Result<Option<EntityMatcher>, String> {
+    let deny = std::str::from_utf8(payload)
+        .map_err(|e| format!("invalid UTF-8 regex: {e}"))?
+        .trim();
+    if deny.is_empty() {
+        Ok(None)
+    } else if deny.starts_with('[') {
+        let exprs: Vec<String> = serde_json::from_str(deny)
+            .map_err(|e| format!("invalid list of expressions '{deny}': {e}"))?;
+        EntityMatcher::new(&exprs).map(Some)
+    } else {
+        EntityMatcher::new(&[deny]).map(Some)
+    }
+}
+
+// The creation of the routes serving the DDS entities discovered in "local discovery" mode
+// (see routing::route_local_entity())
+#[async_trait]
+impl<'a> RouteFactory for DdsPluginRuntime<'a> {
+    fn config(&self) -> &Config {
+        &self.config
+    }
+
+    async fn route_dds_writer(&mut self, ke: OwnedKeyExpr, entity: &DdsEntity) -> RouteStatus {
+        // the creation of a new route might be delayed as per route_creation_limit (see schedule_route_retry())
+        if !self.config.dry_run
+            && !self.routes_from_dds.contains_key(&ke)
+            && !self.check_route_creation_limit(&ke)
+        {
+            return RouteStatus::RateLimited;
+        }
+        let qos = resolve_route_qos(RouteDirection::FromDds, false, &entity.qos);
+        // CongestionControl to be used when re-publishing over zenoh: Blocking if Writer is RELIABLE (since we don't know what is remote Reader's QoS)
+        let congestion_ctrl = match (
+            self.config.reliable_routes_blocking,
+            is_writer_reliable(&entity.qos.reliability),
+        ) {
+            (true, true) => CongestionControl::Block,
+            _ => CongestionControl::Drop,
+        };
+        // zenoh priority mapped from the Writer's TRANSPORT_PRIORITY (unless overwritten by config)
+        let transport_priority = entity.qos.transport_priority.as_ref().map(|p| p.value);
+        // samples expired as per the Writer's LIFESPAN are not routed
+        let lifespan = get_lifespan(&entity.qos);
+        let route_status = self
+            .try_add_route_from_dds(
+                ke,
+                &entity.topic_name,
+                &entity.type_name,
+                &entity.type_info,
+                entity.keyless,
+                qos,
+                congestion_ctrl,
+                transport_priority,
+                lifespan,
+            )
+            .await;
+        if let Some(route_key) = route_status.route_key() {
+            if let Some(r) = self.routes_from_dds.get_mut(route_key) {
+                // if route has been created, add this Writer in its routed_writers list
+                r.add_local_routed_writer(entity.key.clone());
+            }
+        }
+        route_status
+    }
+
+    async fn route_dds_reader(&mut self, ke: OwnedKeyExpr, entity: &DdsEntity) -> RouteStatus {
+        if !self.config.dry_run
+            && !self.routes_to_dds.contains_key(&ke)
+            && !self.check_route_creation_limit(&ke)
+        {
+            return RouteStatus::RateLimited;
+        }
+        let qos = resolve_route_qos(RouteDirection::ToDds, false, &entity.qos);
+        let route_status = self
+            .try_add_route_to_dds(
+                ke,
+                &entity.topic_name,
+                &entity.type_name,
+                entity.keyless,
+                is_transient_local(&qos),
+                Some(qos),
+            )
+            .await;
+        if let Some(route_key) = route_status.route_key() {
+            if let Some(r) = self.routes_to_dds.get_mut(route_key) {
+                // if route has been created, add this Reader in its routed_readers list
+                r.add_local_routed_reader(entity.key.clone());
+            }
+        }
+        route_status
+    }
+}
+
 struct ChannelEvent {
     tx: Sender<()>,
 }
@@ -1720,3 +5483,565 @@ impl Timed for ChannelEvent {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        cyclonedds_network_config, dds_entity_name, discovered_entities_summary, discovery_graph,
+        get_existing_route_status, least_recently_active_route, plugin_requests, resolve_route_qos,
+        route_creation_retry_delay, routes_snapshot, type_conflicts_with, AdminQueryPage,
+        DdsPluginRuntime, PluginRequests, RemoteRouteStatus, RouteStatusAck, UnconfirmedEntities,
+    };
+    use crate::config::{DdsInterface, RouteDirection};
+    use crate::dds_mgt::{DdsEntity, DdsParticipant, RouteStatus};
+    use cyclors::qos::{
+        Durability, DurabilityKind, IgnoreLocalKind, Qos, Reliability, ReliabilityKind,
+        TransportPriority, DDS_100MS_DURATION,
+    };
+    use flume::Sender;
+    use std::collections::HashMap;
+    use std::sync::RwLock;
+    use std::time::{Duration, Instant};
+    use zenoh::prelude::OwnedKeyExpr;
+
+    lazy_static::lazy_static!(
+        static ref TEST_REQUESTS: RwLock<Option<Sender<u32>>> = RwLock::new(None);
+    );
+
+    fn dds_writer(
+        key: &str,
+        reliability: ReliabilityKind,
+        durability: DurabilityKind,
+    ) -> DdsEntity {
+        let mut qos = Qos::default();
+        qos.reliability = Some(Reliability {
+            kind: reliability,
+            max_blocking_time: DDS_100MS_DURATION,
+        });
+        qos.durability = Some(Durability { kind: durability });
+        DdsEntity {
+            key: key.into(),
+            participant_key: "p".into(),
+            topic_name: "rt/map".into(),
+            normalized_topic_name: None,
+            type_name: "nav_msgs::msg::dds_::OccupancyGrid_".into(),
+            type_info: None,
+            keyless: true,
+            qos,
+            routes: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_plugin_requests() {
+        // no request is accepted while not running
+        assert!(plugin_requests(&*TEST_REQUESTS).is_none());
+
+        let requests = PluginRequests::register(&*TEST_REQUESTS);
+        let tx = plugin_requests(&*TEST_REQUESTS).unwrap();
+        assert!(tx.try_send(1).is_ok());
+        assert_eq!(requests.rcv.try_recv(), Ok(1));
+        assert!(tx.try_send(2).is_ok());
+        // the pending requests are returned at the end of the run, and not left for the next one
+        assert_eq!(requests.unregister(), vec![2]);
+        assert!(plugin_requests(&*TEST_REQUESTS).is_none());
+        drop(requests);
+        let requests = PluginRequests::register(&*TEST_REQUESTS);
+        assert!(requests.rcv.try_recv().is_err());
+        drop(requests);
+        assert!(plugin_requests(&*TEST_REQUESTS).is_none());
+    }
+
+    #[test]
+    fn test_existing_route_status() {
+        let ke: OwnedKeyExpr = "rt/map".try_into().unwrap();
+        let first = dds_writer(
+            "w1",
+            ReliabilityKind::RELIABLE,
+            DurabilityKind::TRANSIENT_LOCAL,
+        );
+        let route_qos = resolve_route_qos(RouteDirection::FromDds, false, &first.qos);
+
+        // a Writer with the same QoS is served by the route
+        let same = dds_writer(
+            "w2",
+            ReliabilityKind::RELIABLE,
+            DurabilityKind::TRANSIENT_LOCAL,
+        );
+        let qos = resolve_route_qos(RouteDirection::FromDds, false, &same.qos);
+        let status = get_existing_route_status(ke.clone(), &route_qos, &qos);
+        assert_eq!(status, RouteStatus::Routed(ke.clone()));
+
+        // a Writer with conflicting reliability and durability is still served by the route, but with a conflict
+        let conflicting = dds_writer("w3", ReliabilityKind::BEST_EFFORT, DurabilityKind::VOLATILE);
+        let qos = resolve_route_qos(RouteDirection::FromDds, false, &conflicting.qos);
+        let status = get_existing_route_status(ke.clone(), &route_qos, &qos);
+        assert_eq!(status.route_key(), Some(&ke));
+        match status {
+            RouteStatus::QoSConflict { conflicts, .. } => {
+                assert!(conflicts.starts_with("reliability"));
+                assert!(conflicts.contains("durability"));
+                assert!(!conflicts.contains("history"));
+            }
+            _ => panic!("expected a QoS conflict, got {status:?}"),
+        }
+    }
+
+    #[test]
+    fn test_discovered_entities_summary() {
+        let ke: OwnedKeyExpr = "rt/map".try_into().unwrap();
+        let mut routed = dds_writer(
+            "w2",
+            ReliabilityKind::RELIABLE,
+            DurabilityKind::TRANSIENT_LOCAL,
+        );
+        routed.routes.insert("*".into(), RouteStatus::Routed(ke));
+        let mut denied = dds_writer("w1", ReliabilityKind::BEST_EFFORT, DurabilityKind::VOLATILE);
+        denied.qos.partition = Some(vec!["A".into()]);
+        denied.routes.insert("A".into(), RouteStatus::NotAllowed);
+        let unrouted = dds_writer("w3", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        let entities: HashMap<String, DdsEntity> = [routed, denied, unrouted]
+            .into_iter()
+            .map(|e| (e.key.clone(), e))
+            .collect();
+
+        let summary = discovered_entities_summary(&entities).unwrap();
+        let summary = summary.as_array().unwrap();
+        assert_eq!(summary.len(), 3);
+        // ordered by key
+        assert_eq!(summary[0]["key"], "w1");
+        assert_eq!(summary[0]["topic_name"], "rt/map");
+        assert_eq!(
+            summary[0]["type_name"],
+            "nav_msgs::msg::dds_::OccupancyGrid_"
+        );
+        assert_eq!(summary[0]["partition"], serde_json::json!(["A"]));
+        assert_eq!(summary[0]["keyless"], true);
+        assert_eq!(summary[0]["reliability"], "BEST_EFFORT");
+        assert_eq!(summary[0]["durability"], "VOLATILE");
+        assert_eq!(summary[0]["routes"], serde_json::json!({"A": "NotAllowed"}));
+        assert_eq!(summary[1]["key"], "w2");
+        assert_eq!(summary[1]["partition"], serde_json::Value::Null);
+        assert_eq!(
+            summary[1]["routes"],
+            serde_json::json!({"*": {"Routed": "rt/map"}})
+        );
+        // an entity for which no route was attempted
+        assert_eq!(summary[2]["key"], "w3");
+        assert_eq!(summary[2]["routes"], serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_routes_snapshot() {
+        let ke: OwnedKeyExpr = "rt/map".try_into().unwrap();
+        let mut writer = dds_writer("w1", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        writer.qos.partition = Some(vec!["B".into(), "A".into()]);
+        writer
+            .routes
+            .insert("B".into(), RouteStatus::Routed(ke.clone()));
+        writer.routes.insert("A".into(), RouteStatus::NotAllowed);
+        let unrouted = dds_writer("w2", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        let mut reader = dds_writer("r1", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        reader.routes.insert(
+            "*".into(),
+            RouteStatus::QoSConflict {
+                route: ke.clone(),
+                conflicts: "durability".into(),
+            },
+        );
+        let writers: HashMap<String, DdsEntity> = [writer, unrouted]
+            .into_iter()
+            .map(|e| (e.key.clone(), e))
+            .collect();
+        let readers: HashMap<String, DdsEntity> = [(reader.key.clone(), reader)].into();
+
+        let snapshot = routes_snapshot(&writers, &readers);
+        // one route per partition, ordered by direction, entity key and partition
+        assert_eq!(snapshot.routes.len(), 3);
+        let route = &snapshot.routes[0];
+        assert_eq!(route.direction, RouteDirection::FromDds);
+        assert_eq!(route.entity_key, "w1");
+        assert_eq!(route.topic_name, "rt/map");
+        assert_eq!(route.type_name, "nav_msgs::msg::dds_::OccupancyGrid_");
+        assert_eq!(route.partition, "A");
+        assert_eq!(route.key_expr, None);
+        assert_eq!(route.status, RouteStatus::NotAllowed);
+        let route = &snapshot.routes[1];
+        assert_eq!(route.partition, "B");
+        assert_eq!(route.key_expr.as_ref(), Some(&ke));
+        assert_eq!(route.status, RouteStatus::Routed(ke.clone()));
+        let route = &snapshot.routes[2];
+        assert_eq!(route.direction, RouteDirection::ToDds);
+        assert_eq!(route.entity_key, "r1");
+        assert_eq!(route.partition, "*");
+        assert_eq!(route.key_expr.as_ref(), Some(&ke));
+    }
+
+    #[test]
+    fn test_type_conflicts_with() {
+        let ke: OwnedKeyExpr = "rt/map".try_into().unwrap();
+        let other: OwnedKeyExpr = "rt/scan".try_into().unwrap();
+        let conflict = |route: &OwnedKeyExpr| RouteStatus::TypeConflict {
+            route: route.clone(),
+            route_type: "std_msgs::msg::dds_::String_".into(),
+            type_name: "nav_msgs::msg::dds_::OccupancyGrid_".into(),
+        };
+        let mut w1 = dds_writer("w1", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        w1.routes.insert("*".into(), conflict(&ke));
+        let mut w2 = dds_writer("w2", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        w2.qos.partition = Some(vec!["A".into(), "B".into()]);
+        w2.routes
+            .insert("A".into(), RouteStatus::Routed(other.clone()));
+        w2.routes.insert("B".into(), conflict(&ke));
+        let mut w3 = dds_writer("w3", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        w3.routes.insert("*".into(), conflict(&other));
+        let mut w4 = dds_writer("w4", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        w4.routes
+            .insert("*".into(), RouteStatus::Routed(ke.clone()));
+        let writers: HashMap<String, DdsEntity> = [w3, w2, w1, w4]
+            .into_iter()
+            .map(|e| (e.key.clone(), e))
+            .collect();
+
+        // the Writers conflicting with a removed route are routed again, whatever their other routes
+        assert_eq!(
+            type_conflicts_with(&writers, &[ke.clone()]),
+            vec!["w1", "w2"]
+        );
+        assert_eq!(
+            type_conflicts_with(&writers, &[ke.clone(), other.clone()]),
+            vec!["w1", "w2", "w3"]
+        );
+        assert!(type_conflicts_with(&writers, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_discovery_graph() {
+        let ke: OwnedKeyExpr = "rt/map".try_into().unwrap();
+        let participants: HashMap<String, DdsParticipant> = ["p2", "p1"]
+            .into_iter()
+            .map(|key| {
+                (
+                    key.to_string(),
+                    DdsParticipant {
+                        key: key.into(),
+                        qos: Qos::default(),
+                    },
+                )
+            })
+            .collect();
+        let mut writer = dds_writer("w1", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        writer
+            .routes
+            .insert("*".into(), RouteStatus::Routed(ke.clone()));
+        let writers: HashMap<String, DdsEntity> = [
+            writer,
+            dds_writer("w2", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE),
+        ]
+        .into_iter()
+        .map(|e| (e.key.clone(), e))
+        .collect();
+        let mut reader = dds_writer("r1", ReliabilityKind::RELIABLE, DurabilityKind::VOLATILE);
+        reader.topic_name = "rt/scan".into();
+        let readers: HashMap<String, DdsEntity> = [(reader.key.clone(), reader)].into();
+        let keys = |graph: &serde_json::Value, list: &str| -> Vec<String> {
+            graph[list]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["key"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        // the whole graph, ordered by key
+        let page = AdminQueryPage::parse("", None).unwrap();
+        let graph = discovery_graph(&participants, &writers, &readers, &page).unwrap();
+        assert_eq!(keys(&graph, "participants"), vec!["p1", "p2"]);
+        assert_eq!(keys(&graph, "publications"), vec!["w1", "w2"]);
+        assert_eq!(keys(&graph, "subscriptions"), vec!["r1"]);
+        assert_eq!(graph["total"], 5);
+        assert!(graph.get("next_offset").is_none());
+        // the entities are serialized with their routes, but without their type info
+        assert!(graph["publications"][0]["routes"].get("*").is_some());
+        assert!(graph["publications"][0].get("type_info").is_none());
+
+        // a page spanning the participants and the publications
+        let page = AdminQueryPage::parse("offset=1&limit=2", None).unwrap();
+        let graph = discovery_graph(&participants, &writers, &readers, &page).unwrap();
+        assert_eq!(keys(&graph, "participants"), vec!["p2"]);
+        assert_eq!(keys(&graph, "publications"), vec!["w1"]);
+        assert!(keys(&graph, "subscriptions").is_empty());
+        assert_eq!(graph["next_offset"], 3);
+        // the last page
+        let page = AdminQueryPage::parse("offset=3&limit=2", None).unwrap();
+        let graph = discovery_graph(&participants, &writers, &readers, &page).unwrap();
+        assert_eq!(keys(&graph, "publications"), vec!["w2"]);
+        assert_eq!(keys(&graph, "subscriptions"), vec!["r1"]);
+        assert!(graph.get("next_offset").is_none());
+        // an offset beyond the graph
+        let page = AdminQueryPage::parse("offset=10", None).unwrap();
+        let graph = discovery_graph(&participants, &writers, &readers, &page).unwrap();
+        assert!(keys(&graph, "participants").is_empty());
+        assert_eq!(graph["total"], 5);
+
+        // the topic filters the publications and subscriptions
+        let page = AdminQueryPage::parse("topic=rt/scan", None).unwrap();
+        let graph = discovery_graph(&participants, &writers, &readers, &page).unwrap();
+        assert_eq!(keys(&graph, "participants"), vec!["p1", "p2"]);
+        assert!(keys(&graph, "publications").is_empty());
+        assert_eq!(keys(&graph, "subscriptions"), vec!["r1"]);
+        assert_eq!(graph["total"], 3);
+    }
+
+    #[test]
+    fn test_least_recently_active_route() {
+        let a: OwnedKeyExpr = "rt/a".try_into().unwrap();
+        let b: OwnedKeyExpr = "rt/b".try_into().unwrap();
+        let c: OwnedKeyExpr = "rt/c".try_into().unwrap();
+        assert_eq!(least_recently_active_route(std::iter::empty()), None);
+        let routes = [
+            (RouteDirection::FromDds, &a, 2000),
+            (RouteDirection::ToDds, &b, 1000),
+            (RouteDirection::FromDds, &c, 3000),
+        ];
+        assert_eq!(
+            least_recently_active_route(routes.into_iter()),
+            Some((RouteDirection::ToDds, &b))
+        );
+    }
+
+    #[test]
+    fn test_admin_query_page() {
+        let page = AdminQueryPage::parse("", None).unwrap();
+        assert!(page.topic.is_none());
+        assert!(!page.is_paginated());
+
+        let page = AdminQueryPage::parse("", Some(100)).unwrap();
+        assert_eq!(page.limit, Some(100));
+        assert!(page.is_paginated());
+
+        let page =
+            AdminQueryPage::parse("topic=rt/.*&offset=200&limit=50&_time=now", Some(100)).unwrap();
+        assert!(page.topic.unwrap().is_match("rt/cmd_vel"));
+        assert_eq!(page.offset, 200);
+        assert_eq!(page.limit, Some(50));
+
+        assert!(AdminQueryPage::parse("limit=-1", None).is_err());
+        assert!(AdminQueryPage::parse("topic=rt/(", None).is_err());
+        assert!(AdminQueryPage::parse("page=2", None).is_err());
+    }
+
+    #[test]
+    fn test_parse_deny_payload() {
+        let deny = super::parse_deny_payload(b"rt/cmd_vel|rt/map\n")
+            .unwrap()
+            .unwrap();
+        assert!(deny.is_match("rt/map", ""));
+        assert!(!deny.is_match("rt/scan", ""));
+        let deny = super::parse_deny_payload(br#"["rt/map", "type:.*::Log_"]"#)
+            .unwrap()
+            .unwrap();
+        assert!(deny.is_match("rt/map", ""));
+        assert!(deny.is_match("rt/rosout", "rcl_interfaces::msg::dds_::Log_"));
+        assert!(!deny.is_match("rt/scan", "sensor_msgs::msg::dds_::LaserScan_"));
+        assert!(super::parse_deny_payload(b"[\"rt/map\"").is_err());
+        assert!(super::parse_deny_payload(b"").unwrap().is_none());
+        assert!(super::parse_deny_payload(b"  ").unwrap().is_none());
+        assert!(super::parse_deny_payload(b"rt/(").is_err());
+        assert!(super::parse_deny_payload(&[0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(super::xml_escape("fleet-1"), "fleet-1");
+        assert_eq!(super::xml_escape("a<b>&c"), "a&lt;b&gt;&amp;c");
+    }
+
+    #[test]
+    fn test_route_creation_retry_delay() {
+        // the delay doubles at each failed attempt, up to 1 minute
+        assert_eq!(route_creation_retry_delay(1), Duration::from_secs(1));
+        assert_eq!(route_creation_retry_delay(2), Duration::from_secs(2));
+        assert_eq!(route_creation_retry_delay(4), Duration::from_secs(8));
+        assert_eq!(route_creation_retry_delay(7), Duration::from_secs(60));
+        assert_eq!(
+            route_creation_retry_delay(u32::MAX),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_remote_route_status() {
+        let ack_ke = zenoh::prelude::keyexpr::new(
+            "@dds_fwd_ack/aaaa/bbbb/participant/p1/writer/w1/rt/chatter",
+        )
+        .unwrap();
+        let (remote_uuid, admin_keyexpr) = DdsPluginRuntime::parse_fwd_ack_keyexpr(ack_ke).unwrap();
+        assert_eq!(remote_uuid.as_str(), "bbbb");
+        assert_eq!(
+            admin_keyexpr.as_str(),
+            "participant/p1/writer/w1/rt/chatter"
+        );
+        let ack_ke = zenoh::prelude::keyexpr::new("@dds_fwd_ack/aaaa/bbbb").unwrap();
+        assert!(DdsPluginRuntime::parse_fwd_ack_keyexpr(ack_ke).is_none());
+
+        // "pending" until the timeout, then "unconfirmed" if no remote bridge acknowledged (e.g. older versions)
+        let mut status = RemoteRouteStatus::new();
+        assert_eq!(status.to_value(Duration::from_secs(60)), "pending");
+        assert_eq!(status.to_value(Duration::ZERO), "unconfirmed");
+        status.acks.insert(
+            "bbbb".into(),
+            HashMap::from([("*".to_string(), RouteStatus::NotAllowed)]),
+        );
+        assert_eq!(
+            status.to_value(Duration::ZERO),
+            serde_json::json!({"bbbb": {"*": "NotAllowed"}})
+        );
+    }
+
+    #[test]
+    fn test_route_status_ack() {
+        let ke = OwnedKeyExpr::new("rt/chatter").unwrap();
+        let statuses = [
+            RouteStatus::Routed(ke.clone()),
+            RouteStatus::NotAllowed,
+            RouteStatus::CreationFailure {
+                error: "failed".into(),
+                permanent: true,
+                attempts: 3,
+            },
+            RouteStatus::QoSConflict {
+                route: ke.clone(),
+                conflicts: "durability".into(),
+            },
+            RouteStatus::TypeConflict {
+                route: ke.clone(),
+                route_type: "A".into(),
+                type_name: "B".into(),
+            },
+            RouteStatus::Throttled,
+            RouteStatus::RateLimited,
+            RouteStatus::DryRun(ke.clone()),
+        ];
+        for (code, status) in statuses.iter().enumerate() {
+            let ser = bincode::serialize(&RouteStatusAck::from(status)).unwrap();
+            // the code is encoded first, and doesn't depend on the order of the RouteStatus variants
+            assert_eq!(ser[..4], (code as u32).to_le_bytes());
+            let ack: RouteStatusAck = bincode::deserialize(&ser).unwrap();
+            assert_eq!(ack.into_status().as_ref(), Some(status));
+        }
+        // a status unknown to this version (e.g. sent by a newer bridge) is ignored
+        let unknown = RouteStatusAck::new(100, None, vec![]);
+        assert_eq!(unknown.into_status(), None);
+    }
+
+    #[test]
+    fn test_unconfirmed_entities() {
+        let timeout = Duration::from_secs(30);
+        let recovery = Instant::now();
+        let mut unconfirmed = UnconfirmedEntities::default();
+        assert!(unconfirmed.take_expired(timeout, recovery).is_empty());
+
+        unconfirmed.reset(
+            ["w1".to_string(), "w2".to_string(), "r1".to_string()].into(),
+            recovery,
+        );
+        unconfirmed.confirm("w2");
+        // the entities are kept until the timeout, even if checked several times in between
+        assert!(unconfirmed
+            .take_expired(timeout, recovery + Duration::from_secs(5))
+            .is_empty());
+        assert!(unconfirmed
+            .take_expired(timeout, recovery + Duration::from_secs(10))
+            .is_empty());
+        unconfirmed.confirm("r1");
+        assert_eq!(
+            unconfirmed.take_expired(timeout, recovery + timeout),
+            vec!["w1".to_string()]
+        );
+        // they're undiscovered only once
+        assert!(unconfirmed
+            .take_expired(timeout, recovery + 2 * timeout)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_dds_entity_name() {
+        assert_eq!(
+            dds_entity_name("zenoh-bridge-dds", "from_dds", "rt/chatter").as_deref(),
+            Some("zenoh-bridge-dds:from_dds:rt/chatter")
+        );
+        assert_eq!(dds_entity_name("", "to_dds", "rt/chatter"), None);
+    }
+
+    #[test]
+    fn test_cyclonedds_network_config() {
+        let eth0 = DdsInterface::Name("eth0".into());
+        let addr = DdsInterface::Address([192, 168, 1, 10].into());
+        assert_eq!(cyclonedds_network_config(false, None, false), None);
+        assert_eq!(
+            cyclonedds_network_config(false, Some(&eth0), false).as_deref(),
+            Some(
+                r#"<CycloneDDS><Domain><General><Interfaces><NetworkInterface name="eth0"/></Interfaces></General></Domain></CycloneDDS>,"#
+            )
+        );
+        assert_eq!(
+            cyclonedds_network_config(false, Some(&addr), false).as_deref(),
+            Some(
+                r#"<CycloneDDS><Domain><General><Interfaces><NetworkInterface address="192.168.1.10"/></Interfaces></General></Domain></CycloneDDS>,"#
+            )
+        );
+        assert_eq!(
+            cyclonedds_network_config(false, None, true).as_deref(),
+            Some("<CycloneDDS><Domain><General><Transport>udp6</Transport></General></Domain></CycloneDDS>,")
+        );
+        assert_eq!(
+            cyclonedds_network_config(false, Some(&eth0), true).as_deref(),
+            Some(
+                r#"<CycloneDDS><Domain><General><Transport>udp6</Transport><Interfaces><NetworkInterface name="eth0"/></Interfaces></General></Domain></CycloneDDS>,"#
+            )
+        );
+        // localhost_only takes precedence over the interface
+        assert_eq!(
+            cyclonedds_network_config(true, Some(&eth0), false).as_deref(),
+            Some(
+                r#"<CycloneDDS><Domain><General><Interfaces><NetworkInterface address="127.0.0.1"/></Interfaces></General></Domain></CycloneDDS>,"#
+            )
+        );
+        assert_eq!(
+            cyclonedds_network_config(true, Some(&eth0), true).as_deref(),
+            Some(
+                r#"<CycloneDDS><Domain><General><Transport>udp6</Transport><Interfaces><NetworkInterface address="::1"/></Interfaces></General></Domain></CycloneDDS>,"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_resolve_route_qos() {
+        // a Writer with default QoS is served by a BEST_EFFORT Reader
+        let mut writer = Qos::default();
+        writer.transport_priority = Some(TransportPriority { value: 10 });
+        let qos = resolve_route_qos(RouteDirection::FromDds, false, &writer);
+        assert_eq!(
+            qos.reliability.map(|r| r.kind),
+            Some(ReliabilityKind::BEST_EFFORT)
+        );
+        assert!(qos.transport_priority.is_none());
+
+        // a TRANSIENT_LOCAL Reader is served by a Writer with a durability_service
+        let mut reader = Qos::default();
+        reader.durability = Some(Durability {
+            kind: DurabilityKind::TRANSIENT_LOCAL,
+        });
+        let qos = resolve_route_qos(RouteDirection::ToDds, false, &reader);
+        assert!(qos.durability_service.is_some());
+        assert!(matches!(
+            qos.ignore_local.map(|i| i.kind),
+            Some(IgnoreLocalKind::PARTICIPANT)
+        ));
+
+        // in "forward discovery" mode, the remote Writer's QoS is kept
+        let qos = resolve_route_qos(RouteDirection::ToDds, true, &writer);
+        assert_eq!(qos.transport_priority.map(|p| p.value), Some(10));
+    }
+}
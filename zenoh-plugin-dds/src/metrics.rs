@@ -0,0 +1,265 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The bridge-level metrics, maintained by the plugin alongside its route tables and served in the
+// Prometheus text exposition format on "http://<metrics_address>:<metrics_port>/metrics" (if metrics_port is configured).
+use async_std::io::{Read, ReadExt, WriteExt};
+use async_std::net::{TcpListener, TcpStream};
+use async_std::task::{self, JoinHandle};
+use std::fmt::Write;
+use std::io;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+const METRICS_PATH: &str = "/metrics";
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+// the max size of an HTTP request line (the longer ones are rejected without response)
+const MAX_REQUEST_LINE_SIZE: usize = 8 * 1024;
+
+#[derive(Default)]
+pub(crate) struct BridgeMetrics {
+    // the number of DDS Writers and Readers currently discovered
+    pub(crate) discovered_publications: AtomicU64,
+    pub(crate) discovered_subscriptions: AtomicU64,
+    // the number of routes currently established
+    pub(crate) routes_from_dds: AtomicU64,
+    pub(crate) routes_to_dds: AtomicU64,
+    // the number of samples routed since startup
+    pub(crate) samples_from_dds: AtomicU64,
+    pub(crate) samples_to_dds: AtomicU64,
+    // the number of DDS discovery events processed since startup
+    pub(crate) discovery_events: AtomicU64,
+    // the max number of DDS discovery events pending processing since startup
+    pub(crate) discovery_queue_high_water_mark: AtomicU64,
+}
+
+impl BridgeMetrics {
+    // Render the metrics in the Prometheus text exposition format
+    pub(crate) fn render(&self) -> String {
+        let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let mut s = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, values: &[(&str, u64)]| {
+            let _ = writeln!(s, "# HELP zenoh_bridge_dds_{name} {help}");
+            let _ = writeln!(s, "# TYPE zenoh_bridge_dds_{name} {kind}");
+            for (labels, value) in values {
+                let _ = writeln!(s, "zenoh_bridge_dds_{name}{labels} {value}");
+            }
+        };
+        metric(
+            "discovered_publications",
+            "gauge",
+            "The number of DDS Writers currently discovered.",
+            &[("", get(&self.discovered_publications))],
+        );
+        metric(
+            "discovered_subscriptions",
+            "gauge",
+            "The number of DDS Readers currently discovered.",
+            &[("", get(&self.discovered_subscriptions))],
+        );
+        metric(
+            "routes",
+            "gauge",
+            "The number of routes currently established.",
+            &[
+                ("{direction=\"from_dds\"}", get(&self.routes_from_dds)),
+                ("{direction=\"to_dds\"}", get(&self.routes_to_dds)),
+            ],
+        );
+        metric(
+            "routed_samples_total",
+            "counter",
+            "The number of samples routed since startup.",
+            &[
+                ("{direction=\"from_dds\"}", get(&self.samples_from_dds)),
+                ("{direction=\"to_dds\"}", get(&self.samples_to_dds)),
+            ],
+        );
+        metric(
+            "discovery_events_total",
+            "counter",
+            "The number of DDS discovery events processed since startup.",
+            &[("", get(&self.discovery_events))],
+        );
+        metric(
+            "discovery_queue_high_water_mark",
+            "gauge",
+            "The max number of DDS discovery events pending processing since startup.",
+            &[("", get(&self.discovery_queue_high_water_mark))],
+        );
+        s
+    }
+}
+
+// Build the HTTP response to a request, given its request line (e.g. "GET /metrics HTTP/1.1")
+fn http_response(request_line: &str, metrics: &BridgeMetrics) -> String {
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(METRICS_PATH)) => ("200 OK", METRICS_CONTENT_TYPE, metrics.render()),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method Not Allowed\n".to_string(),
+        ),
+    };
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+// Read the request line of an HTTP request (without its "\r\n"), whatever the number of reads it spans.
+// The rest of the request (its headers) is ignored.
+async fn read_request_line<R: Read + Unpin>(reader: &mut R) -> io::Result<String> {
+    let mut line = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            // the connection closed before the end of the line: take what was received
+            break;
+        }
+        line.extend_from_slice(&buf[..n]);
+        if let Some(end) = line.windows(2).position(|w| w == b"\r\n") {
+            line.truncate(end);
+            break;
+        }
+        if line.len() > MAX_REQUEST_LINE_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request line too long",
+            ));
+        }
+    }
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+async fn serve_connection(mut stream: TcpStream, metrics: Arc<BridgeMetrics>) {
+    let request_line = match read_request_line(&mut stream).await {
+        Ok(line) => line,
+        Err(e) => {
+            tracing::debug!("Metrics endpoint: failed to read request: {}", e);
+            return;
+        }
+    };
+    let response = http_response(&request_line, &metrics);
+    if let Err(e) = stream.write_all(response.as_bytes()).await {
+        tracing::debug!("Metrics endpoint: failed to write response: {}", e);
+    }
+}
+
+// Spawn the HTTP server exposing the metrics on the address and port
+pub(crate) fn spawn_metrics_server(
+    address: IpAddr,
+    port: u16,
+    metrics: Arc<BridgeMetrics>,
+) -> JoinHandle<()> {
+    task::spawn(async move {
+        let listener = match TcpListener::bind((address, port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to start the metrics endpoint on {}:{}: {}",
+                    address,
+                    port,
+                    e
+                );
+                return;
+            }
+        };
+        let socket_addr = match listener.local_addr() {
+            Ok(socket_addr) => socket_addr.to_string(),
+            Err(_) => format!("{address}:{port}"),
+        };
+        tracing::info!(
+            "Metrics endpoint available on http://{}{}",
+            socket_addr,
+            METRICS_PATH
+        );
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    task::spawn(serve_connection(stream, metrics.clone()));
+                }
+                Err(e) => tracing::warn!("Metrics endpoint: failed to accept connection: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{http_response, read_request_line, BridgeMetrics, MAX_REQUEST_LINE_SIZE};
+    use async_std::io::{Cursor, ReadExt};
+    use async_std::task;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = BridgeMetrics::default();
+        metrics.discovered_publications.store(3, Ordering::Relaxed);
+        metrics.routes_from_dds.store(2, Ordering::Relaxed);
+        metrics.samples_to_dds.store(42, Ordering::Relaxed);
+        metrics.discovery_events.store(7, Ordering::Relaxed);
+        metrics
+            .discovery_queue_high_water_mark
+            .store(5, Ordering::Relaxed);
+        let text = metrics.render();
+        assert!(text.contains("# TYPE zenoh_bridge_dds_discovered_publications gauge\n"));
+        assert!(text.contains("\nzenoh_bridge_dds_discovered_publications 3\n"));
+        assert!(text.contains("\nzenoh_bridge_dds_routes{direction=\"from_dds\"} 2\n"));
+        assert!(text.contains("\nzenoh_bridge_dds_routes{direction=\"to_dds\"} 0\n"));
+        assert!(text.contains("# TYPE zenoh_bridge_dds_routed_samples_total counter\n"));
+        assert!(text.contains("\nzenoh_bridge_dds_routed_samples_total{direction=\"to_dds\"} 42\n"));
+        assert!(text.contains("\nzenoh_bridge_dds_discovery_events_total 7\n"));
+        assert!(text.contains("\nzenoh_bridge_dds_discovery_queue_high_water_mark 5\n"));
+    }
+
+    #[test]
+    fn test_http_response() {
+        let metrics = BridgeMetrics::default();
+        let ok = http_response("GET /metrics HTTP/1.1", &metrics);
+        assert!(ok.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(ok.contains("Content-Type: text/plain; version=0.0.4\r\n"));
+        assert!(ok.ends_with(&metrics.render()));
+        assert!(http_response("GET / HTTP/1.1", &metrics).starts_with("HTTP/1.1 404 "));
+        assert!(http_response("POST /metrics HTTP/1.1", &metrics).starts_with("HTTP/1.1 405 "));
+        assert!(http_response("", &metrics).starts_with("HTTP/1.1 405 "));
+    }
+
+    #[test]
+    fn test_read_request_line() {
+        task::block_on(async {
+            // a request line split across several reads
+            let mut reader = Cursor::new(b"GET /met".to_vec())
+                .chain(Cursor::new(b"rics HT".to_vec()))
+                .chain(Cursor::new(b"TP/1.1\r\nHost: localhost\r\n\r\n".to_vec()));
+            assert_eq!(
+                read_request_line(&mut reader).await.unwrap(),
+                "GET /metrics HTTP/1.1"
+            );
+            // a connection closed before the end of the line
+            let mut reader = Cursor::new(b"GET /metrics".to_vec());
+            assert_eq!(
+                read_request_line(&mut reader).await.unwrap(),
+                "GET /metrics"
+            );
+            // a request line too long
+            let mut reader = Cursor::new(vec![b'a'; MAX_REQUEST_LINE_SIZE + 2048]);
+            assert!(read_request_line(&mut reader).await.is_err());
+        });
+    }
+}
@@ -0,0 +1,159 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The ordering of the writes of the samples routed to DDS per key (i.e. per instance): the samples of a key are
+// written one at a time, in the order zenoh delivered them, while the samples of distinct keys can be written
+// concurrently. A sample whose write is delayed (e.g. waiting for the DDS Writer) holds back the next samples
+// of its key only.
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+pub(crate) struct OrderedWrites<K, T> {
+    // the samples waiting for the write of a preceding sample of their key, indexed by key.
+    // A key is present as long as a sample of this key is being written.
+    queues: Mutex<HashMap<K, VecDeque<T>>>,
+}
+
+impl<K, T> Default for OrderedWrites<K, T> {
+    fn default() -> Self {
+        OrderedWrites {
+            queues: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K: Eq + Hash, T> OrderedWrites<K, T> {
+    // Submit a sample: return it if it can be written right away (no other sample of its key being written),
+    // otherwise it's queued, to be returned by next() once the preceding samples of its key are written
+    pub(crate) fn push(&self, key: K, sample: T) -> Option<T> {
+        let mut queues = self.queues.lock().unwrap();
+        match queues.get_mut(&key) {
+            Some(queue) => {
+                queue.push_back(sample);
+                None
+            }
+            None => {
+                queues.insert(key, VecDeque::new());
+                Some(sample)
+            }
+        }
+    }
+
+    // Return the next sample of a key to be written, once the previous one is written. If none is left, the
+    // writing of the key ends: the next submitted sample of this key is returned by push().
+    pub(crate) fn next<Q>(&self, key: &Q) -> Option<T>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mut queues = self.queues.lock().unwrap();
+        let next = queues.get_mut(key).and_then(|queue| queue.pop_front());
+        if next.is_none() {
+            queues.remove(key);
+        }
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OrderedWrites;
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ordered_writes() {
+        let writes = OrderedWrites::default();
+        let mut written = Vec::new();
+
+        // the write of "a1" is delayed: the next samples of "a" are queued...
+        let a1 = writes.push("a", "a1").unwrap();
+        assert_eq!(writes.push("a", "a2"), None);
+        // ... while the samples of "b" are written right away
+        written.push(writes.push("b", "b1").unwrap());
+        assert_eq!(writes.next(&"b"), None);
+        assert_eq!(writes.push("a", "a3"), None);
+        written.push(writes.push("b", "b2").unwrap());
+        assert_eq!(writes.next(&"b"), None);
+
+        // once "a1" is written, the queued samples of "a" are written in order
+        written.push(a1);
+        while let Some(sample) = writes.next(&"a") {
+            written.push(sample);
+        }
+        assert_eq!(written, vec!["b1", "b2", "a1", "a2", "a3"]);
+
+        // the writing of "a" ended: its next sample is written right away
+        assert_eq!(writes.push("a", "a4"), Some("a4"));
+    }
+
+    #[test]
+    fn test_ordered_writes_concurrency() {
+        const COUNT: usize = 200;
+        let writes = Arc::new(OrderedWrites::default());
+        let written: Arc<Mutex<HashMap<&str, Vec<usize>>>> = Arc::new(Mutex::new(HashMap::new()));
+
+        // the 1st write of "a" doesn't complete until a write of "b" completed
+        let (b_written_tx, b_written_rcv) = mpsc::channel::<()>();
+        let b_written_rcv = Arc::new(Mutex::new(Some(b_written_rcv)));
+
+        // the samples of 2 instances are interleaved, each sample returned by push() being written in its own
+        // thread, and the writes of "a" being slower than the ones of "b"
+        let mut threads = Vec::new();
+        for i in 0..COUNT {
+            for key in ["a", "b"] {
+                if let Some(sample) = writes.push(key, i) {
+                    let writes = writes.clone();
+                    let written = written.clone();
+                    let b_written_tx = b_written_tx.clone();
+                    let b_written_rcv = b_written_rcv.clone();
+                    threads.push(thread::spawn(move || {
+                        let delay = if key == "a" { 200 } else { 10 };
+                        let mut sample = Some(sample);
+                        while let Some(i) = sample {
+                            if key == "a" && i == 0 {
+                                let rcv = b_written_rcv.lock().unwrap().take().unwrap();
+                                assert!(
+                                    rcv.recv_timeout(Duration::from_secs(5)).is_ok(),
+                                    "no write of \"b\" completed while a write of \"a\" was in progress"
+                                );
+                            }
+                            thread::sleep(Duration::from_micros(delay));
+                            written.lock().unwrap().entry(key).or_default().push(i);
+                            if key == "b" {
+                                let _ = b_written_tx.send(());
+                            }
+                            sample = writes.next(&key);
+                        }
+                    }));
+                }
+            }
+        }
+        drop(b_written_tx);
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        // each instance's samples are written in order
+        let written = written.lock().unwrap();
+        let expected: Vec<usize> = (0..COUNT).collect();
+        assert_eq!(written["a"], expected);
+        assert_eq!(written["b"], expected);
+    }
+}
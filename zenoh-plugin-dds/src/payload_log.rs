@@ -0,0 +1,248 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The logging of the routed payloads to a dedicated file (see payload_log_file configuration), one line per
+// sample: "<unix time> <from_dds|to_dds> <DDS topic> <zenoh key> <hex payload>".
+// The routing only pushes the payloads in a bounded channel (dropping them if it's full, rather than stalling
+// the routing): they are written by a dedicated thread. The file is rotated when it exceeds its max size,
+// the previous one being renamed with a ".1" suffix.
+use flume::{Receiver, Sender, TrySendError};
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The max number of payloads waiting to be written
+const PAYLOAD_LOG_QUEUE_SIZE: usize = 4096;
+
+static PAYLOAD_LOG: OnceLock<PayloadLog> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PayloadDirection {
+    FromDds,
+    ToDds,
+}
+
+impl PayloadDirection {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PayloadDirection::FromDds => "from_dds",
+            PayloadDirection::ToDds => "to_dds",
+        }
+    }
+}
+
+struct PayloadRecord {
+    time: SystemTime,
+    direction: PayloadDirection,
+    topic_name: String,
+    key_expr: String,
+    payload: Vec<u8>,
+}
+
+struct PayloadLog {
+    tx: Sender<PayloadRecord>,
+    // the number of payloads dropped as the writer thread didn't keep up
+    dropped: AtomicU64,
+}
+
+// Open the payload log file and start its writer thread. Must be called once, at startup.
+pub(crate) fn init(path: &str, max_size: u64) -> Result<(), String> {
+    let writer = RotatingFile::open(PathBuf::from(path), max_size)
+        .map_err(|e| format!("failed to open payload log file '{path}': {e}"))?;
+    let (tx, rx) = flume::bounded(PAYLOAD_LOG_QUEUE_SIZE);
+    PAYLOAD_LOG
+        .set(PayloadLog {
+            tx,
+            dropped: AtomicU64::new(0),
+        })
+        .map_err(|_| "payload log file already initialized".to_string())?;
+    std::thread::Builder::new()
+        .name("dds-payload-log".into())
+        .spawn(move || run_writer(writer, rx))
+        .map_err(|e| format!("failed to start payload log writer: {e}"))?;
+    tracing::info!("Log the routed payloads to {}", path);
+    Ok(())
+}
+
+// Log a routed payload to the payload log file, returning false if it's not configured
+pub(crate) fn log(
+    direction: PayloadDirection,
+    topic_name: &str,
+    key_expr: &str,
+    payload: &[u8],
+) -> bool {
+    let log = match PAYLOAD_LOG.get() {
+        Some(log) => log,
+        None => return false,
+    };
+    let record = PayloadRecord {
+        time: SystemTime::now(),
+        direction,
+        topic_name: topic_name.into(),
+        key_expr: key_expr.into(),
+        payload: payload.to_vec(),
+    };
+    if let Err(TrySendError::Full(_)) = log.tx.try_send(record) {
+        // warn only once per series of drops, when the 1st is counted
+        if log.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+            tracing::warn!("Payload log file doesn't keep up with the routed samples: drop some of their payloads");
+        }
+    }
+    true
+}
+
+fn format_record(record: &PayloadRecord) -> String {
+    let time = record.time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut line = String::with_capacity(
+        record.topic_name.len() + record.key_expr.len() + 2 * record.payload.len() + 40,
+    );
+    let _ = writeln!(
+        line,
+        "{}.{:06} {} {} {} {}",
+        time.as_secs(),
+        time.subsec_micros(),
+        record.direction.as_str(),
+        record.topic_name,
+        record.key_expr,
+        hex::encode(record.payload.as_slice())
+    );
+    line
+}
+
+fn run_writer(mut writer: RotatingFile, rx: Receiver<PayloadRecord>) {
+    while let Ok(record) = rx.recv() {
+        if let Err(e) = writer.write(format_record(&record).as_bytes()) {
+            tracing::error!("Failed to write to payload log file: {}", e);
+        }
+        if rx.is_empty() {
+            if let Err(e) = writer.flush() {
+                tracing::error!("Failed to write to payload log file: {}", e);
+            }
+            if let Some(log) = PAYLOAD_LOG.get() {
+                let dropped = log.dropped.swap(0, Ordering::Relaxed);
+                if dropped > 0 {
+                    tracing::warn!(
+                        "{} payloads were dropped from the payload log file",
+                        dropped
+                    );
+                }
+            }
+        }
+    }
+}
+
+// A file renamed with a ".1" suffix when exceeding max_size, and re-created
+struct RotatingFile {
+    path: PathBuf,
+    max_size: u64,
+    size: u64,
+    file: BufWriter<File>,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_size: u64) -> std::io::Result<RotatingFile> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            max_size,
+            size,
+            file: BufWriter::new(file),
+        })
+    }
+
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush()?;
+        std::fs::rename(&self.path, rotated_path(&self.path))?;
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.file = BufWriter::new(file);
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_record, rotated_path, PayloadDirection, PayloadRecord, RotatingFile};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_format_record() {
+        let record = PayloadRecord {
+            time: UNIX_EPOCH + Duration::from_micros(1_700_000_000_123_456),
+            direction: PayloadDirection::FromDds,
+            topic_name: "rt/chatter".into(),
+            key_expr: "robot-1/rt/chatter".into(),
+            payload: vec![0x00, 0x01, 0x00, 0x00, 0xab],
+        };
+        assert_eq!(
+            format_record(&record),
+            "1700000000.123456 from_dds rt/chatter robot-1/rt/chatter 00010000ab\n"
+        );
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let path = std::env::temp_dir().join("zenoh-plugin-dds-test-payload-log.txt");
+        let rotated = rotated_path(&path);
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&rotated);
+
+        let mut file = RotatingFile::open(path.clone(), 10).unwrap();
+        file.write(b"123456\n").unwrap();
+        file.write(b"abc\n").unwrap();
+        file.flush().unwrap();
+        assert_eq!(std::fs::read_to_string(&rotated).unwrap(), "123456\n");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "abc\n");
+
+        // a line larger than max_size is written in a file of its own
+        file.write(b"0123456789abcdef\n").unwrap();
+        file.flush().unwrap();
+        assert_eq!(std::fs::read_to_string(&rotated).unwrap(), "abc\n");
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "0123456789abcdef\n"
+        );
+
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&rotated);
+    }
+}
@@ -11,7 +11,10 @@
 // Contributors:
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
-use cyclors::qos::{DurabilityKind, DurabilityService, History, Qos, Reliability, ReliabilityKind};
+use cyclors::qos::{
+    DestinationOrderKind, DurabilityKind, DurabilityService, History, HistoryKind, LivelinessKind,
+    OwnershipKind, Qos, Reliability, ReliabilityKind,
+};
 
 pub(crate) fn get_history_or_default(qos: &Qos) -> History {
     match &qos.history {
@@ -27,6 +30,44 @@ pub(crate) fn get_durability_service_or_default(qos: &Qos) -> DurabilityService
     }
 }
 
+// The size of the PublicationCache of a route from DDS, per the HISTORY and DURABILITY_SERVICE QoS of its DDS Reader
+// (i.e. of the DDS Writer it serves), and bounded by max_samples if set (see transient_local_max_samples configuration)
+pub(crate) fn publication_cache_history(
+    qos: &Qos,
+    keyless: bool,
+    max_samples: Option<usize>,
+) -> usize {
+    let history_qos = get_history_or_default(qos);
+    let durability_service_qos = get_durability_service_or_default(qos);
+    let history = match (history_qos.kind, history_qos.depth) {
+        (HistoryKind::KEEP_LAST, n) => {
+            if keyless {
+                // only 1 instance => history=n
+                n as usize
+            } else if durability_service_qos.max_instances == DDS_LENGTH_UNLIMITED {
+                // No limit! => history=MAX
+                usize::MAX
+            } else if durability_service_qos.max_instances > 0 {
+                // Compute cache size as history.depth * durability_service.max_instances
+                // This makes the assumption that the frequency of publication is the same for all instances...
+                // But as we have no way to have 1 cache per-instance, there is no other choice.
+                if let Some(m) = n.checked_mul(durability_service_qos.max_instances) {
+                    m as usize
+                } else {
+                    usize::MAX
+                }
+            } else {
+                n as usize
+            }
+        }
+        (HistoryKind::KEEP_ALL, _) => usize::MAX,
+    };
+    match max_samples {
+        Some(max_samples) => history.min(max_samples),
+        None => history,
+    }
+}
+
 pub(crate) fn partition_is_empty(partition: &Option<Vec<String>>) -> bool {
     partition
         .as_ref()
@@ -56,3 +97,417 @@ pub(crate) fn is_transient_local(qos: &Qos) -> bool {
         durability.kind == DurabilityKind::TRANSIENT_LOCAL
     })
 }
+
+pub(crate) fn is_coherent_access(qos: &Qos) -> bool {
+    qos.presentation
+        .as_ref()
+        .map_or(false, |presentation| presentation.coherent_access)
+}
+
+// The DDS Infinite duration (for unset deadline or liveliness lease duration)
+const DDS_INFINITE_DURATION: i64 = i64::MAX;
+
+// Return the LIFESPAN duration of a Writer's samples (in nanoseconds), or None if unset or infinite
+pub(crate) fn get_lifespan(qos: &Qos) -> Option<i64> {
+    qos.lifespan
+        .as_ref()
+        .map(|lifespan| lifespan.duration)
+        .filter(|duration| *duration != DDS_INFINITE_DURATION)
+}
+
+// Return the DEADLINE period of a DDS entity (in nanoseconds), or None if unset or infinite
+pub(crate) fn get_deadline(qos: &Qos) -> Option<i64> {
+    qos.deadline
+        .as_ref()
+        .map(|deadline| deadline.period)
+        .filter(|period| *period != DDS_INFINITE_DURATION)
+}
+
+// The DDS unlimited length (for unset resource limits)
+const DDS_LENGTH_UNLIMITED: i32 = -1;
+
+// The QoS policies that can be overridden per topic with the 'qos_overrides' configuration.
+// The others are either used by the bridge to identify and route the entities (partition, user_data...),
+// or would make the bridge's entities unable to match the ones they serve (ownership, liveliness...).
+pub(crate) const OVERRIDABLE_QOS_POLICIES: [&str; 7] = [
+    "reliability",
+    "durability",
+    "durability_service",
+    "history",
+    "deadline",
+    "lifespan",
+    "transport_priority",
+];
+
+// Override the policies of a QoS with the ones set in an override (see OVERRIDABLE_QOS_POLICIES)
+pub(crate) fn apply_qos_override(qos: &mut Qos, qos_override: &Qos) {
+    if qos_override.reliability.is_some() {
+        qos.reliability = qos_override.reliability.clone();
+    }
+    if qos_override.durability.is_some() {
+        qos.durability = qos_override.durability.clone();
+    }
+    if qos_override.durability_service.is_some() {
+        qos.durability_service = qos_override.durability_service.clone();
+    }
+    if qos_override.history.is_some() {
+        qos.history = qos_override.history.clone();
+    }
+    if qos_override.deadline.is_some() {
+        qos.deadline = qos_override.deadline.clone();
+    }
+    if qos_override.lifespan.is_some() {
+        qos.lifespan = qos_override.lifespan.clone();
+    }
+    if qos_override.transport_priority.is_some() {
+        qos.transport_priority = qos_override.transport_priority.clone();
+    }
+}
+
+// Check that a QoS received from a remote bridge (in forward discovery mode) is within sane bounds,
+// before creating a DDS entity with it. Return the description of the 1st invalid policy found.
+pub(crate) fn validate_qos(qos: &Qos) -> Result<(), String> {
+    if let Some(history) = &qos.history {
+        if history.kind == HistoryKind::KEEP_LAST && history.depth < 1 {
+            return Err(format!(
+                "history: invalid KEEP_LAST depth {}",
+                history.depth
+            ));
+        }
+    }
+    if let Some(ds) = &qos.durability_service {
+        if ds.history_kind == HistoryKind::KEEP_LAST && ds.history_depth < 1 {
+            return Err(format!(
+                "durability_service: invalid KEEP_LAST history depth {}",
+                ds.history_depth
+            ));
+        }
+        for (name, limit) in [
+            ("max_samples", ds.max_samples),
+            ("max_instances", ds.max_instances),
+            ("max_samples_per_instance", ds.max_samples_per_instance),
+        ] {
+            if limit < 1 && limit != DDS_LENGTH_UNLIMITED {
+                return Err(format!("durability_service: invalid {name} {limit}"));
+            }
+        }
+        if ds.service_cleanup_delay < 0 {
+            return Err(format!(
+                "durability_service: negative service_cleanup_delay {}ns",
+                ds.service_cleanup_delay
+            ));
+        }
+    }
+    for (name, duration) in [
+        ("deadline", qos.deadline.as_ref().map(|d| d.period)),
+        (
+            "liveliness",
+            qos.liveliness.as_ref().map(|l| l.lease_duration),
+        ),
+        (
+            "reliability",
+            qos.reliability.as_ref().map(|r| r.max_blocking_time),
+        ),
+        ("lifespan", qos.lifespan.as_ref().map(|l| l.duration)),
+    ] {
+        if let Some(d) = duration.filter(|d| *d < 0) {
+            return Err(format!("{name}: negative duration {d}ns"));
+        }
+    }
+    if let Some(name) = qos
+        .partition
+        .iter()
+        .flatten()
+        .find(|name| name.contains('\0'))
+    {
+        return Err(format!("partition: invalid name {name:?}"));
+    }
+    Ok(())
+}
+
+// Return the list of the conflicts between the QoS of an established route and the QoS the route would have
+// for another DDS entity on the same topic, on the policies changing the routing behaviour.
+pub(crate) fn get_qos_conflicts(route_qos: &Qos, qos: &Qos) -> Vec<String> {
+    let mut result = Vec::new();
+    let route_reliability = route_qos.reliability.as_ref().map(|r| &r.kind);
+    let reliability = qos.reliability.as_ref().map(|r| &r.kind);
+    if route_reliability != reliability {
+        result.push(format!(
+            "reliability: {route_reliability:?} for the route vs. {reliability:?}"
+        ));
+    }
+    let route_durability = route_qos.durability.as_ref().map(|d| &d.kind);
+    let durability = qos.durability.as_ref().map(|d| &d.kind);
+    if route_durability != durability {
+        result.push(format!(
+            "durability: {route_durability:?} for the route vs. {durability:?}"
+        ));
+    }
+    let route_history = route_qos.history.as_ref().map(|h| &h.kind);
+    let history = qos.history.as_ref().map(|h| &h.kind);
+    if route_history != history {
+        result.push(format!(
+            "history: {route_history:?} for the route vs. {history:?}"
+        ));
+    }
+    result
+}
+
+// A partition set to None or empty is the default partition ("")
+pub(crate) fn partitions_overlap(p1: &Option<Vec<String>>, p2: &Option<Vec<String>>) -> bool {
+    let default = vec![String::new()];
+    let p1 = p1.as_ref().filter(|p| !p.is_empty()).unwrap_or(&default);
+    let p2 = p2.as_ref().filter(|p| !p.is_empty()).unwrap_or(&default);
+    p1.iter().any(|name| p2.contains(name))
+}
+
+fn durability_rank(qos: &Qos) -> u8 {
+    match qos.durability.as_ref().map(|d| &d.kind) {
+        None | Some(DurabilityKind::VOLATILE) => 0,
+        Some(DurabilityKind::TRANSIENT_LOCAL) => 1,
+        Some(DurabilityKind::TRANSIENT) => 2,
+        Some(DurabilityKind::PERSISTENT) => 3,
+    }
+}
+
+fn liveliness_rank(qos: &Qos) -> u8 {
+    match qos.liveliness.as_ref().map(|l| &l.kind) {
+        None | Some(LivelinessKind::AUTOMATIC) => 0,
+        Some(LivelinessKind::MANUAL_BY_PARTICIPANT) => 1,
+        Some(LivelinessKind::MANUAL_BY_TOPIC) => 2,
+    }
+}
+
+fn destination_order_rank(qos: &Qos) -> u8 {
+    match qos.destination_order.as_ref().map(|d| &d.kind) {
+        None | Some(DestinationOrderKind::BY_RECEPTION_TIMESTAMP) => 0,
+        Some(DestinationOrderKind::BY_SOURCE_TIMESTAMP) => 1,
+    }
+}
+
+// Return the list of the incompatibilities between the QoS offered by a Writer and the QoS requested
+// by a Reader, according to the DDS "Requested/Offered" rules. An empty list means they can match.
+pub(crate) fn get_qos_incompatibilities(writer_qos: &Qos, reader_qos: &Qos) -> Vec<String> {
+    let mut result = Vec::new();
+    if !is_writer_reliable(&writer_qos.reliability) && is_reader_reliable(&reader_qos.reliability) {
+        result.push("reliability: BEST_EFFORT Writer vs. RELIABLE Reader".into());
+    }
+    if durability_rank(writer_qos) < durability_rank(reader_qos) {
+        result.push(format!(
+            "durability: {:?} Writer vs. {:?} Reader",
+            writer_qos.durability.as_ref().map(|d| &d.kind),
+            reader_qos.durability.as_ref().map(|d| &d.kind)
+        ));
+    }
+    let writer_deadline = writer_qos
+        .deadline
+        .as_ref()
+        .map_or(DDS_INFINITE_DURATION, |d| d.period);
+    let reader_deadline = reader_qos
+        .deadline
+        .as_ref()
+        .map_or(DDS_INFINITE_DURATION, |d| d.period);
+    if writer_deadline > reader_deadline {
+        result.push(format!(
+            "deadline: Writer period {}ns > Reader period {}ns",
+            writer_deadline, reader_deadline
+        ));
+    }
+    let writer_lease = writer_qos
+        .liveliness
+        .as_ref()
+        .map_or(DDS_INFINITE_DURATION, |l| l.lease_duration);
+    let reader_lease = reader_qos
+        .liveliness
+        .as_ref()
+        .map_or(DDS_INFINITE_DURATION, |l| l.lease_duration);
+    if liveliness_rank(writer_qos) < liveliness_rank(reader_qos) || writer_lease > reader_lease {
+        result.push(format!(
+            "liveliness: Writer {:?} (lease {}ns) vs. Reader {:?} (lease {}ns)",
+            writer_qos.liveliness.as_ref().map(|l| &l.kind),
+            writer_lease,
+            reader_qos.liveliness.as_ref().map(|l| &l.kind),
+            reader_lease
+        ));
+    }
+    let writer_ownership = writer_qos.ownership.as_ref().map(|o| &o.kind);
+    let reader_ownership = reader_qos.ownership.as_ref().map(|o| &o.kind);
+    if writer_ownership.unwrap_or(&OwnershipKind::SHARED)
+        != reader_ownership.unwrap_or(&OwnershipKind::SHARED)
+    {
+        result.push(format!(
+            "ownership: {:?} Writer vs. {:?} Reader",
+            writer_ownership, reader_ownership
+        ));
+    }
+    if destination_order_rank(writer_qos) < destination_order_rank(reader_qos) {
+        result.push(
+            "destination_order: BY_RECEPTION_TIMESTAMP Writer vs. BY_SOURCE_TIMESTAMP Reader"
+                .into(),
+        );
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        apply_qos_override, get_qos_incompatibilities, partitions_overlap,
+        publication_cache_history, validate_qos, DDS_LENGTH_UNLIMITED,
+    };
+    use cyclors::qos::{
+        Durability, DurabilityKind, DurabilityService, History, HistoryKind, Qos, Reliability,
+        ReliabilityKind,
+    };
+
+    #[test]
+    fn test_qos_incompatibilities() {
+        let default = Qos::default();
+        assert!(get_qos_incompatibilities(&default, &default).is_empty());
+
+        let mut writer = Qos::default();
+        writer.reliability = Some(Reliability {
+            kind: ReliabilityKind::BEST_EFFORT,
+            max_blocking_time: 0,
+        });
+        let mut reader = Qos::default();
+        reader.reliability = Some(Reliability {
+            kind: ReliabilityKind::RELIABLE,
+            max_blocking_time: 0,
+        });
+        reader.durability = Some(Durability {
+            kind: DurabilityKind::TRANSIENT_LOCAL,
+        });
+        let incompatibilities = get_qos_incompatibilities(&writer, &reader);
+        assert_eq!(incompatibilities.len(), 2);
+        assert!(incompatibilities[0].starts_with("reliability"));
+        assert!(incompatibilities[1].starts_with("durability"));
+
+        // a Writer offering more than requested is compatible
+        assert!(get_qos_incompatibilities(&reader, &writer).is_empty());
+    }
+
+    #[test]
+    fn test_validate_qos() {
+        assert!(validate_qos(&Qos::default()).is_ok());
+
+        let mut qos = Qos::default();
+        qos.history = Some(History {
+            kind: HistoryKind::KEEP_LAST,
+            depth: 0,
+        });
+        assert!(validate_qos(&qos).unwrap_err().starts_with("history"));
+        qos.history = Some(History {
+            kind: HistoryKind::KEEP_ALL,
+            depth: 0,
+        });
+        assert!(validate_qos(&qos).is_ok());
+
+        let mut qos = Qos::default();
+        qos.reliability = Some(Reliability {
+            kind: ReliabilityKind::RELIABLE,
+            max_blocking_time: -1,
+        });
+        assert!(validate_qos(&qos).unwrap_err().starts_with("reliability"));
+
+        let mut qos = Qos::default();
+        qos.partition = Some(vec!["a".into(), "b\0c".into()]);
+        assert!(validate_qos(&qos).unwrap_err().starts_with("partition"));
+    }
+
+    #[test]
+    fn test_partitions_overlap() {
+        assert!(partitions_overlap(&None, &Some(vec![])));
+        assert!(partitions_overlap(&None, &Some(vec!["".into()])));
+        assert!(!partitions_overlap(&None, &Some(vec!["a".into()])));
+        assert!(partitions_overlap(
+            &Some(vec!["a".into(), "b".into()]),
+            &Some(vec!["b".into()])
+        ));
+    }
+
+    #[test]
+    fn test_apply_qos_override() {
+        let mut qos = Qos {
+            reliability: Some(Reliability {
+                kind: ReliabilityKind::BEST_EFFORT,
+                max_blocking_time: 0,
+            }),
+            history: Some(History {
+                kind: HistoryKind::KEEP_LAST,
+                depth: 5,
+            }),
+            partition: Some(vec!["P1".to_string()]),
+            ..Default::default()
+        };
+        let qos_override = Qos {
+            durability: Some(Durability {
+                kind: DurabilityKind::TRANSIENT_LOCAL,
+            }),
+            history: Some(History {
+                kind: HistoryKind::KEEP_ALL,
+                depth: 1,
+            }),
+            partition: Some(vec!["P2".to_string()]),
+            ..Default::default()
+        };
+        apply_qos_override(&mut qos, &qos_override);
+        // the policies unset in the override are kept
+        assert_eq!(
+            qos.reliability.as_ref().map(|r| &r.kind),
+            Some(&ReliabilityKind::BEST_EFFORT)
+        );
+        assert_eq!(
+            qos.durability.as_ref().map(|d| &d.kind),
+            Some(&DurabilityKind::TRANSIENT_LOCAL)
+        );
+        assert_eq!(
+            qos.history.as_ref().map(|h| &h.kind),
+            Some(&HistoryKind::KEEP_ALL)
+        );
+        // the non-overridable policies are ignored
+        assert_eq!(qos.partition, Some(vec!["P1".to_string()]));
+    }
+
+    #[test]
+    fn test_publication_cache_history() {
+        let mut qos = Qos::default();
+        qos.durability = Some(Durability {
+            kind: DurabilityKind::TRANSIENT_LOCAL,
+        });
+        qos.history = Some(History {
+            kind: HistoryKind::KEEP_ALL,
+            depth: 0,
+        });
+        // a KEEP_ALL DDS Writer is fully cached, unless bounded
+        assert_eq!(publication_cache_history(&qos, true, None), usize::MAX);
+        assert_eq!(publication_cache_history(&qos, false, None), usize::MAX);
+        assert_eq!(publication_cache_history(&qos, true, Some(100)), 100);
+        assert_eq!(publication_cache_history(&qos, false, Some(100)), 100);
+
+        // a KEEP_LAST DDS Writer caches depth samples per instance, for at most max_instances
+        qos.history = Some(History {
+            kind: HistoryKind::KEEP_LAST,
+            depth: 10,
+        });
+        let mut durability_service = DurabilityService {
+            service_cleanup_delay: 0,
+            history_kind: HistoryKind::KEEP_LAST,
+            history_depth: 10,
+            max_samples: DDS_LENGTH_UNLIMITED,
+            max_instances: DDS_LENGTH_UNLIMITED,
+            max_samples_per_instance: DDS_LENGTH_UNLIMITED,
+        };
+        qos.durability_service = Some(durability_service.clone());
+        assert_eq!(publication_cache_history(&qos, true, None), 10);
+        assert_eq!(publication_cache_history(&qos, true, Some(100)), 10);
+        assert_eq!(publication_cache_history(&qos, true, Some(5)), 5);
+        assert_eq!(publication_cache_history(&qos, false, None), usize::MAX);
+        assert_eq!(publication_cache_history(&qos, false, Some(100)), 100);
+        durability_service.max_instances = 4;
+        qos.durability_service = Some(durability_service);
+        assert_eq!(publication_cache_history(&qos, false, None), 40);
+        assert_eq!(publication_cache_history(&qos, false, Some(100)), 40);
+        assert_eq!(publication_cache_history(&qos, false, Some(25)), 25);
+    }
+}
@@ -0,0 +1,152 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// A token bucket limiting the rate of an operation (e.g. the creation of routes) to at most
+// "capacity" operations per period. The bucket is refilled at the start of each period.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    capacity: u32,
+    period: Duration,
+    tokens: u32,
+    period_start: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(capacity: u32, period: Duration) -> TokenBucket {
+        let capacity = capacity.max(1);
+        TokenBucket {
+            capacity,
+            period,
+            tokens: capacity,
+            period_start: Instant::now(),
+        }
+    }
+
+    // Take a token if available at the time "now", or return the delay until the next refill
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.period_start);
+        if elapsed >= self.period {
+            self.tokens = self.capacity;
+            self.period_start = now;
+        }
+        if self.tokens > 0 {
+            self.tokens -= 1;
+            Ok(())
+        } else {
+            Err(self.period - elapsed)
+        }
+    }
+
+    // Take a token if available, returning false otherwise (see refill_delay())
+    pub(crate) fn try_acquire(&mut self) -> bool {
+        self.try_take(Instant::now()).is_ok()
+    }
+
+    // The delay until the next refill
+    pub(crate) fn refill_delay(&self) -> Duration {
+        self.period
+            .saturating_sub(Instant::now().saturating_duration_since(self.period_start))
+    }
+}
+
+// A gate letting pass only the first operation per period for each instance (e.g. the first sample of each
+// DDS instance with the "first" downsampling strategy), the period starting with the operation that passed.
+#[derive(Debug)]
+pub(crate) struct IntervalGate {
+    period: Duration,
+    last_passed: HashMap<u64, Instant>,
+}
+
+impl IntervalGate {
+    pub(crate) fn new(period: Duration) -> IntervalGate {
+        IntervalGate {
+            period,
+            last_passed: HashMap::new(),
+        }
+    }
+
+    // Return true if the operation on instance at the time "now" passes, i.e. if none passed within the period
+    pub(crate) fn try_pass(&mut self, instance: u64, now: Instant) -> bool {
+        match self.last_passed.get(&instance) {
+            Some(last) if now.saturating_duration_since(*last) < self.period => false,
+            _ => {
+                self.last_passed.insert(instance, now);
+                true
+            }
+        }
+    }
+
+    // Forget an instance (e.g. disposed or unregistered), its next operation passing
+    pub(crate) fn remove(&mut self, instance: u64) {
+        self.last_passed.remove(&instance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntervalGate, TokenBucket};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_token_bucket() {
+        let period = Duration::from_millis(100);
+        let mut bucket = TokenBucket::new(2, period);
+        let start = bucket.period_start;
+
+        assert!(bucket.try_take(start).is_ok());
+        assert!(bucket.try_take(start + Duration::from_millis(10)).is_ok());
+        assert_eq!(
+            bucket.try_take(start + Duration::from_millis(40)),
+            Err(Duration::from_millis(60))
+        );
+        // refilled at the next period
+        let next = start + period;
+        assert!(bucket.try_take(next).is_ok());
+        assert!(bucket.try_take(next).is_ok());
+        assert!(bucket.try_take(next).is_err());
+
+        // a capacity of 0 is considered as 1
+        let mut bucket = TokenBucket::new(0, period);
+        let now = Instant::now();
+        assert!(bucket.try_take(now).is_ok());
+        assert!(bucket.try_take(now).is_err());
+    }
+
+    #[test]
+    fn test_interval_gate() {
+        let period = Duration::from_millis(100);
+        let mut gate = IntervalGate::new(period);
+        let start = Instant::now();
+
+        // the first sample of each interval passes, the next ones are dropped
+        assert!(gate.try_pass(1, start));
+        assert!(!gate.try_pass(1, start + Duration::from_millis(10)));
+        assert!(!gate.try_pass(1, start + Duration::from_millis(99)));
+        // the instances are gated independently
+        assert!(gate.try_pass(2, start + Duration::from_millis(50)));
+        // the next interval starts with the first sample passing after the period
+        assert!(gate.try_pass(1, start + Duration::from_millis(120)));
+        assert!(!gate.try_pass(1, start + Duration::from_millis(200)));
+        assert!(!gate.try_pass(2, start + Duration::from_millis(140)));
+        assert!(gate.try_pass(2, start + Duration::from_millis(150)));
+
+        // a removed instance is forgotten
+        gate.remove(2);
+        assert!(!gate.last_passed.contains_key(&2));
+        assert!(gate.try_pass(2, start + Duration::from_millis(160)));
+    }
+}
@@ -12,26 +12,38 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use cyclors::qos::{HistoryKind, Qos};
-use cyclors::{dds_entity_t, DDS_LENGTH_UNLIMITED};
-use serde::Serialize;
+use cyclors::dds_entity_t;
+use cyclors::qos::{EntityName, Qos};
+use serde::{Serialize, Serializer};
+use std::sync::Arc;
 use std::{collections::HashSet, fmt};
+use zenoh::liveliness::LivelinessToken;
 use zenoh::prelude::r#async::AsyncResolve;
 use zenoh::prelude::*;
 use zenoh_ext::{PublicationCache, SessionExt};
 
-use crate::{dds_mgt::*, qos_helpers::*, DdsPluginRuntime, KE_PREFIX_PUB_CACHE};
+use crate::cdr_json::cdr_to_json;
+use crate::config::{
+    congestion_control_to_str, priority_to_str, DeliveryMode, DownsamplingStrategy, SetupOrder,
+};
+use crate::routing::KeyFilter;
+use crate::sample_hook::{get_sample_hook, FieldsLayout};
+use crate::sla::{spawn_sla_evaluation, SlaMonitor};
+use crate::stats_state::RouteStats;
+use crate::{dds_mgt::*, qos_helpers::*, DdsPluginRuntime, KE_ANY_1_SEGMENT, KE_PREFIX_PUB_CACHE};
 
 enum ZPublisher<'a> {
     Publisher(KeyExpr<'a>),
-    PublicationCache(PublicationCache<'a>),
+    // the PublicationCache, and the one caching the publications on the instances' sub-keys
+    // (if keyed_instance_routing is configured for the route)
+    PublicationCache(PublicationCache<'a>, Option<PublicationCache<'a>>),
 }
 
 impl ZPublisher<'_> {
     fn key_expr(&self) -> &KeyExpr<'_> {
         match self {
             ZPublisher::Publisher(k) => k,
-            ZPublisher::PublicationCache(p) => p.key_expr(),
+            ZPublisher::PublicationCache(p, _) => p.key_expr(),
         }
     }
 }
@@ -49,6 +61,27 @@ pub(crate) struct RouteDDSZenoh<'a> {
     topic_type: String,
     // is DDS topic keyess
     keyless: bool,
+    // the zenoh priority of the re-publications (see priorities and transport_priority_mapping)
+    #[serde(serialize_with = "serialize_priority")]
+    priority: Priority,
+    // the congestion control of the publications to zenoh
+    #[serde(serialize_with = "serialize_congestion_control")]
+    congestion_control: CongestionControl,
+    // if the re-publications are sent with the zenoh express flag (see express configuration)
+    express: bool,
+    // if each instance is re-published on its own "<keyexpr>/<instance-keyhash>" sub-key
+    // (see keyed_instance_routing configuration)
+    instance_routing: bool,
+    // the QoS of the local DDS Reader
+    #[serde(skip)]
+    reader_qos: Qos,
+    // the DEADLINE period requested by the DDS Reader, in nanoseconds (if any)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deadline: Option<i64>,
+    // the serialization instructions of the topic type, to decode the last routed sample
+    // (only if last_sample_preview is configured for the route, and the type information is known)
+    #[serde(skip)]
+    type_ops: Option<Vec<u32>>,
     // the zenoh publisher used to re-publish to zenoh the data received by the DDS Reader
     #[serde(skip)]
     zenoh_publisher: ZPublisher<'a>,
@@ -56,6 +89,33 @@ pub(crate) struct RouteDDSZenoh<'a> {
     remote_routed_readers: HashSet<OwnedKeyExpr>,
     // the list of local readers served by this route (entity keys)
     local_routed_writers: HashSet<String>,
+    // true if the route was pre-created from the startup manifest (it's then never removed)
+    from_manifest: bool,
+    // true if some DDS Writers on the topic disagree with the route on keyless-ness
+    keyless_conflict: bool,
+    // the counters of the data routing
+    #[serde(flatten)]
+    counters: ForwardingCounters,
+    // the liveliness token declared while the DDS Reader is matched (if configured)
+    #[serde(skip)]
+    matched_liveliness: Option<Arc<MatchedLiveliness>>,
+    // the liveliness token advertising the route as long as it exists (if configured)
+    #[serde(skip)]
+    _route_liveliness: Option<LivelinessToken<'a>>,
+}
+
+fn serialize_priority<S>(priority: &Priority, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    s.serialize_str(priority_to_str(*priority))
+}
+
+fn serialize_congestion_control<S>(cc: &CongestionControl, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    s.serialize_str(congestion_control_to_str(*cc))
 }
 
 impl Drop for RouteDDSZenoh<'_> {
@@ -63,6 +123,9 @@ impl Drop for RouteDDSZenoh<'_> {
         if let Err(e) = delete_dds_entity(self.dds_reader) {
             tracing::warn!("{}: error deleting DDS Reader:  {}", self, e);
         }
+        if let Some(matched) = &self.matched_liveliness {
+            matched.clear();
+        }
     }
 }
 
@@ -88,7 +151,9 @@ impl RouteDDSZenoh<'_> {
         reader_qos: Qos,
         ke: OwnedKeyExpr,
         congestion_ctrl: CongestionControl,
-    ) -> Result<RouteDDSZenoh<'a>, String> {
+        priority: Priority,
+        lifespan: Option<i64>,
+    ) -> Result<RouteDDSZenoh<'a>, RouteError> {
         tracing::debug!(
             "Route DDS->Zenoh ({} -> {}): creation with topic_type={}",
             topic_name,
@@ -106,98 +171,230 @@ impl RouteDDSZenoh<'_> {
                 format!("Route Zenoh->DDS ({topic_name} -> {ke}): failed to declare KeyExpr: {e}")
             })?;
 
-        // declare the zenoh Publisher
-        let zenoh_publisher: ZPublisher<'a> = if is_transient_local(&reader_qos) {
-            #[allow(non_upper_case_globals)]
-            let history_qos = get_history_or_default(&reader_qos);
-            let durability_service_qos = get_durability_service_or_default(&reader_qos);
-            let history = match (history_qos.kind, history_qos.depth) {
-                (HistoryKind::KEEP_LAST, n) => {
-                    if keyless {
-                        // only 1 instance => history=n
-                        n as usize
-                    } else if durability_service_qos.max_instances == DDS_LENGTH_UNLIMITED {
-                        // No limit! => history=MAX
-                        usize::MAX
-                    } else if durability_service_qos.max_instances > 0 {
-                        // Compute cache size as history.depth * durability_service.max_instances
-                        // This makes the assumption that the frequency of publication is the same for all instances...
-                        // But as we have no way to have 1 cache per-instance, there is no other choice.
-                        if let Some(m) = n.checked_mul(durability_service_qos.max_instances) {
-                            m as usize
-                        } else {
-                            usize::MAX
-                        }
-                    } else {
-                        n as usize
-                    }
-                }
-                (HistoryKind::KEEP_ALL, _) => usize::MAX,
-            };
-            tracing::debug!(
-                "Caching publications for TRANSIENT_LOCAL Writer on resource {} with history {} (Writer uses {:?} and DurabilityService.max_instances={})",
-                ke, history, reader_qos.history, durability_service_qos.max_instances
+        let downsampling = plugin.get_downsampling(&ke);
+        // express publications bypass the zenoh batching: the batching of the samples would defeat their purpose
+        let express = plugin.is_express(&ke);
+        // each instance being routed on its own sub-key, the samples of distinct instances can't be batched together
+        let instance_routing = plugin.is_keyed_instance_routed(&ke, keyless);
+        let batch = match plugin.get_batch(&ke) {
+            Some(_) if express => {
+                tracing::debug!(
+                    "Route DDS->Zenoh ({topic_name} -> {ke}): published with express flag - ignore 'batch' configuration"
+                );
+                None
+            }
+            Some(_) if instance_routing => {
+                tracing::debug!(
+                    "Route DDS->Zenoh ({topic_name} -> {ke}): instances routed on sub-keys - ignore 'batch' configuration"
+                );
+                None
+            }
+            batch => batch,
+        };
+        let payload_filter = plugin.get_payload_filter(&ke);
+        let content_filter = plugin.get_content_filter(&ke);
+        let profile = plugin.get_forwarding_profile(&ke);
+        let put_timeout = plugin.get_put_timeout(&ke);
+        // with a put timeout, the samples received by the DDS listener are queued as with async_forward, so that the
+        // blocking puts are made in order by the route's forwarding thread, and the samples pending for longer than
+        // the timeout are dropped from the queue (the "latest" downsampling task applies the timeout on its own)
+        let is_put_timed = put_timeout.is_some()
+            && congestion_ctrl == CongestionControl::Block
+            && !matches!(
+                downsampling,
+                Some(Downsampling {
+                    strategy: DownsamplingStrategy::Latest,
+                    ..
+                })
             );
-            let pub_cache = plugin
-                .zsession
-                .declare_publication_cache(&declared_ke)
-                .history(history)
-                .queryable_prefix(*KE_PREFIX_PUB_CACHE / &plugin.member_id)
-                .queryable_allowed_origin(Locality::Remote) // Note: don't reply to queries from local QueryingSubscribers
-                .res()
-                .await
-                .map_err(|e| {
-                    format!("Failed create PublicationCache for key {ke} (rid={declared_ke}): {e}")
-                })?;
-            ZPublisher::PublicationCache(pub_cache)
-        } else {
-            if let Err(e) = plugin
-                .zsession
-                .declare_publisher(declared_ke.clone())
-                .res()
-                .await
-            {
-                tracing::warn!(
-                    "Failed to declare publisher for key {} (rid={}): {}",
-                    ke,
-                    declared_ke,
-                    e
+        let async_forward = plugin
+            .get_async_forward(&topic_name)
+            .or_else(|| is_put_timed.then(|| plugin.async_forward_settings()));
+        let compression = plugin.get_compression(&topic_name);
+        let sla_monitor = match plugin.get_sla_thresholds(&ke) {
+            Some(thresholds) => {
+                let monitor = Arc::new(SlaMonitor::default());
+                spawn_sla_evaluation(
+                    Arc::downgrade(&monitor),
+                    thresholds.clone(),
+                    plugin.config.sla_check_period,
+                    plugin.zsession.clone(),
+                    plugin.get_sla_events_keyexpr(&ke),
+                    format!("Route DDS->Zenoh ({topic_name} -> {ke})"),
                 );
+                Some(monitor)
+            }
+            None => None,
+        };
+        let matched_liveliness = plugin.get_matched_liveliness("from_dds", &ke);
+        let deadline_missed_ke = plugin.get_deadline_missed_keyexpr(&ke);
+        let historical_data_timeout = plugin.config.historical_data_timeout;
+
+        // the type information is decoded to preview the last sample, and to decode the fields of the samples given
+        // to the sample hook (if registered at the route creation)
+        let sample_hook = get_sample_hook().is_some();
+        let type_ops = match type_info {
+            Some(type_info)
+                if sample_hook || plugin.get_last_sample_preview_max_bytes(&ke).is_some() =>
+            {
+                match get_type_ops(plugin.dp, type_info) {
+                    Ok(ops) => Some(ops),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Route DDS->Zenoh ({topic_name} -> {ke}): the last sample won't be decodable, nor the fields of the samples given to the sample hook: {e}"
+                        );
+                        None
+                    }
+                }
             }
-            ZPublisher::Publisher(declared_ke.clone())
+            _ => None,
         };
+        let counters = ForwardingCounters::new(
+            sla_monitor,
+            plugin.get_last_sample_preview_max_bytes(&ke),
+            type_ops.is_some(),
+            plugin.is_coalescing_writers(&ke),
+            plugin.metrics.clone(),
+        );
 
-        let read_period = plugin.get_read_period(&ke);
+        let hook_context = HookContext {
+            fields_layout: type_ops
+                .as_deref()
+                .filter(|_| sample_hook)
+                .and_then(FieldsLayout::from_type_ops),
+            remap_filter: KeyFilter::new(&plugin.config),
+        };
 
-        // create matching DDS Writer that forwards data coming from zenoh
-        let dds_reader = create_forwarding_dds_reader(
-            plugin.dp,
-            topic_name.clone(),
-            topic_type.clone(),
-            type_info,
-            keyless,
-            reader_qos,
-            declared_ke,
-            plugin.zsession.clone(),
-            read_period,
-            congestion_ctrl,
-        )?;
+        // the QoS of the DDS Reader, with the configured override (if any). The route keeps the non-overridden
+        // QoS, to be compared with the one of other DDS Writers on the same topic.
+        let mut dds_reader_qos = reader_qos.clone();
+        if let Some(qos_override) = plugin.get_qos_override(&ke) {
+            apply_qos_override(&mut dds_reader_qos, &qos_override);
+        }
+        // name the DDS Reader, to identify it in the DDS tools
+        dds_reader_qos.entity_name = plugin
+            .get_entity_name("from_dds", &topic_name)
+            .map(|name| EntityName { name });
+
+        // create matching DDS Reader that forwards data coming from DDS to zenoh
+        let create_dds_reader = || {
+            create_forwarding_dds_reader(
+                plugin.dp,
+                topic_name.clone(),
+                topic_type.clone(),
+                type_info,
+                keyless,
+                dds_reader_qos.clone(),
+                declared_ke.clone(),
+                plugin.zsession.clone(),
+                downsampling,
+                batch,
+                congestion_ctrl,
+                priority,
+                express,
+                instance_routing,
+                async_forward,
+                compression,
+                put_timeout,
+                plugin.config.preserve_source_timestamp,
+                lifespan,
+                payload_filter,
+                content_filter,
+                hook_context,
+                profile,
+                counters.clone(),
+                matched_liveliness.clone(),
+                deadline_missed_ke.clone(),
+                historical_data_timeout,
+            )
+        };
+
+        // create the zenoh and DDS entities in the configured order, waiting for the settle delay in between
+        let settle_delay = plugin.config.route_setup.settle_delay;
+        let (zenoh_publisher, dds_reader) = match plugin.config.route_setup.from_dds {
+            SetupOrder::ZenohFirst => {
+                let zenoh_publisher = declare_zenoh_publisher(
+                    plugin,
+                    &ke,
+                    &declared_ke,
+                    &dds_reader_qos,
+                    keyless,
+                    instance_routing,
+                )
+                .await?;
+                if !settle_delay.is_zero() {
+                    async_std::task::sleep(settle_delay).await;
+                }
+                (zenoh_publisher, create_dds_reader()?)
+            }
+            SetupOrder::DdsFirst => {
+                let dds_reader = create_dds_reader()?;
+                if !settle_delay.is_zero() {
+                    async_std::task::sleep(settle_delay).await;
+                }
+                match declare_zenoh_publisher(
+                    plugin,
+                    &ke,
+                    &declared_ke,
+                    &dds_reader_qos,
+                    keyless,
+                    instance_routing,
+                )
+                .await
+                {
+                    Ok(zenoh_publisher) => (zenoh_publisher, dds_reader),
+                    Err(e) => {
+                        if let Err(e) = delete_dds_entity(dds_reader) {
+                            tracing::warn!(
+                                "Route DDS->Zenoh ({topic_name} -> {ke}): error deleting DDS Reader: {e}"
+                            );
+                        }
+                        return Err(e.into());
+                    }
+                }
+            }
+        };
+        let route_liveliness = plugin.declare_route_liveliness("from_dds", &ke).await;
 
         Ok(RouteDDSZenoh {
             dds_reader,
             topic_name,
             topic_type,
             keyless,
+            priority,
+            congestion_control: congestion_ctrl,
+            express,
+            instance_routing,
+            deadline: get_deadline(&dds_reader_qos),
+            reader_qos,
+            type_ops,
             zenoh_publisher,
             remote_routed_readers: HashSet::new(),
             local_routed_writers: HashSet::new(),
+            from_manifest: false,
+            keyless_conflict: false,
+            counters,
+            matched_liveliness,
+            _route_liveliness: route_liveliness,
         })
     }
 
-    pub(crate) fn dds_reader_guid(&self) -> Result<String, String> {
+    pub(crate) fn topic_name(&self) -> &str {
+        &self.topic_name
+    }
+
+    pub(crate) fn topic_type(&self) -> &str {
+        &self.topic_type
+    }
+
+    pub(crate) fn dds_reader_guid(&self) -> Result<String, DdsError> {
         get_guid(&self.dds_reader)
     }
 
+    pub(crate) fn dds_reader_qos(&self) -> &Qos {
+        &self.reader_qos
+    }
+
     pub(crate) fn add_remote_routed_reader(&mut self, admin_ke: OwnedKeyExpr) {
         self.remote_routed_readers.insert(admin_ke);
     }
@@ -227,9 +424,153 @@ impl RouteDDSZenoh<'_> {
 
     pub(crate) fn remove_local_routed_writer(&mut self, entity_key: &str) {
         self.local_routed_writers.remove(entity_key);
+        self.release_selected_writer(entity_key);
+    }
+
+    // If coalescing the DDS Writers, and the Writer is the selected one, select another one
+    pub(crate) fn release_selected_writer(&self, entity_key: &str) {
+        if self.counters.release_selected_writer(entity_key) {
+            tracing::info!(
+                "{}: selected DDS Writer {} is gone - failover to the next Writer",
+                self,
+                entity_key
+            );
+        }
     }
 
     pub(crate) fn has_local_routed_writer(&self) -> bool {
         !self.local_routed_writers.is_empty()
     }
+
+    pub(crate) fn is_keyless(&self) -> bool {
+        self.keyless
+    }
+
+    pub(crate) fn set_keyless_conflict(&mut self) {
+        self.keyless_conflict = true;
+    }
+
+    pub(crate) fn has_keyless_conflict(&self) -> bool {
+        self.keyless_conflict
+    }
+
+    // Take the routed entities of this route, to be transferred to a route replacing it
+    pub(crate) fn take_routed_entities(&mut self) -> (HashSet<OwnedKeyExpr>, HashSet<String>) {
+        (
+            std::mem::take(&mut self.remote_routed_readers),
+            std::mem::take(&mut self.local_routed_writers),
+        )
+    }
+
+    pub(crate) fn set_from_manifest(&mut self) {
+        self.from_manifest = true;
+    }
+
+    pub(crate) fn is_from_manifest(&self) -> bool {
+        self.from_manifest
+    }
+
+    pub(crate) fn throughput(&self) -> RouteThroughput {
+        self.counters.throughput()
+    }
+
+    // Decode the last routed sample to JSON, with its source timestamp
+    pub(crate) fn decode_last_sample(&self) -> Result<serde_json::Value, String> {
+        let ops = self.type_ops.as_ref().ok_or_else(|| {
+            format!(
+                "the type information of {} is not available (it's only known when discovered from a local DDS Writer)",
+                self.topic_type
+            )
+        })?;
+        let (source_timestamp, data) = self.counters.last_sample()?;
+        let value = cdr_to_json(ops, &data)?;
+        Ok(serde_json::json!({
+            "source_timestamp": source_timestamp,
+            "value": value,
+        }))
+    }
+
+    pub(crate) fn stats(&self) -> RouteStats {
+        self.counters.stats()
+    }
+
+    pub(crate) fn last_activity(&self) -> u64 {
+        self.counters.last_activity()
+    }
+
+    pub(crate) fn restore_stats(&self, stats: &RouteStats) {
+        self.counters.restore_stats(stats);
+    }
+}
+
+// Declare the zenoh Publisher of a route from DDS: with a PublicationCache for TRANSIENT_LOCAL Writers,
+// unless overwritten by config
+async fn declare_zenoh_publisher<'a>(
+    plugin: &DdsPluginRuntime<'a>,
+    ke: &OwnedKeyExpr,
+    declared_ke: &KeyExpr<'a>,
+    reader_qos: &Qos,
+    keyless: bool,
+    instance_routing: bool,
+) -> Result<ZPublisher<'a>, String> {
+    let use_pub_cache = match plugin.get_delivery_mode(ke) {
+        Some(DeliveryMode::Push) => false,
+        Some(DeliveryMode::Pull) => true,
+        None => is_transient_local(reader_qos),
+    };
+    if use_pub_cache {
+        let max_samples = plugin.get_transient_local_max_samples(ke);
+        let history = publication_cache_history(reader_qos, keyless, max_samples);
+        tracing::debug!(
+            "Caching publications for {} Writer on resource {} with history {} (Writer uses {:?} and DurabilityService.max_instances={}, max samples configured: {:?})",
+            if is_transient_local(reader_qos) { "TRANSIENT_LOCAL" } else { "pull delivery mode" },
+            ke, history, reader_qos.history, get_durability_service_or_default(reader_qos).max_instances, max_samples
+        );
+        let pub_cache = plugin
+            .zsession
+            .declare_publication_cache(declared_ke)
+            .history(history)
+            .queryable_prefix(*KE_PREFIX_PUB_CACHE / &plugin.member_id)
+            .queryable_allowed_origin(Locality::Remote) // Note: don't reply to queries from local QueryingSubscribers
+            .res()
+            .await
+            .map_err(|e| {
+                format!("Failed create PublicationCache for key {ke} (rid={declared_ke}): {e}")
+            })?;
+        // with keyed_instance_routing, the publications are also on the instances' sub-keys: cache them
+        // (but not "<ke>/**" that matches the nested topics)
+        let instances_cache = if instance_routing {
+            let instances_ke = &**ke / *KE_ANY_1_SEGMENT;
+            let cache = plugin
+                .zsession
+                .declare_publication_cache(&instances_ke)
+                .history(history)
+                .queryable_prefix(*KE_PREFIX_PUB_CACHE / &plugin.member_id)
+                .queryable_allowed_origin(Locality::Remote) // Note: don't reply to queries from local QueryingSubscribers
+                .res()
+                .await
+                .map_err(|e| {
+                    format!("Failed create PublicationCache for key {instances_ke}: {e}")
+                })?;
+            Some(cache)
+        } else {
+            None
+        };
+        Ok(ZPublisher::PublicationCache(pub_cache, instances_cache))
+    } else {
+        if let Err(e) = plugin
+            .zsession
+            .declare_publisher(declared_ke.clone())
+            .res()
+            .await
+        {
+            tracing::warn!(
+                "Failed to declare publisher for key {} (rid={}): {}",
+                ke,
+                declared_ke,
+                e
+            );
+        }
+        Ok(ZPublisher::Publisher(declared_ke.clone()))
+    }
 }
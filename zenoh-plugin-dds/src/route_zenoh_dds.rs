@@ -13,19 +13,33 @@
 //
 
 use cyclors::{
-    dds_entity_t, dds_get_entity_sertype, dds_strretcode, dds_writecdr, ddsi_serdata_from_ser_iov,
-    ddsi_serdata_kind_SDK_DATA, ddsi_sertype, ddsrt_iovec_t,
+    dds_begin_coherent, dds_end_coherent, dds_entity_t, dds_forwardcdr, dds_get_entity_sertype,
+    dds_strretcode, dds_time, dds_writecdr, ddsi_serdata_from_ser_iov, ddsi_serdata_kind_SDK_DATA,
+    ddsi_serdata_kind_SDK_KEY, ddsi_sertype, ddsrt_iovec_t,
 };
-use serde::{Serialize, Serializer};
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::sync::atomic::Ordering;
-use std::sync::Arc;
-use std::{ffi::CStr, fmt, sync::atomic::AtomicI32, time::Duration};
+use std::sync::{Arc, Mutex};
+use std::{
+    ffi::CStr,
+    fmt,
+    sync::atomic::{AtomicI32, AtomicU64},
+    time::Duration,
+};
+use zenoh::liveliness::LivelinessToken;
 use zenoh::prelude::*;
-use zenoh::query::ReplyKeyExpr;
+use zenoh::query::{Reply, ReplyKeyExpr};
 use zenoh::{prelude::r#async::AsyncResolve, subscriber::Subscriber};
 use zenoh_ext::{FetchingSubscriber, SubscriberBuilderExt};
 
+use crate::compression::{decompress, get_compression};
+use crate::config::{Config, SetupOrder};
+use crate::metrics::BridgeMetrics;
+use crate::ordered_writes::OrderedWrites;
+use crate::payload_log::{self, PayloadDirection};
+use crate::stats_state::RouteStats;
 use crate::DdsPluginRuntime;
 use crate::{
     dds_mgt::*, qos::Qos, vec_into_raw_parts, KE_ANY_1_SEGMENT, KE_PREFIX_PUB_CACHE, LOG_PAYLOAD,
@@ -34,6 +48,11 @@ use crate::{
 type AtomicDDSEntity = AtomicI32;
 const DDS_ENTITY_NULL: dds_entity_t = 0;
 
+// The status info of a serialized sample marking the dispose or the unregistration of its instance
+// (see DDSI_STATUSINFO_DISPOSE and DDSI_STATUSINFO_UNREGISTER in CycloneDDS)
+const DDSI_STATUSINFO_DISPOSE: u32 = 0x1;
+const DDSI_STATUSINFO_UNREGISTER: u32 = 0x2;
+
 enum ZSubscriber<'a> {
     Subscriber(Subscriber<'a, ()>),
     FetchingSubscriber(FetchingSubscriber<'a, ()>),
@@ -48,6 +67,100 @@ impl ZSubscriber<'_> {
     }
 }
 
+// Declare a zenoh subscriber on sub_ke for a route to DDS, writing the received samples with sample_writer.
+// If querying, it's a FetchingSubscriber querying the historical publications of all PublicationCaches.
+async fn declare_zenoh_subscriber<'a>(
+    plugin: &DdsPluginRuntime<'a>,
+    sub_ke: &keyexpr,
+    topic_name: &str,
+    querying: bool,
+    sample_writer: Arc<SampleWriter>,
+) -> Result<ZSubscriber<'a>, String> {
+    let subscriber_callback = move |s: Sample| sample_writer.on_sample(s);
+    if querying {
+        // query all PublicationCaches on "<KE_PREFIX_PUB_CACHE>/*/<sub_ke>"
+        let query_selector: Selector = (*KE_PREFIX_PUB_CACHE / *KE_ANY_1_SEGMENT / sub_ke).into();
+        tracing::debug!(
+            "Route Zenoh->DDS ({} -> {}): query historical data from everybody for TRANSIENT_LOCAL Reader on {}",
+            sub_ke,
+            topic_name,
+            query_selector
+        );
+
+        plugin
+            .zsession
+            .declare_subscriber(sub_ke.to_owned())
+            .callback(subscriber_callback)
+            .allowed_origin(Locality::Remote) // Allow only remote publications to avoid loops
+            .reliable()
+            .querying()
+            .query_timeout(plugin.config.queries_timeout)
+            .query_selector(query_selector)
+            .query_accept_replies(ReplyKeyExpr::Any)
+            .res()
+            .await
+            .map(ZSubscriber::FetchingSubscriber)
+            .map_err(|e| {
+                format!(
+                    "Route Zenoh->DDS ({sub_ke} -> {topic_name}): failed to create FetchingSubscriber: {e}"
+                )
+            })
+    } else {
+        plugin
+            .zsession
+            .declare_subscriber(sub_ke.to_owned())
+            .callback(subscriber_callback)
+            .allowed_origin(Locality::Remote) // Allow only remote publications to avoid loops
+            .reliable()
+            .res()
+            .await
+            .map(ZSubscriber::Subscriber)
+            .map_err(|e| {
+                format!(
+                    "Route Zenoh->DDS ({sub_ke} -> {topic_name}): failed to create Subscriber: {e}"
+                )
+            })
+    }
+}
+
+// A failed query for historical publications, to be retried by the discovery loop once its backoff delay expired
+// (see RouteZenohDDS::query_historical_publications())
+pub(crate) struct HistoryQueryRetry {
+    // the key expression of the route to DDS that issued the query
+    pub(crate) route_ke: OwnedKeyExpr,
+    // the key expression of the query
+    pub(crate) query_ke: OwnedKeyExpr,
+    // the number of retries of the query (including this one)
+    pub(crate) retries: u32,
+}
+
+// The counters of the queries for historical publications of a route
+#[derive(Clone, Default)]
+struct HistoryQueryCounters {
+    // the number of retries of failed queries for historical publications
+    retries: Arc<AtomicU64>,
+    // the number of queries for historical publications that failed after all retries
+    failures: Arc<AtomicU64>,
+}
+
+impl Serialize for HistoryQueryCounters {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut s = serializer.serialize_struct("HistoryQueryCounters", 2)?;
+        s.serialize_field(
+            "historical_queries_retries",
+            &self.retries.load(Ordering::Relaxed),
+        )?;
+        s.serialize_field(
+            "historical_queries_failures",
+            &self.failures.load(Ordering::Relaxed),
+        )?;
+        s.end()
+    }
+}
+
 // a route from Zenoh to DDS
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Serialize)]
@@ -58,6 +171,10 @@ pub(crate) struct RouteZenohDDS<'a> {
     // the zenoh subscriber receiving data to be re-published by the DDS Writer
     #[serde(skip)]
     zenoh_subscriber: ZSubscriber<'a>,
+    // the zenoh subscriber receiving the instances published on "<keyexpr>/<instance-keyhash>" sub-keys
+    // (if keyed_instance_routing is configured for the route)
+    #[serde(skip)]
+    instance_subscriber: Option<ZSubscriber<'a>>,
     // the DDS topic name for re-publication
     topic_name: String,
     // the DDS topic type
@@ -69,10 +186,40 @@ pub(crate) struct RouteZenohDDS<'a> {
     // of a local DDS Reader, and the forwarded discovery msg for the DDS Writer didn't arrive yet.
     #[serde(serialize_with = "serialize_atomic_entity_guid")]
     dds_writer: Arc<AtomicDDSEntity>,
+    // the QoS of the local DDS Writer (None as long as the DDS Writer is not created)
+    #[serde(skip)]
+    writer_qos: Mutex<Option<Qos>>,
+    // if the DDS Writer can keep a BEST_EFFORT reliability (see allow_best_effort configuration)
+    #[serde(skip)]
+    allow_best_effort: bool,
+    // the QoS override applied to the DDS Writer (if configured)
+    #[serde(skip)]
+    qos_override: Option<Qos>,
+    // the EntityName QoS of the DDS Writer (None if entity_name_prefix is empty)
+    #[serde(skip)]
+    entity_name: Option<String>,
+    // the liveliness token declared while the DDS Writer is matched (if configured)
+    #[serde(skip)]
+    matched_liveliness: Option<Arc<MatchedLiveliness>>,
+    // the liveliness token advertising the route as long as it exists (if configured)
+    #[serde(skip)]
+    _route_liveliness: Option<LivelinessToken<'a>>,
     // the list of remote writers served by this route (admin key expr)
     remote_routed_writers: HashSet<OwnedKeyExpr>,
     // the list of local readers served by this route (entity keys)
     local_routed_readers: HashSet<String>,
+    // the partition the remote DDS Writers' one is rewritten into, if it differs from the partition of the route's
+    // key expression (see partition_rewrite configuration)
+    rewritten_partition: Option<String>,
+    // true if the route was pre-created from the startup manifest (it's then never removed)
+    from_manifest: bool,
+    // the QoS incompatibilities between the DDS Writer and the local DDS Readers (if check_qos_compatibility is configured)
+    qos_incompatibilities: Mutex<Vec<String>>,
+    // the counters of the queries for historical publications (updated by the tasks awaiting their completion)
+    #[serde(flatten)]
+    historical_queries: HistoryQueryCounters,
+    // the time of the last sample written to DDS (or of the creation of the route)
+    last_activity: RouteActivity,
 }
 
 impl Drop for RouteZenohDDS<'_> {
@@ -110,7 +257,8 @@ impl RouteZenohDDS<'_> {
         topic_name: String,
         topic_type: String,
         keyless: bool,
-    ) -> Result<RouteZenohDDS<'a>, String> {
+        writer_qos: Option<Qos>,
+    ) -> Result<RouteZenohDDS<'a>, RouteError> {
         tracing::debug!(
             "Route Zenoh->DDS ({} -> {}): creation with topic_type={} querying_subscriber={}",
             ke,
@@ -124,105 +272,149 @@ impl RouteZenohDDS<'_> {
         // Clone it for the subscriber_callback
         let arc_dw = dds_writer.clone();
 
-        // Callback routing data received by Zenoh subscriber to DDS Writer (if set)
-        let ton = topic_name.clone();
-        let subscriber_callback = move |s: Sample| {
-            let dw = arc_dw.load(Ordering::Relaxed);
-            if dw != DDS_ENTITY_NULL {
-                do_route_data(s, &ton, dw);
-            } else {
-                // delay the routing of data for few ms in case this publication arrived
-                // before the discovery message provoking the creation of the Data Writer
-                tracing::debug!(
-                    "Route Zenoh->DDS ({} -> {}): data arrived but no DDS Writer yet to route it... wait 3s for discovery forwarding msg",
-                    s.key_expr,
-                    &ton
-                );
-                let arc_dw2 = arc_dw.clone();
-                let ton2 = ton.clone();
-                let ke = s.key_expr.clone();
-                async_std::task::spawn(async move {
-                    for _ in 1..30 {
-                        async_std::task::sleep(Duration::from_millis(100)).await;
-                        let dw = arc_dw2.load(Ordering::Relaxed);
-                        if dw != DDS_ENTITY_NULL {
-                            do_route_data(s, &ton2, dw);
-                            break;
-                        } else {
-                            tracing::warn!(
-                                "Route Zenoh->DDS ({} -> {}): still no DDS Writer after 3s - drop incoming data!",
-                                ke,
-                                &ton2
-                            );
-                        }
-                    }
-                });
-            }
-        };
+        let matched_liveliness = plugin.get_matched_liveliness("to_dds", &ke);
+        let qos_override = plugin.get_qos_override(&ke);
+        let entity_name = plugin.get_entity_name("to_dds", &topic_name);
+        let allow_best_effort = plugin
+            .config
+            .allow_best_effort
+            .as_ref()
+            .map_or(false, |re| re.is_match(&topic_name));
+        let settle_delay = plugin.config.route_setup.settle_delay;
+        let dds_first = plugin.config.route_setup.to_dds == SetupOrder::DdsFirst;
 
-        // create zenoh subscriber
-        let zenoh_subscriber = if querying_subscriber {
-            // query all PublicationCaches on "<KE_PREFIX_PUB_CACHE>/*/<routing_keyexpr>"
-            let query_selector: Selector = (*KE_PREFIX_PUB_CACHE / *KE_ANY_1_SEGMENT / &ke).into();
+        // if configured, create the DDS Writer before the zenoh Subscriber (if its QoS is already known)
+        if let (true, Some(qos)) = (dds_first, &writer_qos) {
             tracing::debug!(
-                    "Route Zenoh->DDS ({} -> {}): query historical data from everybody for TRANSIENT_LOCAL Reader on {}",
-                    ke,
-                    topic_name,
-                    query_selector
-                );
+                "Route Zenoh->DDS ({} -> {}): create DDS Writer before zenoh Subscriber",
+                ke,
+                topic_name
+            );
+            let dw = create_forwarding_dds_writer(
+                plugin.dp,
+                topic_name.clone(),
+                topic_type.clone(),
+                keyless,
+                qos.clone(),
+                allow_best_effort,
+                qos_override.as_ref(),
+                entity_name.as_deref(),
+                matched_liveliness.clone(),
+            )?;
+            dds_writer.store(dw, Ordering::SeqCst);
+            if !settle_delay.is_zero() {
+                async_std::task::sleep(settle_delay).await;
+            }
+        }
 
-            let sub = plugin
-                .zsession
-                .declare_subscriber(ke.clone())
-                .callback(subscriber_callback)
-                .allowed_origin(Locality::Remote) // Allow only remote publications to avoid loops
-                .reliable()
-                .querying()
-                .query_timeout(plugin.config.queries_timeout)
-                .query_selector(query_selector)
-                .query_accept_replies(ReplyKeyExpr::Any)
-                .res()
-                .await
-                .map_err(|e| {
-                    format!(
-                        "Route Zenoh->DDS ({ke} -> {topic_name}): failed to create FetchingSubscriber: {e}"
-                    )
-                })?;
-            ZSubscriber::FetchingSubscriber(sub)
-        } else {
-            let sub = plugin
-                .zsession
-                .declare_subscriber(ke.clone())
-                .callback(subscriber_callback)
-                .allowed_origin(Locality::Remote) // Allow only remote publications to avoid loops
-                .reliable()
-                .res()
-                .await
-                .map_err(|e| {
-                    format!(
-                        "Route Zenoh->DDS ({ke} -> {topic_name}): failed to create Subscriber: {e}"
-                    )
-                })?;
-            ZSubscriber::Subscriber(sub)
+        // The dead letters publisher for the samples that can't be routed (if configured)
+        let dead_letters = plugin
+            .config
+            .dead_letters_key
+            .as_ref()
+            .map(|dl_ke| DeadLetters {
+                z: plugin.zsession.clone(),
+                ke: dl_ke.clone(),
+            });
+
+        // Callback routing data received by Zenoh subscriber to DDS Writer (if set)
+        let last_activity = RouteActivity::default();
+        let sample_writer = Arc::new(SampleWriter {
+            topic_name: topic_name.clone(),
+            dds_writer: arc_dw,
+            preserve_source_timestamp: plugin.config.preserve_source_timestamp,
+            max_decompressed_size: plugin.config.max_decompressed_size,
+            metrics: plugin.metrics.clone(),
+            dead_letters,
+            last_activity: last_activity.clone(),
+            pending: OrderedWrites::default(),
+        });
+
+        // create zenoh subscriber
+        let zenoh_subscriber = declare_zenoh_subscriber(
+            plugin,
+            &ke,
+            &topic_name,
+            querying_subscriber,
+            sample_writer.clone(),
+        )
+        .await;
+        // with keyed_instance_routing, each instance is published on its own "<routing_keyexpr>/<instance-keyhash>"
+        // sub-key: subscribe to them also (but not to "<routing_keyexpr>/**" that matches the nested topics).
+        // The instance is given by the key fields of the data, written as such.
+        let zenoh_subscriber = match zenoh_subscriber {
+            Ok(sub) if plugin.is_keyed_instance_routed(&ke, keyless) => declare_zenoh_subscriber(
+                plugin,
+                &(&ke / *KE_ANY_1_SEGMENT),
+                &topic_name,
+                querying_subscriber,
+                sample_writer,
+            )
+            .await
+            .map(|instance_sub| (sub, Some(instance_sub))),
+            Ok(sub) => Ok((sub, None)),
+            Err(e) => Err(e),
+        };
+        let (zenoh_subscriber, instance_subscriber) = match zenoh_subscriber {
+            Ok(subs) => subs,
+            Err(e) => {
+                // delete the DDS Writer if created before the zenoh Subscriber
+                let dw = dds_writer.swap(DDS_ENTITY_NULL, Ordering::SeqCst);
+                if dw != DDS_ENTITY_NULL {
+                    if let Err(e) = delete_dds_entity(dw) {
+                        tracing::warn!(
+                            "Route Zenoh->DDS ({ke} -> {topic_name}): error deleting DDS Writer: {e}"
+                        );
+                    }
+                }
+                return Err(e.into());
+            }
         };
 
-        Ok(RouteZenohDDS {
+        let mut route = RouteZenohDDS {
             zenoh_session: plugin.zsession,
             zenoh_subscriber,
+            instance_subscriber,
             topic_name,
             topic_type,
             keyless,
             dds_writer,
+            writer_qos: Mutex::new(None),
+            allow_best_effort,
+            qos_override,
+            entity_name,
+            matched_liveliness,
+            _route_liveliness: None,
             remote_routed_writers: HashSet::new(),
             local_routed_readers: HashSet::new(),
-        })
+            rewritten_partition: None,
+            from_manifest: false,
+            qos_incompatibilities: Mutex::new(Vec::new()),
+            historical_queries: HistoryQueryCounters::default(),
+            last_activity,
+        };
+
+        if let Some(qos) = writer_qos {
+            if dds_first {
+                *route.writer_qos.lock().unwrap() = Some(qos);
+            } else {
+                // create the DDS Writer after the zenoh Subscriber
+                if !settle_delay.is_zero() {
+                    async_std::task::sleep(settle_delay).await;
+                }
+                route.set_dds_writer(plugin.dp, qos)?;
+            }
+        }
+        // advertise the route only once its creation succeeded
+        route._route_liveliness = plugin.declare_route_liveliness("to_dds", &ke).await;
+        Ok(route)
     }
 
     pub(crate) fn set_dds_writer(
         &self,
         data_participant: dds_entity_t,
         writer_qos: Qos,
-    ) -> Result<(), String> {
+    ) -> Result<(), RouteError> {
         // check if dds_writer was already set
         let old = self.dds_writer.load(Ordering::SeqCst);
 
@@ -233,7 +425,11 @@ impl RouteZenohDDS<'_> {
                 self.topic_name.clone(),
                 self.topic_type.clone(),
                 self.keyless,
-                writer_qos,
+                writer_qos.clone(),
+                self.allow_best_effort,
+                self.qos_override.as_ref(),
+                self.entity_name.as_deref(),
+                self.matched_liveliness.clone(),
             )?;
             if self
                 .dds_writer
@@ -252,6 +448,8 @@ impl RouteZenohDDS<'_> {
                         self, e
                     )
                 }
+            } else {
+                *self.writer_qos.lock().unwrap() = Some(writer_qos);
             }
         }
         Ok(())
@@ -262,62 +460,148 @@ impl RouteZenohDDS<'_> {
             .dds_writer
             .swap(DDS_ENTITY_NULL, std::sync::atomic::Ordering::Relaxed);
         if dds_entity != DDS_ENTITY_NULL {
+            *self.writer_qos.lock().unwrap() = None;
             if let Err(e) = delete_dds_entity(dds_entity) {
                 tracing::warn!("{}: error deleting DDS Writer:  {}", self, e);
             }
+            if let Some(matched) = &self.matched_liveliness {
+                matched.clear();
+            }
         }
     }
 
-    /// If this route uses a FetchingSubscriber, query for historical publications
-    /// using the specified Selector. Otherwise, do nothing.
-    pub(crate) async fn query_historical_publications<'a, F>(
-        &mut self,
-        selector: F,
-        query_timeout: Duration,
-    ) where
-        F: Fn() -> Selector<'a>,
-    {
-        if let ZSubscriber::FetchingSubscriber(sub) = &mut self.zenoh_subscriber {
-            let s = selector();
+    /// If this route uses a FetchingSubscriber, query for historical publications on `query_ke`.
+    /// Otherwise, do nothing. The completion of the query is not awaited: if it fails or times out, it's retried
+    /// up to `queries_max_retries` times, doubling the `queries_retry_backoff` delay after each retry, by sending
+    /// a [`HistoryQueryRetry`] to `retry_tx` once the delay expired (`retries` being the number of retries done).
+    pub(crate) async fn query_historical_publications(
+        &self,
+        route_ke: &keyexpr,
+        query_ke: OwnedKeyExpr,
+        retries: u32,
+        config: &Config,
+        retry_tx: &Sender<HistoryQueryRetry>,
+    ) {
+        // the FetchingSubscriber on the key expression queried (i.e. on the instances' sub-keys or not)
+        let sub = std::iter::once(&self.zenoh_subscriber)
+            .chain(self.instance_subscriber.as_ref())
+            .find_map(|s| match s {
+                ZSubscriber::FetchingSubscriber(sub)
+                    if (*KE_PREFIX_PUB_CACHE / *KE_ANY_1_SEGMENT / &**sub.key_expr())
+                        .includes(&query_ke) =>
+                {
+                    Some(sub)
+                }
+                _ => None,
+            });
+        if let Some(sub) = sub {
             tracing::debug!(
                 "Route Zenoh->DDS ({} -> {}): query historical publications from {}",
                 sub.key_expr(),
                 self.topic_name,
-                s
+                query_ke
             );
-            if let Err(e) = sub
+            // the error replies of the query (e.g. its timeout) are sent to error_tx, that is dropped with the
+            // query's callback when the query completes
+            let (error_tx, error_rcv) = flume::bounded::<String>(1);
+            let query_timeout = config.queries_timeout;
+            let result = sub
                 .fetch({
                     let session = &self.zenoh_session;
-                    let s = s.clone();
+                    let query_ke = query_ke.clone();
                     move |cb| {
                         use zenoh_core::SyncResolve;
                         session
-                            .get(&s)
+                            .get(Selector::from(&query_ke))
                             .target(QueryTarget::All)
                             .consolidation(ConsolidationMode::None)
                             .accept_replies(ReplyKeyExpr::Any)
                             .timeout(query_timeout)
-                            .callback(cb)
+                            .callback(move |reply: Reply| {
+                                if let Err(e) = &reply.sample {
+                                    let _ = error_tx.try_send(e.to_string());
+                                }
+                                cb(reply)
+                            })
                             .res_sync()
                     }
                 })
                 .res()
-                .await
-            {
-                tracing::warn!(
-                    "{}: query for historical publications on {} failed: {}",
-                    self,
-                    s,
-                    e
-                );
-            }
+                .await;
+
+            let label = self.to_string();
+            let route_ke = route_ke.to_owned();
+            let max_retries = config.queries_max_retries;
+            let retry_backoff = config.queries_retry_backoff;
+            let counters = self.historical_queries.clone();
+            let retry_tx = retry_tx.clone();
+            async_std::task::spawn(async move {
+                let error = match result {
+                    Ok(()) => error_rcv.recv_async().await.ok(),
+                    Err(e) => Some(e.to_string()),
+                };
+                match error {
+                    None if retries > 0 => tracing::info!(
+                        "{label}: query for historical publications on {query_ke} succeeded after {retries} retries"
+                    ),
+                    None => {}
+                    Some(e) if retries < max_retries => {
+                        let delay = retry_backoff.saturating_mul(2u32.saturating_pow(retries));
+                        counters.retries.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(
+                            "{label}: query for historical publications on {query_ke} failed: {e} - retry {}/{max_retries} in {delay:?}",
+                            retries + 1
+                        );
+                        async_std::task::sleep(delay).await;
+                        let _ = retry_tx
+                            .send_async(HistoryQueryRetry {
+                                route_ke,
+                                query_ke,
+                                retries: retries + 1,
+                            })
+                            .await;
+                    }
+                    Some(e) => {
+                        counters.failures.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(
+                            "{label}: query for historical publications on {query_ke} failed after {retries} retries: {e}"
+                        );
+                    }
+                }
+            });
         }
     }
 
-    pub(crate) fn dds_writer_guid(&self) -> Result<String, String> {
+    // The key expressions to query the historical publications of the remote bridge member_id on: one per
+    // FetchingSubscriber of the route ("<KE_PREFIX_PUB_CACHE>/<member_id>/<subscriber's key expression>")
+    pub(crate) fn history_query_keyexprs(&self, member_id: &keyexpr) -> Vec<OwnedKeyExpr> {
+        std::iter::once(&self.zenoh_subscriber)
+            .chain(self.instance_subscriber.as_ref())
+            .filter(|s| matches!(s, ZSubscriber::FetchingSubscriber(_)))
+            .map(|s| *KE_PREFIX_PUB_CACHE / member_id / &**s.key_expr())
+            .collect()
+    }
+
+    pub(crate) fn topic_name(&self) -> &str {
+        &self.topic_name
+    }
+
+    pub(crate) fn topic_type(&self) -> &str {
+        &self.topic_type
+    }
+
+    pub(crate) fn is_keyless(&self) -> bool {
+        self.keyless
+    }
+
+    pub(crate) fn dds_writer_guid(&self) -> Result<String, DdsError> {
         get_guid(&self.dds_writer.load(Ordering::Relaxed))
     }
 
+    pub(crate) fn dds_writer_qos(&self) -> Option<Qos> {
+        self.writer_qos.lock().unwrap().clone()
+    }
+
     pub(crate) fn add_remote_routed_writer(&mut self, admin_ke: OwnedKeyExpr) {
         self.remote_routed_writers.insert(admin_ke);
     }
@@ -341,6 +625,14 @@ impl RouteZenohDDS<'_> {
             .any(|s| s.contains(entity_key))
     }
 
+    pub(crate) fn set_rewritten_partition(&mut self, partition: String) {
+        self.rewritten_partition = Some(partition);
+    }
+
+    pub(crate) fn rewritten_partition(&self) -> Option<&String> {
+        self.rewritten_partition.as_ref()
+    }
+
     pub(crate) fn add_local_routed_reader(&mut self, entity_key: String) {
         self.local_routed_readers.insert(entity_key);
     }
@@ -352,15 +644,96 @@ impl RouteZenohDDS<'_> {
     pub(crate) fn has_local_routed_reader(&self) -> bool {
         !self.local_routed_readers.is_empty()
     }
+
+    pub(crate) fn set_from_manifest(&mut self) {
+        self.from_manifest = true;
+    }
+
+    pub(crate) fn is_from_manifest(&self) -> bool {
+        self.from_manifest
+    }
+
+    pub(crate) fn set_qos_incompatibilities(&self, incompatibilities: Vec<String>) {
+        *self.qos_incompatibilities.lock().unwrap() = incompatibilities;
+    }
+
+    pub(crate) fn last_activity(&self) -> u64 {
+        self.last_activity.last()
+    }
+
+    // The counters to be persisted (see stats_state_file configuration), with the same names than in admin space
+    pub(crate) fn stats(&self) -> RouteStats {
+        RouteStats::from([
+            (
+                "historical_queries_retries".to_string(),
+                self.historical_queries.retries.load(Ordering::Relaxed),
+            ),
+            (
+                "historical_queries_failures".to_string(),
+                self.historical_queries.failures.load(Ordering::Relaxed),
+            ),
+        ])
+    }
+
+    // Add the persisted counters to the current ones
+    pub(crate) fn restore_stats(&mut self, stats: &RouteStats) {
+        if let Some(n) = stats.get("historical_queries_retries") {
+            self.historical_queries
+                .retries
+                .fetch_add(*n, Ordering::Relaxed);
+        }
+        if let Some(n) = stats.get("historical_queries_failures") {
+            self.historical_queries
+                .failures
+                .fetch_add(*n, Ordering::Relaxed);
+        }
+    }
 }
 
-fn do_route_data(s: Sample, topic_name: &str, data_writer: dds_entity_t) {
-    if *LOG_PAYLOAD {
+// Route a sample to DDS, returning the reason why it couldn't be routed in case of failure
+// If preserve_source_timestamp is set, the DDS source timestamp attached to the sample (if any) is re-applied.
+// A payload compressed by the remote bridge (as named by the sample's attachment) is decompressed first,
+// up to max_decompressed_size.
+fn do_route_data(
+    s: &Sample,
+    topic_name: &str,
+    data_writer: dds_entity_t,
+    preserve_source_timestamp: bool,
+    max_decompressed_size: usize,
+) -> Result<(), String> {
+    let payload = match s.attachment.as_ref().and_then(get_compression) {
+        None => s.value.payload.contiguous(),
+        Some(compression) => {
+            match compression
+                .and_then(|c| decompress(c, &s.value.payload.contiguous(), max_decompressed_size))
+            {
+                Ok(payload) => Cow::Owned(payload),
+                Err(e) => {
+                    tracing::warn!(
+                        topic = topic_name,
+                        keyexpr = %s.key_expr,
+                        direction = "to_dds",
+                        "Route Zenoh->DDS: can't route data; failed to decompress payload: {}",
+                        e
+                    );
+                    return Err(format!("failed to decompress payload: {e}"));
+                }
+            }
+        }
+    };
+    // the logs reuse the payload made contiguous to be written to DDS: none is made contiguous only to be logged
+    let logged_to_file = payload_log::log(
+        PayloadDirection::ToDds,
+        topic_name,
+        s.key_expr.as_str(),
+        &payload,
+    );
+    if *LOG_PAYLOAD && !logged_to_file {
         tracing::trace!(
             "Route Zenoh->DDS ({} -> {}): routing data - payload: {:?}",
             s.key_expr,
             &topic_name,
-            s.value.payload
+            PayloadLog(&payload)
         );
     } else {
         tracing::trace!(
@@ -370,50 +743,380 @@ fn do_route_data(s: Sample, topic_name: &str, data_writer: dds_entity_t) {
         );
     }
 
-    unsafe {
-        let bs = s.value.payload.contiguous().into_owned();
-        // As per the Vec documentation (see https://doc.rust-lang.org/std/vec/struct.Vec.html#method.into_raw_parts)
-        // the only way to correctly releasing it is to create a vec using from_raw_parts
-        // and then have its destructor do the cleanup.
-        // Thus, while tempting to just pass the raw pointer to cyclone and then free it from C,
-        // that is not necessarily safe or guaranteed to be leak free.
-        // TODO replace when stable https://github.com/rust-lang/rust/issues/65816
-        let (ptr, len, capacity) = vec_into_raw_parts(bs);
-
-        let data_out: ddsrt_iovec_t;
-        #[cfg(not(target_os = "windows"))]
-        {
-            data_out = ddsrt_iovec_t {
-                iov_base: ptr as *mut std::ffi::c_void,
-                iov_len: len,
-            };
-        }
-        #[cfg(target_os = "windows")]
-        {
-            data_out = ddsrt_iovec_t {
-                iov_base: ptr as *mut std::ffi::c_void,
-                iov_len: len as u32,
-            };
-        }
+    // an empty payload is an event routed from a topic with an "event_only" forwarding profile:
+    // there is no DDS sample to write
+    if payload.is_empty() {
+        tracing::trace!(
+            "Route Zenoh->DDS ({} -> {}): event without payload - skip the DDS write",
+            s.key_expr,
+            &topic_name
+        );
+        return Ok(());
+    }
 
+    unsafe {
         let mut sertype_ptr: *const ddsi_sertype = std::ptr::null_mut();
         let ret = dds_get_entity_sertype(data_writer, &mut sertype_ptr);
         if ret < 0 {
-            tracing::warn!(
-                "Route Zenoh->DDS ({} -> {}): can't route data; sertype lookup failed ({})",
-                s.key_expr,
-                topic_name,
+            let reason = format!(
+                "sertype lookup failed ({})",
                 CStr::from_ptr(dds_strretcode(ret))
                     .to_str()
                     .unwrap_or("unrecoverable DDS retcode")
             );
-            return;
+            tracing::warn!(
+                topic = topic_name,
+                keyexpr = %s.key_expr,
+                direction = "to_dds",
+                retcode = ret,
+                "Route Zenoh->DDS: can't route data; {}",
+                reason
+            );
+            return Err(reason);
+        }
+
+        let source_timestamp = if preserve_source_timestamp {
+            s.attachment.as_ref().and_then(get_source_timestamp)
+        } else {
+            None
+        };
+        match decode_instance_state(&payload) {
+            Some(Ok((change, key))) => {
+                tracing::trace!(
+                    "Route Zenoh->DDS ({} -> {}): routing instance {:?}",
+                    s.key_expr,
+                    topic_name,
+                    change
+                );
+                return write_cdr(
+                    data_writer,
+                    sertype_ptr,
+                    key.to_vec(),
+                    Some(change),
+                    source_timestamp,
+                );
+            }
+            Some(Err(e)) => {
+                tracing::warn!(
+                    "Route Zenoh->DDS ({} -> {}): can't route data; invalid instance state change: {}",
+                    s.key_expr,
+                    topic_name,
+                    e
+                );
+                return Err(format!("invalid instance state change: {e}"));
+            }
+            None => (),
+        }
+        match decode_batch(&payload) {
+            Some(Ok(samples)) => {
+                // write the samples of the batch one by one, in order, with their own source timestamp
+                tracing::trace!(
+                    "Route Zenoh->DDS ({} -> {}): routing a batch of {} samples",
+                    s.key_expr,
+                    topic_name,
+                    samples.len()
+                );
+                let mut result = Ok(());
+                for (sample_timestamp, sample) in
+                    samples.into_iter().filter(|(_, sample)| !sample.is_empty())
+                {
+                    let source_timestamp = if preserve_source_timestamp && sample_timestamp >= 0 {
+                        Some(sample_timestamp)
+                    } else {
+                        None
+                    };
+                    if let Err(e) = write_cdr(
+                        data_writer,
+                        sertype_ptr,
+                        sample.to_vec(),
+                        None,
+                        source_timestamp,
+                    ) {
+                        result = Err(e);
+                    }
+                }
+                return result;
+            }
+            Some(Err(e)) => {
+                tracing::warn!(
+                    "Route Zenoh->DDS ({} -> {}): can't route data; invalid batch: {}",
+                    s.key_expr,
+                    topic_name,
+                    e
+                );
+                return Err(format!("invalid batch: {e}"));
+            }
+            None => (),
+        }
+        match decode_coherent_set(&payload) {
+            None => write_cdr(
+                data_writer,
+                sertype_ptr,
+                payload.to_vec(),
+                None,
+                source_timestamp,
+            ),
+            Some(Ok(samples)) => {
+                // write the coherent set atomically (if the DDS Writer's PRESENTATION QoS has coherent_access)
+                let ret = dds_begin_coherent(data_writer);
+                if ret < 0 {
+                    tracing::debug!(
+                        "Route Zenoh->DDS ({} -> {}): routing a coherent set of {} samples non-coherently ({})",
+                        s.key_expr,
+                        topic_name,
+                        samples.len(),
+                        CStr::from_ptr(dds_strretcode(ret))
+                            .to_str()
+                            .unwrap_or("unrecoverable DDS retcode")
+                    );
+                }
+                let mut result = Ok(());
+                for sample in samples.into_iter().filter(|sample| !sample.is_empty()) {
+                    if let Err(e) = write_cdr(data_writer, sertype_ptr, sample.to_vec(), None, None)
+                    {
+                        result = Err(e);
+                    }
+                }
+                if ret >= 0 {
+                    dds_end_coherent(data_writer);
+                }
+                result
+            }
+            Some(Err(e)) => {
+                tracing::warn!(
+                    "Route Zenoh->DDS ({} -> {}): can't route data; invalid coherent set: {}",
+                    s.key_expr,
+                    topic_name,
+                    e
+                );
+                Err(format!("invalid coherent set: {e}"))
+            }
+        }
+    }
+}
+
+// Write a serialized sample, or if instance_state is set, dispose or unregister the instance with this serialized key.
+// If source_timestamp is set, it's used as the source timestamp instead of the current time.
+pub(crate) unsafe fn write_cdr(
+    data_writer: dds_entity_t,
+    sertype_ptr: *const ddsi_sertype,
+    bs: Vec<u8>,
+    instance_state: Option<InstanceStateChange>,
+    source_timestamp: Option<i64>,
+) -> Result<(), String> {
+    // As per the Vec documentation (see https://doc.rust-lang.org/std/vec/struct.Vec.html#method.into_raw_parts)
+    // the only way to correctly releasing it is to create a vec using from_raw_parts
+    // and then have its destructor do the cleanup.
+    // Thus, while tempting to just pass the raw pointer to cyclone and then free it from C,
+    // that is not necessarily safe or guaranteed to be leak free.
+    // TODO replace when stable https://github.com/rust-lang/rust/issues/65816
+    let (ptr, len, capacity) = vec_into_raw_parts(bs);
+
+    let data_out: ddsrt_iovec_t;
+    #[cfg(not(target_os = "windows"))]
+    {
+        data_out = ddsrt_iovec_t {
+            iov_base: ptr as *mut std::ffi::c_void,
+            iov_len: len,
+        };
+    }
+    #[cfg(target_os = "windows")]
+    {
+        data_out = ddsrt_iovec_t {
+            iov_base: ptr as *mut std::ffi::c_void,
+            iov_len: len as u32,
+        };
+    }
+
+    let ret = match instance_state {
+        None => {
+            let fwdp = ddsi_serdata_from_ser_iov(
+                sertype_ptr,
+                ddsi_serdata_kind_SDK_DATA,
+                1,
+                &data_out,
+                len,
+            );
+            match source_timestamp {
+                // dds_writecdr() sets the timestamp to the current time: use dds_forwardcdr() that preserves it
+                Some(t) => {
+                    (*fwdp).statusinfo = 0;
+                    (*fwdp).timestamp.v = t;
+                    dds_forwardcdr(data_writer, fwdp)
+                }
+                None => dds_writecdr(data_writer, fwdp),
+            }
+        }
+        Some(change) => {
+            let fwdp = ddsi_serdata_from_ser_iov(
+                sertype_ptr,
+                ddsi_serdata_kind_SDK_KEY,
+                1,
+                &data_out,
+                len,
+            );
+            // dds_writecdr() resets the status info: use dds_forwardcdr() that preserves it
+            (*fwdp).statusinfo = match change {
+                InstanceStateChange::Disposed => DDSI_STATUSINFO_DISPOSE,
+                InstanceStateChange::Unregistered => DDSI_STATUSINFO_UNREGISTER,
+            };
+            (*fwdp).timestamp.v = source_timestamp.unwrap_or_else(|| dds_time());
+            dds_forwardcdr(data_writer, fwdp)
+        }
+    };
+    drop(Vec::from_raw_parts(ptr, len, capacity));
+    if ret < 0 {
+        return Err(format!(
+            "write failed ({})",
+            CStr::from_ptr(dds_strretcode(ret))
+                .to_str()
+                .unwrap_or("unrecoverable DDS retcode")
+        ));
+    }
+    Ok(())
+}
+
+// The writer of the samples received by the zenoh subscriber of a route on its DDS Writer.
+// The samples are written per key expression (i.e. per instance with keyed_instance_routing) in the order zenoh
+// delivered them: a sample arrived before the creation of the DDS Writer (in "forward discovery" mode) holds back
+// the next samples of its key until it's written, while the samples of other keys are still written right away.
+struct SampleWriter {
+    topic_name: String,
+    dds_writer: Arc<AtomicDDSEntity>,
+    preserve_source_timestamp: bool,
+    // the max size of the payloads once decompressed (see max_decompressed_size configuration)
+    max_decompressed_size: usize,
+    metrics: Arc<BridgeMetrics>,
+    dead_letters: Option<DeadLetters>,
+    last_activity: RouteActivity,
+    // the samples waiting for the write of a preceding sample of their key expression
+    pending: OrderedWrites<String, Sample>,
+}
+
+impl SampleWriter {
+    // Route a sample received by the zenoh subscriber
+    fn on_sample(self: &Arc<Self>, s: Sample) {
+        let key = s.key_expr.as_str().to_string();
+        let s = match self.pending.push(key.clone(), s) {
+            Some(s) => s,
+            // queued until the preceding samples of its key are written
+            None => return,
+        };
+        let dw = self.dds_writer.load(Ordering::Relaxed);
+        if dw != DDS_ENTITY_NULL {
+            self.write(&s, dw);
+            self.write_pending(&key, dw);
+        } else {
+            // delay the routing of data for few ms in case this publication arrived
+            // before the discovery message provoking the creation of the Data Writer
+            tracing::debug!(
+                "Route Zenoh->DDS ({} -> {}): data arrived but no DDS Writer yet to route it... wait 3s for discovery forwarding msg",
+                s.key_expr,
+                &self.topic_name
+            );
+            let writer = self.clone();
+            async_std::task::spawn(async move {
+                for _ in 1..30 {
+                    async_std::task::sleep(Duration::from_millis(100)).await;
+                    let dw = writer.dds_writer.load(Ordering::Relaxed);
+                    if dw != DDS_ENTITY_NULL {
+                        writer.write(&s, dw);
+                        writer.write_pending(&key, dw);
+                        return;
+                    }
+                }
+                tracing::warn!(
+                    topic = %writer.topic_name,
+                    keyexpr = %s.key_expr,
+                    direction = "to_dds",
+                    "Route Zenoh->DDS: still no DDS Writer after 3s - drop incoming data!"
+                );
+                // the samples of the same key queued meanwhile are dropped as well
+                writer.drop_sample(&s, "no DDS Writer after 3s");
+                while let Some(s) = writer.pending.next(&key) {
+                    writer.drop_sample(&s, "no DDS Writer after 3s");
+                }
+            });
+        }
+    }
+
+    // Write the samples of a key queued while its previous sample was written
+    fn write_pending(&self, key: &str, dw: dds_entity_t) {
+        while let Some(s) = self.pending.next(key) {
+            self.write(&s, dw);
+        }
+    }
+
+    fn write(&self, s: &Sample, dw: dds_entity_t) {
+        match do_route_data(
+            s,
+            &self.topic_name,
+            dw,
+            self.preserve_source_timestamp,
+            self.max_decompressed_size,
+        ) {
+            Ok(()) => {
+                self.last_activity.touch();
+                self.metrics.samples_to_dds.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(reason) => self.drop_sample(s, &reason),
+        }
+    }
+
+    fn drop_sample(&self, s: &Sample, reason: &str) {
+        if let Some(dl) = &self.dead_letters {
+            dl.publish(s, &self.topic_name, reason);
         }
+    }
+}
 
-        let fwdp =
-            ddsi_serdata_from_ser_iov(sertype_ptr, ddsi_serdata_kind_SDK_DATA, 1, &data_out, len);
+// The publisher of the samples that couldn't be routed to DDS, on "<dead_letters_key>/<sample key>".
+// Each dead letter is a JSON object with the original key, the DDS topic, the failure reason
+// and the payload (hex-encoded), allowing later inspection or reprocessing.
+#[derive(Clone)]
+struct DeadLetters {
+    z: Arc<Session>,
+    ke: OwnedKeyExpr,
+}
 
-        dds_writecdr(data_writer, fwdp);
-        drop(Vec::from_raw_parts(ptr, len, capacity));
+#[derive(Serialize)]
+struct DeadLetter<'a> {
+    key_expr: &'a str,
+    topic_name: &'a str,
+    reason: &'a str,
+    timestamp: Option<String>,
+    payload: String,
+}
+
+impl DeadLetters {
+    fn publish(&self, s: &Sample, topic_name: &str, reason: &str) {
+        use zenoh_core::SyncResolve;
+        let dead_letter = DeadLetter {
+            key_expr: s.key_expr.as_str(),
+            topic_name,
+            reason,
+            timestamp: s.timestamp.as_ref().map(|t| t.to_string()),
+            payload: hex::encode(s.value.payload.contiguous()),
+        };
+        let dl_ke = &*self.ke / &*s.key_expr;
+        match serde_json::to_string(&dead_letter) {
+            Ok(json) => {
+                if let Err(e) = self.z.put(&dl_ke, json).res_sync() {
+                    tracing::warn!(
+                        "Route Zenoh->DDS ({} -> {}): failed to publish dead letter on {}: {}",
+                        s.key_expr,
+                        topic_name,
+                        dl_ke,
+                        e
+                    );
+                }
+            }
+            Err(e) => tracing::error!(
+                "Route Zenoh->DDS ({} -> {}): failed to serialize dead letter: {}",
+                s.key_expr,
+                topic_name,
+                e
+            ),
+        }
     }
 }
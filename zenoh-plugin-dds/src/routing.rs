@@ -0,0 +1,501 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The routing decisions for the DDS entities discovered in "local discovery" mode, separated from the creation of
+// their routes: the decisions (allow/deny matching, key expression mapping, partitions...) depend only on the
+// configuration, while the routes (DDS Readers/Writers and zenoh declarations) are created by a RouteFactory,
+// implemented by the plugin runtime, or by a mock in tests.
+use async_trait::async_trait;
+use tracing::{info, warn};
+use zenoh::prelude::*;
+use zenoh::Result as ZResult;
+use zenoh_core::zerror;
+
+use crate::config::{Config, EntityMatcher, FilterPrecedence, RouteDirection};
+use crate::dds_mgt::{DdsEntity, RouteStatus};
+use crate::log_ros2_deprecation_warning;
+use crate::qos_helpers::partition_is_empty;
+use crate::ros_discovery::ROS_DISCOVERY_INFO_TOPIC_NAME;
+
+// The routing decision for a DDS entity in 1 of its partitions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RouteDecision {
+    // to be served by the route on this key expression (created, or joined if existing)
+    Route(OwnedKeyExpr),
+    NotAllowed,
+}
+
+// The creation of the routes serving the DDS entities discovered in "local discovery" mode, as decided by
+// route_decisions(). The returned status is the one of the route, which might be not created (e.g. throttled).
+#[async_trait]
+pub(crate) trait RouteFactory: Send {
+    fn config(&self) -> &Config;
+
+    // Create (or join) the route from DDS on ke serving a DDS Writer
+    async fn route_dds_writer(&mut self, ke: OwnedKeyExpr, entity: &DdsEntity) -> RouteStatus;
+
+    // Create (or join) the route to DDS on ke serving a DDS Reader
+    async fn route_dds_reader(&mut self, ke: OwnedKeyExpr, entity: &DdsEntity) -> RouteStatus;
+}
+
+// An entity is allowed if its 'partition/topic-name' or its type name matches the 'allow' expressions
+// (if configured), and if none of them matches the 'deny' expressions. An entity matched by both is
+// allowed only if 'filter_precedence' is "allow_wins".
+pub(crate) fn is_allowed(config: &Config, ke: &keyexpr, type_name: &str) -> bool {
+    if ke.ends_with(ROS_DISCOVERY_INFO_TOPIC_NAME) {
+        log_ros2_deprecation_warning();
+    }
+
+    if config.forward_discovery && ke.ends_with(ROS_DISCOVERY_INFO_TOPIC_NAME) {
+        // If fwd-discovery mode is enabled, don't route "ros_discovery_info"
+        return false;
+    }
+    matches_filters(
+        &config.allow,
+        &config.deny,
+        config.filter_precedence,
+        ke,
+        type_name,
+    )
+}
+
+fn matches_filters(
+    allow: &Option<EntityMatcher>,
+    deny: &Option<EntityMatcher>,
+    precedence: FilterPrecedence,
+    ke: &keyexpr,
+    type_name: &str,
+) -> bool {
+    match (allow, deny) {
+        (Some(allow), None) => allow.is_match(ke, type_name),
+        (None, Some(deny)) => !deny.is_match(ke, type_name),
+        (Some(allow), Some(deny)) => {
+            allow.is_match(ke, type_name)
+                && (precedence == FilterPrecedence::AllowWins || !deny.is_match(ke, type_name))
+        }
+        (None, None) => true,
+    }
+}
+
+// The 'allow', 'deny' and 'filter_precedence' configuration, as applied by a route from DDS to the key expressions
+// its samples are remapped to by the sample hook (see is_allowed())
+#[derive(Debug, Clone)]
+pub(crate) struct KeyFilter {
+    allow: Option<EntityMatcher>,
+    deny: Option<EntityMatcher>,
+    precedence: FilterPrecedence,
+}
+
+impl KeyFilter {
+    pub(crate) fn new(config: &Config) -> Self {
+        KeyFilter {
+            allow: config.allow.clone(),
+            deny: config.deny.clone(),
+            precedence: config.filter_precedence,
+        }
+    }
+
+    pub(crate) fn is_allowed(&self, ke: &keyexpr, type_name: &str) -> bool {
+        matches_filters(&self.allow, &self.deny, self.precedence, ke, type_name)
+    }
+}
+
+// Return true if a route in this direction is allowed on ke for an entity of this type, as per the 'allow',
+// 'deny' and 'directions' configuration (logging the reason otherwise)
+pub(crate) fn is_route_allowed(
+    config: &Config,
+    ke: &keyexpr,
+    type_name: &str,
+    direction: RouteDirection,
+) -> bool {
+    let (kind, routing) = match direction {
+        RouteDirection::FromDds => ("Publication", "from DDS"),
+        RouteDirection::ToDds => ("Subscription", "to DDS"),
+    };
+    if !is_allowed(config, ke, type_name) {
+        info!(
+            "Ignoring {} for resource {} as it is not allowed (see your 'allow' or 'deny' configuration)",
+            kind, ke
+        );
+        return false;
+    }
+    if !config.directions.contains(&direction) {
+        info!(
+            "Ignoring {} for resource {} as routing {} is disabled (see your 'directions' configuration)",
+            kind, ke, routing
+        );
+        return false;
+    }
+    true
+}
+
+pub(crate) fn topic_to_keyexpr(
+    config: &Config,
+    topic_name: &str,
+    scope: &Option<OwnedKeyExpr>,
+    partition: Option<&str>,
+) -> ZResult<OwnedKeyExpr> {
+    // key_expr for a topic is: "<scope>/<partition>/<topic_name>" with <scope> and <partition> being optional,
+    // and <topic_name> being normalized as configured (unless partition_keyexpr_template is configured)
+    let normalized = config.topic_normalization.normalize(topic_name);
+    let topic_name: &str = &normalized;
+    if topic_name.is_empty() {
+        return Err(zerror!(
+            "the topic name is empty once normalized (see your 'topic_normalization' configuration)"
+        )
+        .into());
+    }
+    // an explicit mapping of the topic (see topic_keyexpr_map) takes precedence over the default one
+    match config.mapped_topic_keyexpr(topic_name) {
+        Some(Ok(ke)) => return Ok(ke),
+        Some(Err(e)) => warn!("Topic {}: {} - use the default mapping", topic_name, e),
+        None => (),
+    }
+    if let Some(template) = &config.partition_keyexpr_template {
+        // the template's literal parts are validated at configuration, but the substituted scope, partition
+        // or topic name might still give an invalid key expression (e.g. a partition with a '*')
+        return template
+            .apply(scope.as_ref().map(|s| s.as_str()), partition, topic_name)
+            .try_into();
+    }
+    match (scope, partition) {
+        (Some(scope), Some(part)) => scope.join(&format!("{part}/{topic_name}")),
+        (Some(scope), None) => scope.join(topic_name),
+        (None, Some(part)) => format!("{part}/{topic_name}").try_into(),
+        (None, None) => topic_name.try_into(),
+    }
+}
+
+// The routing decisions for a DDS entity discovered in "local discovery" mode: 1 per partition,
+// or just 1 for "*" if no partition (or if its partitions are not allowed)
+pub(crate) fn route_decisions(
+    config: &Config,
+    entity: &DdsEntity,
+    direction: RouteDirection,
+) -> Vec<(String, RouteDecision)> {
+    if !config.partition_filter.is_allowed(&entity.qos.partition) {
+        info!("Ignoring DDS entity {} on {} as its partitions {:?} are not allowed (see your 'partition_filter' configuration)", entity.key, entity.topic_name, entity.qos.partition);
+        return vec![("*".to_string(), RouteDecision::NotAllowed)];
+    }
+    let partitions: Vec<Option<&str>> = if partition_is_empty(&entity.qos.partition) {
+        vec![None]
+    } else {
+        entity
+            .qos
+            .partition
+            .iter()
+            .flatten()
+            .map(|p| Some(p.as_str()))
+            .collect()
+    };
+    partitions
+        .into_iter()
+        .map(|partition| {
+            let decision =
+                match topic_to_keyexpr(config, &entity.topic_name, &config.scope, partition) {
+                    Ok(ke) if is_route_allowed(config, &ke, &entity.type_name, direction) => {
+                        RouteDecision::Route(ke)
+                    }
+                    Ok(_) => RouteDecision::NotAllowed,
+                    Err(e) => {
+                        warn!(
+                            "Ignoring DDS entity {} on {}: no valid key expression for partition {:?}: {}",
+                            entity.key, entity.topic_name, partition, e
+                        );
+                        RouteDecision::NotAllowed
+                    }
+                };
+            (partition.unwrap_or("*").to_string(), decision)
+        })
+        .collect()
+}
+
+// Route a DDS entity discovered in "local discovery" mode (a DDS Writer routed from DDS, or a DDS Reader routed
+// to DDS) as decided by route_decisions(), and set its routes statuses
+pub(crate) async fn route_local_entity<F: RouteFactory + ?Sized>(
+    factory: &mut F,
+    entity: &mut DdsEntity,
+    direction: RouteDirection,
+) {
+    for (partition, decision) in route_decisions(factory.config(), entity, direction) {
+        let route_status = match decision {
+            RouteDecision::Route(ke) => match direction {
+                RouteDirection::FromDds => factory.route_dds_writer(ke, entity).await,
+                RouteDirection::ToDds => factory.route_dds_reader(ke, entity).await,
+            },
+            RouteDecision::NotAllowed => RouteStatus::NotAllowed,
+        };
+        entity.routes.insert(partition, route_status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{route_decisions, route_local_entity, KeyFilter, RouteDecision, RouteFactory};
+    use crate::config::{Config, RouteDirection};
+    use crate::dds_mgt::{DdsEntity, DiscoveryEvent, RouteStatus};
+    use async_trait::async_trait;
+    use cyclors::qos::Qos;
+    use std::collections::HashMap;
+    use zenoh::prelude::{keyexpr, OwnedKeyExpr};
+
+    // A RouteFactory recording the routes it's requested to create, without DDS nor zenoh
+    struct MockFactory {
+        config: Config,
+        created: Vec<(RouteDirection, String, OwnedKeyExpr)>,
+    }
+
+    #[async_trait]
+    impl RouteFactory for MockFactory {
+        fn config(&self) -> &Config {
+            &self.config
+        }
+
+        async fn route_dds_writer(&mut self, ke: OwnedKeyExpr, entity: &DdsEntity) -> RouteStatus {
+            self.created
+                .push((RouteDirection::FromDds, entity.key.clone(), ke.clone()));
+            RouteStatus::Routed(ke)
+        }
+
+        async fn route_dds_reader(&mut self, ke: OwnedKeyExpr, entity: &DdsEntity) -> RouteStatus {
+            self.created
+                .push((RouteDirection::ToDds, entity.key.clone(), ke.clone()));
+            RouteStatus::Routed(ke)
+        }
+    }
+
+    impl MockFactory {
+        fn new(config: &str) -> Self {
+            MockFactory {
+                config: serde_json::from_str(config).unwrap(),
+                created: Vec::new(),
+            }
+        }
+
+        // Feed a discovery event, returning the routes statuses of the discovered DDS Writer or Reader (sorted)
+        fn discover(&mut self, event: DiscoveryEvent) -> Vec<(String, RouteStatus)> {
+            let (mut entity, direction) = match event {
+                DiscoveryEvent::DiscoveredPublication { entity } => {
+                    (entity, RouteDirection::FromDds)
+                }
+                DiscoveryEvent::DiscoveredSubscription { entity } => {
+                    (entity, RouteDirection::ToDds)
+                }
+                _ => panic!("not a discovered DDS Writer or Reader"),
+            };
+            async_std::task::block_on(route_local_entity(self, &mut entity, direction));
+            let mut routes: Vec<(String, RouteStatus)> = entity.routes.into_iter().collect();
+            routes.sort_by(|(p1, _), (p2, _)| p1.cmp(p2));
+            routes
+        }
+    }
+
+    fn dds_entity(key: &str, topic_name: &str, partitions: &[&str]) -> DdsEntity {
+        let mut qos = Qos::default();
+        if !partitions.is_empty() {
+            qos.partition = Some(partitions.iter().map(|p| p.to_string()).collect());
+        }
+        DdsEntity {
+            key: key.into(),
+            participant_key: "p".into(),
+            topic_name: topic_name.into(),
+            normalized_topic_name: None,
+            type_name: "std_msgs::msg::dds_::String_".into(),
+            type_info: None,
+            keyless: true,
+            qos,
+            routes: HashMap::new(),
+        }
+    }
+
+    fn publication(key: &str, topic_name: &str, partitions: &[&str]) -> DiscoveryEvent {
+        DiscoveryEvent::DiscoveredPublication {
+            entity: dds_entity(key, topic_name, partitions),
+        }
+    }
+
+    fn subscription(key: &str, topic_name: &str, partitions: &[&str]) -> DiscoveryEvent {
+        DiscoveryEvent::DiscoveredSubscription {
+            entity: dds_entity(key, topic_name, partitions),
+        }
+    }
+
+    fn routed(partition: &str, ke: &str) -> (String, RouteStatus) {
+        (
+            partition.into(),
+            RouteStatus::Routed(ke.try_into().unwrap()),
+        )
+    }
+
+    fn not_allowed(partition: &str) -> (String, RouteStatus) {
+        (partition.into(), RouteStatus::NotAllowed)
+    }
+
+    #[test]
+    fn test_route_local_entity() {
+        let mut factory = MockFactory::new(r#"{}"#);
+        assert_eq!(
+            factory.discover(publication("w1", "rt/chatter", &[])),
+            vec![routed("*", "rt/chatter")]
+        );
+        assert_eq!(
+            factory.discover(subscription("r1", "rt/chatter", &["A", "B"])),
+            vec![routed("A", "A/rt/chatter"), routed("B", "B/rt/chatter")]
+        );
+        assert_eq!(
+            factory.created,
+            vec![
+                (
+                    RouteDirection::FromDds,
+                    "w1".into(),
+                    "rt/chatter".try_into().unwrap()
+                ),
+                (
+                    RouteDirection::ToDds,
+                    "r1".into(),
+                    "A/rt/chatter".try_into().unwrap()
+                ),
+                (
+                    RouteDirection::ToDds,
+                    "r1".into(),
+                    "B/rt/chatter".try_into().unwrap()
+                ),
+            ]
+        );
+
+        // the key expression mapping
+        let mut factory = MockFactory::new(
+            r#"{"scope": "robot1", "topic_keyexpr_map": ["^rt/(\\w*)/cmd_vel$=robots/$1/cmd_vel"]}"#,
+        );
+        assert_eq!(
+            factory.discover(publication("w1", "rt/chatter", &["A"])),
+            vec![routed("A", "robot1/A/rt/chatter")]
+        );
+        assert_eq!(
+            factory.discover(subscription("r1", "rt/turtle1/cmd_vel", &[])),
+            vec![routed("*", "robots/turtle1/cmd_vel")]
+        );
+
+        // the entities not allowed are not routed
+        let mut factory = MockFactory::new(
+            r#"{"allow": "rt/chatter", "deny": "B/.*", "directions": ["from_dds"],
+                "partition_filter": {"none_of": ["C"]}}"#,
+        );
+        assert_eq!(
+            factory.discover(publication("w1", "rt/other", &[])),
+            vec![not_allowed("*")]
+        );
+        assert_eq!(
+            factory.discover(publication("w2", "rt/chatter", &["A", "B"])),
+            vec![routed("A", "A/rt/chatter"), not_allowed("B")]
+        );
+        assert_eq!(
+            factory.discover(publication("w3", "rt/chatter", &["A", "C"])),
+            vec![not_allowed("*")]
+        );
+        assert_eq!(
+            factory.discover(subscription("r1", "rt/chatter", &[])),
+            vec![not_allowed("*")]
+        );
+        assert_eq!(
+            factory.created,
+            vec![(
+                RouteDirection::FromDds,
+                "w2".into(),
+                "A/rt/chatter".try_into().unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_filter_precedence() {
+        // "rt/chatter" is matched by both 'allow' and 'deny', "rt/other" only by 'deny'
+        let mut factory = MockFactory::new(r#"{"allow": "rt/chatter", "deny": "rt/.*"}"#);
+        assert_eq!(
+            factory.discover(publication("w1", "rt/chatter", &[])),
+            vec![not_allowed("*")]
+        );
+        let mut factory = MockFactory::new(
+            r#"{"allow": "rt/chatter", "deny": "rt/.*", "filter_precedence": "deny_wins"}"#,
+        );
+        assert_eq!(
+            factory.discover(subscription("r1", "rt/chatter", &[])),
+            vec![not_allowed("*")]
+        );
+
+        let mut factory = MockFactory::new(
+            r#"{"allow": "rt/chatter", "deny": "rt/.*", "filter_precedence": "allow_wins"}"#,
+        );
+        assert_eq!(
+            factory.discover(publication("w1", "rt/chatter", &[])),
+            vec![routed("*", "rt/chatter")]
+        );
+        assert_eq!(
+            factory.discover(subscription("r1", "rt/chatter", &[])),
+            vec![routed("*", "rt/chatter")]
+        );
+        assert_eq!(
+            factory.discover(publication("w2", "rt/other", &[])),
+            vec![not_allowed("*")]
+        );
+    }
+
+    #[test]
+    fn test_route_decisions() {
+        // the partition is part of the matched key expression
+        let config: Config = serde_json::from_str(r#"{"deny": "^rt/secret"}"#).unwrap();
+        let entity = dds_entity("w1", "rt/secret", &["A"]);
+        assert_eq!(
+            route_decisions(&config, &entity, RouteDirection::FromDds),
+            vec![(
+                "A".to_string(),
+                RouteDecision::Route("A/rt/secret".try_into().unwrap())
+            )]
+        );
+        let entity = dds_entity("w1", "rt/secret", &[]);
+        assert_eq!(
+            route_decisions(&config, &entity, RouteDirection::FromDds),
+            vec![("*".to_string(), RouteDecision::NotAllowed)]
+        );
+    }
+
+    #[test]
+    fn test_route_decisions_empty_topic_name() {
+        // a topic name that is empty once normalized has no key expression, and is not routed
+        let config: Config =
+            serde_json::from_str(r#"{"topic_normalization": {"trim": true}}"#).unwrap();
+        let entity = dds_entity("w1", " ", &[]);
+        assert_eq!(
+            route_decisions(&config, &entity, RouteDirection::FromDds),
+            vec![("*".to_string(), RouteDecision::NotAllowed)]
+        );
+        let entity = dds_entity("w1", " ", &["A"]);
+        assert_eq!(
+            route_decisions(&config, &entity, RouteDirection::FromDds),
+            vec![("A".to_string(), RouteDecision::NotAllowed)]
+        );
+    }
+
+    #[test]
+    fn test_key_filter() {
+        // the key expressions a sample is remapped to by the sample hook are filtered as the routes
+        let config: Config =
+            serde_json::from_str(r#"{"allow": "^rt/", "deny": "^rt/secret"}"#).unwrap();
+        let filter = KeyFilter::new(&config);
+        let type_name = "std_msgs::msg::dds_::String_";
+        assert!(filter.is_allowed(keyexpr::new("rt/chatter").unwrap(), type_name));
+        assert!(!filter.is_allowed(keyexpr::new("rt/secret/a").unwrap(), type_name));
+        assert!(!filter.is_allowed(keyexpr::new("other/chatter").unwrap(), type_name));
+    }
+}
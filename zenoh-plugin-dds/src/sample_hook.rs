@@ -0,0 +1,349 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// A hook invoked for each sample routed from DDS to zenoh, registered by an application embedding
+// the plugin (e.g. a custom build of zenoh-bridge-dds) to take routing decisions based on the samples
+// content, beyond what DDS content filters allow.
+//
+// The hook is given the leading fields of the sample, decoded as per the layout derived from the type
+// information of its topic (if discovered). Only the plain types (including enums) and strings are decoded,
+// up to the first member of another type. The names of the members not being part of the type information,
+// the fields are named after their index in the type definition ("0", "1"...).
+use std::convert::TryInto;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use zenoh::prelude::*;
+
+use crate::cdr_json::leading_fields;
+
+lazy_static::lazy_static! {
+    static ref SAMPLE_HOOK: RwLock<Option<Arc<dyn SampleHook>>> = RwLock::new(None);
+}
+
+// CDR encapsulation identifiers (see DDS-XTypes 7.6.3.1.2)
+const CDR_BE: [u8; 2] = [0x00, 0x00];
+const CDR_LE: [u8; 2] = [0x00, 0x01];
+const PLAIN_CDR2_BE: [u8; 2] = [0x00, 0x06];
+const PLAIN_CDR2_LE: [u8; 2] = [0x00, 0x07];
+const D_CDR2_BE: [u8; 2] = [0x00, 0x08];
+const D_CDR2_LE: [u8; 2] = [0x00, 0x09];
+
+pub trait SampleHook: Send + Sync {
+    fn on_sample(&self, sample: &HookSample) -> HookDecision;
+}
+
+// A sample routed from DDS to zenoh, as given to the hook
+pub struct HookSample<'a> {
+    pub topic_name: &'a str,
+    pub type_name: &'a str,
+    // the key expression the sample is routed to
+    pub key_expr: &'a keyexpr,
+    // the serialized sample (without the CDR header)
+    pub payload: &'a [u8],
+    // the decoded fields, if the type information is known and the decoding succeeded
+    pub fields: Option<&'a [(String, FieldValue)]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookDecision {
+    // route the sample as usual
+    Forward,
+    // don't route the sample
+    Drop,
+    // route the sample to another key expression
+    Remap(OwnedKeyExpr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Bool,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float32,
+    Float64,
+    String,
+}
+
+impl FromStr for FieldType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bool" => Ok(FieldType::Bool),
+            "i8" => Ok(FieldType::Int8),
+            "u8" => Ok(FieldType::UInt8),
+            "i16" => Ok(FieldType::Int16),
+            "u16" => Ok(FieldType::UInt16),
+            "i32" => Ok(FieldType::Int32),
+            "u32" => Ok(FieldType::UInt32),
+            "i64" => Ok(FieldType::Int64),
+            "u64" => Ok(FieldType::UInt64),
+            "f32" => Ok(FieldType::Float32),
+            "f64" => Ok(FieldType::Float64),
+            "string" => Ok(FieldType::String),
+            _ => Err(format!(
+                "unsupported field type '{s}' (expected bool, i8, u8, i16, u16, i32, u32, i64, u64, f32, f64 or string)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            FieldType::Bool => "bool",
+            FieldType::Int8 => "i8",
+            FieldType::UInt8 => "u8",
+            FieldType::Int16 => "i16",
+            FieldType::UInt16 => "u16",
+            FieldType::Int32 => "i32",
+            FieldType::UInt32 => "u32",
+            FieldType::Int64 => "i64",
+            FieldType::UInt64 => "u64",
+            FieldType::Float32 => "f32",
+            FieldType::Float64 => "f64",
+            FieldType::String => "string",
+        };
+        write!(f, "{s}")
+    }
+}
+
+// The leading fields of a type to be decoded for the hook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldsLayout {
+    fields: Vec<(String, FieldType)>,
+}
+
+impl FieldsLayout {
+    // Create a layout from a list of "<name>:<type>" strings
+    pub fn new(specs: &[String]) -> Result<Self, String> {
+        let mut fields = Vec::with_capacity(specs.len());
+        for spec in specs {
+            let (name, ty) = spec
+                .split_once(':')
+                .ok_or_else(|| format!("invalid field '{spec}' (expected '<name>:<type>')"))?;
+            fields.push((name.to_string(), ty.parse::<FieldType>()?));
+        }
+        Ok(FieldsLayout { fields })
+    }
+
+    // Create the layout of the leading fields of a type, given its serialization instructions (see
+    // cdr_json::leading_fields()). Return None if the type has no leading field that can be decoded.
+    pub(crate) fn from_type_ops(ops: &[u32]) -> Option<Self> {
+        let fields = leading_fields(ops);
+        (!fields.is_empty()).then(|| FieldsLayout { fields })
+    }
+
+    // Decode the fields from a serialized sample, given the encapsulation identifier of its CDR header.
+    // Return None if the encapsulation is not supported or the sample is too short.
+    pub fn decode(
+        &self,
+        encapsulation: &[u8],
+        payload: &[u8],
+    ) -> Option<Vec<(String, FieldValue)>> {
+        let mut reader = CdrReader::from_encapsulation(encapsulation, payload)?;
+        if matches!(encapsulation.get(0..2), Some(&D_CDR2_BE | &D_CDR2_LE)) {
+            // skip the DHEADER (the size of the appendable type)
+            reader.read_u32()?;
+        }
+        self.fields
+            .iter()
+            .map(|(name, ty)| Some((name.clone(), reader.read_value(*ty)?)))
+            .collect()
+    }
+}
+
+impl fmt::Display for FieldsLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, ty)| format!("{name}:{ty}"))
+            .collect();
+        write!(f, "{}", fields.join(","))
+    }
+}
+
+pub(crate) struct CdrReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+    // the max alignment of the primitive types (8 with XCDR1, 4 with XCDR2)
+    max_align: usize,
+}
+
+macro_rules! read_num {
+    ($reader:expr, $t:ty, $size:literal) => {{
+        let bytes = $reader.read::<$size>()?;
+        if $reader.little_endian {
+            <$t>::from_le_bytes(bytes)
+        } else {
+            <$t>::from_be_bytes(bytes)
+        }
+    }};
+}
+
+impl<'a> CdrReader<'a> {
+    fn new(buf: &'a [u8], little_endian: bool, max_align: usize) -> Self {
+        CdrReader {
+            buf,
+            pos: 0,
+            little_endian,
+            max_align,
+        }
+    }
+
+    // Create a reader of a serialized sample, given the encapsulation identifier of its CDR header.
+    // Return None if the encapsulation is not supported (i.e. an XCDR1 or XCDR2 parameter list).
+    pub(crate) fn from_encapsulation(encapsulation: &[u8], payload: &'a [u8]) -> Option<Self> {
+        let encapsulation: [u8; 2] = encapsulation.get(0..2)?.try_into().ok()?;
+        match encapsulation {
+            CDR_BE | CDR_LE => Some(CdrReader::new(payload, encapsulation == CDR_LE, 8)),
+            PLAIN_CDR2_BE | PLAIN_CDR2_LE | D_CDR2_BE | D_CDR2_LE => Some(CdrReader::new(
+                payload,
+                encapsulation == PLAIN_CDR2_LE || encapsulation == D_CDR2_LE,
+                4,
+            )),
+            _ => None,
+        }
+    }
+
+    // XCDR2 has a max alignment of 4, and DHEADERs for the appendable types and the collections
+    // of non-primitive types
+    pub(crate) fn is_xcdr2(&self) -> bool {
+        self.max_align == 4
+    }
+
+    // The number of bytes not read yet
+    pub(crate) fn remaining(&self) -> usize {
+        self.buf.len().saturating_sub(self.pos)
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Option<u32> {
+        Some(read_num!(self, u32, 4))
+    }
+
+    fn read<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let align = N.min(self.max_align);
+        self.pos = (self.pos + align - 1) / align * align;
+        let bytes = self.buf.get(self.pos..self.pos + N)?;
+        self.pos += N;
+        bytes.try_into().ok()
+    }
+
+    pub(crate) fn read_value(&mut self, ty: FieldType) -> Option<FieldValue> {
+        Some(match ty {
+            FieldType::Bool => FieldValue::Bool(self.read::<1>()?[0] != 0),
+            FieldType::Int8 => FieldValue::Int(read_num!(self, i8, 1) as i64),
+            FieldType::UInt8 => FieldValue::UInt(read_num!(self, u8, 1) as u64),
+            FieldType::Int16 => FieldValue::Int(read_num!(self, i16, 2) as i64),
+            FieldType::UInt16 => FieldValue::UInt(read_num!(self, u16, 2) as u64),
+            FieldType::Int32 => FieldValue::Int(read_num!(self, i32, 4) as i64),
+            FieldType::UInt32 => FieldValue::UInt(read_num!(self, u32, 4) as u64),
+            FieldType::Int64 => FieldValue::Int(read_num!(self, i64, 8)),
+            FieldType::UInt64 => FieldValue::UInt(read_num!(self, u64, 8)),
+            FieldType::Float32 => FieldValue::Float(read_num!(self, f32, 4) as f64),
+            FieldType::Float64 => FieldValue::Float(read_num!(self, f64, 8)),
+            FieldType::String => {
+                // the length includes the terminating NUL character
+                let len = read_num!(self, u32, 4) as usize;
+                let bytes = self.buf.get(self.pos..self.pos + len)?;
+                self.pos += len;
+                let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+                FieldValue::String(String::from_utf8(bytes.to_vec()).ok()?)
+            }
+        })
+    }
+}
+
+// Register the hook invoked for each sample routed from DDS to zenoh (replacing the previous one, if any)
+pub fn register_sample_hook(hook: Arc<dyn SampleHook>) {
+    *SAMPLE_HOOK.write().unwrap() = Some(hook);
+}
+
+pub fn unregister_sample_hook() {
+    *SAMPLE_HOOK.write().unwrap() = None;
+}
+
+pub(crate) fn get_sample_hook() -> Option<Arc<dyn SampleHook>> {
+    SAMPLE_HOOK.read().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FieldValue, FieldsLayout};
+
+    fn layout(specs: &[&str]) -> FieldsLayout {
+        FieldsLayout::new(&specs.iter().map(|s| s.to_string()).collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn test_fields_layout() {
+        assert_eq!(
+            layout(&["id:u32", "name:string"]).to_string(),
+            "id:u32,name:string"
+        );
+        assert!(FieldsLayout::new(&["id".to_string()]).is_err());
+        assert!(FieldsLayout::new(&["id:u128".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_decode_fields() {
+        let l = layout(&["ok:bool", "x:f64", "name:string", "n:i16"]);
+
+        // XCDR1 little endian: the f64 is aligned on 8 bytes
+        let mut payload = vec![1, 0, 0, 0, 0, 0, 0, 0];
+        payload.extend_from_slice(&1.5f64.to_le_bytes());
+        payload.extend_from_slice(&3u32.to_le_bytes());
+        payload.extend_from_slice(b"ab\0");
+        payload.extend_from_slice(&[0]);
+        payload.extend_from_slice(&(-2i16).to_le_bytes());
+        let expected = vec![
+            ("ok".to_string(), FieldValue::Bool(true)),
+            ("x".to_string(), FieldValue::Float(1.5)),
+            ("name".to_string(), FieldValue::String("ab".to_string())),
+            ("n".to_string(), FieldValue::Int(-2)),
+        ];
+        assert_eq!(l.decode(&[0x00, 0x01], &payload), Some(expected.clone()));
+
+        // XCDR2 big endian: the f64 is aligned on 4 bytes
+        let mut payload = vec![1, 0, 0, 0];
+        payload.extend_from_slice(&1.5f64.to_be_bytes());
+        payload.extend_from_slice(&3u32.to_be_bytes());
+        payload.extend_from_slice(b"ab\0");
+        payload.extend_from_slice(&[0]);
+        payload.extend_from_slice(&(-2i16).to_be_bytes());
+        assert_eq!(l.decode(&[0x00, 0x06], &payload), Some(expected));
+
+        // too short payload and unsupported encapsulation (XCDR2 parameter list)
+        assert_eq!(l.decode(&[0x00, 0x01], &payload[..10]), None);
+        assert_eq!(l.decode(&[0x00, 0x0a], &payload), None);
+    }
+}
@@ -0,0 +1,189 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The self-test of the running DDS plugin (see self_test()): a temporary DDS Participant, acting as a local DDS
+// application, creates a DDS Writer and a DDS Reader on a temporary topic. Both must be discovered and routed by
+// the plugin, and the samples of the DDS Writer must be received by a zenoh Subscriber on the key expression of
+// the route from DDS. As the routes to DDS ignore the publications of their own zenoh session (to avoid loops),
+// only the creation of the route to DDS is checked, not its data path.
+use cyclors::qos::Qos;
+use cyclors::*;
+use std::ffi::{CStr, CString};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+use zenoh::prelude::r#async::AsyncResolve;
+use zenoh::prelude::*;
+use zenoh::Session;
+
+use crate::dds_mgt::{
+    check_dds_participant, create_forwarding_dds_writer, delete_dds_entity, get_guid, DdsError,
+    RouteStatus,
+};
+use crate::route_zenoh_dds::write_cdr;
+
+// The temporary topic is named "<prefix><zenoh id>", not to interfere with the self-tests of other bridges
+const SELF_TEST_TOPIC_PREFIX: &str = "zenoh_bridge_dds_self_test_";
+const SELF_TEST_TYPE_NAME: &str = "zenoh_bridge_dds::SelfTest";
+const SELF_TEST_ENTITY_NAME: &str = "zenoh-bridge-dds self-test";
+
+// The period of the checks of the routes statuses, and of the writes of the test sample until it's received
+const SELF_TEST_POLL_PERIOD: Duration = Duration::from_millis(100);
+
+// The CDR encapsulation header (little-endian plain CDR) of the test sample
+const CDR_LE_HEADER: [u8; 4] = [0x00, 0x01, 0x00, 0x00];
+
+pub(crate) async fn run_self_test(
+    zsession: Arc<Session>,
+    domain: u32,
+    deadline: Instant,
+) -> Result<(), String> {
+    let dp = unsafe { dds_create_participant(domain, std::ptr::null(), std::ptr::null()) };
+    check_dds_participant(dp, domain)?;
+    let result = round_trip(&zsession, dp, deadline).await;
+    // deleting the DDS Participant deletes its DDS Writer and Reader, and so the routes created for them
+    if let Err(e) = delete_dds_entity(dp) {
+        warn!(
+            "Self-test: failed to delete the temporary DDS Participant: {}",
+            e
+        );
+    }
+    result
+}
+
+async fn round_trip(zsession: &Session, dp: dds_entity_t, deadline: Instant) -> Result<(), String> {
+    let topic_name = format!("{SELF_TEST_TOPIC_PREFIX}{}", zsession.zid());
+    let dw = create_forwarding_dds_writer(
+        dp,
+        topic_name.clone(),
+        SELF_TEST_TYPE_NAME.to_string(),
+        true,
+        Qos::default(),
+        false,
+        None,
+        Some(SELF_TEST_ENTITY_NAME),
+        None,
+    )?;
+    let dr = create_dds_reader(dp, &topic_name)?;
+
+    let ke = wait_for_route(&get_guid(&dw)?, deadline)
+        .await
+        .map_err(|e| format!("no route from DDS for the DDS Writer on {topic_name}: {e}"))?;
+    debug!("Self-test: DDS Writer on {} routed to {}", topic_name, ke);
+    wait_for_route(&get_guid(&dr)?, deadline)
+        .await
+        .map_err(|e| format!("no route to DDS for the DDS Reader on {topic_name}: {e}"))?;
+    debug!("Self-test: DDS Reader on {} routed from {}", topic_name, ke);
+
+    let subscriber = zsession
+        .declare_subscriber(&ke)
+        .res_async()
+        .await
+        .map_err(|e| format!("failed to declare a zenoh Subscriber on {ke}: {e}"))?;
+    let nonce = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_le_bytes();
+    let mut sample = CDR_LE_HEADER.to_vec();
+    sample.extend_from_slice(&nonce);
+    loop {
+        // the DDS Reader of the route might not be matched with the DDS Writer yet: write until received
+        write_sample(dw, sample.clone())?;
+        let poll_end = (Instant::now() + SELF_TEST_POLL_PERIOD).min(deadline);
+        while let Ok(Ok(s)) = async_std::future::timeout(
+            poll_end.saturating_duration_since(Instant::now()),
+            subscriber.recv_async(),
+        )
+        .await
+        {
+            if s.value.payload.contiguous().ends_with(&nonce) {
+                return Ok(());
+            }
+        }
+        if Instant::now() >= deadline {
+            return Err(format!(
+                "the sample written on {topic_name} was not received via zenoh on {ke}"
+            ));
+        }
+    }
+}
+
+// Wait for the route of a DDS entity of the temporary DDS Participant, returning its zenoh key expression
+async fn wait_for_route(entity_key: &str, deadline: Instant) -> Result<OwnedKeyExpr, String> {
+    let mut last_status = None;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(match last_status {
+                Some(status) => format!("not routed in time (status: {status:?})"),
+                None => "not discovered in time".into(),
+            });
+        }
+        let snapshot = crate::DDSPlugin::routes(remaining).await?;
+        if let Some(route) = snapshot.routes.iter().find(|r| r.entity_key == entity_key) {
+            match &route.status {
+                RouteStatus::Routed(ke) => return Ok(ke.clone()),
+                RouteStatus::NotAllowed => {
+                    return Err(format!(
+                        "the topic {} is not allowed by the configuration",
+                        route.topic_name
+                    ))
+                }
+                RouteStatus::CreationFailure {
+                    error,
+                    permanent: true,
+                    ..
+                } => return Err(format!("route creation failed: {error}")),
+                RouteStatus::DryRun(_) => {
+                    return Err("nothing is routed as dry_run is configured".into())
+                }
+                status => last_status = Some(status.clone()),
+            }
+        }
+        async_std::task::sleep(SELF_TEST_POLL_PERIOD).await;
+    }
+}
+
+fn create_dds_reader(dp: dds_entity_t, topic_name: &str) -> Result<dds_entity_t, DdsError> {
+    let cton = CString::new(topic_name).unwrap().into_raw();
+    let ctyn = CString::new(SELF_TEST_TYPE_NAME).unwrap().into_raw();
+    unsafe {
+        let t = cdds_create_blob_topic(dp, cton, ctyn, true);
+        if t < 0 {
+            return Err(DdsError::CreateTopic(t));
+        }
+        let dr = dds_create_reader(dp, t, std::ptr::null(), std::ptr::null());
+        if dr < 0 {
+            return Err(DdsError::CreateReader(dr));
+        }
+        Ok(dr)
+    }
+}
+
+fn write_sample(dw: dds_entity_t, sample: Vec<u8>) -> Result<(), String> {
+    unsafe {
+        let mut sertype_ptr: *const ddsi_sertype = std::ptr::null_mut();
+        let ret = dds_get_entity_sertype(dw, &mut sertype_ptr);
+        if ret < 0 {
+            return Err(format!(
+                "sertype lookup failed ({})",
+                CStr::from_ptr(dds_strretcode(ret))
+                    .to_str()
+                    .unwrap_or("unrecoverable DDS retcode")
+            ));
+        }
+        write_cdr(dw, sertype_ptr, sample, None, None)
+    }
+}
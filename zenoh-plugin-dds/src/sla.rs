@@ -0,0 +1,307 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// Evaluation of the SLA thresholds configured for the routes from DDS to zenoh.
+//
+// The metrics (rate, jitter and latency) are computed over each evaluation period.
+// A breach event is emitted when a metric crosses its threshold, and a recovery event
+// when it gets back within its threshold with a margin (hysteresis), to avoid flapping.
+use async_std::task;
+use serde::Serialize;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zenoh::prelude::r#async::AsyncResolve;
+use zenoh::prelude::*;
+use zenoh::Session;
+
+use crate::config::SlaThresholds;
+
+// The relative margin a metric must get back within its threshold before its breach is considered as recovered
+const SLA_HYSTERESIS_RATIO: f64 = 0.1;
+
+// The metrics of a route over an evaluation period
+#[derive(Debug, Default)]
+struct SlaWindow {
+    samples: u64,
+    last_reception: Option<Instant>,
+    // sum and sum of squares of the samples inter-arrival intervals (in seconds)
+    intervals_sum: f64,
+    intervals_sq_sum: f64,
+    intervals_count: u64,
+    // maximum latency (in seconds) between the source timestamp and the reception of a sample
+    max_latency: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SlaMonitor {
+    window: Mutex<SlaWindow>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SlaMetrics {
+    // in Hertz
+    rate: f64,
+    // standard deviation of the inter-arrival intervals, in seconds
+    jitter: Option<f64>,
+    // in seconds
+    latency: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SlaMetric {
+    Rate,
+    Jitter,
+    Latency,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SlaState {
+    Breach,
+    Recovered,
+}
+
+// An event published when a metric breaches its threshold or recovers
+#[derive(Debug, Serialize)]
+struct SlaEvent<'a> {
+    route: &'a str,
+    metric: SlaMetric,
+    state: SlaState,
+    value: f64,
+    threshold: f64,
+}
+
+impl SlaMonitor {
+    // To be called on reception of a sample. source_timestamp is in nanoseconds since UNIX epoch
+    // (or negative if unknown).
+    pub(crate) fn on_sample(&self, source_timestamp: i64) {
+        let now = Instant::now();
+        let latency = if source_timestamp >= 0 {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|t| t.as_secs_f64() - source_timestamp as f64 / 1e9)
+        } else {
+            None
+        };
+
+        let mut w = self.window.lock().unwrap();
+        w.samples += 1;
+        if let Some(last) = w.last_reception {
+            let interval = now.duration_since(last).as_secs_f64();
+            w.intervals_sum += interval;
+            w.intervals_sq_sum += interval * interval;
+            w.intervals_count += 1;
+        }
+        w.last_reception = Some(now);
+        if let Some(l) = latency {
+            w.max_latency = Some(w.max_latency.map_or(l, |m| m.max(l)));
+        }
+    }
+
+    // Return the metrics over the elapsed period and start a new period
+    fn take_metrics(&self, period: Duration) -> SlaMetrics {
+        let mut w = self.window.lock().unwrap();
+        let metrics = SlaMetrics {
+            rate: w.samples as f64 / period.as_secs_f64(),
+            jitter: if w.intervals_count > 1 {
+                let n = w.intervals_count as f64;
+                let mean = w.intervals_sum / n;
+                Some((w.intervals_sq_sum / n - mean * mean).max(0.0).sqrt())
+            } else {
+                None
+            },
+            latency: w.max_latency,
+        };
+        // keep last_reception to compute the interval with the next sample
+        *w = SlaWindow {
+            last_reception: w.last_reception,
+            ..Default::default()
+        };
+        metrics
+    }
+}
+
+// The breach state of each metric of a route
+#[derive(Debug, Default)]
+struct SlaBreaches {
+    rate: bool,
+    jitter: bool,
+    latency: bool,
+}
+
+impl SlaBreaches {
+    // Update the breach states with the metrics, returning the state changes
+    fn update(
+        &mut self,
+        thresholds: &SlaThresholds,
+        metrics: &SlaMetrics,
+    ) -> Vec<(SlaMetric, SlaState, f64, f64)> {
+        let mut changes = Vec::new();
+        if let Some(min_rate) = thresholds.min_rate {
+            if let Some(state) = update_breach(
+                &mut self.rate,
+                metrics.rate < min_rate,
+                metrics.rate >= min_rate * (1.0 + SLA_HYSTERESIS_RATIO),
+            ) {
+                changes.push((SlaMetric::Rate, state, metrics.rate, min_rate));
+            }
+        }
+        if let (Some(max_jitter), Some(jitter)) = (thresholds.max_jitter, metrics.jitter) {
+            let max_jitter = max_jitter.as_secs_f64();
+            if let Some(state) = update_breach(
+                &mut self.jitter,
+                jitter > max_jitter,
+                jitter <= max_jitter * (1.0 - SLA_HYSTERESIS_RATIO),
+            ) {
+                changes.push((SlaMetric::Jitter, state, jitter, max_jitter));
+            }
+        }
+        if let (Some(max_latency), Some(latency)) = (thresholds.max_latency, metrics.latency) {
+            let max_latency = max_latency.as_secs_f64();
+            if let Some(state) = update_breach(
+                &mut self.latency,
+                latency > max_latency,
+                latency <= max_latency * (1.0 - SLA_HYSTERESIS_RATIO),
+            ) {
+                changes.push((SlaMetric::Latency, state, latency, max_latency));
+            }
+        }
+        changes
+    }
+}
+
+fn update_breach(in_breach: &mut bool, breached: bool, recovered: bool) -> Option<SlaState> {
+    if !*in_breach && breached {
+        *in_breach = true;
+        Some(SlaState::Breach)
+    } else if *in_breach && recovered {
+        *in_breach = false;
+        Some(SlaState::Recovered)
+    } else {
+        None
+    }
+}
+
+// Spawn a task periodically evaluating the metrics of a route against its SLA thresholds,
+// publishing the breach/recovery events on events_ke. The task stops when the monitor is dropped.
+pub(crate) fn spawn_sla_evaluation(
+    monitor: Weak<SlaMonitor>,
+    thresholds: SlaThresholds,
+    period: Duration,
+    zsession: Arc<Session>,
+    events_ke: OwnedKeyExpr,
+    route: String,
+) {
+    task::spawn(async move {
+        let mut breaches = SlaBreaches::default();
+        loop {
+            task::sleep(period).await;
+            let metrics = match monitor.upgrade() {
+                Some(monitor) => monitor.take_metrics(period),
+                None => break,
+            };
+            for (metric, state, value, threshold) in breaches.update(&thresholds, &metrics) {
+                let event = SlaEvent {
+                    route: &route,
+                    metric,
+                    state,
+                    value,
+                    threshold,
+                };
+                match state {
+                    SlaState::Breach => tracing::warn!(
+                        "{}: SLA breach on {:?}: {} (threshold: {})",
+                        route,
+                        metric,
+                        value,
+                        threshold
+                    ),
+                    SlaState::Recovered => tracing::info!(
+                        "{}: SLA recovered on {:?}: {} (threshold: {})",
+                        route,
+                        metric,
+                        value,
+                        threshold
+                    ),
+                }
+                match serde_json::to_string(&event) {
+                    Ok(json) => {
+                        if let Err(e) = zsession.put(&events_ke, json).res_async().await {
+                            tracing::warn!(
+                                "{}: failed to publish SLA event on {}: {}",
+                                route,
+                                events_ke,
+                                e
+                            );
+                        }
+                    }
+                    Err(e) => tracing::error!("{}: failed to serialize SLA event: {}", route, e),
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SlaBreaches, SlaMetric, SlaMetrics, SlaState};
+    use crate::config::SlaThresholds;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sla_hysteresis() {
+        let thresholds = SlaThresholds {
+            topic: regex::Regex::new(".*").unwrap(),
+            min_rate: Some(10.0),
+            max_jitter: None,
+            max_latency: Some(Duration::from_millis(100)),
+        };
+        let metrics = |rate: f64, latency: f64| SlaMetrics {
+            rate,
+            jitter: None,
+            latency: Some(latency),
+        };
+        let mut breaches = SlaBreaches::default();
+
+        assert!(breaches
+            .update(&thresholds, &metrics(20.0, 0.05))
+            .is_empty());
+        assert_eq!(
+            breaches.update(&thresholds, &metrics(5.0, 0.05)),
+            vec![(SlaMetric::Rate, SlaState::Breach, 5.0, 10.0)]
+        );
+        // still in breach: no new event
+        assert!(breaches.update(&thresholds, &metrics(8.0, 0.05)).is_empty());
+        // back over the threshold, but within the hysteresis margin: no recovery
+        assert!(breaches
+            .update(&thresholds, &metrics(10.5, 0.05))
+            .is_empty());
+        assert_eq!(
+            breaches.update(&thresholds, &metrics(12.0, 0.2)),
+            vec![
+                (SlaMetric::Rate, SlaState::Recovered, 12.0, 10.0),
+                (SlaMetric::Latency, SlaState::Breach, 0.2, 0.1)
+            ]
+        );
+        assert!(breaches
+            .update(&thresholds, &metrics(12.0, 0.095))
+            .is_empty());
+        assert_eq!(
+            breaches.update(&thresholds, &metrics(12.0, 0.05)),
+            vec![(SlaMetric::Latency, SlaState::Recovered, 0.05, 0.1)]
+        );
+    }
+}
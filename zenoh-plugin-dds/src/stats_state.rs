@@ -0,0 +1,183 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The state of the routes counters and of the bridge uptime, persisted in a file to aggregate
+// the statistics across restarts of the bridge.
+//
+// The routes are matched by direction and zenoh key expression (i.e. partition and topic).
+// The file is loaded leniently: unknown fields and counters are ignored, and missing or invalid
+// ones are considered as 0, so that a file written by another version of the bridge can be reused.
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+// Version of the state file schema (informative, the load doesn't depend on it)
+const STATS_STATE_VERSION: u32 = 1;
+
+// The counters of a route, indexed by name
+pub(crate) type RouteStats = HashMap<String, u64>;
+
+#[derive(Debug, Default)]
+pub(crate) struct StatsState {
+    // the cumulated uptime of the bridge loaded from the file, in seconds
+    uptime: f64,
+    // the counters of the routes from DDS to zenoh, indexed by key expression
+    from_dds: HashMap<String, RouteStats>,
+    // the counters of the routes from zenoh to DDS, indexed by key expression
+    to_dds: HashMap<String, RouteStats>,
+}
+
+// The content of the state file
+#[derive(Serialize)]
+struct StatsStateFile<'a> {
+    version: u32,
+    uptime: f64,
+    from_dds: &'a HashMap<String, RouteStats>,
+    to_dds: &'a HashMap<String, RouteStats>,
+}
+
+impl StatsState {
+    // Load the state from a file. If the file doesn't exist or can't be parsed, an empty state is returned.
+    pub(crate) fn load(path: &str) -> StatsState {
+        match fs::read_to_string(path) {
+            Ok(s) => match serde_json::from_str::<Value>(&s) {
+                Ok(v) => StatsState::from_value(&v),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to parse statistics state file {}: {} - statistics start from 0",
+                        path,
+                        e
+                    );
+                    StatsState::default()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::info!(
+                    "No statistics state file {} yet - statistics start from 0",
+                    path
+                );
+                StatsState::default()
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to read statistics state file {}: {} - statistics start from 0",
+                    path,
+                    e
+                );
+                StatsState::default()
+            }
+        }
+    }
+
+    fn from_value(v: &Value) -> StatsState {
+        StatsState {
+            uptime: v
+                .get("uptime")
+                .and_then(Value::as_f64)
+                .filter(|u| u.is_finite() && *u >= 0.0)
+                .unwrap_or(0.0),
+            from_dds: routes_stats_from_value(v.get("from_dds")),
+            to_dds: routes_stats_from_value(v.get("to_dds")),
+        }
+    }
+
+    // Save the state in a file, replacing it atomically
+    pub(crate) fn save(&self, path: &str, uptime: Duration) -> Result<(), String> {
+        let file = StatsStateFile {
+            version: STATS_STATE_VERSION,
+            uptime: uptime.as_secs_f64(),
+            from_dds: &self.from_dds,
+            to_dds: &self.to_dds,
+        };
+        let json = serde_json::to_string_pretty(&file)
+            .map_err(|e| format!("failed to serialize statistics state: {e}"))?;
+        let tmp_path = format!("{path}.tmp");
+        fs::write(&tmp_path, json)
+            .and_then(|()| fs::rename(&tmp_path, path))
+            .map_err(|e| format!("failed to write statistics state file {path}: {e}"))
+    }
+
+    // The uptime loaded from the file
+    pub(crate) fn uptime(&self) -> Duration {
+        Duration::from_secs_f64(self.uptime)
+    }
+
+    pub(crate) fn route_from_dds(&self, ke: &str) -> Option<&RouteStats> {
+        self.from_dds.get(ke)
+    }
+
+    pub(crate) fn route_to_dds(&self, ke: &str) -> Option<&RouteStats> {
+        self.to_dds.get(ke)
+    }
+
+    pub(crate) fn set_route_from_dds(&mut self, ke: String, stats: RouteStats) {
+        self.from_dds.insert(ke, stats);
+    }
+
+    pub(crate) fn set_route_to_dds(&mut self, ke: String, stats: RouteStats) {
+        self.to_dds.insert(ke, stats);
+    }
+}
+
+fn routes_stats_from_value(v: Option<&Value>) -> HashMap<String, RouteStats> {
+    let mut result = HashMap::new();
+    if let Some(routes) = v.and_then(Value::as_object) {
+        for (ke, counters) in routes {
+            if let Some(counters) = counters.as_object() {
+                let stats: RouteStats = counters
+                    .iter()
+                    .filter_map(|(name, value)| value.as_u64().map(|n| (name.clone(), n)))
+                    .collect();
+                result.insert(ke.clone(), stats);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatsState;
+    use std::time::Duration;
+
+    #[test]
+    fn test_lenient_load() {
+        let state = StatsState::from_value(
+            &serde_json::from_str(
+                r#"{
+                    "version": 42,
+                    "uptime": 3600.5,
+                    "unknown_field": [1, 2, 3],
+                    "from_dds": {
+                        "rt/chatter": {"zenoh_put_failures": 3, "filtered_samples": "oops", "new_counter": 7},
+                        "rt/invalid": 12
+                    }
+                }"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(state.uptime(), Duration::from_secs_f64(3600.5));
+        let stats = state.route_from_dds("rt/chatter").unwrap();
+        assert_eq!(stats.get("zenoh_put_failures"), Some(&3));
+        assert_eq!(stats.get("filtered_samples"), None);
+        assert_eq!(stats.get("new_counter"), Some(&7));
+        assert!(state.route_from_dds("rt/invalid").is_none());
+        assert!(state.route_to_dds("rt/chatter").is_none());
+
+        let state = StatsState::from_value(&serde_json::from_str(r#"{"uptime": -1}"#).unwrap());
+        assert_eq!(state.uptime(), Duration::ZERO);
+    }
+}
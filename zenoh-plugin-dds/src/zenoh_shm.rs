@@ -0,0 +1,153 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// Zenoh shared memory support for the samples received from DDS via Iceoryx (requires the "zenoh_shm" feature).
+//
+// Iceoryx and zenoh use distinct shared memory segments, so an Iceoryx chunk cannot be handed over to
+// zenoh as is: instead the serialized sample (CDR header + payload) is written once into a zenoh shared
+// memory buffer, which is then published without any further copy to the zenoh peers on the same host.
+// Chunks containing raw (non-serialized) data still need to be serialized by Cyclone DDS first, as the
+// routes always publish CDR in zenoh. If no zenoh shared memory buffer can be allocated, the sample is
+// forwarded as a regular zenoh buffer.
+//
+// Each route has its own segment (created at its 1st sample received via Iceoryx), so that the routes
+// don't contend for the allocation of their buffers.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use zenoh::buffers::ZBuf;
+use zenoh::shm::SharedMemoryManager;
+use zenoh::value::Value;
+
+lazy_static::lazy_static! {
+    // the prefix of the segments IDs and their size, if zenoh shared memory is enabled
+    static ref ZENOH_SHM: RwLock<Option<(String, usize)>> = RwLock::new(None);
+}
+
+// the number of segments created, to give them distinct IDs
+static SEGMENTS_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// Enable the zenoh shared memory segments used by the routes to publish the samples received via Iceoryx
+pub(crate) fn init_zenoh_shm(id_prefix: String, size: usize) {
+    tracing::info!(
+        "Samples received via DDS shared memory will be published via a zenoh shared memory segment of {} bytes per route",
+        size
+    );
+    *ZENOH_SHM.write().unwrap() = Some((id_prefix, size));
+}
+
+// Return the zenoh shared memory segment of a new route, or None if zenoh shared memory is not enabled
+pub(crate) fn route_segment() -> Option<ZenohShmSegment> {
+    let (id_prefix, size) = ZENOH_SHM.read().unwrap().clone()?;
+    let id = format!(
+        "{id_prefix}-{}",
+        SEGMENTS_COUNT.fetch_add(1, Ordering::Relaxed)
+    );
+    Some(ZenohShmSegment::new(id, size))
+}
+
+// The zenoh shared memory segment of a route, created at its 1st use
+pub(crate) struct ZenohShmSegment {
+    id: String,
+    size: usize,
+    manager: Option<SharedMemoryManager>,
+    // true if the segment creation failed (not to be retried for each sample)
+    failed: bool,
+}
+
+impl ZenohShmSegment {
+    pub(crate) fn new(id: String, size: usize) -> Self {
+        ZenohShmSegment {
+            id,
+            size,
+            manager: None,
+            failed: false,
+        }
+    }
+
+    fn manager(&mut self) -> Option<&mut SharedMemoryManager> {
+        if self.manager.is_none() && !self.failed {
+            match SharedMemoryManager::make(self.id.clone(), self.size) {
+                Ok(manager) => self.manager = Some(manager),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to create zenoh shared memory segment {} of {} bytes, samples received via DDS shared memory will be copied: {}",
+                        self.id,
+                        self.size,
+                        e
+                    );
+                    self.failed = true;
+                }
+            }
+        }
+        self.manager.as_mut()
+    }
+
+    // Write the concatenation of `parts` in a zenoh shared memory buffer.
+    // Returns None if the segment couldn't be created or if it's full.
+    pub(crate) fn to_value(&mut self, parts: &[&[u8]]) -> Option<Value> {
+        let manager = self.manager()?;
+        let len = parts.iter().map(|p| p.len()).sum();
+        let mut sbuf = match manager.alloc(len) {
+            Ok(sbuf) => sbuf,
+            Err(_) => {
+                // reclaim the buffers released by the zenoh peers and retry once
+                manager.garbage_collect();
+                match manager.alloc(len) {
+                    Ok(sbuf) => sbuf,
+                    Err(e) => {
+                        tracing::debug!(
+                            "No zenoh shared memory available for a sample of {} bytes, fallback to copy: {}",
+                            len,
+                            e
+                        );
+                        return None;
+                    }
+                }
+            }
+        };
+        // Safety: the buffer has just been allocated and is not shared yet
+        let slice = unsafe { sbuf.as_mut_slice() };
+        let mut offset = 0;
+        for part in parts {
+            slice[offset..offset + part.len()].copy_from_slice(part);
+            offset += part.len();
+        }
+        let mut zbuf = ZBuf::default();
+        zbuf.push_zslice(sbuf.into());
+        Some(zbuf.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZenohShmSegment;
+
+    #[test]
+    fn test_zenoh_shm_segment() {
+        let mut segment =
+            ZenohShmSegment::new(format!("zenoh-dds-test-{}", std::process::id()), 1024);
+        // the segment is created at its 1st use
+        assert!(segment.manager.is_none());
+        let value = segment
+            .to_value(&[&[0x00, 0x01, 0x00, 0x00], &[1, 2, 3]])
+            .unwrap();
+        assert_eq!(
+            value.payload.contiguous().as_ref(),
+            &[0x00, 0x01, 0x00, 0x00, 1, 2, 3]
+        );
+        assert!(segment.manager.is_some());
+        // a sample larger than the segment falls back to a copy
+        assert!(segment.to_value(&[&[0; 2048]]).is_none());
+    }
+}